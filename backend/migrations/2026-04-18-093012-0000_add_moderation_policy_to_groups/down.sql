@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE groups
+    DROP COLUMN moderation_policy;
+
+DROP TYPE moderation_policy;
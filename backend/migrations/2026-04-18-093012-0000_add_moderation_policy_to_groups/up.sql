@@ -0,0 +1,5 @@
+-- Your SQL goes here
+CREATE TYPE moderation_policy AS ENUM ('off', 'reject', 'redact');
+
+ALTER TABLE groups
+    ADD COLUMN moderation_policy moderation_policy NOT NULL DEFAULT 'off';
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE groups
+    ADD COLUMN welcome_message TEXT;
@@ -0,0 +1 @@
+ALTER TYPE message_type ADD VALUE IF NOT EXISTS 'announcement';
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE admin_audit_log (
+    id BIGINT PRIMARY KEY,
+    actor_uid INTEGER NOT NULL,
+    action VARCHAR(64) NOT NULL,
+    chat_id BIGINT,
+    metadata JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_admin_audit_log_actor_created
+    ON admin_audit_log (actor_uid, created_at DESC);
+
+CREATE INDEX idx_admin_audit_log_chat_created
+    ON admin_audit_log (chat_id, created_at DESC)
+    WHERE chat_id IS NOT NULL;
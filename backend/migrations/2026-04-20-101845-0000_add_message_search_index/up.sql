@@ -0,0 +1,4 @@
+-- Your SQL goes here
+CREATE INDEX idx_messages_message_fts
+    ON messages USING GIN (to_tsvector('english', COALESCE(message, '')))
+    WHERE deleted_at IS NULL;
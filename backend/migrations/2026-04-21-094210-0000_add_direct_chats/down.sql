@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_groups_direct_key;
+
+ALTER TABLE groups
+    DROP COLUMN direct_key,
+    DROP COLUMN kind;
+
+DROP TYPE chat_kind;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TYPE chat_kind AS ENUM ('group', 'direct');
+
+ALTER TABLE groups
+    ADD COLUMN kind chat_kind NOT NULL DEFAULT 'group',
+    ADD COLUMN direct_key VARCHAR(32);
+
+-- One direct chat per unordered pair of users; the key is "<low uid>:<high uid>".
+CREATE UNIQUE INDEX idx_groups_direct_key ON groups (direct_key) WHERE direct_key IS NOT NULL;
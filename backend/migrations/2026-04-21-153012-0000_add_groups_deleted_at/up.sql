@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- Set when the last member leaves; the row is kept so message history stays intact.
+ALTER TABLE groups ADD COLUMN deleted_at TIMESTAMPTZ;
@@ -0,0 +1,14 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_messages_chat_client_generated_id;
+
+-- Ids reused across chats while the index was per-chat: keep the first
+-- message's id and suffix the rest so the global constraint can be restored.
+UPDATE messages m
+SET client_generated_id = m.client_generated_id || ':' || m.id
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY client_generated_id ORDER BY id) AS rn
+    FROM messages
+) dup
+WHERE m.id = dup.id AND dup.rn > 1;
+
+ALTER TABLE messages ADD CONSTRAINT messages_client_generated_id_key UNIQUE (client_generated_id);
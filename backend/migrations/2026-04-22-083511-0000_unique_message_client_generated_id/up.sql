@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- The initial schema made client_generated_id unique across all chats, so a
+-- client reusing an id in another chat failed its send. Scope it to the chat.
+-- The global constraint already rules out duplicates within a chat.
+ALTER TABLE messages DROP CONSTRAINT messages_client_generated_id_key;
+
+-- Lets a retried send find the message it already created.
+CREATE UNIQUE INDEX idx_messages_chat_client_generated_id ON messages (chat_id, client_generated_id);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE message_edits (
+    id BIGINT PRIMARY KEY,
+    message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    previous_text TEXT,
+    edited_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_message_edits_message_id_edited_at
+    ON message_edits (message_id, edited_at);
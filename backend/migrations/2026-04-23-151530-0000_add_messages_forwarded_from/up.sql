@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE messages
+    ADD COLUMN forwarded_from_message_id BIGINT REFERENCES messages(id) ON DELETE SET NULL;
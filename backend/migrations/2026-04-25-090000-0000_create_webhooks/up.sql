@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE webhooks (
+    id BIGINT PRIMARY KEY,
+    chat_id BIGINT NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    events TEXT[] NOT NULL,
+    created_by INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_webhooks_chat_id ON webhooks (chat_id);
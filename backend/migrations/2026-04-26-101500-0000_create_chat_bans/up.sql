@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE chat_bans (
+    chat_id BIGINT NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    uid INTEGER NOT NULL,
+    banned_by INTEGER NOT NULL,
+    banned_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    reason TEXT,
+    PRIMARY KEY (chat_id, uid)
+);
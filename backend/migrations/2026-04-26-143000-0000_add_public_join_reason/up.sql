@@ -0,0 +1 @@
+ALTER TYPE group_join_reason ADD VALUE IF NOT EXISTS 'public_join';
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE groups DROP COLUMN IF EXISTS retention_days;
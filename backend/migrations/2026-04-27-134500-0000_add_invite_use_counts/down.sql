@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE invites
+    DROP CONSTRAINT IF EXISTS invites_max_uses_chk,
+    DROP COLUMN IF EXISTS uses,
+    DROP COLUMN IF EXISTS max_uses;
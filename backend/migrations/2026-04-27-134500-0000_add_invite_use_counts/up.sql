@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE invites
+    ADD COLUMN max_uses INT4,
+    ADD COLUMN uses INT4 NOT NULL DEFAULT 0,
+    ADD CONSTRAINT invites_max_uses_chk CHECK (max_uses IS NULL OR max_uses > 0);
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE messages ADD COLUMN edited_by_user BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Edits predating this column cannot be told apart, so keep showing them as
+-- edited, as clients already do.
+UPDATE messages SET edited_by_user = TRUE WHERE updated_at IS NOT NULL;
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+ALTER TABLE webhooks
+    ADD COLUMN consecutive_failures INT4 NOT NULL DEFAULT 0,
+    ADD COLUMN disabled_at TIMESTAMPTZ;
+
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    webhook_id BIGINT NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    event TEXT NOT NULL,
+    attempt INT4 NOT NULL,
+    status_code INT4,
+    error TEXT,
+    succeeded BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_webhook_deliveries_webhook_id ON webhook_deliveries (webhook_id, id DESC);
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE groups DROP COLUMN welcome_message_as_dm;
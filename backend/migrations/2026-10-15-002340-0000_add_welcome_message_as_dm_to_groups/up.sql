@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE groups ADD COLUMN welcome_message_as_dm BOOLEAN NOT NULL DEFAULT FALSE;
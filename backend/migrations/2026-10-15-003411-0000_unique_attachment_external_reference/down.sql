@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_attachments_external_reference;
+ALTER TABLE attachments DROP COLUMN IF EXISTS forwarded_from_attachment_id;
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+-- A forwarded message gets its own attachment rows pointing at the original
+-- stored object; record which row each copy came from so only originals
+-- need a reference of their own. No foreign key: a copy must stay a copy even
+-- if its source row goes away.
+ALTER TABLE attachments ADD COLUMN forwarded_from_attachment_id BIGINT;
+
+-- Rows sharing a reference before this column existed: keep the oldest as the
+-- original and mark the rest as its copies.
+UPDATE attachments a
+SET forwarded_from_attachment_id = dup.original_id
+FROM (
+    SELECT id, FIRST_VALUE(id) OVER (PARTITION BY external_reference ORDER BY created_at, id) AS original_id
+    FROM attachments
+) dup
+WHERE a.id = dup.id AND dup.original_id <> a.id;
+
+-- Each stored object backs one attachment, so linking an upload twice fails
+-- here instead of racing a lookup.
+CREATE UNIQUE INDEX idx_attachments_external_reference
+ON attachments (external_reference)
+WHERE forwarded_from_attachment_id IS NULL;
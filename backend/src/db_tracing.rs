@@ -1,5 +1,7 @@
 use diesel::connection::{Instrumentation, InstrumentationEvent};
+use std::future::Future;
 use std::time::Instant;
+use tracing::Instrument;
 
 const SLOW_QUERY_THRESHOLD_MS: u128 = 10;
 
@@ -50,3 +52,108 @@ pub(crate) fn install() {
         .expect("failed to set diesel instrumentation");
     }
 }
+
+/// Rows a traced database step touched, recorded on its span.
+pub(crate) trait RowCount {
+    fn row_count(&self) -> usize;
+}
+
+impl<T> RowCount for Vec<T> {
+    fn row_count(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> RowCount for Option<T> {
+    fn row_count(&self) -> usize {
+        usize::from(self.is_some())
+    }
+}
+
+/// Rows changed, as returned by `execute`.
+impl RowCount for usize {
+    fn row_count(&self) -> usize {
+        *self
+    }
+}
+
+/// Whether a conditional update changed its row.
+impl RowCount for bool {
+    fn row_count(&self) -> usize {
+        usize::from(*self)
+    }
+}
+
+impl<T: RowCount, E> RowCount for Result<T, E> {
+    fn row_count(&self) -> usize {
+        self.as_ref().map_or(0, RowCount::row_count)
+    }
+}
+
+fn db_span(kind: &'static str) -> tracing::Span {
+    tracing::debug_span!(
+        "db",
+        kind,
+        rows = tracing::field::Empty,
+        elapsed_us = tracing::field::Empty
+    )
+}
+
+fn finish(span: &tracing::Span, kind: &'static str, rows: usize, started: Instant) {
+    let elapsed_us = started.elapsed().as_micros() as u64;
+    span.record("rows", rows);
+    span.record("elapsed_us", elapsed_us);
+    span.in_scope(|| tracing::debug!(kind, rows, elapsed_us, "db step"));
+}
+
+/// Run one database step in a debug-level `db` span that records its kind,
+/// row count and latency, nested under the request span so `RUST_LOG=debug`
+/// ties each step to its request id. Costs one timestamp when debug is off.
+pub(crate) fn traced<T: RowCount>(kind: &'static str, step: impl FnOnce() -> T) -> T {
+    let span = db_span(kind);
+    if span.is_disabled() {
+        return step();
+    }
+    let started = Instant::now();
+    let output = span.in_scope(step);
+    finish(&span, kind, output.row_count(), started);
+    output
+}
+
+/// `traced` for async steps such as `attach_metadata`.
+pub(crate) async fn traced_async<T: RowCount>(
+    kind: &'static str,
+    step: impl Future<Output = T>,
+) -> T {
+    let span = db_span(kind);
+    if span.is_disabled() {
+        return step.await;
+    }
+    let started = Instant::now();
+    let output = step.instrument(span.clone()).await;
+    finish(&span, kind, output.row_count(), started);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_counts_follow_the_step_output() {
+        assert_eq!(vec![1, 2, 3].row_count(), 3);
+        assert_eq!(Some(1).row_count(), 1);
+        assert_eq!(None::<i32>.row_count(), 0);
+        assert_eq!(Ok::<_, ()>(vec![1]).row_count(), 1);
+        assert_eq!(Err::<Vec<i32>, _>(()).row_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn traced_steps_return_their_output() {
+        assert_eq!(traced("test.sync", || Ok::<_, ()>(4usize)), Ok(4));
+        assert_eq!(
+            traced_async("test.async", async { vec![1, 2] }).await,
+            vec![1, 2]
+        );
+    }
+}
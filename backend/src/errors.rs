@@ -1,19 +1,33 @@
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::http::{header::RETRY_AFTER, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+
+/// Sent with 503 when no pooled connection frees up in time.
+const DB_POOL_RETRY_AFTER_SECS: u64 = 1;
 
 /// Unified error type for handler functions, replacing repetitive `.map_err()` boilerplate.
 ///
-/// Common database and pool errors implement `From`, so bare `?` works for the 500 case.
-/// Handlers can explicitly return `NotFound`, `Forbidden`, `BadRequest`, `Conflict`, or `Gone`
-/// for non-500 status codes.
+/// Common database and pool errors implement `From`, so bare `?` works for the 500 case
+/// (503 for an exhausted pool).
+/// Handlers can explicitly return `NotFound`, `Forbidden`, `BadRequest`, `Conflict`, `Gone`,
+/// `PayloadTooLarge`, or `TooManyRequests` for non-500 status codes; `InvalidBody` is produced
+/// by the `JsonBody` extractor.
+///
+/// Every variant is answered as `{"error": {"code", "message"}}`. The plain variants send
+/// their default code; `Coded` sends the `ErrorCode` it was raised with.
 #[derive(Debug)]
 pub enum AppError {
-    /// r2d2 pool error (failed to acquire a DB connection).
+    /// r2d2 pool error: no connection freed up within the pool's timeout. Sent
+    /// as 503 so clients back off and retry instead of treating it as a bug.
     DbPool(diesel::r2d2::PoolError),
     /// Diesel query / transaction error.
     DbQuery(diesel::result::Error),
     /// 400 Bad Request with a static message.
     BadRequest(&'static str),
+    /// 400 Bad Request for a JSON body that failed to parse, naming the
+    /// offending field when there is one.
+    InvalidBody(String),
     /// 401 Unauthorized with a static message.
     Unauthorized(&'static str),
     /// 403 Forbidden with a static message.
@@ -24,8 +38,15 @@ pub enum AppError {
     Conflict(&'static str),
     /// 410 Gone with a static message.
     Gone(&'static str),
+    /// 413 Payload Too Large with a static message.
+    PayloadTooLarge(&'static str),
+    /// 429 Too Many Requests; the value is sent as `Retry-After` in seconds.
+    TooManyRequests(u64),
     /// Generic internal server error with a static message (for non-diesel/pool errors).
     Internal(&'static str),
+    /// Any 4xx with a code of its own, for conditions clients tell apart from
+    /// other errors with the same status.
+    Coded(StatusCode, ErrorCode, &'static str),
 }
 
 impl From<diesel::r2d2::PoolError> for AppError {
@@ -52,33 +73,226 @@ impl From<(StatusCode, &'static str)> for AppError {
             StatusCode::NOT_FOUND => AppError::NotFound(msg),
             StatusCode::CONFLICT => AppError::Conflict(msg),
             StatusCode::GONE => AppError::Gone(msg),
+            StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge(msg),
             _ => AppError::Internal(msg),
         }
     }
 }
 
+/// Stable, machine-readable `error.code`. Chosen where the error is raised,
+/// so rewording a message never changes what clients match on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    // Defaults for the plain variants.
+    BadRequest,
+    InvalidBody,
+    InvalidQuery,
+    InvalidPath,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    Gone,
+    PayloadTooLarge,
+    RateLimited,
+    DbBusy,
+    DbError,
+    Internal,
+    // Conditions clients tell apart from others with the same status.
+    NotMember,
+    AdminRequired,
+    PermissionRequired,
+    MessageNotFound,
+    ChatNotFound,
+    UserNotFound,
+    UnknownUser,
+    AlreadyMember,
+    Banned,
+    MemberLimitReached,
+    LastAdmin,
+    BlockedWords,
+    AttachmentRequired,
+    UnsupportedMediaType,
+    AtCapacity,
+}
+
+impl AppError {
+    pub const NOT_MEMBER: AppError = AppError::Coded(
+        StatusCode::FORBIDDEN,
+        ErrorCode::NotMember,
+        "Not a member of this chat",
+    );
+    pub const ADMIN_REQUIRED: AppError = AppError::Coded(
+        StatusCode::FORBIDDEN,
+        ErrorCode::AdminRequired,
+        "Admin role required",
+    );
+    pub const MESSAGE_NOT_FOUND: AppError = AppError::Coded(
+        StatusCode::NOT_FOUND,
+        ErrorCode::MessageNotFound,
+        "Message not found",
+    );
+    pub const CHAT_NOT_FOUND: AppError = AppError::Coded(
+        StatusCode::NOT_FOUND,
+        ErrorCode::ChatNotFound,
+        "Chat not found",
+    );
+
+    /// The code sent as `error.code`: the one a `Coded` error carries, else
+    /// the variant's default.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::DbPool(_) => ErrorCode::DbBusy,
+            AppError::DbQuery(_) => ErrorCode::DbError,
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::InvalidBody(_) => ErrorCode::InvalidBody,
+            AppError::Unauthorized(_) => ErrorCode::Unauthorized,
+            AppError::Forbidden(_) => ErrorCode::Forbidden,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::Conflict(_) => ErrorCode::Conflict,
+            AppError::Gone(_) => ErrorCode::Gone,
+            AppError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            AppError::TooManyRequests(_) => ErrorCode::RateLimited,
+            AppError::Internal(_) => ErrorCode::Internal,
+            AppError::Coded(_, code, _) => *code,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: ErrorDetail<'a>,
+}
+
+#[derive(Serialize)]
+struct ErrorDetail<'a> {
+    code: ErrorCode,
+    message: &'a str,
+}
+
+/// `{"error": {"code": ..., "message": ...}}` with `status`. `code` is for
+/// programs and stays stable; `message` is for people and may be reworded.
+pub fn error_response(status: StatusCode, code: ErrorCode, message: &str) -> Response {
+    (
+        status,
+        Json(ErrorBody {
+            error: ErrorDetail { code, message },
+        }),
+    )
+        .into_response()
+}
+
 impl IntoResponse for AppError {
-    fn into_response(self) -> axum::response::Response {
+    fn into_response(self) -> Response {
+        let code = self.code();
         match self {
             AppError::DbPool(err) => {
                 tracing::error!("database pool error: {:?}", err);
                 (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Database connection failed",
+                    [(RETRY_AFTER, DB_POOL_RETRY_AFTER_SECS.to_string())],
+                    error_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        code,
+                        "Database busy, retry later",
+                    ),
                 )
                     .into_response()
             }
             AppError::DbQuery(err) => {
                 tracing::error!("database query error: {:?}", err);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+                error_response(StatusCode::INTERNAL_SERVER_ERROR, code, "Database error")
             }
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg).into_response(),
-            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg).into_response(),
-            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg).into_response(),
-            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg).into_response(),
-            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg).into_response(),
-            AppError::Gone(msg) => (StatusCode::GONE, msg).into_response(),
-            AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg).into_response(),
+            AppError::BadRequest(msg) => error_response(StatusCode::BAD_REQUEST, code, msg),
+            AppError::InvalidBody(msg) => error_response(StatusCode::BAD_REQUEST, code, &msg),
+            AppError::Unauthorized(msg) => error_response(StatusCode::UNAUTHORIZED, code, msg),
+            AppError::Forbidden(msg) => error_response(StatusCode::FORBIDDEN, code, msg),
+            AppError::NotFound(msg) => error_response(StatusCode::NOT_FOUND, code, msg),
+            AppError::Conflict(msg) => error_response(StatusCode::CONFLICT, code, msg),
+            AppError::Gone(msg) => error_response(StatusCode::GONE, code, msg),
+            AppError::PayloadTooLarge(msg) => {
+                error_response(StatusCode::PAYLOAD_TOO_LARGE, code, msg)
+            }
+            AppError::TooManyRequests(retry_after_secs) => (
+                [(RETRY_AFTER, retry_after_secs.to_string())],
+                error_response(StatusCode::TOO_MANY_REQUESTS, code, "Too many requests"),
+            )
+                .into_response(),
+            AppError::Internal(msg) => error_response(StatusCode::INTERNAL_SERVER_ERROR, code, msg),
+            AppError::Coded(status, _, msg) => error_response(status, code, msg),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::PgConnection;
+    use std::time::Duration;
+
+    #[test]
+    fn pool_timeout_maps_to_503_with_retry_after() {
+        let pool = Pool::builder()
+            .connection_timeout(Duration::from_millis(200))
+            .build_unchecked(ConnectionManager::<PgConnection>::new(
+                "postgres://wetty@127.0.0.1:1/wetty",
+            ));
+        let Err(pool_err) = pool.get() else {
+            panic!("nothing listens on port 1");
+        };
+
+        let response = AppError::from(pool_err).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[RETRY_AFTER], "1");
+    }
+
+    async fn body_json(error: AppError) -> (StatusCode, serde_json::Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn body_is_json_with_stable_code() {
+        let (status, body) = body_json(AppError::NOT_MEMBER).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "error": { "code": "not_member", "message": "Not a member of this chat" }
+            })
+        );
+
+        // The code comes from the call site, never from the message text.
+        let (status, body) = body_json(AppError::Forbidden("Not a member of this chat")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"]["code"], "forbidden");
+
+        let (status, body) = body_json(AppError::Coded(
+            StatusCode::NOT_FOUND,
+            ErrorCode::NotMember,
+            "Reworded",
+        ))
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "not_member");
+
+        let (status, body) = body_json(AppError::DbQuery(diesel::result::Error::NotFound)).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"]["code"], "db_error");
+        assert_eq!(body["error"]["message"], "Database error");
+    }
+
+    #[tokio::test]
+    async fn rate_limit_keeps_retry_after() {
+        let response = AppError::TooManyRequests(7).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "7");
+        let (_, body) = body_json(AppError::TooManyRequests(7)).await;
+        assert_eq!(body["error"]["code"], "rate_limited");
+    }
+}
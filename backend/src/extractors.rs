@@ -1,9 +1,14 @@
-use axum::extract::FromRequestParts;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, FromRequestParts, OptionalFromRequest, Request};
+use axum::http::request::Parts;
+use axum::http::{header::CONTENT_TYPE, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
 use diesel::r2d2::{ConnectionManager, PooledConnection};
 use diesel::PgConnection;
+use serde::de::DeserializeOwned;
 use std::ops::{Deref, DerefMut};
 
-use crate::errors::AppError;
+use crate::errors::{error_response, AppError, ErrorCode};
 use crate::AppState;
 
 /// Axum extractor that acquires a pooled database connection from `AppState.db`.
@@ -36,14 +41,324 @@ impl DerefMut for DbConn {
     }
 }
 
+/// Take a pooled connection; an exhausted pool becomes `AppError::DbPool` (503).
+pub fn get_conn(
+    state: &AppState,
+) -> Result<PooledConnection<ConnectionManager<PgConnection>>, AppError> {
+    Ok(state.db.get()?)
+}
+
 impl FromRequestParts<AppState> for DbConn {
     type Rejection = AppError;
 
     async fn from_request_parts(
-        _parts: &mut axum::http::request::Parts,
+        _parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        let conn = state.db.get()?;
-        Ok(DbConn(conn))
+        Ok(DbConn(get_conn(state)?))
+    }
+}
+
+/// JSON request body, like `axum::Json`, but a body that fails to parse is
+/// answered with 400 naming the offending field (e.g.
+/// `Invalid field messageType: unknown variant ...`) instead of an opaque 422.
+/// Missing or non-JSON `Content-Type` is still 415 and an oversized body 413.
+pub struct JsonBody<T>(pub T);
+
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !has_json_content_type(req.headers()) {
+            return Err(error_response(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ErrorCode::UnsupportedMediaType,
+                "Expected request with `Content-Type: application/json`",
+            ));
+        }
+        let bytes = Bytes::from_request(req, state).await.map_err(|rejection| {
+            let code = if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+                ErrorCode::PayloadTooLarge
+            } else {
+                ErrorCode::BadRequest
+            };
+            error_response(rejection.status(), code, &rejection.body_text())
+        })?;
+        parse_json_body(&bytes)
+            .map(JsonBody)
+            .map_err(IntoResponse::into_response)
+    }
+}
+
+/// `Option<JsonBody<T>>` is `None` for a request without a `Content-Type`,
+/// like `Option<axum::Json<T>>`; any body that is sent must still parse.
+impl<T, S> OptionalFromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        if !req.headers().contains_key(CONTENT_TYPE) {
+            return Ok(None);
+        }
+        <Self as FromRequest<S>>::from_request(req, state)
+            .await
+            .map(Some)
+    }
+}
+
+/// `axum::extract::Query`, answering a query string that fails to parse
+/// (including a malformed page cursor) with the JSON error body.
+pub struct Query<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        axum::extract::Query::from_request_parts(parts, state)
+            .await
+            .map(|axum::extract::Query(value)| Query(value))
+            .map_err(|rejection| {
+                error_response(
+                    rejection.status(),
+                    ErrorCode::InvalidQuery,
+                    &rejection.body_text(),
+                )
+            })
+    }
+}
+
+/// `axum::extract::Path`, answering path parameters that fail to parse with
+/// the JSON error body.
+pub struct Path<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        axum::extract::Path::from_request_parts(parts, state)
+            .await
+            .map(|axum::extract::Path(value)| Path(value))
+            .map_err(|rejection| {
+                let code = if rejection.status().is_server_error() {
+                    ErrorCode::Internal
+                } else {
+                    ErrorCode::InvalidPath
+                };
+                error_response(rejection.status(), code, &rejection.body_text())
+            })
+    }
+}
+
+fn has_json_content_type(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence == "application/json"
+        || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+/// Deserialize `bytes`, reporting the camelCase path of the field that failed.
+pub fn parse_json_body<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, AppError> {
+    let deserializer = &mut serde_json::Deserializer::from_slice(bytes);
+    serde_path_to_error::deserialize(deserializer).map_err(|err| {
+        let path = err.path().to_string();
+        let inner = err.into_inner();
+        let at_root = path == ".";
+        let message = if inner.is_syntax() || inner.is_eof() {
+            if at_root {
+                format!("Malformed JSON body: {inner}")
+            } else {
+                format!("Malformed JSON body at {path}: {inner}")
+            }
+        } else if at_root {
+            // Missing fields are reported against the enclosing object.
+            format!("Invalid request body: {inner}")
+        } else {
+            format!("Invalid field {path}: {inner}")
+        };
+        AppError::InvalidBody(message)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    #[allow(dead_code)]
+    struct SendBody {
+        message_type: Kind,
+        reply: Option<Reply>,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    enum Kind {
+        Text,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    #[allow(dead_code)]
+    struct Reply {
+        message_id: i64,
+    }
+
+    fn error_text(body: &str) -> String {
+        match parse_json_body::<SendBody>(body.as_bytes()) {
+            Err(AppError::InvalidBody(msg)) => msg,
+            other => panic!("expected InvalidBody, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn missing_field_names_the_field() {
+        let msg = error_text("{}");
+        assert!(msg.starts_with("Invalid request body: "), "{msg}");
+        assert!(msg.contains("missing field `messageType`"), "{msg}");
+    }
+
+    #[test]
+    fn bad_value_reports_its_path() {
+        assert!(error_text(r#"{"messageType":"video"}"#)
+            .starts_with("Invalid field messageType: unknown variant `video`"));
+        assert!(
+            error_text(r#"{"messageType":"text","reply":{"messageId":"x"}}"#)
+                .starts_with("Invalid field reply.messageId: invalid type")
+        );
+    }
+
+    #[test]
+    fn syntax_errors_are_reported_as_malformed() {
+        assert!(error_text(r#"{"messageType":"#).starts_with("Malformed JSON body"));
+        assert!(error_text("not json").starts_with("Malformed JSON body: "));
+        // serde_json reports a number where an enum is expected as a syntax error.
+        assert!(
+            error_text(r#"{"messageType":1}"#).starts_with("Malformed JSON body at messageType: ")
+        );
+    }
+
+    #[test]
+    fn json_content_types_are_recognised() {
+        let with = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, value.parse().unwrap());
+            has_json_content_type(&headers)
+        };
+        assert!(with("application/json"));
+        assert!(with("application/json; charset=utf-8"));
+        assert!(with("application/merge-patch+json"));
+        assert!(!with("text/plain"));
+        assert!(!has_json_content_type(&HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn extractor_answers_400_with_the_field_error() {
+        let request = Request::builder()
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"messageType":"video"}"#))
+            .unwrap();
+        let Err(response) =
+            <JsonBody<SendBody> as FromRequest<()>>::from_request(request, &()).await
+        else {
+            panic!("body should be rejected");
+        };
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "invalid_body");
+        let message = body["error"]["message"].as_str().unwrap();
+        assert!(
+            message.starts_with("Invalid field messageType: "),
+            "{message}"
+        );
+    }
+
+    #[tokio::test]
+    async fn extractor_rejects_non_json_content_type() {
+        let request = Request::builder()
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Body::from(r#"{"messageType":"text"}"#))
+            .unwrap();
+        let Err(response) =
+            <JsonBody<SendBody> as FromRequest<()>>::from_request(request, &()).await
+        else {
+            panic!("content type should be rejected");
+        };
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn optional_body_is_none_without_content_type() {
+        let request = Request::builder().body(Body::empty()).unwrap();
+        let body = <JsonBody<SendBody> as OptionalFromRequest<()>>::from_request(request, &())
+            .await
+            .unwrap();
+        assert!(body.is_none());
+
+        let request = Request::builder()
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let rejected =
+            <JsonBody<SendBody> as OptionalFromRequest<()>>::from_request(request, &()).await;
+        assert_eq!(rejected.err().unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn query_and_path_rejections_are_json() {
+        use tower::ServiceExt;
+
+        #[derive(serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Page {
+            limit: Option<i64>,
+        }
+
+        let app = axum::Router::new().route(
+            "/items/{id}",
+            axum::routing::get(|Path(_id): Path<i64>, Query(_page): Query<Page>| async {}),
+        );
+        for (uri, code) in [
+            ("/items/abc", "invalid_path"),
+            ("/items/1?limit=abc", "invalid_query"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(body["error"]["code"], code, "{uri}");
+        }
     }
 }
@@ -0,0 +1,238 @@
+use axum::{extract::State, Json};
+use chrono::Utc;
+use diesel::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+use utoipa_axum::router::OpenApiRouter;
+
+use crate::errors::AppError;
+use crate::extractors::{DbConn, Path, Query};
+use crate::handlers::chats::{attach_metadata, MessageResponse, Pseudonymizer};
+use crate::models::{Message, NewAdminAuditLog};
+use crate::schema::{admin_audit_log, groups, messages};
+use crate::services::authz::{Action as AuthzAction, Resource as AuthzResource};
+use crate::utils::{auth::CurrentUid, ids, pagination::validate_limit};
+use crate::AppState;
+
+const AUDIT_ACTION_READ_CHAT_MESSAGES: &str = "chat.messages.read";
+
+#[derive(serde::Deserialize)]
+struct ChatIdPath {
+    chat_id: i64,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct AdminListMessagesQuery {
+    #[serde(
+        default,
+        deserialize_with = "crate::serde_i64_string::opt::deserialize"
+    )]
+    #[schema(value_type = Option<String>)]
+    before: Option<i64>,
+    #[serde(default)]
+    max: Option<i64>,
+    /// Replace sender and mention identities with per-response aliases.
+    #[serde(default)]
+    pseudonymize: bool,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct AdminListMessagesResponse {
+    messages: Vec<MessageResponse>,
+    #[serde(with = "crate::serde_i64_string::opt")]
+    #[schema(value_type = Option<String>)]
+    next_cursor: Option<i64>,
+}
+
+/// GET /admin/chats/:chat_id/messages — Read any chat's messages for moderation.
+///
+/// Requires the global `message.viewAll` permission; chat membership is not
+/// consulted. Every call is recorded in `admin_audit_log` before data is
+/// returned.
+#[utoipa::path(
+    get,
+    path = "/chats/{chat_id}/messages",
+    tag = "admin",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("before" = Option<String>, Query, description = "Cursor: fetch messages before this ID"),
+        ("max" = Option<i64>, Query, description = "Max number of messages to return"),
+        ("pseudonymize" = Option<bool>, Query, description = "Hide sender identities"),
+    ),
+    responses(
+        (status = OK, body = AdminListMessagesResponse),
+        (status = FORBIDDEN, description = "Permission required"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn get_chat_messages(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+    Query(query): Query<AdminListMessagesQuery>,
+) -> Result<Json<AdminListMessagesResponse>, AppError> {
+    let conn = &mut *conn;
+
+    state.authz_service.require_permission(
+        conn,
+        uid,
+        AuthzAction::MessageViewAll,
+        AuthzResource::Global,
+    )?;
+
+    let chat_exists = groups::table
+        .filter(groups::id.eq(chat_id))
+        .count()
+        .get_result::<i64>(conn)?;
+    if chat_exists == 0 {
+        return Err(AppError::CHAT_NOT_FOUND);
+    }
+
+    let max = validate_limit(query.max, state.page_limits.messages);
+
+    let audit_id = ids::next_id(state.id_gen.as_ref()).await.map_err(|e| {
+        tracing::error!("next_id for audit log: {:?}", e);
+        AppError::Internal("ID generation failed")
+    })?;
+    diesel::insert_into(admin_audit_log::table)
+        .values(&NewAdminAuditLog {
+            id: audit_id,
+            actor_uid: uid,
+            action: AUDIT_ACTION_READ_CHAT_MESSAGES.to_string(),
+            chat_id: Some(chat_id),
+            metadata: json!({
+                "before": query.before.map(|id| id.to_string()),
+                "max": max,
+                "pseudonymize": query.pseudonymize,
+            }),
+            created_at: Utc::now(),
+        })
+        .execute(conn)?;
+
+    let mut rows_query = messages::table
+        .filter(messages::chat_id.eq(chat_id))
+        .filter(messages::is_published.eq(true))
+        .into_boxed();
+    if let Some(before) = query.before {
+        rows_query = rows_query.filter(messages::id.lt(before));
+    }
+    let rows: Vec<Message> = rows_query
+        .order(messages::id.desc())
+        .limit(max + 1)
+        .select(Message::as_select())
+        .load(conn)?;
+
+    let has_more = rows.len() as i64 > max;
+    let rows: Vec<Message> = rows.into_iter().take(max as usize).collect();
+    let next_cursor = has_more.then(|| rows.last().map(|m| m.id)).flatten();
+    let rows: Vec<Message> = rows.into_iter().rev().collect();
+
+    let mut messages = attach_metadata(conn, rows, &state, uid).await;
+    if query.pseudonymize {
+        let mut pseudonymizer = Pseudonymizer::default();
+        for message in &mut messages {
+            pseudonymizer.apply(message);
+        }
+    }
+
+    Ok(Json(AdminListMessagesResponse {
+        messages,
+        next_cursor,
+    }))
+}
+
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(utoipa_axum::routes!(get_chat_messages))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{Method, StatusCode};
+
+    fn audit_rows(conn: &mut PgConnection, chat_id: i64) -> Vec<(i32, String)> {
+        admin_audit_log::table
+            .filter(admin_audit_log::chat_id.eq(chat_id))
+            .order(admin_audit_log::id.asc())
+            .select((admin_audit_log::actor_uid, admin_audit_log::action))
+            .load(conn)
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn members_cannot_read_chats_through_the_admin_route() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let member = 901_081;
+        app.seed_user(member);
+        let chat_id = app.seed_chat("Not for members").await;
+        app.seed_membership(chat_id, member, crate::models::GroupRole::Admin);
+
+        let (status, body) = app
+            .request(
+                Method::GET,
+                &format!("/admin/chats/{chat_id}/messages"),
+                member,
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+        assert!(audit_rows(&mut app.conn(), chat_id).is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn every_admin_read_is_audited_and_pseudonymized_reads_hide_senders() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (operator, member) = (901_082, 901_083);
+        app.seed_user(operator);
+        app.seed_user(member);
+        app.grant(operator, AuthzAction::MessageViewAll).await;
+        let chat_id = app.seed_chat("Reported").await;
+        app.seed_membership(chat_id, member, crate::models::GroupRole::Member);
+        let (status, body) = app
+            .request(
+                Method::POST,
+                &format!("/chats/{chat_id}/messages"),
+                member,
+                Some(json!({
+                    "message": "reported text",
+                    "messageType": "text",
+                    "clientGeneratedId": "device-901083-1",
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{body}");
+
+        let uri = format!("/admin/chats/{chat_id}/messages");
+        let (status, body) = app.request(Method::GET, &uri, operator, None).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["messages"][0]["sender"]["uid"], member);
+
+        let (status, body) = app
+            .request(
+                Method::GET,
+                &format!("{uri}?pseudonymize=true"),
+                operator,
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let message = &body["messages"][0];
+        assert_eq!(message["message"], "reported text");
+        assert_eq!(message["sender"]["uid"], 0);
+        assert_eq!(message["sender"]["name"], "User 1");
+        assert_eq!(message["clientGeneratedId"], "");
+
+        let read = (operator, AUDIT_ACTION_READ_CHAT_MESSAGES.to_string());
+        assert_eq!(
+            audit_rows(&mut app.conn(), chat_id),
+            vec![read.clone(), read]
+        );
+    }
+}
@@ -13,7 +13,7 @@ use utoipa_axum::router::OpenApiRouter;
 use utoipa_axum::routes;
 
 use crate::errors::AppError;
-use crate::extractors::DbConn;
+use crate::extractors::{DbConn, JsonBody};
 use crate::services::media::{build_storage_key, presign_public_upload};
 use crate::utils::auth::CurrentUid;
 use crate::utils::ids;
@@ -66,13 +66,24 @@ pub async fn get_presigned_url(
     Ok(presigned_request.uri().to_string())
 }
 
+pub(crate) fn validate_upload_size(size: i64, max_size: i64) -> Result<(), AppError> {
+    if size < 0 {
+        return Err(AppError::BadRequest("Attachment size must not be negative"));
+    }
+    if size > max_size {
+        return Err(AppError::PayloadTooLarge("Attachment is too large"));
+    }
+    Ok(())
+}
+
 #[utoipa::path(
     post,
     path = "/upload-url",
     tag = "attachments",
     request_body = UploadUrlRequest,
     responses(
-        (status = 201, description = "Upload URL created", body = UploadUrlResponse)
+        (status = 201, description = "Upload URL created", body = UploadUrlResponse),
+        (status = 413, description = "Attachment exceeds the configured size limit"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = []))
 )]
@@ -80,10 +91,12 @@ async fn post_upload_url(
     CurrentUid(_uid): CurrentUid,
     State(state): State<AppState>,
     mut conn: DbConn,
-    Json(payload): Json<UploadUrlRequest>,
+    JsonBody(payload): JsonBody<UploadUrlRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     let conn = &mut *conn;
 
+    validate_upload_size(payload.size, state.max_attachment_size_bytes)?;
+
     let s3_client = &state.s3_client;
     let bucket = &state.s3_bucket_name;
     let prefix = &state.s3_attachment_prefix;
@@ -99,8 +112,15 @@ async fn post_upload_url(
 
     let key = build_storage_key(prefix, &payload.filename, &s3_item_id);
     let expires_in = Duration::minutes(15);
-    let presigned_upload =
-        presign_public_upload(s3_client, bucket, &key, &payload.content_type, expires_in).await?;
+    let presigned_upload = presign_public_upload(
+        s3_client,
+        bucket,
+        &key,
+        &payload.content_type,
+        payload.size,
+        expires_in,
+    )
+    .await?;
 
     let new_attachment = NewAttachment {
         id,
@@ -114,6 +134,7 @@ async fn post_upload_url(
         width: payload.width,
         height: payload.height,
         order: payload.order.unwrap_or(0),
+        forwarded_from_attachment_id: None,
     };
 
     diesel::insert_into(attachments::table)
@@ -132,3 +153,23 @@ async fn post_upload_url(
 pub fn router() -> OpenApiRouter<crate::AppState> {
     OpenApiRouter::new().routes(routes!(post_upload_url))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::validate_upload_size;
+    use crate::errors::AppError;
+
+    #[test]
+    fn upload_size_is_bounded_by_the_configured_limit() {
+        assert!(validate_upload_size(0, 100).is_ok());
+        assert!(validate_upload_size(100, 100).is_ok());
+        assert!(matches!(
+            validate_upload_size(101, 100),
+            Err(AppError::PayloadTooLarge(_))
+        ));
+        assert!(matches!(
+            validate_upload_size(-1, 100),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
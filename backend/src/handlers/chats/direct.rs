@@ -0,0 +1,252 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::PgConnection;
+use serde::Serialize;
+
+use crate::{
+    errors::{AppError, ErrorCode},
+    extractors::{DbConn, JsonBody},
+    models::{GroupJoinReason, GroupRole, NewGroup, NewGroupMembership},
+    schema::{group_membership, groups},
+    services::user::lookup_user_profiles,
+    utils::{auth::CurrentUid, ids},
+    AppState,
+};
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDirectChatBody {
+    uid: i32,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectChatResponse {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    id: i64,
+    peer_uid: i32,
+    #[serde(with = "crate::serde_timestamp")]
+    created_at: DateTime<Utc>,
+}
+
+/// Identifies the direct chat between two users regardless of who starts it.
+fn direct_chat_key(a: i32, b: i32) -> String {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    format!("{low}:{high}")
+}
+
+fn direct_membership(chat_id: i64, uid: i32, join_reason: GroupJoinReason) -> NewGroupMembership {
+    NewGroupMembership {
+        chat_id,
+        uid,
+        role: GroupRole::Member,
+        joined_at: Utc::now(),
+        join_reason,
+        join_reason_extra: None,
+    }
+}
+
+/// The direct chat between `uid` and `peer_uid`, created as `new_id` with both
+/// as members if the pair has none. Returns its id, creation time and whether
+/// it was just created; an existing chat's memberships are left alone.
+fn find_or_create_direct_chat(
+    conn: &mut PgConnection,
+    new_id: i64,
+    uid: i32,
+    peer_uid: i32,
+) -> QueryResult<(i64, DateTime<Utc>, bool)> {
+    let key = direct_chat_key(uid, peer_uid);
+    // The unique index on direct_key turns a concurrent duplicate into a
+    // no-op; both requests then read back the same row.
+    let created = diesel::insert_into(groups::table)
+        .values(&NewGroup::direct(new_id, key.clone(), Utc::now()))
+        .on_conflict_do_nothing()
+        .execute(conn)?
+        > 0;
+    let (chat_id, created_at): (i64, DateTime<Utc>) = groups::table
+        .filter(groups::direct_key.eq(&key))
+        .select((groups::id, groups::created_at))
+        .first(conn)?;
+    if created {
+        diesel::insert_into(group_membership::table)
+            .values(&vec![
+                direct_membership(chat_id, uid, GroupJoinReason::Creator),
+                direct_membership(chat_id, peer_uid, GroupJoinReason::DirectInvite),
+            ])
+            .execute(conn)?;
+    }
+    Ok((chat_id, created_at, created))
+}
+
+/// The live direct chat where `peer_uid` can be reached for `uid`, created
+/// if the pair has none. `None` when `peer_uid` has left it (or both have):
+/// nothing is sent to a chat they chose to leave.
+pub(crate) async fn direct_chat_reaching(
+    conn: &mut PgConnection,
+    state: &AppState,
+    uid: i32,
+    peer_uid: i32,
+) -> Result<Option<i64>, AppError> {
+    let new_id = ids::next_gid(state.id_gen.as_ref()).await.map_err(|e| {
+        tracing::error!("ferroid next_gid: {:?}", e);
+        AppError::Internal("ID generation failed")
+    })?;
+    let (chat_id, created) = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let (chat_id, _, created) = find_or_create_direct_chat(conn, new_id, uid, peer_uid)?;
+        let peer_is_member = diesel::select(diesel::dsl::exists(
+            group_membership::table.filter(
+                group_membership::chat_id
+                    .eq(chat_id)
+                    .and(group_membership::uid.eq(peer_uid)),
+            ),
+        ))
+        .get_result::<bool>(conn)?;
+        Ok((peer_is_member.then_some(chat_id), created))
+    })?;
+    if created {
+        if let Some(chat_id) = chat_id {
+            state.ws_registry.invalidate_chat_members(chat_id);
+        }
+    }
+    Ok(chat_id)
+}
+
+/// POST /chats/direct — Open the 1:1 chat with another user.
+///
+/// Returns the existing direct chat for the pair (200) or creates it (201).
+/// Both users join a new chat as plain members, so neither can add others to
+/// it. Reopening an existing one rejoins only the caller: a peer who left
+/// stays out until they open it themselves.
+#[utoipa::path(
+    post,
+    path = "/direct",
+    tag = "chats",
+    request_body = CreateDirectChatBody,
+    responses(
+        (status = 200, description = "Existing direct chat", body = DirectChatResponse),
+        (status = 201, description = "Direct chat created", body = DirectChatResponse),
+        (status = 400, description = "Unknown user or self"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+pub(super) async fn post_direct_chat(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<CreateDirectChatBody>,
+) -> Result<(StatusCode, Json<DirectChatResponse>), AppError> {
+    let conn = &mut *conn;
+
+    if body.uid == uid {
+        return Err(AppError::BadRequest(
+            "Cannot start a direct chat with yourself",
+        ));
+    }
+    if !lookup_user_profiles(conn, &[body.uid])?.contains_key(&body.uid) {
+        return Err(AppError::Coded(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::UserNotFound,
+            "User not found",
+        ));
+    }
+
+    let new_id = ids::next_gid(state.id_gen.as_ref()).await.map_err(|e| {
+        tracing::error!("ferroid next_gid: {:?}", e);
+        AppError::Internal("ID generation failed")
+    })?;
+
+    let (chat_id, created_at, created) =
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let (chat_id, created_at, created) =
+                find_or_create_direct_chat(conn, new_id, uid, body.uid)?;
+            if !created {
+                // Reopening brings back a chat both sides had left and
+                // rejoins the caller. A peer who left chose to, and stays out.
+                diesel::update(groups::table.filter(groups::id.eq(chat_id)))
+                    .set(groups::deleted_at.eq(None::<DateTime<Utc>>))
+                    .execute(conn)?;
+                diesel::insert_into(group_membership::table)
+                    .values(&direct_membership(chat_id, uid, GroupJoinReason::Creator))
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+            Ok((chat_id, created_at, created))
+        })?;
+    state.ws_registry.invalidate_chat_members(chat_id);
+
+    let status = if created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((
+        status,
+        Json(DirectChatResponse {
+            id: chat_id,
+            peer_uid: body.uid,
+            created_at,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::direct_chat_key;
+    use axum::http::{Method, StatusCode};
+
+    #[test]
+    fn direct_chat_key_ignores_argument_order() {
+        assert_eq!(direct_chat_key(42, 7), "7:42");
+        assert_eq!(direct_chat_key(7, 42), "7:42");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reopening_a_direct_chat_does_not_re_add_a_peer_who_left() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (caller, peer) = (901_031, 901_032);
+        app.seed_user(caller);
+        app.seed_user(peer);
+        let open = |uid: i32, other: i32| {
+            app.request(
+                Method::POST,
+                "/chats/direct",
+                uid,
+                Some(serde_json::json!({ "uid": other })),
+            )
+        };
+
+        let (status, created) = open(caller, peer).await;
+        assert_eq!(status, StatusCode::CREATED, "{created}");
+        let chat_id = created["id"].as_str().unwrap().to_string();
+        let (status, body) = app
+            .request(Method::POST, &format!("/chats/{chat_id}/leave"), peer, None)
+            .await;
+        assert_eq!(status, StatusCode::NO_CONTENT, "{body}");
+
+        let (status, reopened) = open(caller, peer).await;
+        assert_eq!(status, StatusCode::OK, "{reopened}");
+        assert_eq!(reopened["id"], created["id"]);
+        let is_member = |uid: i32| {
+            use crate::schema::group_membership::dsl;
+            use diesel::prelude::*;
+            diesel::select(diesel::dsl::exists(
+                dsl::group_membership
+                    .filter(dsl::chat_id.eq(chat_id.parse::<i64>().unwrap()))
+                    .filter(dsl::uid.eq(uid)),
+            ))
+            .get_result::<bool>(&mut app.conn())
+            .unwrap()
+        };
+        assert!(is_member(caller));
+        assert!(!is_member(peer));
+
+        // The peer can still come back on their own.
+        let (status, body) = open(peer, caller).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert!(is_member(peer));
+    }
+}
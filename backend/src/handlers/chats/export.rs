@@ -0,0 +1,295 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::PgConnection;
+use futures::Stream;
+use serde::Serialize;
+
+use crate::{
+    errors::AppError,
+    extractors::{DbConn, Path},
+    handlers::members::require_admin_role,
+    models::MessageType,
+    schema::messages,
+    utils::auth::CurrentUid,
+    AppState,
+};
+
+use super::ChatIdPath;
+
+/// Messages loaded per DB round trip while streaming an export.
+const EXPORT_BATCH_SIZE: i64 = 500;
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// One line of a chat export. Deleted messages are kept as tombstones: their
+/// position and timestamps survive, their content does not.
+#[derive(Debug, Clone, Queryable, Selectable, Serialize)]
+#[diesel(table_name = messages)]
+#[serde(rename_all = "camelCase")]
+struct ExportedMessage {
+    #[serde(with = "crate::serde_i64_string")]
+    id: i64,
+    message: Option<String>,
+    message_type: MessageType,
+    #[serde(with = "crate::serde_i64_string::opt")]
+    reply_to_id: Option<i64>,
+    #[serde(with = "crate::serde_i64_string::opt")]
+    reply_root_id: Option<i64>,
+    sender_uid: i32,
+    #[serde(with = "crate::serde_timestamp")]
+    created_at: DateTime<Utc>,
+    #[serde(with = "crate::serde_timestamp::opt")]
+    updated_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::serde_timestamp::opt")]
+    deleted_at: Option<DateTime<Utc>>,
+    has_attachments: bool,
+    #[serde(with = "crate::serde_i64_string::opt")]
+    sticker_id: Option<i64>,
+}
+
+impl ExportedMessage {
+    fn into_tombstone_if_deleted(mut self) -> Self {
+        if self.deleted_at.is_some() {
+            self.message = None;
+            self.sticker_id = None;
+            self.has_attachments = false;
+        }
+        self
+    }
+}
+
+/// GET /chats/:chat_id/export — Stream the chat's full history (admin only).
+///
+/// Messages are written oldest first, one JSON object per line, and read
+/// from the database a page at a time so the export never sits in memory.
+#[utoipa::path(
+    get,
+    path = "/export",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+    ),
+    responses(
+        (status = 200, description = "Newline-delimited JSON, one message per line", content_type = "application/x-ndjson"),
+        (status = 403, description = "Not an admin of this chat"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+pub async fn get_chat_export(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+) -> Result<Response, AppError> {
+    let conn = &mut *conn;
+
+    require_admin_role(conn, chat_id, uid)?;
+
+    let db = state.db.clone();
+    let stream = export_stream(EXPORT_BATCH_SIZE, move |after| {
+        load_export_page(&db, chat_id, after, EXPORT_BATCH_SIZE)
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+/// Published messages of the chat with an id above `after`, oldest first.
+fn load_export_page(
+    db: &Pool<ConnectionManager<PgConnection>>,
+    chat_id: i64,
+    after: Option<i64>,
+    limit: i64,
+) -> Result<Vec<ExportedMessage>, String> {
+    let conn = &mut db.get().map_err(|e| format!("pool error: {e}"))?;
+
+    let mut query = messages::table
+        .filter(messages::chat_id.eq(chat_id))
+        .filter(messages::is_published.eq(true))
+        .into_boxed();
+    if let Some(after) = after {
+        query = query.filter(messages::id.gt(after));
+    }
+
+    query
+        .order(messages::id.asc())
+        .limit(limit)
+        .select(ExportedMessage::as_select())
+        .load(conn)
+        .map_err(|e| format!("db error: {e}"))
+}
+
+/// Walk the pages `load_page` returns, keyed by the last id seen, and encode
+/// each as NDJSON. A short page ends the stream; a failed load ends it with an
+/// error, which aborts the response body rather than truncating it silently.
+///
+/// Each page is a blocking DB round trip, so it runs on the blocking pool
+/// rather than stalling the runtime worker that polls the response body.
+fn export_stream<F>(
+    batch_size: i64,
+    load_page: F,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static
+where
+    F: FnMut(Option<i64>) -> Result<Vec<ExportedMessage>, String> + Send + 'static,
+{
+    // The state is the loader and the cursor of the next page; `None` once
+    // the export has ended.
+    futures::stream::unfold(Some((load_page, None)), move |state| async move {
+        let (mut load_page, after) = state?;
+        let loaded = tokio::task::spawn_blocking(move || {
+            let page = load_page(after);
+            (load_page, page)
+        })
+        .await;
+        let (load_page, page) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                tracing::error!("chat export page task: {}", e);
+                return Some((Err(std::io::Error::other(e)), None));
+            }
+        };
+        match page {
+            Ok(rows) if rows.is_empty() => None,
+            Ok(rows) => {
+                let next = ((rows.len() as i64) >= batch_size)
+                    .then(|| rows.last().map(|row| (load_page, Some(row.id))))
+                    .flatten();
+                Some((Ok(encode_ndjson(rows)), next))
+            }
+            Err(e) => {
+                tracing::error!("chat export page: {}", e);
+                Some((Err(std::io::Error::other(e)), None))
+            }
+        }
+    })
+}
+
+fn encode_ndjson(rows: Vec<ExportedMessage>) -> Bytes {
+    let mut out = Vec::new();
+    for row in rows {
+        // Serializing plain fields into a Vec cannot fail.
+        serde_json::to_writer(&mut out, &row.into_tombstone_if_deleted())
+            .expect("export row serializes");
+        out.push(b'\n');
+    }
+    Bytes::from(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    fn message(id: i64, deleted: bool) -> ExportedMessage {
+        ExportedMessage {
+            id,
+            message: Some(format!("message {id}")),
+            message_type: MessageType::Text,
+            reply_to_id: None,
+            reply_root_id: None,
+            sender_uid: 3,
+            created_at: Utc::now(),
+            updated_at: None,
+            deleted_at: deleted.then(Utc::now),
+            has_attachments: false,
+            sticker_id: None,
+        }
+    }
+
+    /// Serve `all` the way `load_export_page` would: ids above the cursor,
+    /// ascending, at most `batch` at a time.
+    fn in_memory_pages(
+        all: Vec<ExportedMessage>,
+        batch: i64,
+    ) -> impl FnMut(Option<i64>) -> Result<Vec<ExportedMessage>, String> + Send + 'static {
+        move |after| {
+            Ok(all
+                .iter()
+                .filter(|m| after.is_none_or(|after| m.id > after))
+                .take(batch as usize)
+                .cloned()
+                .collect())
+        }
+    }
+
+    async fn collect(stream: impl Stream<Item = Result<Bytes, std::io::Error>>) -> Vec<String> {
+        let chunks: Vec<_> = stream.collect().await;
+        let body: Vec<u8> = chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.unwrap().to_vec())
+            .collect();
+        String::from_utf8(body)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn streams_every_message_once_in_order() {
+        // Covers an exact multiple of the batch size as well as a short tail.
+        for count in [0, 1, 9, 10, 25] {
+            let all: Vec<_> = (1..=count).map(|id| message(id * 7, false)).collect();
+            let lines = collect(export_stream(5, in_memory_pages(all, 5))).await;
+
+            let ids: Vec<i64> = lines
+                .iter()
+                .map(|line| {
+                    let value: serde_json::Value = serde_json::from_str(line).unwrap();
+                    value["id"].as_str().unwrap().parse().unwrap()
+                })
+                .collect();
+            let expected: Vec<i64> = (1..=count).map(|id| id * 7).collect();
+            assert_eq!(ids, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn deleted_messages_are_tombstones() {
+        let all = vec![message(1, false), message(2, true)];
+        let lines = collect(export_stream(5, in_memory_pages(all, 5))).await;
+
+        let kept: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        let deleted: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(kept["message"], "message 1");
+        assert_eq!(kept["deletedAt"], serde_json::Value::Null);
+        assert_eq!(deleted["message"], serde_json::Value::Null);
+        assert!(deleted["deletedAt"].is_string());
+    }
+
+    #[tokio::test]
+    async fn failed_page_ends_the_stream_with_an_error() {
+        let mut calls = 0;
+        let stream = export_stream(1, move |_| {
+            calls += 1;
+            if calls == 1 {
+                Ok(vec![message(1, false)])
+            } else {
+                Err("db error: connection lost".to_string())
+            }
+        });
+        let chunks: Vec<_> = stream.collect().await;
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].is_ok());
+        assert!(chunks[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn panicking_page_ends_the_stream_with_an_error() {
+        let stream = export_stream(1, |_| -> Result<Vec<ExportedMessage>, String> {
+            panic!("page loader panicked")
+        });
+        let chunks: Vec<_> = stream.collect().await;
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is_err());
+    }
+}
@@ -0,0 +1,707 @@
+use std::collections::BTreeMap;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use serde::Serialize;
+use utoipa_axum::router::OpenApiRouter;
+
+use crate::{
+    errors::AppError,
+    extractors::{DbConn, JsonBody, Path},
+    handlers::{attachments::validate_upload_size, members::check_membership},
+    models::{Attachment, AttachmentResponse, Message, MessageType, NewAttachment},
+    schema::{attachments, messages},
+    services::media::{
+        build_public_object_url, build_storage_key, AttachmentStorage, StoredObject,
+    },
+    utils::{auth::CurrentUid, ids},
+    AppState,
+};
+
+use super::{
+    attach_metadata, messages::MAX_ATTACHMENTS_PER_MESSAGE, system_edit_stamp, ChatIdPath,
+    MessageResponse,
+};
+
+const UPLOAD_URL_TTL_MINUTES: i64 = 15;
+const FOREIGN_UPLOAD_REFERENCE: &str = "Upload reference does not belong to this chat";
+const UPLOAD_NOT_FOUND: &str = "Upload not found; PUT the file before linking it";
+/// Stored objects without a content type are linked as opaque files.
+const UNKNOWN_CONTENT_TYPE: &str = "application/octet-stream";
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct LinkAttachmentsBody {
+    /// Attachments created by `/attachments/upload-url`.
+    #[serde(default)]
+    attachment_ids: Vec<String>,
+    /// Files uploaded through `/chats/:chat_id/attachments/presign`.
+    #[serde(default)]
+    uploads: Vec<UploadedAttachment>,
+}
+
+/// Size and kind are read from storage, not taken from the client.
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct UploadedAttachment {
+    external_reference: String,
+    file_name: String,
+    width: Option<i32>,
+    height: Option<i32>,
+    order: Option<i16>,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PresignAttachmentBody {
+    filename: String,
+    content_type: String,
+    size: i64,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PresignAttachmentResponse {
+    upload_url: String,
+    upload_headers: BTreeMap<String, String>,
+    /// Storage key to pass back as `uploads[].externalReference` once the PUT succeeds.
+    external_reference: String,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListAttachmentsResponse {
+    attachments: Vec<AttachmentResponse>,
+}
+
+fn load_visible_message(
+    conn: &mut PgConnection,
+    chat_id: i64,
+    message_id: i64,
+) -> Result<Message, AppError> {
+    messages::table
+        .filter(messages::id.eq(message_id))
+        .filter(messages::chat_id.eq(chat_id))
+        .filter(messages::deleted_at.is_null())
+        .filter(messages::is_published.eq(true))
+        .select(Message::as_select())
+        .first(conn)
+        .optional()?
+        .ok_or(AppError::MESSAGE_NOT_FOUND)
+}
+
+/// GET /chats/:chat_id/messages/:message_id/attachments — List a message's attachments.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("message_id" = i64, Path, description = "Message ID"),
+    ),
+    responses(
+        (status = 200, description = "Message attachments", body = ListAttachmentsResponse),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn get_attachments(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path((chat_id, message_id)): Path<(i64, i64)>,
+    mut conn: DbConn,
+) -> Result<Json<ListAttachmentsResponse>, AppError> {
+    let conn = &mut *conn;
+    check_membership(conn, chat_id, uid)?;
+    load_visible_message(conn, chat_id, message_id)?;
+
+    let rows: Vec<Attachment> = attachments::table
+        .filter(attachments::message_id.eq(message_id))
+        .filter(attachments::deleted_at.is_null())
+        .order((attachments::order.asc(), attachments::id.asc()))
+        .select(Attachment::as_select())
+        .load(conn)?;
+
+    let attachments = rows
+        .into_iter()
+        .map(|att| AttachmentResponse {
+            id: att.id,
+            url: build_public_object_url(&state, &att.external_reference),
+            kind: att.kind,
+            size: att.size,
+            file_name: att.file_name,
+            width: att.width,
+            height: att.height,
+        })
+        .collect();
+
+    Ok(Json(ListAttachmentsResponse { attachments }))
+}
+
+/// POST /chats/:chat_id/messages/:message_id/attachments — Attach uploaded files
+/// to one of the caller's messages.
+///
+/// Attachments either come from `/attachments/upload-url` and are not linked to
+/// a message yet, or are files uploaded through this chat's presigned URLs.
+/// The updated message is broadcast as `messageUpdated`.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("message_id" = i64, Path, description = "Message ID"),
+    ),
+    request_body = LinkAttachmentsBody,
+    responses(
+        (status = 200, description = "Updated message", body = MessageResponse),
+        (status = 400, description = "Message type cannot carry attachments"),
+        (status = 409, description = "Upload is already attached to a message"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn post_attachments(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path((chat_id, message_id)): Path<(i64, i64)>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<LinkAttachmentsBody>,
+) -> Result<Json<MessageResponse>, AppError> {
+    let conn = &mut *conn;
+    check_membership(conn, chat_id, uid)?;
+
+    let message = load_visible_message(conn, chat_id, message_id)?;
+    if message.sender_uid != uid {
+        return Err(AppError::Forbidden(
+            "You can only add attachments to your own messages",
+        ));
+    }
+    if !accepts_attachments(&message.message_type) {
+        return Err(AppError::BadRequest(
+            "Attachments can only be added to text, file, or audio messages",
+        ));
+    }
+
+    let attachment_ids = body
+        .attachment_ids
+        .iter()
+        .map(|id| id.parse::<i64>())
+        .collect::<Result<std::collections::HashSet<i64>, _>>()
+        .map_err(|_| AppError::BadRequest("Invalid attachment ID"))?;
+    if attachment_ids.is_empty() && body.uploads.is_empty() {
+        return Err(AppError::BadRequest("No attachments given"));
+    }
+    let attachment_ids: Vec<i64> = attachment_ids.into_iter().collect();
+
+    let mut references = std::collections::HashSet::with_capacity(body.uploads.len());
+    if !body
+        .uploads
+        .iter()
+        .all(|upload| references.insert(upload.external_reference.as_str()))
+    {
+        return Err(AppError::BadRequest("Each upload can only be linked once"));
+    }
+    let mut stored = Vec::with_capacity(body.uploads.len());
+    for upload in &body.uploads {
+        stored.push(
+            check_stored_upload(
+                state.attachment_storage.as_ref(),
+                &state.s3_attachment_prefix,
+                chat_id,
+                &upload.external_reference,
+                state.max_attachment_size_bytes,
+            )
+            .await?,
+        );
+    }
+    let upload_ids = ids::next_message_ids(state.id_gen.as_ref(), body.uploads.len())
+        .await
+        .map_err(|e| {
+            tracing::error!("next_message_ids for attachments: {:?}", e);
+            AppError::Internal("Failed to generate ID")
+        })?;
+    let mut new_attachments = Vec::with_capacity(body.uploads.len());
+    for ((upload, object), id) in body.uploads.into_iter().zip(stored).zip(upload_ids) {
+        new_attachments.push(NewAttachment {
+            id,
+            message_id: Some(message_id),
+            file_name: upload.file_name,
+            kind: object
+                .content_type
+                .unwrap_or_else(|| UNKNOWN_CONTENT_TYPE.to_string()),
+            external_reference: upload.external_reference,
+            size: object.size,
+            created_at: Utc::now(),
+            deleted_at: None,
+            width: upload.width,
+            height: upload.height,
+            order: upload.order.unwrap_or(0),
+            forwarded_from_attachment_id: None,
+        });
+    }
+
+    let updated_message = conn.transaction::<Message, AppError, _>(|conn| {
+        // Lock the message so concurrent links count each other's
+        // attachments against the limit.
+        messages::table
+            .filter(messages::id.eq(message_id))
+            .select(messages::id)
+            .for_update()
+            .first::<i64>(conn)?;
+        let linked = attachments::table
+            .filter(attachments::message_id.eq(message_id))
+            .filter(attachments::deleted_at.is_null())
+            .count()
+            .get_result::<i64>(conn)?;
+        if linked as usize + attachment_ids.len() + new_attachments.len()
+            > MAX_ATTACHMENTS_PER_MESSAGE
+        {
+            return Err(AppError::BadRequest(
+                "Too many attachments (maximum of 20 allowed)",
+            ));
+        }
+
+        let claimed = diesel::update(
+            attachments::table
+                .filter(attachments::id.eq_any(&attachment_ids))
+                .filter(attachments::message_id.is_null())
+                .filter(attachments::deleted_at.is_null()),
+        )
+        .set(attachments::message_id.eq(message_id))
+        .execute(conn)?;
+        if claimed != attachment_ids.len() {
+            return Err(AppError::BadRequest(
+                "Attachments must exist and not belong to another message",
+            ));
+        }
+        if !new_attachments.is_empty() {
+            insert_uploads(conn, &new_attachments)?;
+        }
+
+        Ok(
+            diesel::update(messages::table.filter(messages::id.eq(message_id)))
+                .set((
+                    messages::has_attachments.eq(true),
+                    system_edit_stamp(Utc::now()),
+                ))
+                .returning(Message::as_returning())
+                .get_result(conn)?,
+        )
+    })?;
+
+    let response = attach_metadata(conn, vec![updated_message], &state, uid)
+        .await
+        .into_iter()
+        .next()
+        .ok_or(AppError::Internal("Failed to build message response"))?;
+
+    let member_uids = crate::services::chat::member_uids_or_log(conn, &state.ws_registry, chat_id);
+    let ws_msg = std::sync::Arc::new(
+        crate::handlers::ws::messages::ServerWsMessage::MessageUpdated(response.clone()),
+    );
+    state
+        .ws_registry
+        .broadcast_to_chat(chat_id, &member_uids, ws_msg);
+
+    Ok(Json(response))
+}
+
+/// Every upload for a chat is keyed under this prefix, so a reference can be
+/// checked against the chat it is linked in.
+fn chat_upload_prefix(prefix: &str, chat_id: i64) -> String {
+    format!("{prefix}/chats/{chat_id}")
+}
+
+fn check_upload_reference(prefix: &str, chat_id: i64, reference: &str) -> Result<(), AppError> {
+    let chat_prefix = chat_upload_prefix(prefix, chat_id);
+    match reference
+        .strip_prefix(chat_prefix.as_str())
+        .and_then(|rest| rest.strip_prefix('/'))
+    {
+        Some(object) if !object.is_empty() && !object.contains('/') => Ok(()),
+        _ => Err(AppError::BadRequest(FOREIGN_UPLOAD_REFERENCE)),
+    }
+}
+
+/// Stickers, invites, and system or announcement messages render from their
+/// own fields, so files linked to them would never be shown.
+fn accepts_attachments(message_type: &MessageType) -> bool {
+    matches!(
+        message_type,
+        MessageType::Text | MessageType::File | MessageType::Audio
+    )
+}
+
+/// The stored object behind a presigned upload for this chat: it must exist
+/// and fit the size limit, whatever the presign request claimed.
+async fn check_stored_upload(
+    storage: &dyn AttachmentStorage,
+    prefix: &str,
+    chat_id: i64,
+    reference: &str,
+    max_size: i64,
+) -> Result<StoredObject, AppError> {
+    check_upload_reference(prefix, chat_id, reference)?;
+    let object = storage
+        .head(reference)
+        .await?
+        .ok_or(AppError::BadRequest(UPLOAD_NOT_FOUND))?;
+    validate_upload_size(object.size, max_size)?;
+    Ok(object)
+}
+
+const EXTERNAL_REFERENCE_INDEX: &str = "idx_attachments_external_reference";
+const UPLOAD_ALREADY_LINKED: &str = "Upload is already attached to a message";
+
+/// Each presigned upload becomes one attachment; linking it again would let
+/// one object be deleted out from under another message. The unique index
+/// decides, so two concurrent links of the same upload cannot both succeed.
+fn insert_uploads(conn: &mut PgConnection, uploads: &[NewAttachment]) -> Result<(), AppError> {
+    match diesel::insert_into(attachments::table)
+        .values(uploads)
+        .execute(conn)
+    {
+        Ok(_) => Ok(()),
+        Err(diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            info,
+        )) if info.constraint_name() == Some(EXTERNAL_REFERENCE_INDEX) => {
+            Err(AppError::Conflict(UPLOAD_ALREADY_LINKED))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn presign_chat_upload(
+    storage: &dyn AttachmentStorage,
+    prefix: &str,
+    chat_id: i64,
+    body: &PresignAttachmentBody,
+    max_size: i64,
+) -> Result<PresignAttachmentResponse, AppError> {
+    validate_upload_size(body.size, max_size)?;
+    if body.content_type.trim().is_empty() {
+        return Err(AppError::BadRequest("Content type is required"));
+    }
+
+    let object_id = uuid::Uuid::new_v4().to_string();
+    let key = build_storage_key(
+        &chat_upload_prefix(prefix, chat_id),
+        &body.filename,
+        &object_id,
+    );
+    let upload = storage
+        .presign_put(
+            &key,
+            &body.content_type,
+            body.size,
+            Duration::minutes(UPLOAD_URL_TTL_MINUTES),
+        )
+        .await?;
+
+    Ok(PresignAttachmentResponse {
+        upload_url: upload.upload_url,
+        upload_headers: upload.upload_headers,
+        external_reference: key,
+    })
+}
+
+/// POST /chats/:chat_id/attachments/presign — Get a URL to upload a file for this chat.
+///
+/// No attachment exists until the client PUTs the file and passes the returned
+/// `externalReference` to `POST /chats/:chat_id/messages/:message_id/attachments`.
+#[utoipa::path(
+    post,
+    path = "/attachments/presign",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+    ),
+    request_body = PresignAttachmentBody,
+    responses(
+        (status = 201, description = "Upload URL created", body = PresignAttachmentResponse),
+        (status = 403, description = "Not a member of this chat"),
+        (status = 413, description = "Attachment exceeds the configured size limit"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+pub(super) async fn post_attachment_presign(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<PresignAttachmentBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = &mut *conn;
+    check_membership(conn, chat_id, uid)?;
+
+    let response = presign_chat_upload(
+        state.attachment_storage.as_ref(),
+        &state.s3_attachment_prefix,
+        chat_id,
+        &body,
+        state.max_attachment_size_bytes,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+pub fn router() -> OpenApiRouter<crate::AppState> {
+    OpenApiRouter::new().routes(utoipa_axum::routes!(get_attachments, post_attachments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::media::PresignedUpload;
+    use futures::future::BoxFuture;
+
+    /// Signs nothing; hands back a URL naming the key it was asked for, and
+    /// holds whatever objects a test put in it.
+    #[derive(Default)]
+    struct MockStorage {
+        objects: std::collections::HashMap<String, StoredObject>,
+    }
+
+    impl AttachmentStorage for MockStorage {
+        fn presign_put<'a>(
+            &'a self,
+            storage_key: &'a str,
+            content_type: &'a str,
+            content_length: i64,
+            _expires_in: Duration,
+        ) -> BoxFuture<'a, Result<PresignedUpload, (StatusCode, &'static str)>> {
+            Box::pin(async move {
+                Ok(PresignedUpload {
+                    upload_url: format!("https://storage.test/{storage_key}?signed"),
+                    upload_headers: BTreeMap::from([
+                        ("content-type".to_string(), content_type.to_string()),
+                        ("content-length".to_string(), content_length.to_string()),
+                    ]),
+                })
+            })
+        }
+
+        fn head<'a>(
+            &'a self,
+            storage_key: &'a str,
+        ) -> BoxFuture<'a, Result<Option<StoredObject>, (StatusCode, &'static str)>> {
+            Box::pin(async move { Ok(self.objects.get(storage_key).cloned()) })
+        }
+    }
+
+    fn body(filename: &str, size: i64) -> PresignAttachmentBody {
+        PresignAttachmentBody {
+            filename: filename.to_string(),
+            content_type: "image/png".to_string(),
+            size,
+        }
+    }
+
+    #[tokio::test]
+    async fn presign_returns_a_url_and_a_reference_for_the_chat() {
+        let response = presign_chat_upload(
+            &MockStorage::default(),
+            "attachments",
+            42,
+            &body("cat.png", 10),
+            100,
+        )
+        .await
+        .unwrap();
+
+        assert!(response
+            .external_reference
+            .starts_with("attachments/chats/42/"));
+        assert!(response.external_reference.ends_with(".png"));
+        assert_eq!(
+            response.upload_url,
+            format!(
+                "https://storage.test/{}?signed",
+                response.external_reference
+            )
+        );
+        assert_eq!(response.upload_headers["content-type"], "image/png");
+        assert_eq!(response.upload_headers["content-length"], "10");
+        assert!(check_upload_reference("attachments", 42, &response.external_reference).is_ok());
+    }
+
+    #[tokio::test]
+    async fn presign_enforces_the_size_limit() {
+        let result = presign_chat_upload(
+            &MockStorage::default(),
+            "attachments",
+            42,
+            &body("big.bin", 101),
+            100,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::PayloadTooLarge(_))));
+    }
+
+    #[test]
+    fn upload_references_must_belong_to_the_chat() {
+        for reference in [
+            "attachments/chats/7/abc.png",
+            "attachments/chats/420/abc.png",
+            "attachments/chats/42/",
+            "attachments/chats/42/../7/abc.png",
+            "attachments/abc.png",
+        ] {
+            assert!(matches!(
+                check_upload_reference("attachments", 42, reference),
+                Err(AppError::BadRequest(FOREIGN_UPLOAD_REFERENCE))
+            ));
+        }
+    }
+
+    #[test]
+    fn only_user_content_messages_accept_attachments() {
+        for message_type in [MessageType::Text, MessageType::File, MessageType::Audio] {
+            assert!(accepts_attachments(&message_type));
+        }
+        for message_type in [
+            MessageType::Sticker,
+            MessageType::Invite,
+            MessageType::System,
+            MessageType::Announcement,
+        ] {
+            assert!(!accepts_attachments(&message_type));
+        }
+    }
+
+    #[tokio::test]
+    async fn linked_uploads_take_their_size_and_kind_from_storage() {
+        let object = |size| StoredObject {
+            size,
+            content_type: Some("image/png".to_string()),
+        };
+        let storage = MockStorage {
+            objects: std::collections::HashMap::from([
+                ("attachments/chats/42/small.png".to_string(), object(10)),
+                ("attachments/chats/42/huge.png".to_string(), object(101)),
+            ]),
+        };
+        let check = |reference: &'static str| {
+            check_stored_upload(&storage, "attachments", 42, reference, 100)
+        };
+
+        assert_eq!(
+            check("attachments/chats/42/small.png").await.unwrap(),
+            object(10)
+        );
+        assert!(matches!(
+            check("attachments/chats/42/huge.png").await,
+            Err(AppError::PayloadTooLarge(_))
+        ));
+        assert!(matches!(
+            check("attachments/chats/42/missing.png").await,
+            Err(AppError::BadRequest(UPLOAD_NOT_FOUND))
+        ));
+        assert!(matches!(
+            check("attachments/chats/7/small.png").await,
+            Err(AppError::BadRequest(FOREIGN_UPLOAD_REFERENCE))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn an_upload_already_linked_cannot_be_linked_again() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let reference = "attachments/chats/42/linked.png";
+        let upload = |id, forwarded_from_attachment_id| NewAttachment {
+            id,
+            message_id: None,
+            file_name: "linked.png".to_string(),
+            kind: "image/png".to_string(),
+            external_reference: reference.to_string(),
+            size: 10,
+            created_at: Utc::now(),
+            deleted_at: None,
+            width: None,
+            height: None,
+            order: 0,
+            forwarded_from_attachment_id,
+        };
+        let conn = &mut app.conn();
+
+        insert_uploads(conn, &[upload(900_601, None)]).unwrap();
+        // As in the handler, the failed insert only rolls back its own
+        // transaction.
+        assert!(matches!(
+            conn.transaction(|conn| insert_uploads(conn, &[upload(900_602, None)])),
+            Err(AppError::Conflict(UPLOAD_ALREADY_LINKED))
+        ));
+        // A forwarded copy shares the original's object.
+        insert_uploads(conn, &[upload(900_603, Some(900_601))]).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn linking_stops_at_the_per_message_limit() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_061;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Attachment limit").await;
+        app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+        let (status, sent) = app
+            .request(
+                axum::http::Method::POST,
+                &format!("/chats/{chat_id}/messages"),
+                uid,
+                Some(serde_json::json!({
+                    "message": "photos",
+                    "messageType": "text",
+                    "clientGeneratedId": "attachment-limit",
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{sent}");
+        let message_id = sent["id"].as_str().unwrap().to_string();
+
+        let ids: Vec<i64> = (0..=MAX_ATTACHMENTS_PER_MESSAGE as i64)
+            .map(|i| 901_061_000 + i)
+            .collect();
+        let unlinked: Vec<_> = ids
+            .iter()
+            .map(|&id| NewAttachment {
+                id,
+                message_id: None,
+                file_name: format!("{id}.png"),
+                kind: "image/png".to_string(),
+                external_reference: format!("attachments/{id}.png"),
+                size: 10,
+                created_at: Utc::now(),
+                deleted_at: None,
+                width: None,
+                height: None,
+                order: 0,
+                forwarded_from_attachment_id: None,
+            })
+            .collect();
+        diesel::insert_into(attachments::table)
+            .values(&unlinked)
+            .execute(&mut app.conn())
+            .unwrap();
+
+        let link = |ids: &[i64]| {
+            let body = serde_json::json!({
+                "attachmentIds": ids.iter().map(i64::to_string).collect::<Vec<_>>(),
+            });
+            let uri = format!("/chats/{chat_id}/messages/{message_id}/attachments");
+            let app = &app;
+            async move {
+                app.request(axum::http::Method::POST, &uri, uid, Some(body))
+                    .await
+            }
+        };
+        let (full, over) = ids.split_at(MAX_ATTACHMENTS_PER_MESSAGE);
+        let (status, body) = link(full).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let (status, body) = link(over).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    }
+}
@@ -1,28 +1,40 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    extract::State,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use diesel::connection::{AnsiTransactionManager, TransactionManager};
 use diesel::prelude::*;
 use diesel::PgConnection;
 use serde::Serialize;
 use utoipa_axum::router::OpenApiRouter;
 
 use crate::{
-    errors::AppError,
-    extractors::DbConn,
-    handlers::{groups::load_requester_group_role, members::check_membership},
-    models::{GroupRole, Message, MessageType},
-    schema::{attachments, group_membership, groups, messages},
-    utils::{auth::CurrentUid, pagination::validate_limit},
-    AppState, MAX_MESSAGES_LIMIT,
+    db_tracing::{traced, traced_async},
+    errors::{AppError, ErrorCode},
+    extractors::{DbConn, JsonBody, Path, Query},
+    handlers::{
+        groups::load_requester_group_role,
+        members::{check_membership, require_admin_role},
+    },
+    models::{GroupRole, Message, MessageEdit, MessageType},
+    schema::{attachments, groups, message_edits, messages},
+    services::{webhooks::WebhookEvent, ws_registry::EchoExclusion},
+    utils::{
+        auth::CurrentUid,
+        ids,
+        pagination::{require_positive_limit, validate_limit},
+    },
+    AppState,
 };
 
 use super::{
-    attach_metadata, extract_mention_uids, load_sticker_accessible_ids, send_prepared_message,
-    ChatIdPath, CreateMessageBody, MessageResponse, PreparedMessageSend,
+    attach_metadata, attach_metadata_with, build_message_side_effects, extract_mention_uids,
+    insert_prepared_message, load_sticker_accessible_ids, send_prepared_message,
+    side_effects_or_log, user_edit_stamp, ChatIdPath, CreateMessageBody, MessageResponse,
+    MetadataOptions, PreparedMessageSend,
 };
 
 #[derive(serde::Deserialize, utoipa::ToSchema)]
@@ -30,7 +42,7 @@ use super::{
 pub struct ListMessagesQuery {
     #[serde(
         default,
-        deserialize_with = "crate::serde_i64_string::opt::deserialize"
+        deserialize_with = "crate::utils::pagination::serde_cursor::deserialize"
     )]
     #[schema(value_type = Option<String>)]
     before: Option<i64>,
@@ -42,7 +54,7 @@ pub struct ListMessagesQuery {
     around: Option<i64>,
     #[serde(
         default,
-        deserialize_with = "crate::serde_i64_string::opt::deserialize"
+        deserialize_with = "crate::utils::pagination::serde_cursor::deserialize"
     )]
     #[schema(value_type = Option<String>)]
     after: Option<i64>,
@@ -54,20 +66,58 @@ pub struct ListMessagesQuery {
     )]
     #[schema(value_type = Option<String>)]
     thread_id: Option<i64>,
+    #[serde(default)]
+    expand: Option<MessageExpand>,
+}
+
+/// Extra previews `get_messages` can resolve alongside `replyToMessage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageExpand {
+    /// Each reply's thread root, as `replyRootMessage`.
+    Root,
 }
 
 #[derive(Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ListMessagesResponse {
     messages: Vec<MessageResponse>,
-    #[serde(with = "crate::serde_i64_string::opt")]
+    #[serde(with = "crate::utils::pagination::serde_cursor")]
     #[schema(value_type = Option<String>)]
     next_cursor: Option<i64>,
-    #[serde(with = "crate::serde_i64_string::opt")]
+    #[serde(with = "crate::utils::pagination::serde_cursor")]
     #[schema(value_type = Option<String>)]
     prev_cursor: Option<i64>,
 }
 
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMessagesQuery {
+    #[serde(default)]
+    q: String,
+    #[serde(
+        default,
+        deserialize_with = "crate::utils::pagination::serde_cursor::deserialize"
+    )]
+    #[schema(value_type = Option<String>)]
+    before: Option<i64>,
+    #[serde(default)]
+    max: Option<i64>,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadViewQuery {
+    #[serde(
+        default,
+        deserialize_with = "crate::utils::pagination::serde_cursor::deserialize"
+    )]
+    #[schema(value_type = Option<String>)]
+    after: Option<i64>,
+    #[serde(default)]
+    max: Option<i64>,
+}
+
 #[derive(serde::Deserialize)]
 pub struct ThreadIdPath {
     chat_id: i64,
@@ -88,10 +138,34 @@ pub struct UpdateMessageBody {
     message: String,
     #[serde(default)]
     attachment_ids: Vec<String>,
+    /// The `editCount` the client last saw; a mismatch is rejected with 409.
+    /// Omit it for last-write-wins.
+    #[serde(default)]
+    expected_edit_count: Option<i64>,
+}
+
+const STALE_EDIT: &str = "Message was edited since you loaded it";
+
+/// Optimistic concurrency for edits: every edit adds one `message_edits` row,
+/// so the row count works as the message version.
+fn check_edit_version(expected: Option<i64>, current: i64) -> Result<(), AppError> {
+    match expected {
+        Some(expected) if expected != current => Err(AppError::Conflict(STALE_EDIT)),
+        _ => Ok(()),
+    }
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAnnouncementBody {
+    message: String,
+    client_generated_id: String,
 }
 
 const SYSTEM_MESSAGE_TYPE_FORBIDDEN: &str = "System messages cannot be sent by clients";
 const INVITE_MESSAGE_TYPE_FORBIDDEN: &str = "Invite messages must be sent through invite APIs";
+const ANNOUNCEMENT_MESSAGE_TYPE_FORBIDDEN: &str =
+    "Announcements must be sent through the announce API";
 
 fn validate_client_message_type(message_type: &MessageType) -> Result<(), AppError> {
     if matches!(message_type, MessageType::System) {
@@ -102,16 +176,105 @@ fn validate_client_message_type(message_type: &MessageType) -> Result<(), AppErr
         return Err(AppError::BadRequest(INVITE_MESSAGE_TYPE_FORBIDDEN));
     }
 
+    if matches!(message_type, MessageType::Announcement) {
+        return Err(AppError::BadRequest(ANNOUNCEMENT_MESSAGE_TYPE_FORBIDDEN));
+    }
+
+    Ok(())
+}
+
+/// Seconds `uid` must still wait before posting again under slow mode, if any.
+/// Admins are exempt.
+fn slow_mode_retry_after(
+    slow_mode_secs: i32,
+    role: Option<&GroupRole>,
+    last_sent_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<u64> {
+    if slow_mode_secs <= 0 || role == Some(&GroupRole::Admin) {
+        return None;
+    }
+    let next_allowed_at = last_sent_at? + chrono::Duration::seconds(slow_mode_secs.into());
+    let remaining = next_allowed_at - now;
+    (remaining > chrono::Duration::zero())
+        .then(|| (remaining.num_milliseconds() as u64).div_ceil(1000))
+}
+
+/// Return 429 with the remaining wait when the chat's slow mode still holds
+/// `uid` back. System messages (e.g. welcomes) do not count as posts, and
+/// neither do deleted or not-yet-published ones.
+fn enforce_slow_mode(conn: &mut PgConnection, chat_id: i64, uid: i32) -> Result<(), AppError> {
+    let slow_mode_secs: i32 = groups::table
+        .filter(groups::id.eq(chat_id))
+        .select(groups::slow_mode_secs)
+        .first(conn)?;
+    if slow_mode_secs <= 0 {
+        return Ok(());
+    }
+    let role = load_requester_group_role(conn, chat_id, uid)?;
+    // Matches idx_messages_chat_sender_active (chat_id, sender_uid,
+    // created_at DESC) and its partial predicate, so this is one index probe.
+    let last_sent_at: Option<DateTime<Utc>> = messages::table
+        .filter(messages::chat_id.eq(chat_id))
+        .filter(messages::sender_uid.eq(uid))
+        .filter(messages::deleted_at.is_null())
+        .filter(messages::is_published.eq(true))
+        .filter(messages::message_type.ne(MessageType::System))
+        .order(messages::created_at.desc())
+        .select(messages::created_at)
+        .first(conn)
+        .optional()?;
+    match slow_mode_retry_after(slow_mode_secs, role.as_ref(), last_sent_at, Utc::now()) {
+        Some(retry_after) => Err(AppError::TooManyRequests(retry_after)),
+        None => Ok(()),
+    }
+}
+
+pub(super) const MAX_ATTACHMENTS_PER_MESSAGE: usize = 20;
+
+const MESSAGE_TOO_LONG: &str = "Message too long";
+const MESSAGE_EMPTY: &str = "Message cannot be empty";
+const ATTACHMENT_REQUIRED: AppError = AppError::Coded(
+    StatusCode::BAD_REQUEST,
+    ErrorCode::AttachmentRequired,
+    "File and audio messages require an attachment",
+);
+
+/// File and audio messages are their attachment; sent without one they have
+/// nothing to show. Images travel as text messages with attachments.
+fn validate_attachments_for_type(
+    message_type: &MessageType,
+    has_attachments: bool,
+) -> Result<(), AppError> {
+    if matches!(message_type, MessageType::File | MessageType::Audio) && !has_attachments {
+        return Err(ATTACHMENT_REQUIRED);
+    }
     Ok(())
 }
 
-const MAX_ATTACHMENTS_PER_MESSAGE: usize = 20;
+/// Limit text to `max_length` characters (not bytes, so CJK text is not
+/// penalised) and refuse messages with neither text nor attachments.
+pub(crate) fn validate_message_text(
+    text: Option<&str>,
+    has_attachments: bool,
+    max_length: usize,
+) -> Result<(), AppError> {
+    let text = text.unwrap_or_default();
+    if text.chars().count() > max_length {
+        return Err(AppError::BadRequest(MESSAGE_TOO_LONG));
+    }
+    if text.trim().is_empty() && !has_attachments {
+        return Err(AppError::BadRequest(MESSAGE_EMPTY));
+    }
+    Ok(())
+}
 
 fn validate_message_payload(
     conn: &mut PgConnection,
     uid: i32,
     body: &CreateMessageBody,
     attachment_ids: &[i64],
+    max_length: usize,
 ) -> Result<(), AppError> {
     if attachment_ids.len() > MAX_ATTACHMENTS_PER_MESSAGE {
         return Err(AppError::BadRequest(
@@ -145,26 +308,150 @@ fn validate_message_payload(
         return Err(AppError::BadRequest(
             "Sticker ID is only valid for sticker messages",
         ));
+    } else {
+        validate_attachments_for_type(&body.message_type, !attachment_ids.is_empty())?;
+        validate_message_text(
+            body.message.as_deref(),
+            !attachment_ids.is_empty(),
+            max_length,
+        )?;
+    }
+
+    Ok(())
+}
+
+const REPLY_TARGET_NOT_FOUND: &str = "Reply target not found in this chat";
+const REPLY_TARGET_DELETED: &str = "Cannot reply to a deleted message";
+const REPLY_TARGET_OTHER_THREAD: &str = "Reply target is in a different thread";
+
+/// A reply must point at a live message of the same chat and the same thread:
+/// the thread root or a reply inside it when `thread_id` is set, otherwise a
+/// main-timeline message. The new message's `reply_root_id` comes from the
+/// route, so this keeps the parent and child in agreement.
+fn check_reply_target(
+    parent: Option<&Message>,
+    chat_id: i64,
+    thread_id: Option<i64>,
+) -> Result<(), AppError> {
+    let parent = parent
+        .filter(|parent| parent.chat_id == chat_id && parent.is_published)
+        .ok_or(AppError::BadRequest(REPLY_TARGET_NOT_FOUND))?;
+    if parent.deleted_at.is_some() {
+        return Err(AppError::BadRequest(REPLY_TARGET_DELETED));
+    }
+    let same_thread = match thread_id {
+        Some(thread_id) => parent.id == thread_id || parent.reply_root_id == Some(thread_id),
+        None => parent.reply_root_id.is_none(),
+    };
+    if !same_thread {
+        return Err(AppError::BadRequest(REPLY_TARGET_OTHER_THREAD));
+    }
+    Ok(())
+}
+
+const THREAD_ROOT_NOT_FOUND: &str = "Thread root message not found";
+const THREAD_ROOT_IN_THREAD: &str = "Thread root must be a main-timeline message";
+const THREAD_ROOT_NOT_TEXT: &str = "Threads can only be created on text messages";
+
+/// A thread hangs off a live text message of the same chat that is not itself
+/// inside a thread, so `reply_root_id` can never chain or cross chats. A root
+/// in another chat reads as missing rather than revealing that it exists.
+fn check_thread_root(root: Option<Message>, chat_id: i64) -> Result<Message, AppError> {
+    let root = root
+        .filter(|root| root.chat_id == chat_id && root.is_published && root.deleted_at.is_none())
+        .ok_or(AppError::NotFound(THREAD_ROOT_NOT_FOUND))?;
+    if root.reply_root_id.is_some() {
+        return Err(AppError::BadRequest(THREAD_ROOT_IN_THREAD));
+    }
+    if root.message_type != MessageType::Text {
+        return Err(AppError::BadRequest(THREAD_ROOT_NOT_TEXT));
+    }
+    Ok(root)
+}
+
+fn validate_reply_target(
+    conn: &mut PgConnection,
+    chat_id: i64,
+    reply_to_id: Option<i64>,
+    thread_id: Option<i64>,
+) -> Result<(), AppError> {
+    let Some(reply_to_id) = reply_to_id else {
+        return Ok(());
+    };
+    let parent: Option<Message> = messages::table
+        .filter(messages::id.eq(reply_to_id))
+        .select(Message::as_select())
+        .first(conn)
+        .optional()?;
+    check_reply_target(parent.as_ref(), chat_id, thread_id)
+}
+
+/// Run outgoing text through the chat's keyword moderation policy.
+pub(crate) fn moderate_message_text(
+    conn: &mut PgConnection,
+    state: &AppState,
+    chat_id: i64,
+    message: Option<String>,
+) -> Result<Option<String>, AppError> {
+    let Some(text) = message else {
+        return Ok(None);
+    };
+    if state.keyword_filter.is_empty() {
+        return Ok(Some(text));
     }
 
+    let policy = groups::table
+        .filter(groups::id.eq(chat_id))
+        .select(groups::moderation_policy)
+        .first(conn)
+        .optional()?
+        .unwrap_or_default();
+
+    state.keyword_filter.apply(policy, text).map(Some)
+}
+
+const CONFLICTING_CURSORS: &str = "Only one of before, around and after may be set";
+
+/// The paging cursors select different windows, so at most one may be given.
+fn validate_cursor_params(q: &ListMessagesQuery) -> Result<(), AppError> {
+    let cursors = [q.before, q.around, q.after];
+    if cursors.iter().filter(|cursor| cursor.is_some()).count() > 1 {
+        return Err(AppError::BadRequest(CONFLICTING_CURSORS));
+    }
     Ok(())
 }
 
+/// Cursor for paging further into history: the smallest id on the page,
+/// independent of the order the rows were loaded in.
+fn oldest_id(messages: &[Message]) -> Option<i64> {
+    messages.iter().map(|m| m.id).min()
+}
+
+/// Cursor for paging towards newer messages: the largest id on the page.
+fn newest_id(messages: &[Message]) -> Option<i64> {
+    messages.iter().map(|m| m.id).max()
+}
+
 /// GET /chats/:chat_id/messages — List messages in a chat (cursor-based).
+///
+/// `before` pages into history and `after` fetches newer messages in
+/// ascending order; combining cursors is rejected with 400.
 #[utoipa::path(
     get,
     path = "/",
     tag = "chats",
     params(
         ("chat_id" = i64, Path, description = "Chat ID"),
-        ("before" = Option<String>, Query, description = "Cursor: fetch messages before this ID"),
+        ("before" = Option<String>, Query, description = "Cursor: fetch messages older than this nextCursor (raw message IDs are still accepted)"),
         ("around" = Option<String>, Query, description = "Cursor: fetch messages around this ID"),
-        ("after" = Option<String>, Query, description = "Cursor: fetch messages after this ID"),
+        ("after" = Option<String>, Query, description = "Cursor: fetch messages newer than this prevCursor (raw message IDs are still accepted)"),
         ("max" = Option<i64>, Query, description = "Max number of messages to return"),
         ("thread_id" = Option<String>, Query, description = "Thread root ID to filter by"),
+        ("expand" = Option<MessageExpand>, Query, description = "`root` also returns each reply's thread root as replyRootMessage"),
     ),
     responses(
         (status = 200, description = "List of messages", body = ListMessagesResponse),
+        (status = 400, description = "Invalid or more than one cursor given, or non-positive max"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
@@ -178,8 +465,12 @@ async fn get_messages(
     let conn = &mut *conn;
 
     check_membership(conn, chat_id, uid)?;
+    validate_cursor_params(&q)?;
 
-    let max = validate_limit(q.max, MAX_MESSAGES_LIMIT);
+    let max = require_positive_limit(q.max, state.page_limits.messages)?;
+    let metadata_options = MetadataOptions {
+        expand_reply_root: q.expand == Some(MessageExpand::Root),
+    };
 
     use crate::schema::messages::dsl;
 
@@ -206,20 +497,26 @@ async fn get_messages(
         let half = max / 2;
 
         // Messages with id >= target, ordered ASC (target first, then newer)
-        let newer_rows: Vec<Message> = base_query!()
-            .filter(dsl::id.ge(target))
-            .order(dsl::id.asc())
-            .limit(half + 2)
-            .select(Message::as_select())
-            .load(conn)?;
+        let newer_rows: Vec<Message> = traced("messages.around_newer", || {
+            base_query!()
+                .filter(dsl::id.ge(target))
+                .order(dsl::id.asc())
+                .then_order_by(dsl::created_at.asc())
+                .limit(half + 2)
+                .select(Message::as_select())
+                .load(conn)
+        })?;
 
         // Messages with id < target, ordered DESC (closest to target first)
-        let older_rows: Vec<Message> = base_query!()
-            .filter(dsl::id.lt(target))
-            .order(dsl::id.desc())
-            .limit(half + 1)
-            .select(Message::as_select())
-            .load(conn)?;
+        let older_rows: Vec<Message> = traced("messages.around_older", || {
+            base_query!()
+                .filter(dsl::id.lt(target))
+                .order(dsl::id.desc())
+                .then_order_by(dsl::created_at.desc())
+                .limit(half + 1)
+                .select(Message::as_select())
+                .load(conn)
+        })?;
 
         let has_older = older_rows.len() as i64 > half;
         let has_newer = newer_rows.len() as i64 > half + 1;
@@ -228,18 +525,18 @@ async fn get_messages(
         let newer_to_use: Vec<Message> = newer_rows.into_iter().take((half + 1) as usize).collect();
 
         // next_cursor = oldest id (for loading older), prev_cursor = newest id (for loading newer)
-        let next_cursor = has_older
-            .then(|| older_to_use.last().map(|m| m.id))
-            .flatten();
-        let prev_cursor = has_newer
-            .then(|| newer_to_use.last().map(|m| m.id))
-            .flatten();
+        let next_cursor = has_older.then(|| oldest_id(&older_to_use)).flatten();
+        let prev_cursor = has_newer.then(|| newest_id(&newer_to_use)).flatten();
 
         // Combine: older reversed (oldest first) + newer (target first, ascending)
         let mut combined: Vec<Message> = older_to_use.into_iter().rev().collect();
         combined.extend(newer_to_use);
 
-        let messages_vec = attach_metadata(conn, combined, &state, uid).await;
+        let messages_vec = traced_async(
+            "messages.metadata",
+            attach_metadata_with(conn, combined, &state, uid, metadata_options),
+        )
+        .await;
 
         return Ok(Json(ListMessagesResponse {
             messages: messages_vec,
@@ -250,20 +547,25 @@ async fn get_messages(
 
     // after=<id>: fetch messages newer than `after`, ascending order
     if let Some(after) = q.after {
-        let rows: Vec<Message> = base_query!()
-            .filter(dsl::id.gt(after))
-            .order(dsl::id.asc())
-            .limit(max + 1)
-            .select(Message::as_select())
-            .load(conn)?;
+        let rows: Vec<Message> = traced("messages.after", || {
+            base_query!()
+                .filter(dsl::id.gt(after))
+                .order(dsl::id.asc())
+                .then_order_by(dsl::created_at.asc())
+                .limit(max + 1)
+                .select(Message::as_select())
+                .load(conn)
+        })?;
 
         let has_more = rows.len() as i64 > max;
         let messages_to_process: Vec<Message> = rows.into_iter().take(max as usize).collect();
-        let prev_cursor = has_more
-            .then(|| messages_to_process.last().map(|m| m.id))
-            .flatten();
+        let prev_cursor = has_more.then(|| newest_id(&messages_to_process)).flatten();
 
-        let messages_vec = attach_metadata(conn, messages_to_process, &state, uid).await;
+        let messages_vec = traced_async(
+            "messages.metadata",
+            attach_metadata_with(conn, messages_to_process, &state, uid, metadata_options),
+        )
+        .await;
 
         return Ok(Json(ListMessagesResponse {
             messages: messages_vec,
@@ -273,30 +575,34 @@ async fn get_messages(
     }
 
     // Default: before cursor, descending (newest first in response, reversed by client)
-    let rows: Vec<Message> = match q.before {
+    let rows: Vec<Message> = traced("messages.before", || match q.before {
         None => base_query!()
             .order(dsl::id.desc())
+            .then_order_by(dsl::created_at.desc())
             .limit(max + 1)
             .select(Message::as_select())
             .load(conn),
         Some(before) => base_query!()
             .filter(dsl::id.lt(before))
             .order(dsl::id.desc())
+            .then_order_by(dsl::created_at.desc())
             .limit(max + 1)
             .select(Message::as_select())
             .load(conn),
-    }?;
+    })?;
 
     let has_more = rows.len() as i64 > max;
     let messages_to_process: Vec<Message> = rows.into_iter().take(max as usize).collect();
-    let next_cursor = has_more
-        .then(|| messages_to_process.last().map(|m| m.id))
-        .flatten();
+    let next_cursor = has_more.then(|| oldest_id(&messages_to_process)).flatten();
 
     // Reverse to return ASC (oldest first)
     let messages_to_process: Vec<Message> = messages_to_process.into_iter().rev().collect();
 
-    let messages_vec = attach_metadata(conn, messages_to_process, &state, uid).await;
+    let messages_vec = traced_async(
+        "messages.metadata",
+        attach_metadata_with(conn, messages_to_process, &state, uid, metadata_options),
+    )
+    .await;
 
     Ok(Json(ListMessagesResponse {
         messages: messages_vec,
@@ -305,21 +611,28 @@ async fn get_messages(
     }))
 }
 
-/// GET /chats/:chat_id/messages/:message_id — Get a single message.
+/// GET /chats/:chat_id/messages/:message_id/thread — A thread, oldest first.
+///
+/// The first page starts with the root, shown as a tombstone if deleted, and is
+/// followed by its live replies. Pass `prevCursor` back as `after` for the next
+/// page, as with the `after` cursor of the message list.
 #[utoipa::path(
     get,
-    path = "/{message_id}",
+    path = "/{message_id}/thread",
     tag = "chats",
     params(
         ("chat_id" = i64, Path, description = "Chat ID"),
-        ("message_id" = i64, Path, description = "Message ID"),
+        ("message_id" = i64, Path, description = "Thread root message ID"),
+        ("after" = Option<String>, Query, description = "Cursor: fetch thread messages after this ID"),
+        ("max" = Option<i64>, Query, description = "Max number of messages to return"),
     ),
     responses(
-        (status = 200, description = "Single message", body = MessageResponse),
+        (status = 200, description = "Thread messages", body = ListMessagesResponse),
+        (status = 404, description = "Chat or thread root not found"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
-async fn get_message(
+async fn get_thread_messages(
     CurrentUid(uid): CurrentUid,
     State(state): State<AppState>,
     Path(MessageIdPath {
@@ -327,149 +640,190 @@ async fn get_message(
         message_id,
     }): Path<MessageIdPath>,
     mut conn: DbConn,
-) -> Result<Json<MessageResponse>, AppError> {
+    Query(q): Query<ThreadViewQuery>,
+) -> Result<Json<ListMessagesResponse>, AppError> {
     let conn = &mut *conn;
 
     check_membership(conn, chat_id, uid)?;
 
     use crate::schema::messages::dsl;
-    let message: Message = messages::table
+    let root_exists = messages::table
         .filter(
             dsl::id
                 .eq(message_id)
                 .and(dsl::chat_id.eq(chat_id))
-                .and(dsl::deleted_at.is_null())
+                .and(dsl::reply_root_id.is_null())
                 .and(dsl::is_published.eq(true)),
         )
+        .count()
+        .get_result::<i64>(conn)?;
+    if root_exists == 0 {
+        return Err(AppError::NotFound("Thread root message not found"));
+    }
+
+    let max = validate_limit(q.max, state.page_limits.messages);
+    let rows: Vec<Message> = messages::table
+        .filter(dsl::chat_id.eq(chat_id))
+        .filter(dsl::is_published.eq(true))
+        .filter(
+            dsl::id.eq(message_id).or(dsl::reply_root_id
+                .eq(message_id)
+                .and(dsl::deleted_at.is_null())),
+        )
+        .filter(dsl::id.gt(q.after.unwrap_or(i64::MIN)))
+        .order(dsl::id.asc())
+        .limit(max + 1)
         .select(Message::as_select())
-        .first(conn)
-        .optional()?
-        .ok_or(AppError::NotFound("Message not found"))?;
+        .load(conn)?;
 
-    let messages_vec = attach_metadata(conn, vec![message], &state, uid).await;
-    let response = messages_vec.into_iter().next().unwrap();
+    let has_more = rows.len() as i64 > max;
+    let rows: Vec<Message> = rows.into_iter().take(max as usize).collect();
+    let prev_cursor = has_more.then(|| rows.last().map(|m| m.id)).flatten();
 
-    Ok(Json(response))
+    Ok(Json(ListMessagesResponse {
+        messages: attach_metadata(conn, rows, &state, uid).await,
+        next_cursor: None,
+        prev_cursor,
+    }))
 }
 
-/// POST /chats/:chat_id/messages — Send a message.
+/// Queries shorter than this skip full-text search, which drops short and
+/// stop words, and match as a plain substring instead.
+const MIN_FULL_TEXT_QUERY_CHARS: usize = 3;
+
+/// Whether `term` is matched as a substring rather than by full-text search:
+/// short queries, and any query with letters outside the Latin blocks. The
+/// `english` parser cannot split CJK text into words, so a Chinese query would
+/// only ever match a whole run of text. The scan stays bounded by the chat and
+/// the page limit.
+fn uses_substring_search(term: &str) -> bool {
+    term.chars().count() < MIN_FULL_TEXT_QUERY_CHARS
+        || term.chars().any(|ch| ch.is_alphabetic() && ch > '\u{024F}')
+}
+
+/// Escape `%`, `_` and `\` so user input matches literally inside `ILIKE`.
+fn escape_like_pattern(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for ch in input.chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// GET /chats/:chat_id/messages/search — Search message text in a chat.
+///
+/// Results are newest first; pass `nextCursor` back as `before` for older
+/// matches.
 #[utoipa::path(
-    post,
-    path = "/",
+    get,
+    path = "/search",
     tag = "chats",
     params(
         ("chat_id" = i64, Path, description = "Chat ID"),
+        ("q" = String, Query, description = "Search text"),
+        ("before" = Option<String>, Query, description = "Cursor: fetch matches before this ID"),
+        ("max" = Option<i64>, Query, description = "Max number of messages to return"),
     ),
-    request_body = CreateMessageBody,
     responses(
-        (status = 201, description = "Message created", body = MessageResponse),
+        (status = 200, description = "Matching messages", body = ListMessagesResponse),
+        (status = 400, description = "Empty search query"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
-async fn post_message(
+async fn search_messages(
     CurrentUid(uid): CurrentUid,
     State(state): State<AppState>,
     Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
     mut conn: DbConn,
-    Json(body): Json<CreateMessageBody>,
-) -> Result<impl IntoResponse, AppError> {
+    Query(q): Query<SearchMessagesQuery>,
+) -> Result<Json<ListMessagesResponse>, AppError> {
     let conn = &mut *conn;
 
     check_membership(conn, chat_id, uid)?;
-    validate_client_message_type(&body.message_type)?;
-    let attachment_ids: Vec<i64> = body
-        .attachment_ids
-        .iter()
-        .filter_map(|s| s.parse().ok())
-        .collect();
-    validate_message_payload(conn, uid, &body, &attachment_ids)?;
-
-    // Keep message creation and read-position advancement atomic.
-    diesel::sql_query("BEGIN").execute(conn)?;
 
-    let publish_immediately = !matches!(body.message_type, MessageType::Audio);
-    let tx_result: Result<_, AppError> = async {
-        let send_result = send_prepared_message(
-            conn,
-            &state,
-            PreparedMessageSend {
-                chat_id,
-                sender_uid: uid,
-                message: if matches!(body.message_type, MessageType::Sticker) {
-                    None
-                } else {
-                    body.message
-                },
-                message_type: body.message_type,
-                sticker_id: body.sticker_id,
-                reply_to_id: body.reply_to_id,
-                reply_root_id: None,
-                client_generated_id: body.client_generated_id,
-                attachment_ids,
-                update_group_last_message: true,
-                publish_immediately,
-            },
-        )
-        .await?;
+    let term = q.q.trim();
+    if term.is_empty() {
+        return Err(AppError::BadRequest("Search query must not be empty"));
+    }
 
-        crate::services::chat::mark_chat_as_read(conn, chat_id, uid, send_result.response.id)?;
+    let max = validate_limit(q.max, state.page_limits.messages);
 
-        Ok(send_result)
+    use crate::schema::messages::dsl;
+    let mut query = messages::table.into_boxed().filter(
+        dsl::chat_id
+            .eq(chat_id)
+            .and(dsl::deleted_at.is_null())
+            .and(dsl::is_published.eq(true)),
+    );
+    if uses_substring_search(term) {
+        query = query.filter(dsl::message.ilike(format!("%{}%", escape_like_pattern(term))));
+    } else {
+        query = query.filter(
+            diesel::dsl::sql::<diesel::sql_types::Bool>(
+                "to_tsvector('english', COALESCE(message, '')) @@ plainto_tsquery('english', ",
+            )
+            .bind::<diesel::sql_types::Text, _>(term.to_string())
+            .sql(")"),
+        );
+    }
+    if let Some(before) = q.before {
+        query = query.filter(dsl::id.lt(before));
     }
-    .await;
 
-    let send_result = match tx_result {
-        Ok(send_result) => {
-            diesel::sql_query("COMMIT").execute(conn)?;
-            send_result
-        }
-        Err(err) => {
-            let _ = diesel::sql_query("ROLLBACK").execute(conn);
-            return Err(err);
-        }
-    };
+    let rows: Vec<Message> = query
+        .order(dsl::id.desc())
+        .limit(max + 1)
+        .select(Message::as_select())
+        .load(conn)?;
 
-    send_result.side_effects.fire(&state);
-    if matches!(send_result.response.message_type, MessageType::Audio) {
-        crate::services::audio_transcode::enqueue_message(send_result.response.id);
-    }
+    let has_more = rows.len() as i64 > max;
+    let rows: Vec<Message> = rows.into_iter().take(max as usize).collect();
+    let next_cursor = has_more.then(|| rows.last().map(|m| m.id)).flatten();
 
-    Ok((StatusCode::CREATED, Json(send_result.response)))
+    let messages_vec = attach_metadata(conn, rows, &state, uid).await;
+
+    Ok(Json(ListMessagesResponse {
+        messages: messages_vec,
+        next_cursor,
+        prev_cursor: None,
+    }))
 }
 
-/// POST /chats/:chat_id/threads/:thread_id/messages — Send a message in a thread.
+/// GET /chats/:chat_id/messages/:message_id — Get a single message.
 #[utoipa::path(
-    post,
-    path = "/threads/{thread_id}/messages",
+    get,
+    path = "/{message_id}",
     tag = "chats",
     params(
         ("chat_id" = i64, Path, description = "Chat ID"),
-        ("thread_id" = i64, Path, description = "Thread root message ID"),
+        ("message_id" = i64, Path, description = "Message ID"),
     ),
-    request_body = CreateMessageBody,
     responses(
-        (status = 201, description = "Thread message created", body = MessageResponse),
+        (status = 200, description = "Single message", body = MessageResponse),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
-pub(super) async fn post_thread_message(
+async fn get_message(
     CurrentUid(uid): CurrentUid,
     State(state): State<AppState>,
-    Path(ThreadIdPath { chat_id, thread_id }): Path<ThreadIdPath>,
+    Path(MessageIdPath {
+        chat_id,
+        message_id,
+    }): Path<MessageIdPath>,
     mut conn: DbConn,
-    Json(body): Json<CreateMessageBody>,
-) -> Result<impl IntoResponse, AppError> {
+) -> Result<Json<MessageResponse>, AppError> {
     let conn = &mut *conn;
 
     check_membership(conn, chat_id, uid)?;
-    validate_client_message_type(&body.message_type)?;
 
-    // Load root message: validate existence and message type
     use crate::schema::messages::dsl;
-    let root_msg: Message = messages::table
+    let message: Message = messages::table
         .filter(
             dsl::id
-                .eq(thread_id)
+                .eq(message_id)
                 .and(dsl::chat_id.eq(chat_id))
                 .and(dsl::deleted_at.is_null())
                 .and(dsl::is_published.eq(true)),
@@ -477,45 +831,377 @@ pub(super) async fn post_thread_message(
         .select(Message::as_select())
         .first(conn)
         .optional()?
-        .ok_or(AppError::NotFound("Thread root message not found"))?;
+        .ok_or(AppError::MESSAGE_NOT_FOUND)?;
 
-    if root_msg.message_type != MessageType::Text {
-        return Err(AppError::BadRequest(
-            "Threads can only be created on text messages",
-        ));
+    let messages_vec = attach_metadata(conn, vec![message], &state, uid).await;
+    let response = messages_vec.into_iter().next().unwrap();
+
+    Ok(Json(response))
+}
+
+/// One earlier version of a message, replaced by the edit at `edited_at`.
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageEditResponse {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    id: i64,
+    previous_text: Option<String>,
+    edited_at: chrono::DateTime<Utc>,
+}
+
+impl From<MessageEdit> for MessageEditResponse {
+    fn from(edit: MessageEdit) -> Self {
+        Self {
+            id: edit.id,
+            previous_text: edit.previous_text,
+            edited_at: edit.edited_at,
+        }
     }
-    let attachment_ids: Vec<i64> = body
-        .attachment_ids
-        .iter()
-        .filter_map(|s| s.parse().ok())
-        .collect();
-    validate_message_payload(conn, uid, &body, &attachment_ids)?;
+}
 
-    // Begin transaction: message insert + thread_meta + subscriptions are atomic.
-    // send_prepared_message is async so we use raw BEGIN/COMMIT.
-    diesel::sql_query("BEGIN").execute(conn)?;
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageHistoryResponse {
+    edits: Vec<MessageEditResponse>,
+}
 
-    let publish_immediately = !matches!(body.message_type, MessageType::Audio);
-    let tx_result: Result<_, AppError> = async {
-        let send_result = send_prepared_message(
-            conn,
-            &state,
-            PreparedMessageSend {
-                chat_id,
-                sender_uid: uid,
-                message: if matches!(body.message_type, MessageType::Sticker) {
-                    None
-                } else {
-                    body.message
+/// GET /chats/:chat_id/messages/:message_id/history — List a message's earlier versions.
+///
+/// Edits are returned oldest first; the current body is the message itself.
+/// Deleted messages have no history, matching their tombstone.
+#[utoipa::path(
+    get,
+    path = "/{message_id}/history",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("message_id" = i64, Path, description = "Message ID"),
+    ),
+    responses(
+        (status = 200, description = "Edit history", body = MessageHistoryResponse),
+        (status = 404, description = "Chat or message not found"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn get_message_history(
+    CurrentUid(uid): CurrentUid,
+    Path(MessageIdPath {
+        chat_id,
+        message_id,
+    }): Path<MessageIdPath>,
+    mut conn: DbConn,
+) -> Result<Json<MessageHistoryResponse>, AppError> {
+    let conn = &mut *conn;
+
+    check_membership(conn, chat_id, uid)?;
+
+    use crate::schema::messages::dsl;
+    let visible = messages::table
+        .filter(
+            dsl::id
+                .eq(message_id)
+                .and(dsl::chat_id.eq(chat_id))
+                .and(dsl::deleted_at.is_null())
+                .and(dsl::is_published.eq(true)),
+        )
+        .count()
+        .get_result::<i64>(conn)?;
+    if visible == 0 {
+        return Err(AppError::MESSAGE_NOT_FOUND);
+    }
+
+    let edits: Vec<MessageEdit> = message_edits::table
+        .filter(message_edits::message_id.eq(message_id))
+        .order((message_edits::edited_at.asc(), message_edits::id.asc()))
+        .select(MessageEdit::as_select())
+        .load(conn)?;
+
+    Ok(Json(MessageHistoryResponse {
+        edits: edits.into_iter().map(MessageEditResponse::from).collect(),
+    }))
+}
+
+const CLIENT_GENERATED_ID_TAKEN: &str = "clientGeneratedId is already used in this chat";
+
+/// The message an earlier attempt of this send created, so a client retrying
+/// after a lost response gets it back instead of a duplicate. Another sender's
+/// message with the same id is a conflict, never a match.
+async fn load_retried_message(
+    conn: &mut PgConnection,
+    state: &AppState,
+    chat_id: i64,
+    uid: i32,
+    client_generated_id: &str,
+) -> Result<Option<MessageResponse>, AppError> {
+    use crate::schema::messages::dsl;
+
+    let existing: Option<Message> = messages::table
+        .filter(
+            dsl::chat_id
+                .eq(chat_id)
+                .and(dsl::client_generated_id.eq(client_generated_id)),
+        )
+        .select(Message::as_select())
+        .first(conn)
+        .optional()?;
+    let Some(existing) = existing else {
+        return Ok(None);
+    };
+    if existing.sender_uid != uid {
+        return Err(AppError::Conflict(CLIENT_GENERATED_ID_TAKEN));
+    }
+
+    Ok(attach_metadata(conn, vec![existing], state, uid)
+        .await
+        .into_iter()
+        .next())
+}
+
+const CLIENT_GENERATED_ID_INDEX: &str = "idx_messages_chat_client_generated_id";
+
+/// Whether the insert lost to an earlier send with the same
+/// `(chat_id, client_generated_id)`. Other unique violations in the
+/// transaction are real errors, not retries.
+fn is_client_generated_id_violation(err: &AppError) -> bool {
+    match err {
+        AppError::DbQuery(diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            info,
+        )) => info.constraint_name() == Some(CLIENT_GENERATED_ID_INDEX),
+        _ => false,
+    }
+}
+
+/// Request header naming the sender's sockets that should not get the new
+/// message back: `user` for all of them, or one socket's `connId`.
+pub const X_SKIP_ECHO: &str = "x-skip-echo";
+
+/// Absent header means everyone, the sender included, gets the broadcast.
+fn parse_skip_echo(headers: &HeaderMap, uid: i32) -> Result<Option<EchoExclusion>, AppError> {
+    let Some(value) = headers.get(X_SKIP_ECHO) else {
+        return Ok(None);
+    };
+    const INVALID: &str = "X-Skip-Echo must be `user` or a connection id";
+    let value = value.to_str().map_err(|_| AppError::BadRequest(INVALID))?;
+    if value.eq_ignore_ascii_case("user") {
+        return Ok(Some(EchoExclusion::User(uid)));
+    }
+    let conn_id = value.parse().map_err(|_| AppError::BadRequest(INVALID))?;
+    Ok(Some(EchoExclusion::Connection { uid, conn_id }))
+}
+
+/// POST /chats/:chat_id/messages — Send a message.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("X-Skip-Echo" = Option<String>, Header, description = "`user` or a `connId`: sockets that should not be sent the new message"),
+    ),
+    request_body = CreateMessageBody,
+    responses(
+        (status = 200, description = "Retry of an earlier send; the original message", body = MessageResponse),
+        (status = 201, description = "Message created", body = MessageResponse),
+        (status = 400, description = "Invalid X-Skip-Echo"),
+        (status = 409, description = "clientGeneratedId used by another sender"),
+        (status = 429, description = "Sending too fast; see Retry-After"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn post_message(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    headers: HeaderMap,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<CreateMessageBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let skip_echo = parse_skip_echo(&headers, uid)?;
+    let conn = &mut *conn;
+
+    check_membership(conn, chat_id, uid)?;
+    validate_client_message_type(&body.message_type)?;
+    if let Some(existing) =
+        load_retried_message(conn, &state, chat_id, uid, &body.client_generated_id).await?
+    {
+        return Ok((StatusCode::OK, Json(existing)));
+    }
+    // Only sends that can create a message spend a token: not a non-member's,
+    // and not a retry answered with the message it already created.
+    state.message_rate_limiter.check(uid)?;
+    enforce_slow_mode(conn, chat_id, uid)?;
+    let client_generated_id = body.client_generated_id.clone();
+    let attachment_ids: Vec<i64> = body
+        .attachment_ids
+        .iter()
+        .filter_map(|s| s.parse().ok())
+        .collect();
+    validate_message_payload(conn, uid, &body, &attachment_ids, state.max_message_length)?;
+    validate_reply_target(conn, chat_id, body.reply_to_id, None)?;
+    let message = if matches!(body.message_type, MessageType::Sticker) {
+        None
+    } else {
+        moderate_message_text(conn, &state, chat_id, body.message)?
+    };
+
+    // Keep message creation and read-position advancement atomic.
+    AnsiTransactionManager::begin_transaction(conn)?;
+
+    let publish_immediately = !matches!(body.message_type, MessageType::Audio);
+    let tx_result: Result<_, AppError> = async {
+        let response = traced_async(
+            "messages.insert",
+            insert_prepared_message(
+                conn,
+                &state,
+                PreparedMessageSend {
+                    chat_id,
+                    sender_uid: uid,
+                    message,
+                    message_type: body.message_type,
+                    sticker_id: body.sticker_id,
+                    reply_to_id: body.reply_to_id,
+                    reply_root_id: None,
+                    client_generated_id: client_generated_id.clone(),
+                    attachment_ids,
+                    update_group_last_message: true,
+                    publish_immediately,
+                    forwarded_from_message_id: None,
                 },
+            ),
+        )
+        .await?;
+
+        traced("membership.mark_read", || {
+            crate::services::chat::mark_chat_as_read(conn, chat_id, uid, response.id)
+        })?;
+
+        Ok(response)
+    }
+    .await;
+
+    let response = match tx_result {
+        Ok(response) => {
+            AnsiTransactionManager::commit_transaction(conn)?;
+            response
+        }
+        Err(err) => {
+            let _ = AnsiTransactionManager::rollback_transaction(conn);
+            // A concurrent retry won the insert; answer with its message.
+            if is_client_generated_id_violation(&err) {
+                return match load_retried_message(conn, &state, chat_id, uid, &client_generated_id)
+                    .await?
+                {
+                    Some(existing) => Ok((StatusCode::OK, Json(existing))),
+                    None => Err(AppError::Conflict(CLIENT_GENERATED_ID_TAKEN)),
+                };
+            }
+            return Err(err);
+        }
+    };
+
+    // The message is durable from here on; a failed broadcast must not turn the
+    // send into a 500 that the client would retry.
+    if publish_immediately {
+        let is_system_message = matches!(response.message_type, MessageType::System);
+        let side_effects = traced("messages.broadcast_recipients", || {
+            build_message_side_effects(conn, &response, &state, uid, chat_id, !is_system_message)
+        });
+        if let Some(side_effects) = side_effects_or_log(side_effects, response.id) {
+            side_effects.fire_except(&state, skip_echo);
+        }
+    }
+    if matches!(response.message_type, MessageType::Audio) {
+        crate::services::audio_transcode::enqueue_message(response.id);
+    }
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// POST /chats/:chat_id/threads/:thread_id/messages — Send a message in a thread.
+#[utoipa::path(
+    post,
+    path = "/threads/{thread_id}/messages",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("thread_id" = i64, Path, description = "Thread root message ID"),
+    ),
+    request_body = CreateMessageBody,
+    responses(
+        (status = 200, description = "Retry of an earlier send; the original message", body = MessageResponse),
+        (status = 201, description = "Thread message created", body = MessageResponse),
+        (status = 429, description = "Sending too fast; see Retry-After"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+pub(super) async fn post_thread_message(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ThreadIdPath { chat_id, thread_id }): Path<ThreadIdPath>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<CreateMessageBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = &mut *conn;
+
+    check_membership(conn, chat_id, uid)?;
+    validate_client_message_type(&body.message_type)?;
+    if let Some(existing) =
+        load_retried_message(conn, &state, chat_id, uid, &body.client_generated_id).await?
+    {
+        return Ok((StatusCode::OK, Json(existing)));
+    }
+    state.message_rate_limiter.check(uid)?;
+    enforce_slow_mode(conn, chat_id, uid)?;
+    let client_generated_id = body.client_generated_id.clone();
+
+    use crate::schema::messages::dsl;
+    let root_msg = check_thread_root(
+        messages::table
+            .filter(dsl::id.eq(thread_id))
+            .select(Message::as_select())
+            .first(conn)
+            .optional()?,
+        chat_id,
+    )?;
+    let attachment_ids: Vec<i64> = body
+        .attachment_ids
+        .iter()
+        .filter_map(|s| s.parse().ok())
+        .collect();
+    validate_message_payload(conn, uid, &body, &attachment_ids, state.max_message_length)?;
+    validate_reply_target(conn, chat_id, body.reply_to_id, Some(thread_id))?;
+    let message = if matches!(body.message_type, MessageType::Sticker) {
+        None
+    } else {
+        moderate_message_text(conn, &state, chat_id, body.message)?
+    };
+
+    // Begin transaction: message insert + thread_meta + subscriptions are atomic.
+    // send_prepared_message is async, so the transaction is opened and closed
+    // by hand rather than through the `conn.transaction` closure.
+    AnsiTransactionManager::begin_transaction(conn)?;
+
+    let publish_immediately = !matches!(body.message_type, MessageType::Audio);
+    let tx_result: Result<_, AppError> = async {
+        let send_result = send_prepared_message(
+            conn,
+            &state,
+            PreparedMessageSend {
+                chat_id,
+                sender_uid: uid,
+                message,
                 message_type: body.message_type,
                 sticker_id: body.sticker_id,
                 reply_to_id: body.reply_to_id,
                 reply_root_id: Some(thread_id),
-                client_generated_id: body.client_generated_id,
+                client_generated_id: client_generated_id.clone(),
                 attachment_ids,
                 update_group_last_message: false,
                 publish_immediately,
+                forwarded_from_message_id: None,
             },
         )
         .await?;
@@ -572,12 +1258,21 @@ pub(super) async fn post_thread_message(
 
     let (response, member_uids, msg_side_effects) = match tx_result {
         Ok(data) => {
-            diesel::sql_query("COMMIT").execute(conn)?;
+            AnsiTransactionManager::commit_transaction(conn)?;
             data
         }
-        Err(e) => {
-            let _ = diesel::sql_query("ROLLBACK").execute(conn);
-            return Err(e);
+        Err(err) => {
+            let _ = AnsiTransactionManager::rollback_transaction(conn);
+            // A concurrent retry won the insert; answer with its message.
+            if is_client_generated_id_violation(&err) {
+                return match load_retried_message(conn, &state, chat_id, uid, &client_generated_id)
+                    .await?
+                {
+                    Some(existing) => Ok((StatusCode::OK, Json(existing))),
+                    None => Err(AppError::Conflict(CLIENT_GENERATED_ID_TAKEN)),
+                };
+            }
+            return Err(err);
         }
     };
 
@@ -627,7 +1322,104 @@ pub(super) async fn post_thread_message(
     Ok((StatusCode::CREATED, Json(response)))
 }
 
+/// POST /chats/:chat_id/announce — Post a high-priority announcement (admin only).
+///
+/// Announcements are delivered with a `priority` hint and notify members who
+/// have muted the chat.
+#[utoipa::path(
+    post,
+    path = "/announce",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+    ),
+    request_body = CreateAnnouncementBody,
+    responses(
+        (status = 200, description = "Retry of an earlier send; the original message", body = MessageResponse),
+        (status = 201, description = "Announcement created", body = MessageResponse),
+        (status = 403, description = "Admin role required"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+pub(super) async fn post_announcement(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<CreateAnnouncementBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = &mut *conn;
+
+    require_admin_role(conn, chat_id, uid)?;
+    if body.message.trim().is_empty() {
+        return Err(AppError::BadRequest("Announcement cannot be empty"));
+    }
+    if let Some(existing) =
+        load_retried_message(conn, &state, chat_id, uid, &body.client_generated_id).await?
+    {
+        return Ok((StatusCode::OK, Json(existing)));
+    }
+    let client_generated_id = body.client_generated_id.clone();
+    let message = moderate_message_text(conn, &state, chat_id, Some(body.message))?;
+
+    AnsiTransactionManager::begin_transaction(conn)?;
+
+    let tx_result: Result<_, AppError> = async {
+        let send_result = send_prepared_message(
+            conn,
+            &state,
+            PreparedMessageSend {
+                chat_id,
+                sender_uid: uid,
+                message,
+                message_type: MessageType::Announcement,
+                sticker_id: None,
+                reply_to_id: None,
+                reply_root_id: None,
+                client_generated_id: client_generated_id.clone(),
+                attachment_ids: vec![],
+                update_group_last_message: true,
+                publish_immediately: true,
+                forwarded_from_message_id: None,
+            },
+        )
+        .await?;
+
+        crate::services::chat::mark_chat_as_read(conn, chat_id, uid, send_result.response.id)?;
+
+        Ok(send_result)
+    }
+    .await;
+
+    let send_result = match tx_result {
+        Ok(send_result) => {
+            AnsiTransactionManager::commit_transaction(conn)?;
+            send_result
+        }
+        Err(err) => {
+            let _ = AnsiTransactionManager::rollback_transaction(conn);
+            // A concurrent retry won the insert; answer with its message.
+            if is_client_generated_id_violation(&err) {
+                return match load_retried_message(conn, &state, chat_id, uid, &client_generated_id)
+                    .await?
+                {
+                    Some(existing) => Ok((StatusCode::OK, Json(existing))),
+                    None => Err(AppError::Conflict(CLIENT_GENERATED_ID_TAKEN)),
+                };
+            }
+            return Err(err);
+        }
+    };
+
+    send_result.side_effects.fire(&state);
+
+    Ok((StatusCode::CREATED, Json(send_result.response)))
+}
+
 /// PATCH /chats/:chat_id/messages/:message_id — Edit a message.
+///
+/// With `expectedEditCount`, the edit only applies if nobody else edited the
+/// message in between; otherwise 409, so the client can reload and merge.
 #[utoipa::path(
     patch,
     path = "/{message_id}",
@@ -639,6 +1431,7 @@ pub(super) async fn post_thread_message(
     request_body = UpdateMessageBody,
     responses(
         (status = 200, description = "Updated message", body = MessageResponse),
+        (status = 409, description = "expectedEditCount no longer matches"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
@@ -650,7 +1443,7 @@ async fn patch_message(
         message_id,
     }): Path<MessageIdPath>,
     mut conn: DbConn,
-    Json(body): Json<UpdateMessageBody>,
+    JsonBody(body): JsonBody<UpdateMessageBody>,
 ) -> Result<Json<MessageResponse>, AppError> {
     let conn = &mut *conn;
 
@@ -663,7 +1456,7 @@ async fn patch_message(
         .select(Message::as_select())
         .first(conn)
         .optional()?
-        .ok_or(AppError::NotFound("Message not found"))?;
+        .ok_or(AppError::MESSAGE_NOT_FOUND)?;
 
     if message.sender_uid != uid {
         return Err(AppError::Forbidden("You can only edit your own messages"));
@@ -676,9 +1469,11 @@ async fn patch_message(
         return Err(AppError::BadRequest("Cannot edit unpublished message"));
     }
 
-    if body.message.trim().is_empty() && body.attachment_ids.is_empty() {
-        return Err(AppError::BadRequest("Message cannot be empty"));
-    }
+    validate_message_text(
+        Some(&body.message),
+        !body.attachment_ids.is_empty(),
+        state.max_message_length,
+    )?;
 
     let attachment_ids: Vec<i64> = body
         .attachment_ids
@@ -692,27 +1487,60 @@ async fn patch_message(
         ));
     }
 
-    use crate::schema::attachments::dsl as a_dsl;
-    diesel::update(attachments::table.filter(a_dsl::message_id.eq(message_id)))
-        .set(a_dsl::message_id.eq::<Option<i64>>(None))
-        .execute(conn)?;
+    let text =
+        moderate_message_text(conn, &state, chat_id, Some(body.message))?.unwrap_or_default();
 
-    if !attachment_ids.is_empty() {
-        diesel::update(attachments::table.filter(a_dsl::id.eq_any(&attachment_ids)))
-            .set(a_dsl::message_id.eq(message_id))
-            .execute(conn)?;
-    }
+    let edit_id = ids::next_id(state.id_gen.as_ref()).await.map_err(|e| {
+        tracing::error!("next_id for message edit: {:?}", e);
+        AppError::Internal("ID generation failed")
+    })?;
 
-    // Update message
+    // Transaction: edit history + attachments + message body
     let now = Utc::now();
-    let updated_message: Message = diesel::update(messages::table.filter(dsl::id.eq(message_id)))
-        .set((
-            dsl::message.eq(&body.message),
-            dsl::has_attachments.eq(!attachment_ids.is_empty()),
-            dsl::updated_at.eq(Some(now)),
-        ))
-        .returning(Message::as_returning())
-        .get_result(conn)?;
+    let updated_message: Message = conn.transaction::<_, AppError, _>(|conn| {
+        // Lock the row so concurrent edits each record the body they replaced.
+        let previous_text: Option<String> = messages::table
+            .filter(dsl::id.eq(message_id))
+            .select(dsl::message)
+            .for_update()
+            .first(conn)?;
+        if body.expected_edit_count.is_some() {
+            let edit_count: i64 = message_edits::table
+                .filter(message_edits::message_id.eq(message_id))
+                .count()
+                .get_result(conn)?;
+            check_edit_version(body.expected_edit_count, edit_count)?;
+        }
+        diesel::insert_into(message_edits::table)
+            .values(&MessageEdit {
+                id: edit_id,
+                message_id,
+                previous_text,
+                edited_at: now,
+            })
+            .execute(conn)?;
+
+        use crate::schema::attachments::dsl as a_dsl;
+        diesel::update(attachments::table.filter(a_dsl::message_id.eq(message_id)))
+            .set(a_dsl::message_id.eq::<Option<i64>>(None))
+            .execute(conn)?;
+
+        if !attachment_ids.is_empty() {
+            diesel::update(attachments::table.filter(a_dsl::id.eq_any(&attachment_ids)))
+                .set(a_dsl::message_id.eq(message_id))
+                .execute(conn)?;
+        }
+
+        diesel::update(messages::table.filter(dsl::id.eq(message_id)))
+            .set((
+                dsl::message.eq(&text),
+                dsl::has_attachments.eq(!attachment_ids.is_empty()),
+                user_edit_stamp(now),
+            ))
+            .returning(Message::as_returning())
+            .get_result(conn)
+            .map_err(AppError::from)
+    })?;
 
     let response = attach_metadata(conn, vec![updated_message], &state, uid)
         .await
@@ -721,17 +1549,16 @@ async fn patch_message(
         .unwrap();
 
     // Broadcast update to all members
-    let member_uids: Vec<i32> = {
-        use crate::schema::group_membership as gm_dsl;
-        group_membership::table
-            .filter(gm_dsl::chat_id.eq(chat_id))
-            .select(group_membership::uid)
-            .load(conn)?
-    };
+    let member_uids = crate::services::chat::member_uids_or_log(conn, &state.ws_registry, chat_id);
     let ws_msg = std::sync::Arc::new(
         crate::handlers::ws::messages::ServerWsMessage::MessageUpdated(response.clone()),
     );
-    state.ws_registry.broadcast_to_uids(&member_uids, ws_msg);
+    state
+        .ws_registry
+        .broadcast_to_chat(chat_id, &member_uids, ws_msg);
+    state
+        .webhook_service
+        .enqueue(WebhookEvent::MessageUpdated, &response);
 
     Ok(Json(response))
 }
@@ -770,7 +1597,7 @@ async fn delete_message(
         .select(Message::as_select())
         .first(conn)
         .optional()?
-        .ok_or(AppError::NotFound("Message not found"))?;
+        .ok_or(AppError::MESSAGE_NOT_FOUND)?;
 
     if message.sender_uid != uid {
         // Not the sender — allow if requester is an admin
@@ -792,7 +1619,7 @@ async fn delete_message(
     let deleted_message: Message = conn.transaction::<_, diesel::result::Error, _>(|conn| {
         let deleted_message: Message =
             diesel::update(messages::table.filter(dsl::id.eq(message_id)))
-                .set(dsl::deleted_at.eq(Some(now)))
+                .set((dsl::deleted_at.eq(Some(now)), dsl::deleted_by.eq(Some(uid))))
                 .returning(Message::as_returning())
                 .get_result(conn)?;
 
@@ -823,17 +1650,16 @@ async fn delete_message(
         .unwrap();
 
     // Broadcast deletion to all members
-    let member_uids: Vec<i32> = {
-        use crate::schema::group_membership as gm_dsl;
-        group_membership::table
-            .filter(gm_dsl::chat_id.eq(chat_id))
-            .select(group_membership::uid)
-            .load(conn)?
-    };
+    let member_uids = crate::services::chat::member_uids_or_log(conn, &state.ws_registry, chat_id);
     let ws_msg = std::sync::Arc::new(
         crate::handlers::ws::messages::ServerWsMessage::MessageDeleted(response.clone()),
     );
-    state.ws_registry.broadcast_to_uids(&member_uids, ws_msg);
+    state
+        .ws_registry
+        .broadcast_to_chat(chat_id, &member_uids, ws_msg);
+    state
+        .webhook_service
+        .enqueue(WebhookEvent::MessageDeleted, &response);
 
     if let Some(reply_root_id) = response.reply_root_id {
         if let Err(err) = crate::services::threads::broadcast_thread_update_to_subscribers(
@@ -854,23 +1680,506 @@ async fn delete_message(
     Ok(StatusCode::NO_CONTENT)
 }
 
-pub fn router() -> OpenApiRouter<crate::AppState> {
-    OpenApiRouter::new()
-        .routes(utoipa_axum::routes!(get_messages, post_message))
-        .routes(utoipa_axum::routes!(
-            get_message,
-            patch_message,
-            delete_message
-        ))
-}
+/// Most messages one bulk delete may name.
+const MAX_BULK_DELETE_IDS: usize = 100;
 
-#[cfg(test)]
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkDeleteBody {
+    /// Message ids (string-encoded).
+    ids: Vec<String>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkDeleteResponse {
+    /// Ids that were deleted by this request (string-encoded).
+    deleted_ids: Vec<String>,
+}
+
+/// Parse and de-duplicate the requested ids, keeping their order.
+fn parse_bulk_delete_ids(ids: &[String]) -> Result<Vec<i64>, AppError> {
+    if ids.len() > MAX_BULK_DELETE_IDS {
+        return Err(AppError::BadRequest(
+            "Too many messages (maximum of 100 per request)",
+        ));
+    }
+    let mut parsed: Vec<i64> = Vec::with_capacity(ids.len());
+    for id in ids {
+        let id = id
+            .parse()
+            .map_err(|_| AppError::BadRequest("Invalid message id"))?;
+        if !parsed.contains(&id) {
+            parsed.push(id);
+        }
+    }
+    Ok(parsed)
+}
+
+fn bulk_deleted_event(
+    chat_id: i64,
+    message_ids: &[i64],
+) -> crate::handlers::ws::messages::ServerWsMessage {
+    crate::handlers::ws::messages::ServerWsMessage::MessagesBulkDeleted(
+        crate::handlers::ws::messages::BulkDeletedPayload {
+            chat_id: chat_id.to_string(),
+            message_ids: message_ids.iter().map(|id| id.to_string()).collect(),
+        },
+    )
+}
+
+/// POST /chats/:chat_id/messages/bulk_delete — Soft-delete several messages (admin only).
+///
+/// Ids outside the chat, unpublished or already deleted are skipped; members
+/// get a single `messagesBulkDeleted` event for the rest.
+#[utoipa::path(
+    post,
+    path = "/bulk_delete",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+    ),
+    request_body = BulkDeleteBody,
+    responses(
+        (status = 200, description = "Messages deleted", body = BulkDeleteResponse),
+        (status = 400, description = "Invalid id or more than 100 ids"),
+        (status = 403, description = "Admin role required"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn post_bulk_delete(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<BulkDeleteBody>,
+) -> Result<Json<BulkDeleteResponse>, AppError> {
+    let conn = &mut *conn;
+
+    require_admin_role(conn, chat_id, uid)?;
+    let ids = parse_bulk_delete_ids(&body.ids)?;
+
+    use crate::schema::messages::dsl;
+    let now = Utc::now();
+    // Transaction: soft-delete + thread_meta + group last_message
+    let (deleted_ids, thread_root_ids) = conn.transaction::<_, AppError, _>(|conn| {
+        let deleted: Vec<(i64, Option<i64>)> = diesel::update(
+            messages::table
+                .filter(dsl::chat_id.eq(chat_id))
+                .filter(dsl::id.eq_any(&ids))
+                .filter(dsl::deleted_at.is_null())
+                .filter(dsl::is_published.eq(true)),
+        )
+        .set((dsl::deleted_at.eq(Some(now)), dsl::deleted_by.eq(Some(uid))))
+        .returning((dsl::id, dsl::reply_root_id))
+        .get_results(conn)?;
+
+        let mut deleted_ids: Vec<i64> = deleted.iter().map(|(id, _)| *id).collect();
+        deleted_ids.sort_unstable();
+        let thread_root_ids: std::collections::BTreeSet<i64> =
+            deleted.iter().filter_map(|(_, root)| *root).collect();
+        if deleted_ids.is_empty() {
+            return Ok((deleted_ids, thread_root_ids));
+        }
+
+        for thread_root_id in &thread_root_ids {
+            crate::services::threads::recalculate_thread_meta(conn, chat_id, *thread_root_id)?;
+        }
+        super::recalculate_group_last_message(conn, chat_id)?;
+
+        Ok((deleted_ids, thread_root_ids))
+    })?;
+
+    if deleted_ids.is_empty() {
+        return Ok(Json(BulkDeleteResponse {
+            deleted_ids: Vec::new(),
+        }));
+    }
+
+    let member_uids = crate::services::chat::member_uids_or_log(conn, &state.ws_registry, chat_id);
+    state.ws_registry.broadcast_to_chat(
+        chat_id,
+        &member_uids,
+        std::sync::Arc::new(bulk_deleted_event(chat_id, &deleted_ids)),
+    );
+
+    for reply_root_id in thread_root_ids {
+        if let Err(err) = crate::services::threads::broadcast_thread_update_to_subscribers(
+            conn,
+            &state.ws_registry,
+            chat_id,
+            reply_root_id,
+        ) {
+            tracing::warn!(
+                chat_id,
+                reply_root_id,
+                ?err,
+                "failed to broadcast thread update after bulk delete"
+            );
+        }
+    }
+
+    Ok(Json(BulkDeleteResponse {
+        deleted_ids: deleted_ids.iter().map(|id| id.to_string()).collect(),
+    }))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardMessageBody {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    target_chat_id: i64,
+}
+
+/// Only live, user-authored messages can be forwarded.
+fn check_forward_source(message: &Message) -> Result<(), AppError> {
+    if !message.is_published {
+        return Err(AppError::MESSAGE_NOT_FOUND);
+    }
+    if message.deleted_at.is_some() {
+        return Err(AppError::Gone("Message was deleted"));
+    }
+    validate_client_message_type(&message.message_type)
+}
+
+/// A copy of `source` for the forwarded message. Attachments belong to one
+/// message, so the copy gets its own row pointing at the same stored object.
+fn forwarded_attachment(
+    source: &crate::models::Attachment,
+    id: i64,
+    now: DateTime<Utc>,
+) -> crate::models::NewAttachment {
+    crate::models::NewAttachment {
+        id,
+        message_id: None,
+        file_name: source.file_name.clone(),
+        kind: source.kind.clone(),
+        external_reference: source.external_reference.clone(),
+        size: source.size,
+        created_at: now,
+        deleted_at: None,
+        width: source.width,
+        height: source.height,
+        order: source.order,
+        forwarded_from_attachment_id: Some(source.id),
+    }
+}
+
+/// POST /chats/:chat_id/messages/:message_id/forward — Copy a message into another chat.
+///
+/// The copy keeps the text, sticker and attachments, is sent by the caller and
+/// records the original in `forwardedFromMessageId`.
+#[utoipa::path(
+    post,
+    path = "/{message_id}/forward",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("message_id" = i64, Path, description = "Message ID"),
+    ),
+    request_body = ForwardMessageBody,
+    responses(
+        (status = 201, description = "Forwarded message created in the target chat", body = MessageResponse),
+        (status = 403, description = "Not a member of the source or target chat"),
+        (status = 404, description = "Message not found"),
+        (status = 410, description = "Message was deleted"),
+        (status = 429, description = "Sending too fast; see Retry-After"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn forward_message(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(MessageIdPath {
+        chat_id,
+        message_id,
+    }): Path<MessageIdPath>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<ForwardMessageBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let conn = &mut *conn;
+    let target_chat_id = body.target_chat_id;
+
+    check_membership(conn, chat_id, uid)?;
+    check_membership(conn, target_chat_id, uid)?;
+    state.message_rate_limiter.check(uid)?;
+    enforce_slow_mode(conn, target_chat_id, uid)?;
+
+    use crate::schema::messages::dsl;
+    let source: Message = messages::table
+        .filter(dsl::id.eq(message_id).and(dsl::chat_id.eq(chat_id)))
+        .select(Message::as_select())
+        .first(conn)
+        .optional()?
+        .ok_or(AppError::MESSAGE_NOT_FOUND)?;
+    check_forward_source(&source)?;
+
+    let source_attachments: Vec<crate::models::Attachment> = {
+        use crate::schema::attachments::dsl as a_dsl;
+        attachments::table
+            .filter(a_dsl::message_id.eq(message_id))
+            .filter(a_dsl::deleted_at.is_null())
+            .order(a_dsl::order.asc())
+            .select(crate::models::Attachment::as_select())
+            .load(conn)?
+    };
+    let now = Utc::now();
+    let copy_ids = ids::next_message_ids(state.id_gen.as_ref(), source_attachments.len())
+        .await
+        .map_err(|e| {
+            tracing::error!("next_message_ids for forwarded attachments: {:?}", e);
+            AppError::Internal("ID generation failed")
+        })?;
+    let copied_attachments: Vec<_> = source_attachments
+        .iter()
+        .zip(copy_ids)
+        .map(|(attachment, id)| forwarded_attachment(attachment, id, now))
+        .collect();
+    let attachment_ids: Vec<i64> = copied_attachments.iter().map(|a| a.id).collect();
+
+    // The target chat's keyword filter applies to the copy.
+    let message = moderate_message_text(conn, &state, target_chat_id, source.message)?;
+
+    AnsiTransactionManager::begin_transaction(conn)?;
+
+    let tx_result: Result<_, AppError> = async {
+        if !copied_attachments.is_empty() {
+            diesel::insert_into(attachments::table)
+                .values(&copied_attachments)
+                .execute(conn)?;
+        }
+
+        let send_result = send_prepared_message(
+            conn,
+            &state,
+            PreparedMessageSend {
+                chat_id: target_chat_id,
+                sender_uid: uid,
+                message,
+                message_type: source.message_type,
+                sticker_id: source.sticker_id,
+                reply_to_id: None,
+                reply_root_id: None,
+                client_generated_id: uuid::Uuid::new_v4().to_string(),
+                attachment_ids,
+                update_group_last_message: true,
+                publish_immediately: true,
+                forwarded_from_message_id: Some(message_id),
+            },
+        )
+        .await?;
+
+        crate::services::chat::mark_chat_as_read(
+            conn,
+            target_chat_id,
+            uid,
+            send_result.response.id,
+        )?;
+
+        Ok(send_result)
+    }
+    .await;
+
+    let send_result = match tx_result {
+        Ok(send_result) => {
+            AnsiTransactionManager::commit_transaction(conn)?;
+            send_result
+        }
+        Err(err) => {
+            let _ = AnsiTransactionManager::rollback_transaction(conn);
+            return Err(err);
+        }
+    };
+
+    send_result.side_effects.fire(&state);
+
+    Ok((StatusCode::CREATED, Json(send_result.response)))
+}
+
+/// Why `uid` may not undo the deletion of `message` at `now`, if anything.
+///
+/// Only the sender can restore, and only a deletion they made themselves, so
+/// an admin removal stays removed. The window is inclusive of its last instant.
+fn check_restore_allowed(
+    message: &Message,
+    uid: i32,
+    now: DateTime<Utc>,
+    window: chrono::Duration,
+) -> Result<(), AppError> {
+    let Some(deleted_at) = message.deleted_at else {
+        return Err(AppError::Conflict("Message is not deleted"));
+    };
+    if message.sender_uid != uid {
+        return Err(AppError::Forbidden(
+            "You can only restore your own messages",
+        ));
+    }
+    if message.deleted_by != Some(uid) {
+        return Err(AppError::Forbidden(
+            "Message was removed by someone else and cannot be restored",
+        ));
+    }
+    if now - deleted_at > window {
+        return Err(AppError::Gone("Restore window has elapsed"));
+    }
+    Ok(())
+}
+
+/// POST /chats/:chat_id/messages/:message_id/restore — Undo deleting your own message.
+#[utoipa::path(
+    post,
+    path = "/{message_id}/restore",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("message_id" = i64, Path, description = "Message ID"),
+    ),
+    responses(
+        (status = 200, description = "Message restored", body = MessageResponse),
+        (status = 403, description = "Not the sender, or deleted by someone else"),
+        (status = 404, description = "Message not found"),
+        (status = 409, description = "Message is not deleted"),
+        (status = 410, description = "Restore window has elapsed"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn restore_message(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(MessageIdPath {
+        chat_id,
+        message_id,
+    }): Path<MessageIdPath>,
+    mut conn: DbConn,
+) -> Result<Json<MessageResponse>, AppError> {
+    let conn = &mut *conn;
+
+    check_membership(conn, chat_id, uid)?;
+
+    use crate::schema::messages::dsl;
+    let now = Utc::now();
+    let window = state.message_restore_window;
+    // Transaction: checks on the locked row + restore + thread_meta + group last_message
+    let restored_message: Message = conn.transaction::<_, AppError, _>(|conn| {
+        let message: Message = messages::table
+            .filter(dsl::id.eq(message_id).and(dsl::chat_id.eq(chat_id)))
+            .select(Message::as_select())
+            .for_update()
+            .first(conn)
+            .optional()?
+            .ok_or(AppError::MESSAGE_NOT_FOUND)?;
+        check_restore_allowed(&message, uid, now, window)?;
+
+        let restored_message: Message =
+            diesel::update(messages::table.filter(dsl::id.eq(message_id)))
+                .set((
+                    dsl::deleted_at.eq(None::<DateTime<Utc>>),
+                    dsl::deleted_by.eq(None::<i32>),
+                ))
+                .returning(Message::as_returning())
+                .get_result(conn)?;
+
+        match restored_message.reply_root_id {
+            Some(reply_root_id) => {
+                crate::services::threads::recalculate_thread_meta(conn, chat_id, reply_root_id)?
+            }
+            None => super::recalculate_group_last_message(conn, chat_id)?,
+        }
+
+        Ok(restored_message)
+    })?;
+
+    let response = attach_metadata(conn, vec![restored_message], &state, uid)
+        .await
+        .into_iter()
+        .next()
+        .unwrap();
+
+    let member_uids = crate::services::chat::member_uids_or_log(conn, &state.ws_registry, chat_id);
+    let ws_msg = std::sync::Arc::new(
+        crate::handlers::ws::messages::ServerWsMessage::MessageRestored(response.clone()),
+    );
+    state
+        .ws_registry
+        .broadcast_to_chat(chat_id, &member_uids, ws_msg);
+
+    if let Some(reply_root_id) = response.reply_root_id {
+        if let Err(err) = crate::services::threads::broadcast_thread_update_to_subscribers(
+            conn,
+            &state.ws_registry,
+            chat_id,
+            reply_root_id,
+        ) {
+            tracing::warn!(
+                chat_id,
+                reply_root_id,
+                ?err,
+                "failed to broadcast thread update after reply restore"
+            );
+        }
+    }
+
+    Ok(Json(response))
+}
+
+pub fn router() -> OpenApiRouter<crate::AppState> {
+    OpenApiRouter::new()
+        .routes(utoipa_axum::routes!(get_messages, post_message))
+        .routes(utoipa_axum::routes!(search_messages))
+        .routes(utoipa_axum::routes!(
+            get_message,
+            patch_message,
+            delete_message
+        ))
+        .routes(utoipa_axum::routes!(get_message_history))
+        .routes(utoipa_axum::routes!(get_thread_messages))
+        .routes(utoipa_axum::routes!(restore_message))
+        .routes(utoipa_axum::routes!(post_bulk_delete))
+        .routes(utoipa_axum::routes!(forward_message))
+}
+
+#[cfg(test)]
 mod tests {
+    use super::{bulk_deleted_event, parse_bulk_delete_ids, MAX_BULK_DELETE_IDS};
+    use super::{check_edit_version, newest_id, oldest_id, UpdateMessageBody, STALE_EDIT};
+    use super::{check_forward_source, forwarded_attachment, slow_mode_retry_after};
     use super::{
-        validate_client_message_type, INVITE_MESSAGE_TYPE_FORBIDDEN, SYSTEM_MESSAGE_TYPE_FORBIDDEN,
+        check_reply_target, check_restore_allowed, check_thread_root, escape_like_pattern,
+        uses_substring_search, validate_attachments_for_type, validate_client_message_type,
+        validate_cursor_params, validate_message_text, ListMessagesQuery, MessageExpand,
+        ANNOUNCEMENT_MESSAGE_TYPE_FORBIDDEN, CONFLICTING_CURSORS, INVITE_MESSAGE_TYPE_FORBIDDEN,
+        MESSAGE_EMPTY, MESSAGE_TOO_LONG, REPLY_TARGET_DELETED, REPLY_TARGET_NOT_FOUND,
+        REPLY_TARGET_OTHER_THREAD, SYSTEM_MESSAGE_TYPE_FORBIDDEN, THREAD_ROOT_IN_THREAD,
+        THREAD_ROOT_NOT_FOUND, THREAD_ROOT_NOT_TEXT,
     };
-    use crate::errors::AppError;
+    use super::{is_client_generated_id_violation, MessageEditResponse};
+    use super::{parse_skip_echo, X_SKIP_ECHO};
+    use crate::errors::{AppError, ErrorCode};
     use crate::models::MessageType;
+    use crate::services::ws_registry::EchoExclusion;
+    use axum::http::StatusCode;
+
+    #[test]
+    fn skip_echo_header_selects_the_senders_connections() {
+        let with = |value: &'static str| {
+            let mut headers = axum::http::HeaderMap::new();
+            headers.insert(X_SKIP_ECHO, axum::http::HeaderValue::from_static(value));
+            parse_skip_echo(&headers, 7)
+        };
+
+        assert_eq!(parse_skip_echo(&Default::default(), 7).unwrap(), None);
+        assert_eq!(with("user").unwrap(), Some(EchoExclusion::User(7)));
+        assert_eq!(
+            with("42").unwrap(),
+            Some(EchoExclusion::Connection {
+                uid: 7,
+                conn_id: 42
+            })
+        );
+        assert!(matches!(with("-1"), Err(AppError::BadRequest(_))));
+        assert!(matches!(with("everyone"), Err(AppError::BadRequest(_))));
+    }
 
     #[test]
     fn rejects_system_message_type_from_clients() {
@@ -887,10 +2196,1200 @@ mod tests {
         assert!(validate_client_message_type(&MessageType::Sticker).is_ok());
     }
 
+    #[test]
+    fn message_length_limit_counts_characters_at_the_boundary() {
+        let at_limit = "字".repeat(4000);
+        assert!(validate_message_text(Some(&at_limit), false, 4000).is_ok());
+
+        let over_limit = format!("{at_limit}a");
+        let err = validate_message_text(Some(&over_limit), true, 4000)
+            .expect_err("4001 characters should be rejected");
+        assert!(matches!(err, AppError::BadRequest(msg) if msg == MESSAGE_TOO_LONG));
+    }
+
+    #[test]
+    fn message_needs_text_or_attachments() {
+        for text in [None, Some(""), Some("  \n")] {
+            let err = validate_message_text(text, false, 4000).expect_err("empty message");
+            assert!(matches!(err, AppError::BadRequest(msg) if msg == MESSAGE_EMPTY));
+            assert!(validate_message_text(text, true, 4000).is_ok());
+        }
+    }
+
+    #[test]
+    fn file_and_audio_messages_need_an_attachment() {
+        for message_type in [MessageType::File, MessageType::Audio] {
+            let err = validate_attachments_for_type(&message_type, false)
+                .expect_err("attachment-less file or audio message");
+            assert_eq!(err.code(), ErrorCode::AttachmentRequired);
+            assert!(validate_attachments_for_type(&message_type, true).is_ok());
+        }
+        // Text may carry attachments or not; emptiness is checked separately.
+        assert!(validate_attachments_for_type(&MessageType::Text, false).is_ok());
+        assert!(validate_attachments_for_type(&MessageType::Text, true).is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn post_message_rejects_mismatched_types_and_payloads() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 900_301;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Typed messages").await;
+        app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+
+        let cases = [
+            ("file", "report attached", "attachment_required"),
+            ("audio", "", "attachment_required"),
+            ("text", "   ", "bad_request"),
+        ];
+        for (index, (message_type, message, expected)) in cases.into_iter().enumerate() {
+            let (status, body) = app
+                .request(
+                    axum::http::Method::POST,
+                    &format!("/chats/{chat_id}/messages"),
+                    uid,
+                    Some(serde_json::json!({
+                        "message": message,
+                        "messageType": message_type,
+                        "clientGeneratedId": format!("typed-{index}"),
+                    })),
+                )
+                .await;
+            assert_eq!(
+                status,
+                axum::http::StatusCode::BAD_REQUEST,
+                "{message_type}"
+            );
+            assert_eq!(body["error"]["code"], expected, "{message_type}");
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn malformed_cursor_is_a_json_400() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 900_302;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Cursor errors").await;
+        app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::GET,
+                &format!("/chats/{chat_id}/messages?before=not-a-cursor"),
+                uid,
+                None,
+            )
+            .await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "invalid_query");
+    }
+
+    fn message_in(chat_id: i64, id: i64, reply_root_id: Option<i64>) -> crate::models::Message {
+        crate::models::Message {
+            id,
+            message: Some("parent".to_string()),
+            message_type: MessageType::Text,
+            reply_to_id: None,
+            reply_root_id,
+            client_generated_id: format!("cgid-{id}"),
+            sender_uid: 1,
+            chat_id,
+            created_at: chrono::Utc::now(),
+            updated_at: None,
+            deleted_at: None,
+            has_attachments: false,
+            has_thread: false,
+            has_reactions: false,
+            sticker_id: None,
+            is_published: true,
+            transcode_status: crate::models::TranscodeStatus::None,
+            deleted_by: None,
+            forwarded_from_message_id: None,
+            edited_by_user: false,
+        }
+    }
+
+    #[test]
+    fn next_cursor_is_the_smallest_id_on_the_page() {
+        let page: Vec<crate::models::Message> =
+            [40, 10, 30, 20].map(|id| message_in(7, id, None)).to_vec();
+        assert_eq!(oldest_id(&page), Some(10));
+        assert_eq!(newest_id(&page), Some(40));
+        assert_eq!(oldest_id(&[]), None);
+    }
+
+    #[test]
+    fn before_page_cursor_matches_the_loaded_order() {
+        // Rows as the `before` query returns them: newest first, one extra row.
+        let rows: Vec<crate::models::Message> =
+            (1..=6).rev().map(|id| message_in(7, id, None)).collect();
+        let page: Vec<crate::models::Message> = rows.into_iter().take(5).collect();
+
+        assert_eq!(oldest_id(&page), page.last().map(|m| m.id));
+        assert_eq!(oldest_id(&page), Some(2));
+    }
+
+    fn rejection(result: Result<(), AppError>) -> &'static str {
+        match result {
+            Err(AppError::BadRequest(msg)) => msg,
+            other => panic!("expected 400, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn replies_must_target_a_live_message_in_the_same_chat() {
+        assert!(check_reply_target(Some(&message_in(10, 1, None)), 10, None).is_ok());
+        assert_eq!(
+            rejection(check_reply_target(None, 10, None)),
+            REPLY_TARGET_NOT_FOUND
+        );
+        assert_eq!(
+            rejection(check_reply_target(Some(&message_in(20, 1, None)), 10, None)),
+            REPLY_TARGET_NOT_FOUND
+        );
+
+        let mut unpublished = message_in(10, 1, None);
+        unpublished.is_published = false;
+        assert_eq!(
+            rejection(check_reply_target(Some(&unpublished), 10, None)),
+            REPLY_TARGET_NOT_FOUND
+        );
+
+        let mut deleted = message_in(10, 1, None);
+        deleted.deleted_at = Some(chrono::Utc::now());
+        assert_eq!(
+            rejection(check_reply_target(Some(&deleted), 10, None)),
+            REPLY_TARGET_DELETED
+        );
+    }
+
+    #[test]
+    fn thread_roots_are_live_main_timeline_text_in_the_same_chat() {
+        let check = |root: Option<crate::models::Message>| check_thread_root(root, 10).map(drop);
+        assert!(check(Some(message_in(10, 100, None))).is_ok());
+
+        // A forged root from another chat looks exactly like a missing one.
+        for root in [None, Some(message_in(20, 100, None))] {
+            assert!(matches!(
+                check(root),
+                Err(AppError::NotFound(THREAD_ROOT_NOT_FOUND))
+            ));
+        }
+        let mut deleted = message_in(10, 100, None);
+        deleted.deleted_at = Some(chrono::Utc::now());
+        assert!(matches!(check(Some(deleted)), Err(AppError::NotFound(_))));
+
+        assert_eq!(
+            rejection(check(Some(message_in(10, 101, Some(100))))),
+            THREAD_ROOT_IN_THREAD
+        );
+        let mut sticker = message_in(10, 100, None);
+        sticker.message_type = MessageType::Sticker;
+        assert_eq!(rejection(check(Some(sticker))), THREAD_ROOT_NOT_TEXT);
+    }
+
+    #[test]
+    fn replies_stay_within_their_thread() {
+        let root = message_in(10, 100, None);
+        let in_thread = message_in(10, 101, Some(100));
+        let deep_in_thread = message_in(10, 102, Some(100));
+        let other_thread = message_in(10, 201, Some(200));
+
+        assert!(check_reply_target(Some(&root), 10, Some(100)).is_ok());
+        assert!(check_reply_target(Some(&in_thread), 10, Some(100)).is_ok());
+        assert!(check_reply_target(Some(&deep_in_thread), 10, Some(100)).is_ok());
+        assert_eq!(
+            rejection(check_reply_target(Some(&other_thread), 10, Some(100))),
+            REPLY_TARGET_OTHER_THREAD
+        );
+        assert_eq!(
+            rejection(check_reply_target(Some(&in_thread), 10, None)),
+            REPLY_TARGET_OTHER_THREAD
+        );
+        assert_eq!(
+            rejection(check_reply_target(
+                Some(&message_in(10, 5, None)),
+                10,
+                Some(100)
+            )),
+            REPLY_TARGET_OTHER_THREAD
+        );
+    }
+
+    fn deleted_by_sender(deleted_at: chrono::DateTime<chrono::Utc>) -> crate::models::Message {
+        let mut message = message_in(10, 1, None);
+        message.deleted_at = Some(deleted_at);
+        message.deleted_by = Some(message.sender_uid);
+        message
+    }
+
+    #[test]
+    fn restore_is_allowed_up_to_the_end_of_the_window() {
+        let window = chrono::Duration::minutes(5);
+        let deleted_at = chrono::Utc::now();
+        let message = deleted_by_sender(deleted_at);
+
+        assert!(check_restore_allowed(&message, 1, deleted_at, window).is_ok());
+        assert!(check_restore_allowed(&message, 1, deleted_at + window, window).is_ok());
+        assert!(matches!(
+            check_restore_allowed(
+                &message,
+                1,
+                deleted_at + window + chrono::Duration::milliseconds(1),
+                window
+            ),
+            Err(AppError::Gone(_))
+        ));
+    }
+
+    #[test]
+    fn only_the_sender_can_undo_their_own_deletion() {
+        let window = chrono::Duration::minutes(5);
+        let now = chrono::Utc::now();
+
+        let message = deleted_by_sender(now);
+        assert!(matches!(
+            check_restore_allowed(&message, 2, now, window),
+            Err(AppError::Forbidden(_))
+        ));
+
+        let mut removed_by_admin = deleted_by_sender(now);
+        removed_by_admin.deleted_by = Some(2);
+        assert!(matches!(
+            check_restore_allowed(&removed_by_admin, 1, now, window),
+            Err(AppError::Forbidden(_))
+        ));
+
+        let mut bulk_deleted = deleted_by_sender(now);
+        bulk_deleted.deleted_by = None;
+        assert!(matches!(
+            check_restore_allowed(&bulk_deleted, 1, now, window),
+            Err(AppError::Forbidden(_))
+        ));
+
+        assert!(matches!(
+            check_restore_allowed(&message_in(10, 1, None), 1, now, window),
+            Err(AppError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn bulk_delete_is_limited_to_admins() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 900_601;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Bulk delete").await;
+        app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::POST,
+                &format!("/chats/{chat_id}/messages/bulk_delete"),
+                uid,
+                Some(serde_json::json!({ "ids": ["1"] })),
+            )
+            .await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN, "{body}");
+        assert_eq!(body["error"]["code"], "admin_required");
+    }
+
+    #[test]
+    fn bulk_delete_ids_are_capped_and_deduplicated() {
+        let ids: Vec<String> = ["3", "1", "3", "2"].map(String::from).to_vec();
+        assert_eq!(parse_bulk_delete_ids(&ids).unwrap(), vec![3, 1, 2]);
+
+        let at_cap: Vec<String> = (0..MAX_BULK_DELETE_IDS).map(|n| n.to_string()).collect();
+        assert_eq!(parse_bulk_delete_ids(&at_cap).unwrap().len(), 100);
+
+        let over_cap: Vec<String> = (0..=MAX_BULK_DELETE_IDS).map(|n| n.to_string()).collect();
+        assert!(matches!(
+            parse_bulk_delete_ids(&over_cap),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            parse_bulk_delete_ids(&["x".to_string()]),
+            Err(AppError::BadRequest("Invalid message id"))
+        ));
+    }
+
+    #[test]
+    fn bulk_delete_broadcasts_one_event_for_the_batch() {
+        let value = serde_json::to_value(bulk_deleted_event(7, &[9_007_199_254_740_993, 12]))
+            .expect("serialize bulk delete event");
+
+        assert_eq!(value["type"], "messagesBulkDeleted");
+        assert_eq!(value["payload"]["chatId"], "7");
+        assert_eq!(
+            value["payload"]["messageIds"],
+            serde_json::json!(["9007199254740993", "12"])
+        );
+    }
+
+    #[test]
+    fn only_live_user_messages_can_be_forwarded() {
+        assert!(check_forward_source(&message_in(10, 1, None)).is_ok());
+
+        let mut deleted = message_in(10, 1, None);
+        deleted.deleted_at = Some(chrono::Utc::now());
+        assert!(matches!(
+            check_forward_source(&deleted),
+            Err(AppError::Gone(_))
+        ));
+
+        let mut unpublished = message_in(10, 1, None);
+        unpublished.is_published = false;
+        assert_eq!(
+            check_forward_source(&unpublished).unwrap_err().code(),
+            ErrorCode::MessageNotFound
+        );
+
+        let mut system = message_in(10, 1, None);
+        system.message_type = MessageType::System;
+        assert!(matches!(
+            check_forward_source(&system),
+            Err(AppError::BadRequest(SYSTEM_MESSAGE_TYPE_FORBIDDEN))
+        ));
+    }
+
+    #[test]
+    fn forwarded_attachments_share_the_stored_object() {
+        let source = crate::models::Attachment {
+            id: 5,
+            message_id: Some(1),
+            file_name: "cat.png".to_string(),
+            kind: "image/png".to_string(),
+            external_reference: "attachments/abc/cat.png".to_string(),
+            size: 1024,
+            created_at: chrono::Utc::now(),
+            deleted_at: None,
+            width: Some(640),
+            height: Some(480),
+            order: 2,
+            forwarded_from_attachment_id: None,
+        };
+        let now = chrono::Utc::now();
+
+        let copy = forwarded_attachment(&source, 9, now);
+        assert_eq!(copy.id, 9);
+        assert_eq!(copy.message_id, None);
+        assert_eq!(copy.external_reference, source.external_reference);
+        assert_eq!(copy.forwarded_from_attachment_id, Some(5));
+        assert_eq!(
+            (copy.width, copy.height, copy.order),
+            (Some(640), Some(480), 2)
+        );
+        assert_eq!(copy.created_at, now);
+    }
+
+    #[test]
+    fn slow_mode_throttles_members_until_the_interval_passes() {
+        use crate::models::GroupRole;
+        let sent_at = chrono::Utc::now();
+        let member = Some(&GroupRole::Member);
+
+        assert_eq!(
+            slow_mode_retry_after(30, member, Some(sent_at), sent_at),
+            Some(30)
+        );
+        assert_eq!(
+            slow_mode_retry_after(
+                30,
+                member,
+                Some(sent_at),
+                sent_at + chrono::Duration::milliseconds(29_500)
+            ),
+            Some(1)
+        );
+        assert_eq!(
+            slow_mode_retry_after(
+                30,
+                member,
+                Some(sent_at),
+                sent_at + chrono::Duration::seconds(30)
+            ),
+            None
+        );
+        assert_eq!(slow_mode_retry_after(30, member, None, sent_at), None);
+        assert_eq!(
+            slow_mode_retry_after(0, member, Some(sent_at), sent_at),
+            None
+        );
+    }
+
+    #[test]
+    fn slow_mode_exempts_admins() {
+        let sent_at = chrono::Utc::now();
+        assert_eq!(
+            slow_mode_retry_after(
+                30,
+                Some(&crate::models::GroupRole::Admin),
+                Some(sent_at),
+                sent_at
+            ),
+            None
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn slow_mode_holds_back_members_but_not_admins() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, member) = (901_003, 901_004);
+        app.seed_user(admin);
+        app.seed_user(member);
+        let chat_id = app.seed_chat("Slow").await;
+        app.seed_membership(chat_id, admin, crate::models::GroupRole::Admin);
+        app.seed_membership(chat_id, member, crate::models::GroupRole::Member);
+        {
+            use diesel::prelude::*;
+            diesel::update(crate::schema::groups::table.find(chat_id))
+                .set(crate::schema::groups::slow_mode_secs.eq(60))
+                .execute(&mut app.conn())
+                .unwrap();
+        }
+        let app = &app;
+        let send = |uid: i32, client_generated_id: &'static str| async move {
+            app.request(
+                axum::http::Method::POST,
+                &format!("/chats/{chat_id}/messages"),
+                uid,
+                Some(serde_json::json!({
+                    "message": "hello",
+                    "messageType": "text",
+                    "clientGeneratedId": client_generated_id,
+                })),
+            )
+            .await
+        };
+
+        assert_eq!(send(member, "slow-1").await.0, StatusCode::CREATED);
+        let (status, body) = send(member, "slow-2").await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{body}");
+        assert_eq!(send(admin, "slow-3").await.0, StatusCode::CREATED);
+        assert_eq!(send(admin, "slow-4").await.0, StatusCode::CREATED);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn only_admins_announce_and_the_broadcast_is_high_priority() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, member) = (901_013, 901_014);
+        app.seed_user(admin);
+        app.seed_user(member);
+        let chat_id = app.seed_chat("Announcements").await;
+        app.seed_membership(chat_id, admin, crate::models::GroupRole::Admin);
+        app.seed_membership(chat_id, member, crate::models::GroupRole::Member);
+        let (_entry, mut rx, _) = app.state.ws_registry.register(member);
+        let app = &app;
+        let announce = |uid: i32, client_generated_id: &'static str| async move {
+            app.request(
+                axum::http::Method::POST,
+                &format!("/chats/{chat_id}/announce"),
+                uid,
+                Some(serde_json::json!({
+                    "message": "Maintenance at 10pm",
+                    "clientGeneratedId": client_generated_id,
+                })),
+            )
+            .await
+        };
+
+        let (status, body) = announce(member, "announce-1").await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+        assert_eq!(body["error"]["code"], "admin_required");
+
+        let (status, body) = announce(admin, "announce-2").await;
+        assert_eq!(status, StatusCode::CREATED, "{body}");
+        assert_eq!(body["messageType"], "announcement");
+        let frame = loop {
+            let frame: serde_json::Value =
+                serde_json::from_str(&rx.try_recv().expect("a queued frame")).unwrap();
+            if frame["type"] == "message" {
+                break frame;
+            }
+        };
+        assert_eq!(frame["payload"]["id"], body["id"]);
+        assert_eq!(frame["payload"]["priority"], "high");
+    }
+
+    #[test]
+    fn rejects_unknown_message_types_when_parsing_the_body() {
+        let body = |message_type: &str| {
+            serde_json::from_value::<super::CreateMessageBody>(serde_json::json!({
+                "message": "hi",
+                "messageType": message_type,
+                "clientGeneratedId": "cgid",
+            }))
+        };
+
+        assert!(body("text").is_ok());
+        assert!(body("image").is_err());
+        assert!(body("Text").is_err());
+        assert!(body("<script>").is_err());
+    }
+
     #[test]
     fn rejects_invite_message_type_from_generic_message_api() {
         let err = validate_client_message_type(&MessageType::Invite)
             .expect_err("invite should be rejected");
         assert!(matches!(err, AppError::BadRequest(msg) if msg == INVITE_MESSAGE_TYPE_FORBIDDEN));
     }
+
+    #[test]
+    fn rejects_announcement_message_type_from_generic_message_api() {
+        let err = validate_client_message_type(&MessageType::Announcement)
+            .expect_err("announcement should be rejected");
+        assert!(
+            matches!(err, AppError::BadRequest(msg) if msg == ANNOUNCEMENT_MESSAGE_TYPE_FORBIDDEN)
+        );
+    }
+
+    #[test]
+    fn escapes_like_wildcards_in_search_terms() {
+        assert_eq!(escape_like_pattern("50%_off"), "50\\%\\_off");
+        assert_eq!(escape_like_pattern("a\\b"), "a\\\\b");
+        assert_eq!(escape_like_pattern("plain"), "plain");
+    }
+
+    #[test]
+    fn short_and_non_latin_queries_match_as_substrings() {
+        assert!(uses_substring_search("ok"));
+        assert!(uses_substring_search("部署"));
+        assert!(uses_substring_search("今天的部署"));
+        assert!(uses_substring_search("deploy 部署"));
+        assert!(!uses_substring_search("deploy"));
+        assert!(!uses_substring_search("café crème"));
+        assert!(!uses_substring_search("v1.2.3"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn search_matches_words_and_short_or_cjk_substrings() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_021;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Search").await;
+        app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+        let texts = [
+            "deploy finished",
+            "lunch plans",
+            "今天的部署完成了",
+            "50% off",
+        ];
+        for (index, text) in texts.into_iter().enumerate() {
+            let (status, body) = app
+                .request(
+                    axum::http::Method::POST,
+                    &format!("/chats/{chat_id}/messages"),
+                    uid,
+                    Some(serde_json::json!({
+                        "message": text,
+                        "messageType": "text",
+                        "clientGeneratedId": format!("search-{index}"),
+                    })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{body}");
+        }
+        let app = &app;
+        let search = |q: &'static str| async move {
+            let (status, body) = app
+                .request(
+                    axum::http::Method::GET,
+                    &format!(
+                        "/chats/{chat_id}/messages/search?q={}",
+                        urlencoding::encode(q)
+                    ),
+                    uid,
+                    None,
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            body["messages"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|m| m["message"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(search("deployed").await, ["deploy finished"]);
+        assert_eq!(search("de").await, ["deploy finished"]);
+        assert_eq!(search("部署").await, ["今天的部署完成了"]);
+        assert_eq!(search("部署完成").await, ["今天的部署完成了"]);
+        assert_eq!(search("0%").await, ["50% off"]);
+        assert_eq!(search("%").await, ["50% off"], "wildcards match literally");
+
+        let (status, _) = app
+            .request(
+                axum::http::Method::GET,
+                &format!("/chats/{chat_id}/messages/search?q=%20"),
+                uid,
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    fn list_query(
+        before: Option<i64>,
+        around: Option<i64>,
+        after: Option<i64>,
+    ) -> ListMessagesQuery {
+        ListMessagesQuery {
+            before,
+            around,
+            after,
+            max: None,
+            thread_id: None,
+            expand: None,
+        }
+    }
+
+    #[test]
+    fn expand_accepts_only_known_previews() {
+        let parse = |uri: &str| {
+            axum::extract::Query::<ListMessagesQuery>::try_from_uri(&uri.parse().unwrap())
+                .map(|q| q.0.expand)
+        };
+        assert_eq!(parse("/?expand=root").unwrap(), Some(MessageExpand::Root));
+        assert_eq!(parse("/?max=10").unwrap(), None);
+        assert!(parse("/?expand=everything").is_err());
+    }
+
+    #[test]
+    fn list_cursors_accept_opaque_tokens_and_raw_ids() {
+        let parse = |uri: &str| {
+            axum::extract::Query::<ListMessagesQuery>::try_from_uri(&uri.parse().unwrap())
+                .map(|q| (q.0.before, q.0.after))
+        };
+        let token = crate::utils::pagination::encode_cursor(1234);
+        assert_eq!(
+            parse(&format!("/?before={token}")).unwrap(),
+            (Some(1234), None)
+        );
+        assert_eq!(parse("/?after=1234").unwrap(), (None, Some(1234)));
+
+        let rejection = parse("/?before=bogus!").unwrap_err();
+        assert_eq!(rejection.status(), axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn accepts_a_single_paging_cursor() {
+        assert!(validate_cursor_params(&list_query(None, None, None)).is_ok());
+        assert!(validate_cursor_params(&list_query(Some(10), None, None)).is_ok());
+        assert!(validate_cursor_params(&list_query(None, None, Some(10))).is_ok());
+    }
+
+    #[test]
+    fn rejects_combined_paging_cursors() {
+        let err = validate_cursor_params(&list_query(Some(20), None, Some(10)))
+            .expect_err("before and after together should be rejected");
+        assert!(matches!(err, AppError::BadRequest(msg) if msg == CONFLICTING_CURSORS));
+
+        assert!(validate_cursor_params(&list_query(None, Some(15), Some(10))).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn edit_and_delete_routes_take_string_ids_and_broadcast() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (author, watcher) = (900_603, 900_604);
+        app.seed_user(author);
+        app.seed_user(watcher);
+        let chat_id = app.seed_chat("Edits").await;
+        app.seed_membership(chat_id, author, crate::models::GroupRole::Member);
+        app.seed_membership(chat_id, watcher, crate::models::GroupRole::Member);
+        let (_entry, mut rx, _) = app.state.ws_registry.register(watcher);
+
+        let (status, sent) = app
+            .request(
+                axum::http::Method::POST,
+                &format!("/chats/{chat_id}/messages"),
+                author,
+                Some(serde_json::json!({
+                    "message": "first draft",
+                    "messageType": "text",
+                    "clientGeneratedId": "edit-route",
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{sent}");
+        let message_id = sent["id"].as_str().expect("ids are string-encoded");
+        let route = format!("/chats/{chat_id}/messages/{message_id}");
+        let mut next_event = |expected: &str| loop {
+            let frame: serde_json::Value =
+                serde_json::from_str(&rx.try_recv().expect("a queued frame")).unwrap();
+            if frame["type"] == expected {
+                return frame["payload"].clone();
+            }
+        };
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::PATCH,
+                &route,
+                author,
+                Some(serde_json::json!({ "message": "final draft" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let updated = next_event("messageUpdated");
+        assert_eq!(updated["id"], message_id);
+        assert_eq!(updated["message"], "final draft");
+
+        let (status, body) = app
+            .request(axum::http::Method::DELETE, &route, author, None)
+            .await;
+        assert!(status.is_success(), "{status}: {body}");
+        assert_eq!(next_event("messageDeleted")["id"], message_id);
+    }
+
+    #[test]
+    fn thread_view_and_history_routes_are_mounted() {
+        let (_router, api) = super::router().split_for_parts();
+        for path in ["/{message_id}/thread", "/{message_id}/history"] {
+            assert!(api.paths.paths.contains_key(path), "{path} missing");
+        }
+    }
+
+    #[test]
+    fn only_the_client_generated_id_index_counts_as_a_retry() {
+        struct Info(&'static str);
+        impl diesel::result::DatabaseErrorInformation for Info {
+            fn message(&self) -> &str {
+                "duplicate key value violates unique constraint"
+            }
+            fn details(&self) -> Option<&str> {
+                None
+            }
+            fn hint(&self) -> Option<&str> {
+                None
+            }
+            fn table_name(&self) -> Option<&str> {
+                Some("messages")
+            }
+            fn column_name(&self) -> Option<&str> {
+                None
+            }
+            fn constraint_name(&self) -> Option<&str> {
+                Some(self.0)
+            }
+            fn statement_position(&self) -> Option<i32> {
+                None
+            }
+        }
+
+        let violation = |constraint| {
+            AppError::DbQuery(diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                Box::new(Info(constraint)),
+            ))
+        };
+        assert!(is_client_generated_id_violation(&violation(
+            "idx_messages_chat_client_generated_id"
+        )));
+        assert!(!is_client_generated_id_violation(&violation(
+            "group_membership_pkey"
+        )));
+        assert!(!is_client_generated_id_violation(&AppError::DbQuery(
+            diesel::result::Error::NotFound
+        )));
+        assert!(!is_client_generated_id_violation(&AppError::Conflict(
+            "other"
+        )));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn client_generated_id_dedupes_within_a_chat_only() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_001;
+        app.seed_user(uid);
+        let first_chat = app.seed_chat("Retries").await;
+        let other_chat = app.seed_chat("Same id elsewhere").await;
+        app.seed_membership(first_chat, uid, crate::models::GroupRole::Member);
+        app.seed_membership(other_chat, uid, crate::models::GroupRole::Member);
+        let app = &app;
+        let send = |chat_id: i64| async move {
+            app.request(
+                axum::http::Method::POST,
+                &format!("/chats/{chat_id}/messages"),
+                uid,
+                Some(serde_json::json!({
+                    "message": "sent once",
+                    "messageType": "text",
+                    "clientGeneratedId": "retry-1",
+                })),
+            )
+            .await
+        };
+
+        let (status, first) = send(first_chat).await;
+        assert_eq!(status, StatusCode::CREATED, "{first}");
+        let (status, retried) = send(first_chat).await;
+        assert_eq!(status, StatusCode::OK, "{retried}");
+        assert_eq!(retried["id"], first["id"]);
+
+        use crate::schema::messages;
+        use diesel::prelude::*;
+        let rows: i64 = messages::table
+            .filter(messages::chat_id.eq(first_chat))
+            .count()
+            .get_result(&mut app.conn())
+            .unwrap();
+        assert_eq!(rows, 1);
+
+        let (status, elsewhere) = send(other_chat).await;
+        assert_eq!(status, StatusCode::CREATED, "{elsewhere}");
+        assert_ne!(elsewhere["id"], first["id"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn refused_sends_and_retries_do_not_spend_rate_limit_tokens() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_002;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Rate limited").await;
+        let app = &app;
+        let send = |client_generated_id: &'static str| async move {
+            app.request(
+                axum::http::Method::POST,
+                &format!("/chats/{chat_id}/messages"),
+                uid,
+                Some(serde_json::json!({
+                    "message": "hello",
+                    "messageType": "text",
+                    "clientGeneratedId": client_generated_id,
+                })),
+            )
+            .await
+            .0
+        };
+        // Well past the default burst of 10.
+        let attempts = 25;
+
+        for _ in 0..attempts {
+            assert_eq!(send("limited-0").await, StatusCode::FORBIDDEN);
+        }
+        app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+        assert_eq!(send("limited-0").await, StatusCode::CREATED);
+        for _ in 0..attempts {
+            assert_eq!(send("limited-0").await, StatusCode::OK);
+        }
+        assert_eq!(send("limited-1").await, StatusCode::CREATED);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn edit_and_delete_broadcasts_keep_the_reply_context() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (author, watcher) = (901_111, 901_112);
+        app.seed_user(author);
+        app.seed_user(watcher);
+        let chat_id = app.seed_chat("Replies").await;
+        app.seed_membership(chat_id, author, crate::models::GroupRole::Member);
+        app.seed_membership(chat_id, watcher, crate::models::GroupRole::Member);
+        let app = &app;
+        let send = |body: serde_json::Value| async move {
+            let (status, sent) = app
+                .request(
+                    axum::http::Method::POST,
+                    &format!("/chats/{chat_id}/messages"),
+                    author,
+                    Some(body),
+                )
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{sent}");
+            sent["id"].as_str().unwrap().to_string()
+        };
+        let root = send(serde_json::json!({
+            "message": "question",
+            "messageType": "text",
+            "clientGeneratedId": "reply-context-1",
+        }))
+        .await;
+        let reply = send(serde_json::json!({
+            "message": "answer",
+            "messageType": "text",
+            "clientGeneratedId": "reply-context-2",
+            "replyToId": root,
+        }))
+        .await;
+        let (_entry, mut rx, _) = app.state.ws_registry.register(watcher);
+        let mut next_frame = |kind: &str| loop {
+            let frame: serde_json::Value =
+                serde_json::from_str(&rx.try_recv().expect("a queued frame")).unwrap();
+            if frame["type"] == kind {
+                break frame;
+            }
+        };
+
+        let uri = format!("/chats/{chat_id}/messages/{reply}");
+        let (status, body) = app
+            .request(
+                axum::http::Method::PATCH,
+                &uri,
+                author,
+                Some(serde_json::json!({ "message": "better answer" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let updated = next_frame("messageUpdated");
+        assert_eq!(updated["payload"]["replyToMessage"]["id"], root.as_str());
+        assert_eq!(updated["payload"]["replyToMessage"]["message"], "question");
+
+        let (status, body) = app
+            .request(axum::http::Method::DELETE, &uri, author, None)
+            .await;
+        assert!(status.is_success(), "{status}: {body}");
+        let deleted = next_frame("messageDeleted");
+        assert_eq!(deleted["payload"]["replyToMessage"]["id"], root.as_str());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn expanding_roots_returns_parent_and_root_in_one_page() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_201;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Deep threads").await;
+        app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+        let app = &app;
+        let send = |uri: String, body: serde_json::Value| async move {
+            let (status, sent) = app
+                .request(axum::http::Method::POST, &uri, uid, Some(body))
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{sent}");
+            sent["id"].as_str().unwrap().to_string()
+        };
+        let root = send(
+            format!("/chats/{chat_id}/messages"),
+            serde_json::json!({
+                "message": "root",
+                "messageType": "text",
+                "clientGeneratedId": "deep-thread-1",
+            }),
+        )
+        .await;
+        let thread_uri = format!("/chats/{chat_id}/threads/{root}/messages");
+        let parent = send(
+            thread_uri.clone(),
+            serde_json::json!({
+                "message": "parent",
+                "messageType": "text",
+                "clientGeneratedId": "deep-thread-2",
+            }),
+        )
+        .await;
+        let child = send(
+            thread_uri,
+            serde_json::json!({
+                "message": "child",
+                "messageType": "text",
+                "clientGeneratedId": "deep-thread-3",
+                "replyToId": parent,
+            }),
+        )
+        .await;
+
+        let (status, page) = app
+            .request(
+                axum::http::Method::GET,
+                &format!("/chats/{chat_id}/messages?threadId={root}&expand=root"),
+                uid,
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{page}");
+        let listed = page["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|m| m["id"] == child.as_str())
+            .expect("the child is listed");
+        assert_eq!(listed["replyToMessage"]["id"], parent.as_str());
+        assert_eq!(listed["replyToMessage"]["message"], "parent");
+        assert_eq!(listed["replyRootMessage"]["id"], root.as_str());
+        assert_eq!(listed["replyRootMessage"]["message"], "root");
+
+        let (status, page) = app
+            .request(
+                axum::http::Method::GET,
+                &format!("/chats/{chat_id}/messages?threadId={root}"),
+                uid,
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{page}");
+        let listed = page["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|m| m["id"] == child.as_str())
+            .expect("the child is listed");
+        assert_eq!(listed["replyToMessage"]["id"], parent.as_str());
+        assert!(listed["replyRootMessage"].is_null(), "{listed}");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn a_send_is_created_even_when_its_broadcast_fails() {
+        use crate::schema::messages;
+        use diesel::prelude::*;
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_141;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Broadcast outage").await;
+        app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+        // Only the broadcast's member lookup reads muted_until. As text it
+        // still selects, so nothing aborts the test transaction, but it no
+        // longer decodes as a timestamp. Rolled back with the test
+        // transaction.
+        diesel::connection::SimpleConnection::batch_execute(
+            &mut *app.conn(),
+            &format!(
+                "ALTER TABLE group_membership ALTER COLUMN muted_until TYPE TEXT; \
+                 UPDATE group_membership SET muted_until = 'not a timestamp' \
+                 WHERE chat_id = {chat_id}"
+            ),
+        )
+        .unwrap();
+
+        let (_entry, mut rx, _) = app.state.ws_registry.register(uid);
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::POST,
+                &format!("/chats/{chat_id}/messages"),
+                uid,
+                Some(serde_json::json!({
+                    "message": "still delivered",
+                    "messageType": "text",
+                    "clientGeneratedId": "broadcast-outage",
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{body}");
+        let stored: i64 = messages::table
+            .filter(messages::chat_id.eq(chat_id))
+            .filter(messages::client_generated_id.eq("broadcast-outage"))
+            .count()
+            .get_result(&mut app.conn())
+            .unwrap();
+        assert_eq!(stored, 1);
+        let broadcast = std::iter::from_fn(|| rx.try_recv().ok()).find(|frame| {
+            serde_json::from_str::<serde_json::Value>(frame).unwrap()["type"] == "message"
+        });
+        assert_eq!(broadcast, None, "the broadcast should have been skipped");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn paging_after_the_newest_message_is_empty() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_101;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Catching up").await;
+        app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+        let app = &app;
+        let mut sent = Vec::new();
+        for client_generated_id in ["after-1", "after-2", "after-3"] {
+            let (status, body) = app
+                .request(
+                    axum::http::Method::POST,
+                    &format!("/chats/{chat_id}/messages"),
+                    uid,
+                    Some(serde_json::json!({
+                        "message": client_generated_id,
+                        "messageType": "text",
+                        "clientGeneratedId": client_generated_id,
+                    })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{body}");
+            sent.push(body["id"].as_str().unwrap().to_string());
+        }
+        let list = |query: String| async move {
+            app.request(
+                axum::http::Method::GET,
+                &format!("/chats/{chat_id}/messages?{query}"),
+                uid,
+                None,
+            )
+            .await
+        };
+
+        let (status, page) = list(format!("after={}&max=1", sent[0])).await;
+        assert_eq!(status, StatusCode::OK, "{page}");
+        assert_eq!(page["messages"][0]["id"], sent[1].as_str());
+        assert!(!page["prevCursor"].is_null(), "{page}");
+
+        let (status, page) = list(format!("after={}", sent[2])).await;
+        assert_eq!(status, StatusCode::OK, "{page}");
+        assert_eq!(page["messages"], serde_json::json!([]));
+        assert!(page["prevCursor"].is_null(), "{page}");
+        assert!(page["nextCursor"].is_null(), "{page}");
+
+        let (status, _) = list(format!("after={}&before={}", sent[0], sent[2])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn message_edit_serializes_with_string_id() {
+        let edited_at = chrono::DateTime::parse_from_rfc3339("2026-04-22T14:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let edit = MessageEditResponse::from(crate::models::MessageEdit {
+            id: 9_007_199_254_740_993,
+            message_id: 1,
+            previous_text: Some("before".to_string()),
+            edited_at,
+        });
+
+        let json = serde_json::to_value(&edit).unwrap();
+        assert_eq!(json["id"], "9007199254740993");
+        assert_eq!(json["previousText"], "before");
+        assert_eq!(json["editedAt"], "2026-04-22T14:00:00Z");
+    }
+
+    #[test]
+    fn stale_edit_is_rejected_with_conflict() {
+        // Both clients loaded the message at editCount 1; the first edit lands
+        // and bumps it to 2, so the second client's edit is stale.
+        assert!(check_edit_version(Some(1), 1).is_ok());
+        assert!(matches!(
+            check_edit_version(Some(1), 2),
+            Err(AppError::Conflict(STALE_EDIT))
+        ));
+    }
+
+    #[test]
+    fn edit_without_expected_version_is_last_write_wins() {
+        assert!(check_edit_version(None, 5).is_ok());
+
+        let body: UpdateMessageBody = serde_json::from_str(r#"{"message":"hi"}"#).unwrap();
+        assert_eq!(body.expected_edit_count, None);
+        let body: UpdateMessageBody =
+            serde_json::from_str(r#"{"message":"hi","expectedEditCount":3}"#).unwrap();
+        assert_eq!(body.expected_edit_count, Some(3));
+    }
 }
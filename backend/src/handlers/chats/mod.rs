@@ -1,19 +1,22 @@
+mod direct;
+mod export;
+mod message_attachments;
 mod messages;
+mod pseudonym;
 mod reactions;
 
-use axum::{
-    extract::{Path, Query, State},
-    Json,
-};
+use axum::{extract::State, Json};
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use diesel::PgConnection;
 use serde::Serialize;
 use utoipa_axum::router::OpenApiRouter;
 
+use crate::AppState;
 use crate::{
+    db_tracing::{traced, traced_async},
     errors::AppError,
-    extractors::DbConn,
+    extractors::{DbConn, JsonBody, Path, Query},
     handlers::members::check_membership,
     models::NewMessage,
     services::{
@@ -21,13 +24,16 @@ use crate::{
         media::build_public_object_url,
         push::{PushJob, PushMessagePreview, PushMessagePreviewSticker},
         user::{lookup_user_avatars, lookup_user_profiles, UserProfile},
+        webhooks::WebhookEvent,
+        ws_registry::{ConnectionRegistry, EchoExclusion},
     },
-    utils::{auth::CurrentUid, ids, pagination::validate_limit},
+    utils::{auth::CurrentUid, ids, pagination::require_positive_limit},
 };
 use crate::{
     models::{
         Attachment,
         AttachmentResponse,
+        ChatKind,
         Media,
         Message,
         MessageType,
@@ -43,12 +49,15 @@ use crate::{
         user_favorite_stickers, user_sticker_pack_subscriptions,
     },
 };
-use crate::{AppState, MAX_CHATS_LIMIT};
 
 // ---------------------------------------------------------------------------
 // Re-exports for external consumers (pins.rs, threads.rs, invites.rs, ws/messages.rs)
 // ---------------------------------------------------------------------------
+pub(crate) use self::direct::direct_chat_reaching;
+pub use self::message_attachments::router as message_attachments_router;
 pub use self::messages::router as messages_router;
+pub(crate) use self::messages::{moderate_message_text, validate_message_text};
+pub(crate) use self::pseudonym::Pseudonymizer;
 pub use self::reactions::router as reactions_router;
 
 // ---------------------------------------------------------------------------
@@ -125,21 +134,86 @@ pub struct MessageResponse {
     #[serde(with = "crate::serde_i64_string::opt")]
     #[schema(value_type = Option<String>)]
     pub reply_root_id: Option<i64>,
+    /// The message this one was forwarded from, if any.
+    #[serde(with = "crate::serde_i64_string::opt")]
+    #[schema(value_type = Option<String>)]
+    pub forwarded_from_message_id: Option<i64>,
     pub client_generated_id: String,
     pub sender: Sender,
     #[serde(with = "crate::serde_i64_string")]
     #[schema(value_type = String)]
     pub chat_id: i64,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
     pub is_edited: bool,
+    /// Number of earlier versions listed by the message history endpoint.
+    pub edit_count: i64,
     pub is_deleted: bool,
     pub has_attachments: bool,
     pub thread_info: Option<ThreadInfo>,
     pub reply_to_message: Option<Box<ReplyToMessage>>,
+    /// The thread root, filled only when the list was fetched with `expand=root`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_root_message: Option<Box<ReplyToMessage>>,
     pub attachments: Vec<AttachmentResponse>,
     pub reactions: Vec<ReactionSummary>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub mentions: Vec<MentionInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<MessagePriority>,
+    /// WebSocket-only hint that the recipient muted this chat, so clients
+    /// should deliver the message without notifying.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub muted: bool,
+}
+
+impl MessageResponse {
+    /// Reduce a deleted message to a tombstone: identity, sender and timestamps
+    /// stay so clients can render a placeholder, but no content survives.
+    fn strip_deleted_content(&mut self) {
+        if !self.is_deleted {
+            return;
+        }
+        self.message = None;
+        self.sticker = None;
+        self.has_attachments = false;
+        self.attachments.clear();
+        self.mentions.clear();
+    }
+}
+
+type UpdatedAtStamp = diesel::dsl::Eq<messages_schema::updated_at, Option<DateTime<Utc>>>;
+
+/// Columns stamped when the sender edits a message; clients show "(edited)".
+pub(super) fn user_edit_stamp(
+    now: DateTime<Utc>,
+) -> (
+    UpdatedAtStamp,
+    diesel::dsl::Eq<messages_schema::edited_by_user, bool>,
+) {
+    (
+        messages_schema::updated_at.eq(Some(now)),
+        messages_schema::edited_by_user.eq(true),
+    )
+}
+
+/// Columns stamped when the server modifies a message on its own, leaving
+/// `edited_by_user` as it was.
+pub(super) fn system_edit_stamp(now: DateTime<Utc>) -> UpdatedAtStamp {
+    messages_schema::updated_at.eq(Some(now))
+}
+
+/// Delivery hint for messages clients should surface above normal traffic.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MessagePriority {
+    High,
+}
+
+impl MessagePriority {
+    pub fn for_message_type(message_type: &MessageType) -> Option<Self> {
+        matches!(message_type, MessageType::Announcement).then_some(Self::High)
+    }
 }
 
 #[derive(Debug, Serialize, Clone, utoipa::ToSchema)]
@@ -181,6 +255,19 @@ pub struct ReplyToMessage {
     mentions: Vec<MentionInfo>,
 }
 
+impl ReplyToMessage {
+    /// Same as [`MessageResponse::strip_deleted_content`] for reply previews.
+    fn strip_deleted_content(&mut self) {
+        if !self.is_deleted {
+            return;
+        }
+        self.message = None;
+        self.sticker = None;
+        self.first_attachment_kind = None;
+        self.mentions.clear();
+    }
+}
+
 #[derive(Debug, Serialize, Clone, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct StickerMediaResponse {
@@ -203,6 +290,7 @@ pub struct MessageStickerResponse {
     pub emoji: String,
     pub name: Option<String>,
     pub description: Option<String>,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
     pub is_favorited: bool,
     pub media: StickerMediaResponse,
@@ -220,6 +308,7 @@ pub(crate) struct PreparedMessageSend {
     pub attachment_ids: Vec<i64>,
     pub update_group_last_message: bool,
     pub publish_immediately: bool,
+    pub forwarded_from_message_id: Option<i64>,
 }
 
 pub(crate) struct SendMessageResult {
@@ -230,21 +319,87 @@ pub(crate) struct SendMessageResult {
 
 #[must_use = "side effects must be fired via .fire()"]
 pub(crate) struct PendingSideEffects {
+    pub(crate) chat_id: i64,
     pub(crate) ws_msg: std::sync::Arc<crate::handlers::ws::messages::ServerWsMessage>,
     pub(crate) broadcast_uids: Vec<i32>,
+    /// Members who muted the chat; their copy of a `message` event is marked `muted`.
+    pub(crate) muted_uids: Vec<i32>,
+    /// Members mentioned in the message, other than the sender.
+    pub(crate) mentioned_uids: Vec<i32>,
+    pub(crate) mention_msg: Option<std::sync::Arc<crate::handlers::ws::messages::ServerWsMessage>>,
     pub(crate) push_job: Option<PushJob>,
 }
 
+/// Members the event goes out to.
+impl crate::db_tracing::RowCount for PendingSideEffects {
+    fn row_count(&self) -> usize {
+        self.broadcast_uids.len()
+    }
+}
+
+impl crate::db_tracing::RowCount for MessageResponse {
+    fn row_count(&self) -> usize {
+        1
+    }
+}
+
 impl PendingSideEffects {
-    /// Fire WS broadcast and push notification. Call after transaction commit.
+    /// Fire WS broadcast, push notification and webhooks. Call after transaction commit.
     pub fn fire(self, state: &AppState) {
-        state
-            .ws_registry
-            .broadcast_to_uids(&self.broadcast_uids, self.ws_msg);
-        if let Some(job) = self.push_job {
+        self.fire_except(state, None);
+    }
+
+    /// `fire`, leaving the connections `except` names out of the `message` broadcast.
+    pub fn fire_except(mut self, state: &AppState, except: Option<EchoExclusion>) {
+        use crate::handlers::ws::messages::ServerWsMessage;
+
+        // Unpublished sends (audio awaiting transcode) have no recipients yet;
+        // their webhook event goes out when they are published.
+        if let ServerWsMessage::Message(response) = self.ws_msg.as_ref() {
+            if !self.broadcast_uids.is_empty() {
+                state
+                    .webhook_service
+                    .enqueue(WebhookEvent::MessageCreated, response);
+            }
+        }
+
+        let push_job = self.push_job.take();
+        self.broadcast(&state.ws_registry, except);
+        if let Some(job) = push_job {
             state.push_service.enqueue(job);
         }
     }
+
+    /// Send the `message` event to the members and the `mention` event to
+    /// those mentioned. A muted member's `message` copy is marked `muted`, but
+    /// the `mention` frame goes out unmarked: being mentioned directly is worth
+    /// a notification even in a chat the member muted.
+    fn broadcast(self, registry: &ConnectionRegistry, except: Option<EchoExclusion>) {
+        use crate::handlers::ws::messages::ServerWsMessage;
+
+        let (muted, unmuted): (Vec<i32>, Vec<i32>) = self
+            .broadcast_uids
+            .iter()
+            .partition(|uid| self.muted_uids.contains(uid));
+        if !muted.is_empty() {
+            if let ServerWsMessage::Message(response) = self.ws_msg.as_ref() {
+                let hinted = MessageResponse {
+                    muted: true,
+                    ..response.clone()
+                };
+                registry.broadcast_to_chat_except(
+                    self.chat_id,
+                    &muted,
+                    except,
+                    std::sync::Arc::new(ServerWsMessage::Message(hinted)),
+                );
+            }
+        }
+        registry.broadcast_to_chat_except(self.chat_id, &unmuted, except, self.ws_msg);
+        if let Some(mention_msg) = self.mention_msg {
+            registry.broadcast_to_uids(&self.mentioned_uids, mention_msg);
+        }
+    }
 }
 
 #[derive(serde::Deserialize, utoipa::ToSchema)]
@@ -548,21 +703,36 @@ fn build_push_preview_bundle(response: &MessageResponse) -> PushPreviewBundle {
     }
 }
 
+/// Mentioned uids that should get a `mention` event: chat members only, and
+/// never the sender mentioning themselves.
+fn mentioned_member_uids(mentioned: &[i32], member_uids: &[i32], sender_uid: i32) -> Vec<i32> {
+    mentioned
+        .iter()
+        .copied()
+        .filter(|uid| *uid != sender_uid && member_uids.contains(uid))
+        .collect()
+}
+
+/// Members whose mute is still in effect at `now`.
+fn muted_member_uids(memberships: &[(i32, Option<DateTime<Utc>>)], now: DateTime<Utc>) -> Vec<i32> {
+    memberships
+        .iter()
+        .filter(|(_, muted_until)| muted_until.is_some_and(|until| until > now))
+        .map(|(uid, _)| *uid)
+        .collect()
+}
+
 pub(crate) fn build_message_side_effects(
     conn: &mut PgConnection,
     response: &MessageResponse,
-    _state: &AppState,
+    state: &AppState,
     sender_uid: i32,
     chat_id: i64,
     enqueue_push: bool,
 ) -> Result<PendingSideEffects, AppError> {
-    let member_uids: Vec<i32> = {
-        use crate::schema::group_membership as gm_dsl;
-        group_membership::table
-            .filter(gm_dsl::chat_id.eq(chat_id))
-            .select(group_membership::uid)
-            .load(conn)?
-    };
+    let memberships = crate::services::chat::chat_members(conn, &state.ws_registry, chat_id)?;
+    let member_uids: Vec<i32> = memberships.iter().map(|(uid, _)| *uid).collect();
+    let muted_uids = muted_member_uids(&memberships, Utc::now());
 
     let ws_msg = std::sync::Arc::new(crate::handlers::ws::messages::ServerWsMessage::Message(
         response.clone(),
@@ -593,9 +763,27 @@ pub(crate) fn build_message_side_effects(
         None
     };
 
+    let mentioned_uids = response
+        .message
+        .as_deref()
+        .map(|text| mentioned_member_uids(&extract_mention_uids(text), &member_uids, sender_uid))
+        .unwrap_or_default();
+    let mention_msg = (!mentioned_uids.is_empty()).then(|| {
+        std::sync::Arc::new(crate::handlers::ws::messages::ServerWsMessage::Mention(
+            crate::handlers::ws::messages::MentionPayload {
+                message_id: response.id,
+                chat_id,
+            },
+        ))
+    });
+
     Ok(PendingSideEffects {
+        chat_id,
         ws_msg,
         broadcast_uids: member_uids,
+        muted_uids,
+        mentioned_uids,
+        mention_msg,
         push_job,
     })
 }
@@ -609,6 +797,78 @@ pub(crate) async fn send_prepared_message(
     state: &AppState,
     prepared: PreparedMessageSend,
 ) -> Result<SendMessageResult, AppError> {
+    let chat_id = prepared.chat_id;
+    let sender_uid = prepared.sender_uid;
+    let publish_immediately = prepared.publish_immediately;
+    let response = insert_prepared_message(conn, state, prepared).await?;
+    let is_system_message = matches!(response.message_type, MessageType::System);
+
+    let (member_uids, side_effects) = if publish_immediately {
+        let side_effects = build_message_side_effects(
+            conn,
+            &response,
+            state,
+            sender_uid,
+            chat_id,
+            !is_system_message,
+        )?;
+        let member_uids = side_effects.broadcast_uids.clone();
+        (member_uids, side_effects)
+    } else {
+        (Vec::new(), unpublished_side_effects(&response))
+    };
+
+    Ok(SendMessageResult {
+        response,
+        member_uids,
+        side_effects,
+    })
+}
+
+/// Side effects for a message that is not visible yet: nothing to broadcast.
+pub(crate) fn unpublished_side_effects(response: &MessageResponse) -> PendingSideEffects {
+    PendingSideEffects {
+        chat_id: response.chat_id,
+        ws_msg: std::sync::Arc::new(crate::handlers::ws::messages::ServerWsMessage::Message(
+            response.clone(),
+        )),
+        broadcast_uids: Vec::new(),
+        muted_uids: Vec::new(),
+        mentioned_uids: Vec::new(),
+        mention_msg: None,
+        push_job: None,
+    }
+}
+
+/// Side effects for a message that is already committed. A failure here must
+/// not fail the send, so it is logged and the broadcast skipped; clients
+/// still pick the message up on their next fetch or catch-up.
+pub(crate) fn side_effects_or_log(
+    result: Result<PendingSideEffects, AppError>,
+    message_id: i64,
+) -> Option<PendingSideEffects> {
+    match result {
+        Ok(side_effects) => Some(side_effects),
+        Err(e) => {
+            tracing::warn!(
+                message_id,
+                error = ?e,
+                "Message saved but building its broadcast failed"
+            );
+            None
+        }
+    }
+}
+
+/// Insert the message and link its attachments, without building any
+/// broadcast. Callers that need the send to succeed even when the broadcast
+/// cannot be prepared use this inside their transaction and build the side
+/// effects after commit.
+pub(crate) async fn insert_prepared_message(
+    conn: &mut PgConnection,
+    state: &AppState,
+    prepared: PreparedMessageSend,
+) -> Result<MessageResponse, AppError> {
     let id = ids::next_message_id(state.id_gen.as_ref())
         .await
         .map_err(|e| {
@@ -618,7 +878,6 @@ pub(crate) async fn send_prepared_message(
 
     let now = Utc::now();
     let message_type = prepared.message_type.clone();
-    let is_system_message = matches!(message_type, MessageType::System);
     let transcode_status = if matches!(message_type, MessageType::Audio) {
         if prepared.publish_immediately {
             TranscodeStatus::Done
@@ -647,6 +906,7 @@ pub(crate) async fn send_prepared_message(
         has_reactions: false,
         is_published: prepared.publish_immediately,
         transcode_status,
+        forwarded_from_message_id: prepared.forwarded_from_message_id,
     };
 
     let inserted_msg: Message = diesel::insert_into(messages_schema::table)
@@ -672,47 +932,39 @@ pub(crate) async fn send_prepared_message(
             .execute(conn)?;
     }
 
-    let response = attach_metadata(conn, vec![inserted_msg], state, prepared.sender_uid)
+    attach_metadata(conn, vec![inserted_msg], state, prepared.sender_uid)
         .await
         .into_iter()
         .next()
-        .ok_or(AppError::Internal("Failed to build message response"))?;
-
-    let (member_uids, side_effects) = if prepared.publish_immediately {
-        let side_effects = build_message_side_effects(
-            conn,
-            &response,
-            state,
-            prepared.sender_uid,
-            prepared.chat_id,
-            !is_system_message,
-        )?;
-        let member_uids = side_effects.broadcast_uids.clone();
-        (member_uids, side_effects)
-    } else {
-        (
-            Vec::new(),
-            PendingSideEffects {
-                ws_msg: std::sync::Arc::new(
-                    crate::handlers::ws::messages::ServerWsMessage::Message(response.clone()),
-                ),
-                broadcast_uids: Vec::new(),
-                push_job: None,
-            },
-        )
-    };
-
-    Ok(SendMessageResult {
-        response,
-        member_uids,
-        side_effects,
-    })
+        .ok_or(AppError::Internal("Failed to build message response"))
 }
 
 // ---------------------------------------------------------------------------
 // attach_metadata (shared by messages, threads, pins)
 // ---------------------------------------------------------------------------
 
+/// Optional extras for [`attach_metadata_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct MetadataOptions {
+    /// Also resolve each reply's thread root into `reply_root_message`.
+    pub expand_reply_root: bool,
+}
+
+/// Ids of the messages referenced as previews: every `reply_to_id`, plus every
+/// `reply_root_id` when roots are expanded, so both load in one query.
+fn referenced_message_ids(messages: &[Message], options: MetadataOptions) -> Vec<i64> {
+    messages
+        .iter()
+        .flat_map(|m| {
+            let root = m.reply_root_id.filter(|_| options.expand_reply_root);
+            [m.reply_to_id, root]
+        })
+        .flatten()
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect()
+}
+
 /// Attach reply_to_message to a list of messages by fetching referenced messages in one query.
 pub async fn attach_metadata(
     conn: &mut PgConnection,
@@ -720,10 +972,25 @@ pub async fn attach_metadata(
     state: &AppState,
     current_user_uid: i32,
 ) -> Vec<MessageResponse> {
-    let reply_ids: Vec<i64> = messages_to_process
-        .iter()
-        .filter_map(|m| m.reply_to_id)
-        .collect();
+    attach_metadata_with(
+        conn,
+        messages_to_process,
+        state,
+        current_user_uid,
+        MetadataOptions::default(),
+    )
+    .await
+}
+
+/// [`attach_metadata`] with the extras selected in `options`.
+pub(crate) async fn attach_metadata_with(
+    conn: &mut PgConnection,
+    messages_to_process: Vec<Message>,
+    state: &AppState,
+    current_user_uid: i32,
+    options: MetadataOptions,
+) -> Vec<MessageResponse> {
+    let reply_ids = referenced_message_ids(&messages_to_process, options);
 
     let message_ids: Vec<i64> = messages_to_process.iter().map(|m| m.id).collect();
     let reply_messages_map = load_reply_messages(conn, &reply_ids).unwrap_or_default();
@@ -812,6 +1079,23 @@ pub async fn attach_metadata(
         }
     }
 
+    let mut edit_counts_map: std::collections::HashMap<i64, i64> = std::collections::HashMap::new();
+    let edited_message_ids: Vec<i64> = messages_to_process
+        .iter()
+        .filter(|m| m.updated_at.is_some() && m.deleted_at.is_none())
+        .map(|m| m.id)
+        .collect();
+    if !edited_message_ids.is_empty() {
+        use crate::schema::message_edits::dsl as e_dsl;
+        let counts: Vec<(i64, i64)> = crate::schema::message_edits::table
+            .filter(e_dsl::message_id.eq_any(&edited_message_ids))
+            .group_by(e_dsl::message_id)
+            .select((e_dsl::message_id, diesel::dsl::count_star()))
+            .load(conn)
+            .unwrap_or_default();
+        edit_counts_map.extend(counts);
+    }
+
     // --- Reactions ---
     let mut reaction_summaries_map: std::collections::HashMap<i64, Vec<ReactionSummary>> =
         std::collections::HashMap::new();
@@ -938,63 +1222,64 @@ pub async fn attach_metadata(
         user_avatars.extend(lookup_user_avatars(state, &extra_mention_uids));
     }
 
-    let mut responses = Vec::with_capacity(messages_to_process.len());
-    for (idx, m) in messages_to_process.into_iter().enumerate() {
-        let reply_to_message = m.reply_to_id.and_then(|reply_id| {
-            reply_messages_map.get(&reply_id).map(|reply_msg| {
-                if reply_msg.has_attachments
-                    && message_attachments_map
-                        .get(&reply_msg.id)
-                        .is_none_or(|attachments| attachments.is_empty())
-                {
-                    tracing::warn!(
-                        reply_id = reply_msg.id,
-                        parent_message_id = m.id,
-                        chat_id = m.chat_id,
-                        "attach_metadata: reply message has_attachments=true but no attachments were hydrated"
-                    );
-                }
+    let reply_preview = |reply_msg: &Message, parent: &Message| {
+        if reply_msg.has_attachments
+            && message_attachments_map
+                .get(&reply_msg.id)
+                .is_none_or(|attachments| attachments.is_empty())
+        {
+            tracing::warn!(
+                reply_id = reply_msg.id,
+                parent_message_id = parent.id,
+                chat_id = parent.chat_id,
+                "attach_metadata: reply message has_attachments=true but no attachments were hydrated"
+            );
+        }
 
-                Box::new(ReplyToMessage {
-                    id: reply_msg.id,
-                    message: if reply_msg.deleted_at.is_some() {
-                        None
-                    } else {
-                        reply_msg.message.clone()
-                    },
-                    message_type: reply_msg.message_type.clone(),
-                    sticker: reply_msg.sticker_id.and_then(|sticker_id| {
-                        sticker_rows.get(&sticker_id).map(|(sticker, media_row)| {
-                            build_message_sticker_response(
-                                state,
-                                sticker,
-                                media_row,
-                                favorited_sticker_ids.contains(&sticker_id),
-                            )
-                        })
-                    }),
-                    sender: build_sender(reply_msg.sender_uid, &user_avatars, &user_profiles),
-                    is_deleted: reply_msg.deleted_at.is_some(),
-                    first_attachment_kind: first_attachment_kind(
-                        &message_attachments_map,
-                        reply_msg.id,
-                    ),
-                    mentions: reply_msg
-                        .message
-                        .as_deref()
-                        .filter(|_| reply_msg.deleted_at.is_none())
-                        .map(|text| {
-                            extract_mention_uids(text)
-                                .into_iter()
-                                .map(|uid| {
-                                    build_mention_info(uid, &user_avatars, &user_profiles)
-                                })
-                                .collect()
-                        })
-                        .unwrap_or_default(),
+        let mut reply = ReplyToMessage {
+            id: reply_msg.id,
+            message: reply_msg.message.clone(),
+            message_type: reply_msg.message_type.clone(),
+            sticker: reply_msg.sticker_id.and_then(|sticker_id| {
+                sticker_rows.get(&sticker_id).map(|(sticker, media_row)| {
+                    build_message_sticker_response(
+                        state,
+                        sticker,
+                        media_row,
+                        favorited_sticker_ids.contains(&sticker_id),
+                    )
                 })
-            })
-        });
+            }),
+            sender: build_sender(reply_msg.sender_uid, &user_avatars, &user_profiles),
+            is_deleted: reply_msg.deleted_at.is_some(),
+            first_attachment_kind: first_attachment_kind(&message_attachments_map, reply_msg.id),
+            mentions: reply_msg
+                .message
+                .as_deref()
+                .filter(|_| reply_msg.deleted_at.is_none())
+                .map(|text| {
+                    extract_mention_uids(text)
+                        .into_iter()
+                        .map(|uid| build_mention_info(uid, &user_avatars, &user_profiles))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        reply.strip_deleted_content();
+        Box::new(reply)
+    };
+
+    let mut responses = Vec::with_capacity(messages_to_process.len());
+    for (idx, m) in messages_to_process.into_iter().enumerate() {
+        let reply_to_message = m
+            .reply_to_id
+            .and_then(|reply_id| reply_messages_map.get(&reply_id))
+            .map(|reply_msg| reply_preview(reply_msg, &m));
+        let reply_root_message = m
+            .reply_root_id
+            .filter(|_| options.expand_reply_root)
+            .and_then(|root_id| reply_messages_map.get(&root_id))
+            .map(|root_msg| reply_preview(root_msg, &m));
 
         let mut attachments = Vec::new();
         if let Some(atts) = message_attachments_map.get(&m.id) {
@@ -1011,13 +1296,11 @@ pub async fn attach_metadata(
             }
         }
 
-        responses.push(MessageResponse {
+        let mut response = MessageResponse {
+            priority: MessagePriority::for_message_type(&m.message_type),
+            muted: false,
             id: m.id,
-            message: if m.deleted_at.is_some() {
-                None
-            } else {
-                m.message
-            },
+            message: m.message,
             message_type: m.message_type,
             sticker: m.sticker_id.and_then(|sticker_id| {
                 sticker_rows.get(&sticker_id).map(|(sticker, media_row)| {
@@ -1030,11 +1313,13 @@ pub async fn attach_metadata(
                 })
             }),
             reply_root_id: m.reply_root_id,
+            forwarded_from_message_id: m.forwarded_from_message_id,
             client_generated_id: m.client_generated_id,
             sender: build_sender(m.sender_uid, &user_avatars, &user_profiles),
             chat_id: m.chat_id,
             created_at: m.created_at,
-            is_edited: m.updated_at.is_some(),
+            is_edited: m.edited_by_user,
+            edit_count: edit_counts_map.get(&m.id).copied().unwrap_or(0),
             is_deleted: m.deleted_at.is_some(),
             has_attachments: m.has_attachments,
             thread_info: if m.has_thread {
@@ -1045,6 +1330,7 @@ pub async fn attach_metadata(
                 None
             },
             reply_to_message,
+            reply_root_message,
             attachments,
             reactions: reaction_summaries_map.remove(&m.id).unwrap_or_default(),
             mentions: {
@@ -1053,7 +1339,9 @@ pub async fn attach_metadata(
                     .map(|&uid| build_mention_info(uid, &user_avatars, &user_profiles))
                     .collect()
             },
-        });
+        };
+        response.strip_deleted_content();
+        responses.push(response);
     }
     responses
 }
@@ -1069,12 +1357,15 @@ pub struct ListChatsQuery {
     limit: Option<i64>,
     #[serde(
         default,
-        deserialize_with = "crate::serde_i64_string::opt::deserialize"
+        deserialize_with = "crate::utils::pagination::serde_cursor::deserialize"
     )]
     #[schema(value_type = Option<String>)]
     after: Option<i64>,
     #[serde(default)]
     archived: Option<bool>,
+    /// List archived and active chats together; overrides `archived`.
+    #[serde(default)]
+    include_archived: bool,
 }
 
 #[derive(Serialize, utoipa::ToSchema)]
@@ -1090,20 +1381,60 @@ pub struct ChatListItem {
     #[serde(with = "crate::serde_i64_string::opt")]
     #[schema(value_type = Option<String>)]
     last_read_message_id: Option<i64>,
+    /// Latest non-deleted, published top-level message; its text is cut to a
+    /// short preview.
     last_message: Option<MessageResponse>,
     muted_until: Option<DateTime<Utc>>,
+    /// Whether `muted_until` is still in the future.
+    muted: bool,
     archived: bool,
+    kind: ChatKind,
 }
 
 #[derive(Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ListChatsResponse {
     chats: Vec<ChatListItem>,
-    #[serde(with = "crate::serde_i64_string::opt")]
+    #[serde(with = "crate::utils::pagination::serde_cursor")]
     #[schema(value_type = Option<String>)]
     next_cursor: Option<i64>,
 }
 
+/// Characters of the last message's text kept in a chat list preview.
+const CHAT_LIST_PREVIEW_MAX_CHARS: usize = 200;
+
+/// Cut `text` to `max_chars` for a chat list preview, adding `…` when
+/// shortened. Mention tokens are kept whole or dropped, never split, so
+/// clients do not render a half token as plain text.
+fn truncate_list_preview(text: &str, max_chars: usize) -> String {
+    let mut end = 0;
+    let mut chars = 0;
+    while end < text.len() {
+        let (next, width) = match parse_mention_token(text, end) {
+            Some((_, next)) => (next, text[end..next].chars().count()),
+            None => {
+                let ch = text[end..].chars().next().expect("end is a char boundary");
+                (end + ch.len_utf8(), 1)
+            }
+        };
+        if chars + width > max_chars {
+            return format!("{}…", &text[..end]);
+        }
+        chars += width;
+        end = next;
+    }
+    text.to_string()
+}
+
+/// Archive states `get_chats` lists: active chats unless asked otherwise.
+fn listed_archive_states(archived: Option<bool>, include_archived: bool) -> Vec<bool> {
+    if include_archived {
+        vec![false, true]
+    } else {
+        vec![archived.unwrap_or(false)]
+    }
+}
+
 /// GET /chats — List chats for the current user (cursor-based).
 #[utoipa::path(
     get,
@@ -1111,11 +1442,13 @@ pub struct ListChatsResponse {
     tag = "chats",
     params(
         ("limit" = Option<i64>, Query, description = "Max number of chats to return"),
-        ("after" = Option<String>, Query, description = "Cursor for pagination"),
+        ("after" = Option<String>, Query, description = "Cursor from nextCursor (raw chat IDs are still accepted)"),
         ("archived" = Option<bool>, Query, description = "When true, list archived chats instead of active ones"),
+        ("includeArchived" = Option<bool>, Query, description = "When true, list archived and active chats together"),
     ),
     responses(
         (status = 200, description = "List of chats", body = ListChatsResponse),
+        (status = 400, description = "Invalid cursor or non-positive limit"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
@@ -1127,8 +1460,9 @@ async fn get_chats(
 ) -> Result<Json<ListChatsResponse>, AppError> {
     let conn = &mut *conn;
 
-    let limit = validate_limit(q.limit, MAX_CHATS_LIMIT);
-    let archived = q.archived.unwrap_or(false);
+    let limit = require_positive_limit(q.limit, state.page_limits.chats)?;
+    let archive_states = listed_archive_states(q.archived, q.include_archived);
+    let now = Utc::now();
 
     let unread_count_sql = format!(
         "(SELECT count(*) FROM (
@@ -1142,6 +1476,8 @@ async fn get_chats(
                 deleted_at IS NULL
             AND
                 is_published = TRUE
+            AND
+                sender_uid <> group_membership.uid
             AND
                 id > COALESCE(group_membership.last_read_message_id, 0)
             LIMIT {}
@@ -1161,7 +1497,7 @@ async fn get_chats(
                 .and(media::deleted_at.is_null())),
         )
         .filter(group_membership::uid.eq(uid))
-        .filter(group_membership::archived.eq(archived));
+        .filter(group_membership::archived.eq_any(archive_states));
 
     type RowType = (
         i64,
@@ -1173,110 +1509,93 @@ async fn get_chats(
         Option<crate::models::Message>,
         Option<DateTime<Utc>>,
         bool,
+        ChatKind,
     );
 
-    let rows: Vec<RowType> = match q.after {
-        None => base_query
-            .select((
-                groups::id,
-                groups::name,
-                media::storage_key.nullable(),
-                groups::last_message_at,
-                unread_count_sq.clone(),
-                group_membership::last_read_message_id,
-                messages_schema::all_columns.nullable(),
-                group_membership::muted_until,
-                group_membership::archived,
-            ))
-            .order_by((
-                groups::last_message_at.desc().nulls_last(),
-                groups::id.desc(),
-            ))
-            .limit(limit + 1)
-            .load(conn)?,
+    // Resolve the cursor chat's position before building the page query.
+    let cursor = match q.after {
+        None => None,
         Some(after_id) => {
-            let cursor_at: Option<Option<DateTime<Utc>>> = groups::table
-                .inner_join(group_membership::table)
-                .filter(group_membership::uid.eq(uid))
-                .filter(groups::id.eq(after_id))
-                .select(groups::last_message_at)
-                .first(conn)
-                .optional()?;
-
-            let cursor_at = match cursor_at {
-                Some(c) => c,
+            let cursor_at: Option<Option<DateTime<Utc>>> = traced("chats.cursor", || {
+                groups::table
+                    .inner_join(group_membership::table)
+                    .filter(group_membership::uid.eq(uid))
+                    .filter(groups::id.eq(after_id))
+                    .select(groups::last_message_at)
+                    .first(conn)
+                    .optional()
+            })?;
+
+            match cursor_at {
+                Some(cursor_at) => Some((cursor_at, after_id)),
                 None => {
                     return Ok(Json(ListChatsResponse {
                         chats: vec![],
                         next_cursor: None,
                     }))
                 }
-            };
-            let cursor_id = after_id;
-
-            match cursor_at {
-                Some(c_at) => base_query
-                    .select((
-                        groups::id,
-                        groups::name,
-                        media::storage_key.nullable(),
-                        groups::last_message_at,
-                        unread_count_sq.clone(),
-                        group_membership::last_read_message_id,
-                        messages_schema::all_columns.nullable(),
-                        group_membership::muted_until,
-                        group_membership::archived,
-                    ))
-                    .filter(
-                        groups::last_message_at
-                            .lt(c_at)
-                            .or(groups::last_message_at
-                                .eq(c_at)
-                                .and(groups::id.lt(cursor_id)))
-                            .or(groups::last_message_at.is_null()),
-                    )
-                    .order_by((
-                        groups::last_message_at.desc().nulls_last(),
-                        groups::id.desc(),
-                    ))
-                    .limit(limit + 1)
-                    .load(conn)?,
-                None => base_query
-                    .select((
-                        groups::id,
-                        groups::name,
-                        media::storage_key.nullable(),
-                        groups::last_message_at,
-                        unread_count_sq.clone(),
-                        group_membership::last_read_message_id,
-                        messages_schema::all_columns.nullable(),
-                        group_membership::muted_until,
-                        group_membership::archived,
-                    ))
-                    .filter(
-                        groups::last_message_at
-                            .is_null()
-                            .and(groups::id.lt(cursor_id)),
-                    )
-                    .order_by((
-                        groups::last_message_at.desc().nulls_last(),
-                        groups::id.desc(),
-                    ))
-                    .limit(limit + 1)
-                    .load(conn)?,
             }
         }
     };
 
+    let mut page_query = base_query
+        .select((
+            groups::id,
+            groups::name,
+            media::storage_key.nullable(),
+            groups::last_message_at,
+            unread_count_sq,
+            group_membership::last_read_message_id,
+            messages_schema::all_columns.nullable(),
+            group_membership::muted_until,
+            group_membership::archived,
+            groups::kind,
+        ))
+        .order_by((
+            groups::last_message_at.desc().nulls_last(),
+            groups::id.desc(),
+        ))
+        .limit(limit + 1)
+        .into_boxed();
+
+    // Chats after the cursor in `last_message_at DESC NULLS LAST, id DESC`
+    // order; chats without messages sort after every chat that has one.
+    match cursor {
+        None => {}
+        Some((Some(cursor_at), cursor_id)) => {
+            page_query = page_query.filter(
+                groups::last_message_at
+                    .lt(cursor_at)
+                    .or(groups::last_message_at
+                        .eq(cursor_at)
+                        .and(groups::id.lt(cursor_id)))
+                    .or(groups::last_message_at.is_null()),
+            );
+        }
+        Some((None, cursor_id)) => {
+            page_query = page_query.filter(
+                groups::last_message_at
+                    .is_null()
+                    .and(groups::id.lt(cursor_id)),
+            );
+        }
+    }
+
+    let rows: Vec<RowType> = traced("chats.page", || page_query.load(conn))?;
+
     let has_more = rows.len() as i64 > limit;
     let items_to_process: Vec<RowType> = rows.into_iter().take(limit as usize).collect();
 
     let messages_to_process: Vec<crate::models::Message> = items_to_process
         .iter()
-        .filter_map(|(_, _, _, _, _, _, msg, _, _)| msg.clone())
+        .filter_map(|(_, _, _, _, _, _, msg, _, _, _)| msg.clone())
         .collect();
 
-    let message_responses = attach_metadata(conn, messages_to_process, &state, uid).await;
+    let message_responses = traced_async(
+        "chats.last_message_metadata",
+        attach_metadata(conn, messages_to_process, &state, uid),
+    )
+    .await;
 
     let mut message_response_map: std::collections::HashMap<i64, MessageResponse> =
         message_responses
@@ -1297,8 +1616,16 @@ async fn get_chats(
                 msg,
                 muted_until,
                 archived,
+                kind,
             )| {
-                let mr = msg.and_then(|m| message_response_map.remove(&m.id));
+                let mr = msg
+                    .and_then(|m| message_response_map.remove(&m.id))
+                    .map(|mut mr| {
+                        mr.message = mr
+                            .message
+                            .map(|text| truncate_list_preview(&text, CHAT_LIST_PREVIEW_MAX_CHARS));
+                        mr
+                    });
                 ChatListItem {
                     id,
                     name: Some(name),
@@ -1309,8 +1636,10 @@ async fn get_chats(
                     unread_count,
                     last_read_message_id,
                     last_message: mr,
+                    muted: muted_until.is_some_and(|until| until > now),
                     muted_until,
                     archived,
+                    kind,
                 }
             },
         )
@@ -1338,7 +1667,85 @@ struct MarkChatReadStateResponse {
     unread_count: i64,
 }
 
-/// POST /chats/:chat_id/messages/read — Mark messages as read up to a specific message ID.
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct MemberReadState {
+    uid: i32,
+    #[serde(serialize_with = "crate::serde_i64_string::opt::serialize")]
+    #[schema(value_type = Option<String>)]
+    last_read_message_id: Option<i64>,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListReadStatesQuery {
+    limit: Option<i64>,
+    after: Option<i32>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListReadStatesResponse {
+    members: Vec<MemberReadState>,
+    next_cursor: Option<i32>,
+}
+
+/// GET /chats/:chat_id/read — Read cursors of the chat's members, by uid.
+#[utoipa::path(
+    get,
+    path = "/read",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("limit" = Option<i64>, Query, description = "Page size limit"),
+        ("after" = Option<i32>, Query, description = "Return members with a uid above this cursor"),
+    ),
+    responses(
+        (status = 200, description = "Read cursor per member", body = ListReadStatesResponse),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn get_read_states(
+    CurrentUid(uid): CurrentUid,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+    Query(q): Query<ListReadStatesQuery>,
+) -> Result<Json<ListReadStatesResponse>, AppError> {
+    let conn = &mut *conn;
+
+    check_membership(conn, chat_id, uid)?;
+    let limit = require_positive_limit(q.limit, crate::MAX_MEMBERS_LIMIT)?;
+
+    use crate::schema::group_membership::dsl as gm_dsl;
+    let mut query = group_membership::table
+        .filter(gm_dsl::chat_id.eq(chat_id))
+        .into_boxed();
+    if let Some(after) = q.after {
+        query = query.filter(gm_dsl::uid.gt(after));
+    }
+    let mut members: Vec<MemberReadState> = query
+        .order(gm_dsl::uid.asc())
+        .limit(limit + 1)
+        .select((gm_dsl::uid, gm_dsl::last_read_message_id))
+        .load::<(i32, Option<i64>)>(conn)?
+        .into_iter()
+        .map(|(uid, last_read_message_id)| MemberReadState {
+            uid,
+            last_read_message_id,
+        })
+        .collect();
+
+    let has_more = members.len() as i64 > limit;
+    members.truncate(limit as usize);
+    let next_cursor = has_more.then(|| members.last().map(|m| m.uid)).flatten();
+
+    Ok(Json(ListReadStatesResponse {
+        members,
+        next_cursor,
+    }))
+}
+
+/// POST /chats/:chat_id/read — Mark messages as read up to a specific message ID.
 #[utoipa::path(
     post,
     path = "/read",
@@ -1349,23 +1756,61 @@ struct MarkChatReadStateResponse {
     request_body = MarkAsReadBody,
     responses(
         (status = 200, description = "Updated read state", body = MarkChatReadStateResponse),
+        (status = 400, description = "Message does not belong to this chat"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
 async fn mark_as_read(
     CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
     Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
     mut conn: DbConn,
-    Json(body): Json<MarkAsReadBody>,
+    JsonBody(body): JsonBody<MarkAsReadBody>,
 ) -> Result<Json<MarkChatReadStateResponse>, AppError> {
     let conn = &mut *conn;
 
     check_membership(conn, chat_id, uid)?;
 
-    crate::services::chat::mark_chat_as_read(conn, chat_id, uid, body.message_id)?;
+    let message_in_chat = messages_schema::table
+        .filter(
+            messages_schema::id
+                .eq(body.message_id)
+                .and(messages_schema::chat_id.eq(chat_id)),
+        )
+        .count()
+        .get_result::<i64>(conn)?;
+    if message_in_chat == 0 {
+        return Err(AppError::BadRequest("Message does not belong to this chat"));
+    }
+
+    let advanced = crate::services::chat::mark_chat_as_read(conn, chat_id, uid, body.message_id)?;
+    if advanced {
+        // The reader's other sessions follow every advance; the rest of the
+        // chat gets a receipt per READ_RECEIPT_INTERVAL, not one per read, and
+        // the newest cursor held back meanwhile when the interval ends.
+        use crate::services::ws_registry::ReadReceiptClaim;
+        let claim = state
+            .ws_registry
+            .claim_read_receipt(chat_id, uid, body.message_id);
+        let recipients = match claim {
+            ReadReceiptClaim::Send => {
+                crate::services::chat::member_uids_or_log(conn, &state.ws_registry, chat_id)
+            }
+            ReadReceiptClaim::FlushAfter(wait) => {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(wait).await;
+                    flush_held_read_receipt(&state, chat_id, uid);
+                });
+                vec![uid]
+            }
+            ReadReceiptClaim::Held => vec![uid],
+        };
+        broadcast_read_receipt(&state, &recipients, chat_id, uid, body.message_id);
+    }
 
     let unread_count =
-        crate::services::chat::get_chat_unread_count(conn, chat_id, Some(body.message_id))?;
+        crate::services::chat::get_chat_unread_count(conn, chat_id, uid, Some(body.message_id))?;
 
     Ok(Json(MarkChatReadStateResponse {
         last_read_message_id: Some(body.message_id),
@@ -1373,6 +1818,44 @@ async fn mark_as_read(
     }))
 }
 
+fn broadcast_read_receipt(
+    state: &AppState,
+    recipients: &[i32],
+    chat_id: i64,
+    uid: i32,
+    last_read_message_id: i64,
+) {
+    let ws_msg = std::sync::Arc::new(
+        crate::handlers::ws::messages::ServerWsMessage::ReadStateUpdated(
+            crate::handlers::ws::messages::ReadStateUpdatedPayload {
+                chat_id,
+                uid,
+                last_read_message_id,
+            },
+        ),
+    );
+    state.ws_registry.broadcast_to_uids(recipients, ws_msg);
+}
+
+/// Send the cursor `mark_as_read` held back during `uid`'s receipt interval to
+/// the rest of the chat; the reader's own sessions already have it.
+pub(crate) fn flush_held_read_receipt(state: &AppState, chat_id: i64, uid: i32) {
+    let Some(message_id) = state.ws_registry.take_held_read_receipt(chat_id, uid) else {
+        return;
+    };
+    let mut conn = match state.db.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            tracing::warn!(chat_id, uid, error = %e, "read receipt flush: no database connection");
+            return;
+        }
+    };
+    let mut recipients =
+        crate::services::chat::member_uids_or_log(&mut conn, &state.ws_registry, chat_id);
+    recipients.retain(|&member| member != uid);
+    broadcast_read_receipt(state, &recipients, chat_id, uid, message_id);
+}
+
 /// Optional body for the unread endpoint — allows resetting read position to a specific message.
 #[derive(serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -1403,13 +1886,13 @@ async fn mark_as_unread(
     CurrentUid(uid): CurrentUid,
     Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
     mut conn: DbConn,
-    body: Option<Json<MarkAsUnreadBody>>,
+    body: Option<JsonBody<MarkAsUnreadBody>>,
 ) -> Result<Json<MarkChatReadStateResponse>, AppError> {
     let conn = &mut *conn;
 
     check_membership(conn, chat_id, uid)?;
 
-    let explicit_id = body.and_then(|Json(b)| b.message_id);
+    let explicit_id = body.and_then(|JsonBody(b)| b.message_id);
 
     let (new_read_id, unread_count) = if let Some(message_id) = explicit_id {
         use crate::schema::group_membership::dsl as gm_dsl;
@@ -1420,7 +1903,7 @@ async fn mark_as_unread(
         .execute(conn)?;
 
         let unread_count =
-            crate::services::chat::get_chat_unread_count(conn, chat_id, Some(message_id))?;
+            crate::services::chat::get_chat_unread_count(conn, chat_id, uid, Some(message_id))?;
         (Some(message_id), unread_count)
     } else {
         use crate::schema::messages::dsl;
@@ -1494,7 +1977,7 @@ async fn get_chat_unread_count(
         .first(conn)?;
 
     let unread_count =
-        crate::services::chat::get_chat_unread_count(conn, chat_id, last_read_message_id)?;
+        crate::services::chat::get_chat_unread_count(conn, chat_id, uid, last_read_message_id)?;
 
     Ok(Json(MarkChatReadStateResponse {
         last_read_message_id,
@@ -1572,6 +2055,7 @@ async fn archive_chat(
         group_membership::muted_until.eq(Some(chat::indefinite_mute_until())),
     ))
     .execute(conn)?;
+    state.ws_registry.invalidate_chat_members(chat_id);
 
     state.ws_registry.broadcast_to_uids(
         &[uid],
@@ -1624,6 +2108,7 @@ async fn unarchive_chat(
         group_membership::muted_until.eq(None::<DateTime<Utc>>),
     ))
     .execute(conn)?;
+    state.ws_registry.invalidate_chat_members(chat_id);
 
     state.ws_registry.broadcast_to_uids(
         &[uid],
@@ -1696,23 +2181,36 @@ pub fn router() -> OpenApiRouter<crate::AppState> {
     OpenApiRouter::new()
         .routes(utoipa_axum::routes!(get_chats))
         .routes(utoipa_axum::routes!(get_unread_count))
+        .routes(utoipa_axum::routes!(self::direct::post_direct_chat))
         .nest(
             "/{chat_id}",
             OpenApiRouter::new()
+                .routes(utoipa_axum::routes!(crate::handlers::groups::delete_chat))
                 .routes(utoipa_axum::routes!(archive_chat, unarchive_chat))
+                .routes(utoipa_axum::routes!(
+                    crate::handlers::members::post_leave_chat
+                ))
                 .nest(
                     "/messages",
-                    messages_router().nest("/{message_id}/reactions", reactions_router()),
+                    messages_router()
+                        .nest("/{message_id}/reactions", reactions_router())
+                        .nest("/{message_id}/attachments", message_attachments_router()),
                 )
-                .routes(utoipa_axum::routes!(mark_as_read))
+                .routes(utoipa_axum::routes!(get_read_states, mark_as_read))
                 .routes(utoipa_axum::routes!(mark_as_unread))
                 .routes(utoipa_axum::routes!(get_chat_unread_count))
+                .routes(utoipa_axum::routes!(self::export::get_chat_export))
+                .routes(utoipa_axum::routes!(
+                    self::message_attachments::post_attachment_presign
+                ))
                 .routes(utoipa_axum::routes!(self::messages::post_thread_message))
+                .routes(utoipa_axum::routes!(self::messages::post_announcement))
                 .nest(
                     "/threads/{thread_root_id}",
                     super::threads::subscribe_router(),
                 )
-                .nest("/pins", super::pins::router()),
+                .nest("/pins", super::pins::router())
+                .nest("/webhooks", super::webhooks::router()),
         )
 }
 
@@ -1723,15 +2221,39 @@ pub fn router() -> OpenApiRouter<crate::AppState> {
 #[cfg(test)]
 mod tests {
     use super::{
-        attachment_preview_text, build_push_preview_bundle, extract_mention_uids,
-        first_attachment_kind, render_mentions_as_text, sticker_preview_text, MentionInfo,
-        ReplyToMessage,
+        attachment_preview_text, build_push_preview_bundle, build_sender, extract_mention_uids,
+        first_attachment_kind, listed_archive_states, mentioned_member_uids, muted_member_uids,
+        referenced_message_ids, render_mentions_as_text, sticker_preview_text, system_edit_stamp,
+        truncate_list_preview, user_edit_stamp, MentionInfo, MessagePriority, MetadataOptions,
+        ReplyToMessage, CHAT_LIST_PREVIEW_MAX_CHARS,
     };
-    use crate::models::{Attachment, AttachmentResponse, MessageType, Sender};
+    use crate::models::{Attachment, AttachmentResponse, Message, MessageType, Sender};
     use chrono::Utc;
     use serde_json::json;
     use std::collections::HashMap;
 
+    #[test]
+    fn sender_carries_username_and_omits_it_for_unknown_users() {
+        let profiles = HashMap::from([(
+            3,
+            crate::services::user::UserProfile {
+                username: Some("alice".to_string()),
+                gender: 2,
+                user_group: None,
+            },
+        )]);
+        let avatars = HashMap::new();
+
+        let known = build_sender(3, &avatars, &profiles);
+        assert_eq!(known.name.as_deref(), Some("alice"));
+        assert_eq!(known.gender, 2);
+
+        let missing = build_sender(4, &avatars, &profiles);
+        assert_eq!(missing.uid, 4);
+        assert_eq!(missing.name, None);
+        assert_eq!(serde_json::to_value(&missing).unwrap()["name"], json!(null));
+    }
+
     #[test]
     fn sticker_preview_text_includes_emoji_when_available() {
         assert_eq!(sticker_preview_text(Some("🙂")), "[Sticker] 🙂");
@@ -1776,6 +2298,46 @@ mod tests {
         assert_eq!(extract_mention_uids(text), vec![7, 8]);
     }
 
+    #[test]
+    fn mention_events_only_target_other_chat_members() {
+        let mentioned = extract_mention_uids("@[uid:7] @[uid:8] @[uid:9] @[uid:3]");
+        assert_eq!(mentioned_member_uids(&mentioned, &[3, 7, 9], 3), vec![7, 9]);
+    }
+
+    #[test]
+    fn chat_list_hides_archived_chats_unless_asked() {
+        assert_eq!(listed_archive_states(None, false), vec![false]);
+        assert_eq!(listed_archive_states(Some(true), false), vec![true]);
+        assert_eq!(listed_archive_states(Some(false), false), vec![false]);
+        assert_eq!(listed_archive_states(None, true), vec![false, true]);
+        assert_eq!(listed_archive_states(Some(true), true), vec![false, true]);
+    }
+
+    #[test]
+    fn list_preview_truncates_by_chars_without_splitting_mentions() {
+        assert_eq!(truncate_list_preview("hello", 5), "hello");
+        assert_eq!(truncate_list_preview("hello world", 5), "hello…");
+        assert_eq!(truncate_list_preview("你好世界", 2), "你好…");
+        // `@[uid:42]` is 9 chars: it fits whole or not at all.
+        assert_eq!(
+            truncate_list_preview("hi @[uid:42] there", 12),
+            "hi @[uid:42]…"
+        );
+        assert_eq!(truncate_list_preview("hi @[uid:42] there", 8), "hi …");
+    }
+
+    #[test]
+    fn only_unexpired_mutes_mark_members_muted() {
+        let now = Utc::now();
+        let memberships = [
+            (1, None),
+            (2, Some(now + chrono::Duration::hours(1))),
+            (3, Some(now - chrono::Duration::seconds(1))),
+            (4, Some(crate::services::chat::indefinite_mute_until())),
+        ];
+        assert_eq!(muted_member_uids(&memberships, now), vec![2, 4]);
+    }
+
     #[test]
     fn render_mentions_as_text_leaves_invalid_tokens_untouched() {
         let text = "@[user:7] 你好";
@@ -1799,6 +2361,7 @@ mod tests {
             message_type: MessageType::Invite,
             sticker: None,
             reply_root_id: None,
+            forwarded_from_message_id: None,
             client_generated_id: "cgid".to_string(),
             sender: Sender {
                 uid: 7,
@@ -1810,13 +2373,17 @@ mod tests {
             chat_id: 10,
             created_at: Utc::now(),
             is_edited: false,
+            edit_count: 0,
             is_deleted: false,
             has_attachments: false,
             thread_info: None,
             reply_to_message: None,
+            reply_root_message: None,
             attachments: Vec::new(),
             reactions: Vec::new(),
             mentions: Vec::new(),
+            priority: None,
+            muted: false,
         };
 
         let preview = build_push_preview_bundle(&response);
@@ -1825,6 +2392,249 @@ mod tests {
         assert_eq!(preview.message_preview.message, None);
     }
 
+    #[test]
+    fn deleted_message_serializes_as_tombstone() {
+        let sender = Sender {
+            uid: 7,
+            avatar_url: None,
+            name: Some("Alice".to_string()),
+            gender: 0,
+            user_group: None,
+        };
+        let mut reply = ReplyToMessage {
+            id: 1,
+            message: Some("secret reply".to_string()),
+            message_type: MessageType::Text,
+            sticker: None,
+            sender: sender.clone(),
+            is_deleted: true,
+            first_attachment_kind: Some("image/png".to_string()),
+            mentions: Vec::new(),
+        };
+        reply.strip_deleted_content();
+
+        let mut response = super::MessageResponse {
+            id: 2,
+            message: Some("secret text".to_string()),
+            message_type: MessageType::Text,
+            sticker: None,
+            reply_root_id: None,
+            forwarded_from_message_id: None,
+            client_generated_id: "cgid".to_string(),
+            sender,
+            chat_id: 10,
+            created_at: Utc::now(),
+            is_edited: false,
+            edit_count: 0,
+            is_deleted: true,
+            has_attachments: true,
+            thread_info: None,
+            reply_to_message: Some(Box::new(reply)),
+            reply_root_message: None,
+            attachments: vec![AttachmentResponse {
+                id: 1,
+                url: "https://example.com/image.png".to_string(),
+                kind: "image/png".to_string(),
+                size: 123,
+                file_name: "image.png".to_string(),
+                width: Some(100),
+                height: Some(100),
+            }],
+            reactions: Vec::new(),
+            mentions: Vec::new(),
+            priority: None,
+            muted: false,
+        };
+        response.strip_deleted_content();
+
+        let value = serde_json::to_value(&response).expect("serialize tombstone");
+        assert_eq!(value["id"], json!("2"));
+        assert_eq!(value["isDeleted"], json!(true));
+        assert_eq!(value["message"], json!(null));
+        assert_eq!(value["hasAttachments"], json!(false));
+        assert_eq!(value["attachments"], json!([]));
+        assert_eq!(value["replyToMessage"]["message"], json!(null));
+        assert!(value["replyToMessage"].get("firstAttachmentKind").is_none());
+        assert!(!value.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn announcement_broadcast_carries_high_priority_hint() {
+        assert_eq!(
+            MessagePriority::for_message_type(&MessageType::Announcement),
+            Some(MessagePriority::High)
+        );
+        assert_eq!(MessagePriority::for_message_type(&MessageType::Text), None);
+
+        let response = super::MessageResponse {
+            id: 1,
+            message: Some("Maintenance at 10pm".to_string()),
+            message_type: MessageType::Announcement,
+            sticker: None,
+            reply_root_id: None,
+            forwarded_from_message_id: None,
+            client_generated_id: "cgid".to_string(),
+            sender: Sender {
+                uid: 7,
+                avatar_url: None,
+                name: Some("Alice".to_string()),
+                gender: 0,
+                user_group: None,
+            },
+            chat_id: 10,
+            created_at: Utc::now(),
+            is_edited: false,
+            edit_count: 0,
+            is_deleted: false,
+            has_attachments: false,
+            thread_info: None,
+            reply_to_message: None,
+            reply_root_message: None,
+            attachments: Vec::new(),
+            reactions: Vec::new(),
+            mentions: Vec::new(),
+            priority: MessagePriority::for_message_type(&MessageType::Announcement),
+            muted: false,
+        };
+
+        let value = serde_json::to_value(crate::handlers::ws::messages::ServerWsMessage::Message(
+            response,
+        ))
+        .expect("serialize announcement");
+        assert_eq!(value["type"], json!("message"));
+        assert_eq!(value["payload"]["messageType"], json!("announcement"));
+        assert_eq!(value["payload"]["priority"], json!("high"));
+        assert!(value["payload"].get("muted").is_none());
+    }
+
+    #[test]
+    fn mentions_reach_muted_members_unsuppressed() {
+        use crate::handlers::ws::messages::{MentionPayload, ServerWsMessage};
+        use crate::services::ws_registry::ConnectionRegistry;
+        use std::sync::Arc;
+
+        let response = super::MessageResponse {
+            id: 5,
+            message: Some("hi @[uid:3]".to_string()),
+            message_type: MessageType::Text,
+            sticker: None,
+            reply_root_id: None,
+            forwarded_from_message_id: None,
+            client_generated_id: "cgid".to_string(),
+            sender: Sender {
+                uid: 1,
+                avatar_url: None,
+                name: None,
+                gender: 0,
+                user_group: None,
+            },
+            chat_id: 10,
+            created_at: Utc::now(),
+            is_edited: false,
+            edit_count: 0,
+            is_deleted: false,
+            has_attachments: false,
+            thread_info: None,
+            reply_to_message: None,
+            reply_root_message: None,
+            attachments: Vec::new(),
+            reactions: Vec::new(),
+            mentions: Vec::new(),
+            priority: None,
+            muted: false,
+        };
+        let registry = ConnectionRegistry::new(Arc::new(crate::metrics::Metrics::new()));
+        // 2 muted the chat, 3 muted it but is mentioned, 4 did not mute it.
+        let mut receivers: HashMap<i32, _> = [2, 3, 4]
+            .into_iter()
+            .map(|uid| {
+                let (_entry, mut rx, _) = registry.register(uid);
+                while rx.try_recv().is_ok() {}
+                (uid, rx)
+            })
+            .collect();
+        let side_effects = super::PendingSideEffects {
+            chat_id: 10,
+            ws_msg: Arc::new(ServerWsMessage::Message(response)),
+            broadcast_uids: vec![1, 2, 3, 4],
+            muted_uids: vec![2, 3],
+            mentioned_uids: vec![3],
+            mention_msg: Some(Arc::new(ServerWsMessage::Mention(MentionPayload {
+                message_id: 5,
+                chat_id: 10,
+            }))),
+            push_job: None,
+        };
+
+        side_effects.broadcast(&registry, None);
+
+        let mut frames = |uid: i32| -> Vec<serde_json::Value> {
+            let rx = receivers.get_mut(&uid).unwrap();
+            std::iter::from_fn(|| rx.try_recv().ok())
+                .map(|frame| serde_json::from_str(frame.as_str()).unwrap())
+                .collect()
+        };
+        let muted = frames(2);
+        assert_eq!(muted.len(), 1);
+        assert_eq!(muted[0]["type"], json!("message"));
+        assert_eq!(muted[0]["payload"]["muted"], json!(true));
+
+        let mentioned = frames(3);
+        assert_eq!(mentioned.len(), 2);
+        assert_eq!(mentioned[0]["payload"]["muted"], json!(true));
+        assert_eq!(mentioned[1]["type"], json!("mention"));
+        assert!(mentioned[1]["payload"].get("muted").is_none());
+
+        let unmuted = frames(4);
+        assert_eq!(unmuted.len(), 1);
+        assert!(unmuted[0]["payload"].get("muted").is_none());
+    }
+
+    #[test]
+    fn failed_broadcast_build_skips_side_effects_without_failing_send() {
+        let response = super::MessageResponse {
+            id: 3,
+            message: Some("hello".to_string()),
+            message_type: MessageType::Text,
+            sticker: None,
+            reply_root_id: None,
+            forwarded_from_message_id: None,
+            client_generated_id: "cgid".to_string(),
+            sender: Sender {
+                uid: 7,
+                avatar_url: None,
+                name: None,
+                gender: 0,
+                user_group: None,
+            },
+            chat_id: 10,
+            created_at: Utc::now(),
+            is_edited: false,
+            edit_count: 0,
+            is_deleted: false,
+            has_attachments: false,
+            thread_info: None,
+            reply_to_message: None,
+            reply_root_message: None,
+            attachments: Vec::new(),
+            reactions: Vec::new(),
+            mentions: Vec::new(),
+            priority: None,
+            muted: false,
+        };
+
+        let failed = super::side_effects_or_log(
+            Err(crate::errors::AppError::Internal("member query failed")),
+            response.id,
+        );
+        assert!(failed.is_none());
+
+        let built = super::side_effects_or_log(Ok(super::unpublished_side_effects(&response)), 3)
+            .expect("successful build is kept");
+        assert_eq!(built.chat_id, 10);
+        assert!(built.broadcast_uids.is_empty());
+    }
+
     #[test]
     fn build_push_preview_bundle_uses_attachment_label_for_file_messages() {
         let response = super::MessageResponse {
@@ -1833,6 +2643,7 @@ mod tests {
             message_type: MessageType::File,
             sticker: None,
             reply_root_id: None,
+            forwarded_from_message_id: None,
             client_generated_id: "cgid".to_string(),
             sender: Sender {
                 uid: 7,
@@ -1844,10 +2655,12 @@ mod tests {
             chat_id: 10,
             created_at: Utc::now(),
             is_edited: false,
+            edit_count: 0,
             is_deleted: false,
             has_attachments: true,
             thread_info: None,
             reply_to_message: None,
+            reply_root_message: None,
             attachments: vec![AttachmentResponse {
                 id: 1,
                 url: "https://example.com/image.png".to_string(),
@@ -1859,6 +2672,8 @@ mod tests {
             }],
             reactions: Vec::new(),
             mentions: Vec::new(),
+            priority: None,
+            muted: false,
         };
 
         let preview = build_push_preview_bundle(&response);
@@ -1913,6 +2728,7 @@ mod tests {
             width: Some(100),
             height: Some(100),
             order: 0,
+            forwarded_from_attachment_id: None,
         };
 
         let mut attachments_map = HashMap::new();
@@ -1927,4 +2743,278 @@ mod tests {
             Some("image/png".to_string())
         );
     }
+
+    fn threaded(id: i64, reply_to_id: Option<i64>, reply_root_id: Option<i64>) -> Message {
+        Message {
+            id,
+            message: Some(format!("message {id}")),
+            message_type: MessageType::Text,
+            reply_to_id,
+            reply_root_id,
+            client_generated_id: format!("cgid-{id}"),
+            sender_uid: 1,
+            chat_id: 7,
+            created_at: chrono::Utc::now(),
+            updated_at: None,
+            deleted_at: None,
+            has_attachments: false,
+            has_thread: false,
+            has_reactions: false,
+            sticker_id: None,
+            is_published: true,
+            transcode_status: crate::models::TranscodeStatus::None,
+            deleted_by: None,
+            forwarded_from_message_id: None,
+            edited_by_user: false,
+        }
+    }
+
+    #[test]
+    fn only_user_edits_mark_a_message_edited() {
+        use crate::schema::messages;
+        use diesel::pg::Pg;
+
+        let now = Utc::now();
+        let user = diesel::update(messages::table).set(user_edit_stamp(now));
+        let system = diesel::update(messages::table).set(system_edit_stamp(now));
+        let user_sql = diesel::debug_query::<Pg, _>(&user).to_string();
+        let system_sql = diesel::debug_query::<Pg, _>(&system).to_string();
+
+        assert!(user_sql.contains(r#""updated_at" = $1, "edited_by_user" = $2"#));
+        assert!(user_sql.ends_with(", true]"));
+        assert!(system_sql.contains(r#""updated_at" = $1"#));
+        assert!(!system_sql.contains("edited_by_user"));
+    }
+
+    #[test]
+    fn expanding_roots_loads_parent_and_root_together() {
+        // root 1 <- reply 2 <- reply 3, all in thread 1.
+        let page = vec![threaded(2, Some(1), Some(1)), threaded(3, Some(2), Some(1))];
+
+        let mut default_ids = referenced_message_ids(&page, MetadataOptions::default());
+        default_ids.sort_unstable();
+        assert_eq!(default_ids, vec![1, 2]);
+
+        let expanded = MetadataOptions {
+            expand_reply_root: true,
+        };
+        let mut expanded_ids = referenced_message_ids(&page[1..], expanded);
+        expanded_ids.sort_unstable();
+        assert_eq!(expanded_ids, vec![1, 2]);
+        assert_eq!(
+            referenced_message_ids(&page[1..], MetadataOptions::default()),
+            vec![2]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn read_receipts_reach_the_chat_once_per_interval_and_cursors_page() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (reader, other) = (901_071, 901_072);
+        app.seed_user(reader);
+        app.seed_user(other);
+        let chat_id = app.seed_chat("Read receipts").await;
+        app.seed_membership(chat_id, reader, crate::models::GroupRole::Member);
+        app.seed_membership(chat_id, other, crate::models::GroupRole::Member);
+        let app = &app;
+        let send = |client_generated_id: &'static str| async move {
+            let (status, body) = app
+                .request(
+                    axum::http::Method::POST,
+                    &format!("/chats/{chat_id}/messages"),
+                    other,
+                    Some(json!({
+                        "message": "hello",
+                        "messageType": "text",
+                        "clientGeneratedId": client_generated_id,
+                    })),
+                )
+                .await;
+            assert_eq!(status, axum::http::StatusCode::CREATED, "{body}");
+            body["id"].as_str().unwrap().to_string()
+        };
+        let read = |message_id: String| async move {
+            let (status, body) = app
+                .request(
+                    axum::http::Method::POST,
+                    &format!("/chats/{chat_id}/read"),
+                    reader,
+                    Some(json!({ "messageId": message_id })),
+                )
+                .await;
+            assert_eq!(status, axum::http::StatusCode::OK, "{body}");
+        };
+        let receipts = |rx: &mut tokio::sync::mpsc::Receiver<_>| {
+            std::iter::from_fn(|| rx.try_recv().ok())
+                .map(|frame: axum::extract::ws::Utf8Bytes| {
+                    serde_json::from_str::<serde_json::Value>(&frame).unwrap()
+                })
+                .filter(|frame| frame["type"] == "readStateUpdated")
+                .map(|frame| {
+                    frame["payload"]["lastReadMessageId"]
+                        .as_str()
+                        .unwrap()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+        let first = send("receipt-1").await;
+        let second = send("receipt-2").await;
+        let (_reader_entry, mut reader_rx, _) = app.state.ws_registry.register(reader);
+        let (_other_entry, mut other_rx, _) = app.state.ws_registry.register(other);
+
+        read(first.clone()).await;
+        read(second.clone()).await;
+        assert_eq!(receipts(&mut reader_rx), [first.clone(), second.clone()]);
+        assert_eq!(receipts(&mut other_rx), [first]);
+
+        // The interval ending sends the held cursor to everyone else.
+        super::flush_held_read_receipt(&app.state, chat_id, reader);
+        assert_eq!(receipts(&mut other_rx), [second]);
+        assert!(receipts(&mut reader_rx).is_empty());
+
+        let page = |after: Option<i32>| async move {
+            let after = after.map(|uid| format!("&after={uid}")).unwrap_or_default();
+            let (status, body) = app
+                .request(
+                    axum::http::Method::GET,
+                    &format!("/chats/{chat_id}/read?limit=1{after}"),
+                    reader,
+                    None,
+                )
+                .await;
+            assert_eq!(status, axum::http::StatusCode::OK, "{body}");
+            body
+        };
+        let body = page(None).await;
+        assert_eq!(body["members"][0]["uid"], reader);
+        assert_eq!(body["nextCursor"], reader);
+        let body = page(Some(reader)).await;
+        assert_eq!(body["members"][0]["uid"], other);
+        assert_eq!(body["nextCursor"], serde_json::Value::Null);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn chat_pages_neither_drop_nor_repeat_tied_or_empty_chats() {
+        use crate::schema::groups;
+        use diesel::prelude::*;
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_121;
+        app.seed_user(uid);
+        let tied_at = Utc::now();
+        let epoch = chrono::DateTime::<Utc>::UNIX_EPOCH;
+        let mut expected = Vec::new();
+        for last_message_at in [
+            Some(tied_at),
+            Some(tied_at),
+            Some(tied_at),
+            Some(epoch),
+            None,
+            None,
+            None,
+        ] {
+            let chat_id = app.seed_chat("Paged").await;
+            app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+            diesel::update(groups::table.find(chat_id))
+                .set(groups::last_message_at.eq(last_message_at))
+                .execute(&mut app.conn())
+                .unwrap();
+            expected.push(chat_id.to_string());
+        }
+
+        let mut seen = Vec::new();
+        let mut after = String::new();
+        for _ in 0..expected.len() {
+            let (status, page) = app
+                .request(
+                    axum::http::Method::GET,
+                    &format!("/chats?limit=2{after}"),
+                    uid,
+                    None,
+                )
+                .await;
+            assert_eq!(status, axum::http::StatusCode::OK, "{page}");
+            seen.extend(
+                page["chats"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|chat| chat["id"].as_str().unwrap().to_string()),
+            );
+            match page["nextCursor"].as_str() {
+                Some(cursor) => after = format!("&after={cursor}"),
+                None => break,
+            }
+        }
+
+        let mut sorted = seen.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), seen.len(), "repeated chats: {seen:?}");
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn chat_list_previews_the_latest_live_message() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_151;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Previews").await;
+        app.seed_membership(chat_id, uid, crate::models::GroupRole::Member);
+        let app = &app;
+        let send = |message: String, client_generated_id: &'static str| async move {
+            let (status, body) = app
+                .request(
+                    axum::http::Method::POST,
+                    &format!("/chats/{chat_id}/messages"),
+                    uid,
+                    Some(json!({
+                        "message": message,
+                        "messageType": "text",
+                        "clientGeneratedId": client_generated_id,
+                    })),
+                )
+                .await;
+            assert_eq!(status, axum::http::StatusCode::CREATED, "{body}");
+            body["id"].as_str().unwrap().to_string()
+        };
+        let preview = || async move {
+            let (status, body) = app
+                .request(axum::http::Method::GET, "/chats", uid, None)
+                .await;
+            assert_eq!(status, axum::http::StatusCode::OK, "{body}");
+            body["chats"][0]["lastMessage"].clone()
+        };
+
+        send("older".to_string(), "preview-1").await;
+        let latest = send("x".repeat(CHAT_LIST_PREVIEW_MAX_CHARS + 50), "preview-2").await;
+        let last_message = preview().await;
+        assert_eq!(last_message["id"], latest.as_str());
+        assert_eq!(last_message["sender"]["uid"], uid);
+        assert_eq!(last_message["messageType"], "text");
+        let text = last_message["message"].as_str().unwrap();
+        assert!(
+            text.chars().count() <= CHAT_LIST_PREVIEW_MAX_CHARS + 1,
+            "{text}"
+        );
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::DELETE,
+                &format!("/chats/{chat_id}/messages/{latest}"),
+                uid,
+                None,
+            )
+            .await;
+        assert!(status.is_success(), "{status}: {body}");
+        assert_eq!(preview().await["message"], "older");
+    }
 }
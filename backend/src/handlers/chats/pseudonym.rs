@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use crate::models::Sender;
+
+use super::{parse_mention_token, MentionInfo, MessageResponse};
+
+/// Replaces user identities in message responses with aliases that are stable
+/// within one response ("User 1", "User 2", ...), for operator views that
+/// should not reveal who said what.
+#[derive(Debug, Default)]
+pub(crate) struct Pseudonymizer {
+    aliases: HashMap<i32, usize>,
+}
+
+impl Pseudonymizer {
+    fn alias(&mut self, uid: i32) -> String {
+        let next = self.aliases.len() + 1;
+        let index = *self.aliases.entry(uid).or_insert(next);
+        format!("User {index}")
+    }
+
+    fn sender(&mut self, sender: &mut Sender) {
+        sender.name = Some(self.alias(sender.uid));
+        sender.uid = 0;
+        sender.avatar_url = None;
+        sender.gender = 0;
+        sender.user_group = None;
+    }
+
+    fn mentions(&mut self, mentions: &mut [MentionInfo]) {
+        for mention in mentions {
+            mention.username = Some(self.alias(mention.uid));
+            mention.uid = 0;
+            mention.avatar_url = None;
+            mention.gender = 0;
+            mention.user_group = None;
+        }
+    }
+
+    /// Render `@[uid:N]` tokens as `@User k` so the raw uid never leaves.
+    fn text(&mut self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut i = 0;
+        let mut copied_until = 0;
+        while i < text.len() {
+            if let Some((uid, next)) = parse_mention_token(text, i) {
+                result.push_str(&text[copied_until..i]);
+                result.push('@');
+                result.push_str(&self.alias(uid));
+                i = next;
+                copied_until = next;
+                continue;
+            }
+            i += 1;
+        }
+        result.push_str(&text[copied_until..]);
+        result
+    }
+
+    pub(crate) fn apply(&mut self, message: &mut MessageResponse) {
+        self.sender(&mut message.sender);
+        // Clients often derive these ids from the device or session, so they
+        // can link messages back to a sender.
+        message.client_generated_id.clear();
+        if let Some(text) = message.message.take() {
+            message.message = Some(self.text(&text));
+        }
+        self.mentions(&mut message.mentions);
+
+        if let Some(reply) = message.reply_to_message.as_deref_mut() {
+            self.sender(&mut reply.sender);
+            if let Some(text) = reply.message.take() {
+                reply.message = Some(self.text(&text));
+            }
+            self.mentions(&mut reply.mentions);
+        }
+
+        for reaction in &mut message.reactions {
+            for reactor in reaction.reactors.iter_mut().flatten() {
+                reactor.name = Some(self.alias(reactor.uid));
+                reactor.uid = 0;
+                reactor.avatar_url = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pseudonymizer;
+
+    #[test]
+    fn aliases_are_stable_per_uid_and_replace_mention_tokens() {
+        let mut pseudonymizer = Pseudonymizer::default();
+        assert_eq!(pseudonymizer.alias(42), "User 1");
+        assert_eq!(pseudonymizer.alias(7), "User 2");
+        assert_eq!(pseudonymizer.alias(42), "User 1");
+
+        assert_eq!(
+            pseudonymizer.text("hi @[uid:7] and @[uid:99]!"),
+            "hi @User 2 and @User 3!"
+        );
+    }
+}
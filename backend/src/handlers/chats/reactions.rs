@@ -1,8 +1,4 @@
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::State, http::StatusCode, Json};
 use chrono::Utc;
 use diesel::prelude::*;
 use diesel::PgConnection;
@@ -12,10 +8,11 @@ use utoipa_axum::router::OpenApiRouter;
 
 use crate::{
     errors::AppError,
-    extractors::DbConn,
+    extractors::{DbConn, JsonBody, Path},
     handlers::members::check_membership,
+    handlers::ws::messages::{ReactionDeltaPayload, ReactionOp, ServerWsMessage},
     models::{Message, MessageReaction},
-    schema::{group_membership, message_reactions, messages},
+    schema::{message_reactions, messages},
     services::user::lookup_user_avatars,
     utils::auth::CurrentUid,
     AppState,
@@ -41,89 +38,142 @@ fn validate_emoji(input: &str) -> Result<String, AppError> {
     Ok(input.to_string())
 }
 
-fn broadcast_reaction_update(
+/// Reactors named per emoji in a summary; `count` covers the rest.
+const SUMMARY_REACTORS_PER_EMOJI: usize = 5;
+
+/// Per-emoji counts of `(emoji, uid)` rows in reaction order, each naming its
+/// first reactors (profiles left empty). `reacted_by_me` is set for a `viewer`.
+fn summarize_reactions(rows: &[(String, i32)], viewer: Option<i32>) -> Vec<ReactionSummary> {
+    let mut summaries: Vec<ReactionSummary> = Vec::new();
+    for (emoji, uid) in rows {
+        let index = match summaries.iter().position(|s| &s.emoji == emoji) {
+            Some(index) => index,
+            None => {
+                summaries.push(ReactionSummary {
+                    emoji: emoji.clone(),
+                    count: 0,
+                    reacted_by_me: viewer.map(|_| false),
+                    reactors: Some(Vec::new()),
+                });
+                summaries.len() - 1
+            }
+        };
+        let summary = &mut summaries[index];
+        summary.count += 1;
+        if viewer == Some(*uid) {
+            summary.reacted_by_me = Some(true);
+        }
+        let reactors = summary.reactors.get_or_insert_with(Vec::new);
+        if reactors.len() < SUMMARY_REACTORS_PER_EMOJI {
+            reactors.push(ReactionReactor {
+                uid: *uid,
+                name: None,
+                avatar_url: None,
+                sort_index: None,
+            });
+        }
+    }
+    summaries
+}
+
+/// The message's current reactions with reactor profiles filled in.
+fn load_reaction_summaries(
     conn: &mut PgConnection,
     state: &AppState,
-    chat_id: i64,
     message_id: i64,
-) {
-    let counts: Vec<(String, i64)> = message_reactions::table
-        .filter(message_reactions::message_id.eq(message_id))
-        .group_by(message_reactions::emoji)
-        .select((message_reactions::emoji, diesel::dsl::count_star()))
-        .load(conn)
-        .unwrap_or_default();
-
-    // Load reactor UIDs (capped at 5 per emoji)
-    let raw_reactors: Vec<(String, i32)> = message_reactions::table
+    viewer: Option<i32>,
+) -> QueryResult<Vec<ReactionSummary>> {
+    let rows: Vec<(String, i32)> = message_reactions::table
         .filter(message_reactions::message_id.eq(message_id))
+        .order(message_reactions::created_at.asc())
         .select((message_reactions::emoji, message_reactions::user_uid))
-        .load(conn)
-        .unwrap_or_default();
-
-    let mut reactor_uids_map: std::collections::HashMap<String, Vec<i32>> =
-        std::collections::HashMap::new();
-    for (emoji, uid) in &raw_reactors {
-        let entry = reactor_uids_map.entry(emoji.clone()).or_default();
-        if entry.len() < 5 {
-            entry.push(*uid);
-        }
-    }
+        .load(conn)?;
+    let mut summaries = summarize_reactions(&rows, viewer);
 
-    let all_uids: Vec<i32> = reactor_uids_map
-        .values()
-        .flatten()
-        .copied()
+    let all_uids: Vec<i32> = summaries
+        .iter()
+        .flat_map(|s| s.reactors.iter().flatten().map(|r| r.uid))
         .collect::<std::collections::HashSet<i32>>()
         .into_iter()
         .collect();
     let names = load_usernames_by_uids(conn, &all_uids);
     let avatars = lookup_user_avatars(state, &all_uids);
+    for reactor in summaries
+        .iter_mut()
+        .flat_map(|s| s.reactors.iter_mut().flatten())
+    {
+        reactor.name = names.get(&reactor.uid).cloned().flatten();
+        reactor.avatar_url = avatars.get(&reactor.uid).cloned().flatten();
+    }
+    Ok(summaries)
+}
 
-    let reactions: Vec<ReactionSummary> = counts
-        .into_iter()
-        .map(|(emoji, count)| {
-            let reactors = reactor_uids_map.get(&emoji).map(|uids| {
-                uids.iter()
-                    .map(|&uid| ReactionReactor {
-                        uid,
-                        name: names.get(&uid).cloned().flatten(),
-                        avatar_url: avatars.get(&uid).cloned().flatten(),
-                        sort_index: None,
-                    })
-                    .collect()
-            });
-            ReactionSummary {
-                emoji,
-                count,
-                reacted_by_me: None,
-                reactors,
-            }
-        })
-        .collect();
-
-    let member_uids: Vec<i32> = group_membership::table
-        .filter(group_membership::chat_id.eq(chat_id))
-        .select(group_membership::uid)
-        .load(conn)
-        .unwrap_or_default();
+/// Reactions with `emoji` currently stored on the message.
+fn count_emoji_reactions(
+    conn: &mut PgConnection,
+    message_id: i64,
+    emoji: &str,
+) -> QueryResult<i64> {
+    message_reactions::table
+        .filter(message_reactions::message_id.eq(message_id))
+        .filter(message_reactions::emoji.eq(emoji))
+        .count()
+        .get_result(conn)
+}
 
-    let ws_msg = std::sync::Arc::new(
-        crate::handlers::ws::messages::ServerWsMessage::ReactionUpdated(
-            crate::handlers::ws::messages::ReactionUpdatePayload {
-                message_id,
+/// Tell the chat that `uid` added or removed `emoji`. Call after the change
+/// commits: the count sent is read back from the database, never derived
+/// from what the client asked for.
+fn broadcast_reaction_delta(
+    conn: &mut PgConnection,
+    state: &AppState,
+    chat_id: i64,
+    message_id: i64,
+    emoji: String,
+    uid: i32,
+    op: ReactionOp,
+) {
+    let new_count = match count_emoji_reactions(conn, message_id, &emoji) {
+        Ok(count) => count,
+        Err(e) => {
+            tracing::warn!(
                 chat_id,
-                reactions,
-            },
-        ),
-    );
-    state.ws_registry.broadcast_to_uids(&member_uids, ws_msg);
+                message_id,
+                "reaction broadcast: counting reactions failed: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    let member_uids = crate::services::chat::member_uids_or_log(conn, &state.ws_registry, chat_id);
+
+    let ws_msg = std::sync::Arc::new(ServerWsMessage::ReactionDelta(ReactionDeltaPayload {
+        message_id,
+        chat_id,
+        emoji,
+        uid,
+        op,
+        new_count,
+    }));
+    state
+        .ws_registry
+        .broadcast_to_chat(chat_id, &member_uids, ws_msg);
 }
 
+/// Reactors listed per emoji in the details view; `count` stays exact and
+/// `has_more` flags the rest.
+const MAX_REACTORS_PER_EMOJI: i64 = 50;
+
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct ReactionDetailGroup {
     emoji: String,
+    count: i64,
+    /// Earliest reactors, at most `MAX_REACTORS_PER_EMOJI`.
+    uids: Vec<i32>,
+    has_more: bool,
+    /// Profiles for `uids`, in the same order.
     reactors: Vec<ReactionReactor>,
 }
 
@@ -133,6 +183,72 @@ struct ReactionDetailResponse {
     reactions: Vec<ReactionDetailGroup>,
 }
 
+#[derive(QueryableByName)]
+struct ReactionGroupRow {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    emoji: String,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    count: i64,
+    #[diesel(sql_type = diesel::sql_types::Array<diesel::sql_types::Integer>)]
+    uids: Vec<i32>,
+    /// Position of each of `uids` among all of the message's reactions.
+    #[diesel(sql_type = diesel::sql_types::Array<diesel::sql_types::Integer>)]
+    positions: Vec<i32>,
+}
+
+/// Per-emoji counts plus the first `cap` reactors of each, in one grouped
+/// query, ordered by each emoji's first reaction.
+fn load_reaction_groups(
+    conn: &mut PgConnection,
+    message_id: i64,
+    cap: i64,
+) -> Result<Vec<ReactionGroupRow>, diesel::result::Error> {
+    diesel::sql_query(
+        "SELECT r.emoji,
+                COUNT(*)::bigint AS count,
+                (ARRAY_AGG(r.user_uid ORDER BY r.position))[1:$2] AS uids,
+                (ARRAY_AGG(r.position ORDER BY r.position))[1:$2] AS positions
+         FROM (
+             SELECT emoji,
+                    user_uid,
+                    (ROW_NUMBER() OVER (ORDER BY created_at, user_uid) - 1)::int4 AS position
+             FROM message_reactions
+             WHERE message_id = $1
+         ) AS r
+         GROUP BY r.emoji
+         ORDER BY MIN(r.position)",
+    )
+    .bind::<diesel::sql_types::BigInt, _>(message_id)
+    .bind::<diesel::sql_types::BigInt, _>(cap)
+    .load(conn)
+}
+
+fn reaction_detail_group(row: ReactionGroupRow) -> ReactionDetailGroup {
+    let reactors = row
+        .uids
+        .iter()
+        .zip(&row.positions)
+        .map(|(&uid, &position)| ReactionReactor {
+            uid,
+            name: None,
+            avatar_url: None,
+            sort_index: Some(position),
+        })
+        .collect();
+    ReactionDetailGroup {
+        has_more: row.count > row.uids.len() as i64,
+        emoji: row.emoji,
+        count: row.count,
+        uids: row.uids,
+        reactors,
+    }
+}
+
+/// GET /chats/:chat_id/messages/:message_id/reactions — Reactions grouped by emoji.
+///
+/// Each group has the exact count and up to `MAX_REACTORS_PER_EMOJI` of the
+/// earliest reactors; `hasMore` is set when more reacted. Live updates arrive
+/// as `reactionDelta` events; this is the full state to resync from.
 #[utoipa::path(
     get,
     path = "/",
@@ -163,47 +279,21 @@ async fn get_reaction_details(
         .filter(messages::is_published.eq(true))
         .first(conn)
         .optional()?
-        .ok_or(AppError::NotFound("Message not found"))?;
-
-    // Load all reactions for this message
-    let raw: Vec<(String, i32)> = message_reactions::table
-        .filter(message_reactions::message_id.eq(message_id))
-        .order(message_reactions::created_at.asc())
-        .select((message_reactions::emoji, message_reactions::user_uid))
-        .load(conn)?;
-
-    // Group by emoji, preserving order
-    let mut groups: Vec<ReactionDetailGroup> = Vec::new();
-    let mut emoji_index: std::collections::HashMap<String, usize> =
-        std::collections::HashMap::new();
-    let mut all_uids = std::collections::HashSet::new();
+        .ok_or(AppError::MESSAGE_NOT_FOUND)?;
 
-    for (idx, (emoji, uid)) in raw.iter().enumerate() {
-        all_uids.insert(*uid);
-        if let Some(&gi) = emoji_index.get(emoji) {
-            groups[gi].reactors.push(ReactionReactor {
-                uid: *uid,
-                name: None,
-                avatar_url: None,
-                sort_index: Some(idx as i32),
-            });
-        } else {
-            let gi = groups.len();
-            emoji_index.insert(emoji.clone(), gi);
-            groups.push(ReactionDetailGroup {
-                emoji: emoji.clone(),
-                reactors: vec![ReactionReactor {
-                    uid: *uid,
-                    name: None,
-                    avatar_url: None,
-                    sort_index: Some(idx as i32),
-                }],
-            });
-        }
-    }
+    let mut groups: Vec<ReactionDetailGroup> =
+        load_reaction_groups(conn, message_id, MAX_REACTORS_PER_EMOJI)?
+            .into_iter()
+            .map(reaction_detail_group)
+            .collect();
 
     // Resolve names + avatars
-    let uids_vec: Vec<i32> = all_uids.into_iter().collect();
+    let uids_vec: Vec<i32> = groups
+        .iter()
+        .flat_map(|group| group.uids.iter().copied())
+        .collect::<std::collections::HashSet<i32>>()
+        .into_iter()
+        .collect();
     let names = load_usernames_by_uids(conn, &uids_vec);
     let avatars = lookup_user_avatars(&state, &uids_vec);
 
@@ -249,14 +339,14 @@ async fn put_reaction(
         .filter(messages::is_published.eq(true))
         .first(conn)
         .optional()?
-        .ok_or(AppError::NotFound("Message not found"))?;
+        .ok_or(AppError::MESSAGE_NOT_FOUND)?;
 
     // Insert reaction (ON CONFLICT DO NOTHING for idempotency)
-    diesel::insert_into(message_reactions::table)
+    let inserted = diesel::insert_into(message_reactions::table)
         .values(&MessageReaction {
             message_id,
             user_uid: uid,
-            emoji,
+            emoji: emoji.clone(),
             created_at: Utc::now(),
         })
         .on_conflict_do_nothing()
@@ -267,7 +357,18 @@ async fn put_reaction(
         .set(messages::has_reactions.eq(true))
         .execute(conn)?;
 
-    broadcast_reaction_update(conn, &state, chat_id, message_id);
+    // A repeat of an existing reaction changes nothing, so there is no delta.
+    if inserted > 0 {
+        broadcast_reaction_delta(
+            conn,
+            &state,
+            chat_id,
+            message_id,
+            emoji,
+            uid,
+            ReactionOp::Add,
+        );
+    }
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -317,23 +418,181 @@ async fn delete_reaction(
                 .execute(conn)?;
         }
 
-        broadcast_reaction_update(conn, &state, chat_id, message_id);
+        broadcast_reaction_delta(
+            conn,
+            &state,
+            chat_id,
+            message_id,
+            emoji,
+            uid,
+            ReactionOp::Remove,
+        );
     }
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ToggleReactionBody {
+    emoji: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ToggleReactionResponse {
+    /// Whether the caller now has this reaction on the message.
+    reacted: bool,
+    reactions: Vec<ReactionSummary>,
+}
+
+/// POST /chats/:chat_id/messages/:message_id/reactions/toggle — Add the
+/// caller's reaction if missing, remove it otherwise.
+///
+/// The message row is locked for the toggle, so concurrent toggles by the same
+/// user apply one after the other instead of both adding.
+#[utoipa::path(
+    post,
+    path = "/toggle",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("message_id" = i64, Path, description = "Message ID"),
+    ),
+    request_body = ToggleReactionBody,
+    responses(
+        (status = 200, description = "Reactions after the toggle", body = ToggleReactionResponse),
+        (status = 404, description = "Message not found"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn post_toggle_reaction(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path((chat_id, message_id)): Path<(i64, i64)>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<ToggleReactionBody>,
+) -> Result<Json<ToggleReactionResponse>, AppError> {
+    let conn = &mut *conn;
+    let emoji = validate_emoji(&body.emoji)?;
+    check_membership(conn, chat_id, uid)?;
+
+    let reacted = conn.transaction::<bool, AppError, _>(|conn| {
+        messages::table
+            .filter(messages::id.eq(message_id))
+            .filter(messages::chat_id.eq(chat_id))
+            .filter(messages::deleted_at.is_null())
+            .filter(messages::is_published.eq(true))
+            .select(messages::id)
+            .for_update()
+            .first::<i64>(conn)
+            .optional()?
+            .ok_or(AppError::MESSAGE_NOT_FOUND)?;
+
+        let removed = diesel::delete(
+            message_reactions::table
+                .filter(message_reactions::message_id.eq(message_id))
+                .filter(message_reactions::user_uid.eq(uid))
+                .filter(message_reactions::emoji.eq(&emoji)),
+        )
+        .execute(conn)?;
+        if removed > 0 {
+            let remaining: i64 = message_reactions::table
+                .filter(message_reactions::message_id.eq(message_id))
+                .count()
+                .get_result(conn)?;
+            diesel::update(messages::table.filter(messages::id.eq(message_id)))
+                .set(messages::has_reactions.eq(remaining > 0))
+                .execute(conn)?;
+            return Ok(false);
+        }
+
+        diesel::insert_into(message_reactions::table)
+            .values(&MessageReaction {
+                message_id,
+                user_uid: uid,
+                emoji: emoji.clone(),
+                created_at: Utc::now(),
+            })
+            .execute(conn)?;
+        diesel::update(messages::table.filter(messages::id.eq(message_id)))
+            .set(messages::has_reactions.eq(true))
+            .execute(conn)?;
+        Ok(true)
+    })?;
+
+    let op = if reacted {
+        ReactionOp::Add
+    } else {
+        ReactionOp::Remove
+    };
+    broadcast_reaction_delta(conn, &state, chat_id, message_id, emoji, uid, op);
+    let reactions = load_reaction_summaries(conn, &state, message_id, Some(uid))?;
+
+    Ok(Json(ToggleReactionResponse { reacted, reactions }))
+}
+
 pub fn router() -> OpenApiRouter<crate::AppState> {
     OpenApiRouter::new()
         .routes(utoipa_axum::routes!(get_reaction_details))
+        .routes(utoipa_axum::routes!(post_toggle_reaction))
         .routes(utoipa_axum::routes!(put_reaction, delete_reaction))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::validate_emoji;
+    use super::{reaction_detail_group, validate_emoji, ReactionGroupRow, MAX_REACTORS_PER_EMOJI};
+    use super::{summarize_reactions, SUMMARY_REACTORS_PER_EMOJI};
     use crate::errors::AppError;
 
+    fn rows(reactions: &[(&str, i32)]) -> Vec<(String, i32)> {
+        reactions
+            .iter()
+            .map(|&(emoji, uid)| (emoji.to_string(), uid))
+            .collect()
+    }
+
+    fn counts(rows: &[(String, i32)], viewer: i32) -> Vec<(String, i64, Option<bool>)> {
+        summarize_reactions(rows, Some(viewer))
+            .into_iter()
+            .map(|s| (s.emoji, s.count, s.reacted_by_me))
+            .collect()
+    }
+
+    #[test]
+    fn toggling_twice_restores_the_reaction_summary() {
+        let before = rows(&[("👍", 3), ("❤️", 4), ("👍", 5)]);
+        let mut toggled = before.clone();
+        toggled.push(("👍".to_string(), 7));
+
+        assert_eq!(
+            counts(&toggled, 7),
+            vec![
+                ("👍".to_string(), 3, Some(true)),
+                ("❤️".to_string(), 1, Some(false)),
+            ]
+        );
+
+        toggled.retain(|(emoji, uid)| !(emoji == "👍" && *uid == 7));
+        assert_eq!(counts(&toggled, 7), counts(&before, 7));
+        assert!(counts(&before, 7)
+            .iter()
+            .all(|(_, _, me)| *me == Some(false)));
+    }
+
+    #[test]
+    fn summaries_name_only_the_first_reactors() {
+        let many: Vec<(String, i32)> = (1..=8).map(|uid| ("🎉".to_string(), uid)).collect();
+        let summaries = summarize_reactions(&many, None);
+
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].count, 8);
+        assert_eq!(summaries[0].reacted_by_me, None);
+        let reactors = summaries[0].reactors.as_ref().unwrap();
+        assert_eq!(reactors.len(), SUMMARY_REACTORS_PER_EMOJI);
+        assert_eq!(reactors[0].uid, 1);
+    }
+
     #[test]
     fn reaction_emoji_must_be_exactly_one_grapheme() {
         for emoji in ["🙂", "👍🏽", "👨‍👩‍👧‍👦", "❤️"] {
@@ -350,4 +609,217 @@ mod tests {
             ));
         }
     }
+
+    #[test]
+    fn popular_reaction_is_capped_but_counted_exactly() {
+        let cap = MAX_REACTORS_PER_EMOJI as i32;
+        let group = reaction_detail_group(ReactionGroupRow {
+            emoji: "👍".to_string(),
+            count: 1_200,
+            uids: (1..=cap).collect(),
+            positions: (0..cap).map(|i| i * 2).collect(),
+        });
+
+        assert_eq!(group.count, 1_200);
+        assert_eq!(group.uids.len(), MAX_REACTORS_PER_EMOJI as usize);
+        assert!(group.has_more);
+        assert_eq!(group.reactors.len(), group.uids.len());
+        assert_eq!(group.reactors[1].uid, 2);
+        assert_eq!(group.reactors[1].sort_index, Some(2));
+    }
+
+    #[test]
+    fn reaction_under_the_cap_has_no_more() {
+        let group = reaction_detail_group(ReactionGroupRow {
+            emoji: "❤️".to_string(),
+            count: 2,
+            uids: vec![4, 9],
+            positions: vec![0, 3],
+        });
+
+        assert!(!group.has_more);
+        assert_eq!(group.uids, vec![4, 9]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reaction_deltas_match_the_stored_counts() {
+        use axum::http::{Method, StatusCode};
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (alice, bob) = (900_401, 900_402);
+        app.seed_user(alice);
+        app.seed_user(bob);
+        let chat_id = app.seed_chat("Reactions").await;
+        app.seed_membership(chat_id, alice, crate::models::GroupRole::Member);
+        app.seed_membership(chat_id, bob, crate::models::GroupRole::Member);
+
+        let (status, sent) = app
+            .request(
+                Method::POST,
+                &format!("/chats/{chat_id}/messages"),
+                alice,
+                Some(serde_json::json!({
+                    "message": "react to me",
+                    "messageType": "text",
+                    "clientGeneratedId": "reactions-1",
+                })),
+            )
+            .await;
+        assert!(status.is_success(), "{status}: {sent}");
+        let message_id = sent["id"].as_str().unwrap().to_string();
+        let reactions_uri = format!("/chats/{chat_id}/messages/{message_id}/reactions");
+
+        let (_entry, mut rx, _) = app.state.ws_registry.register(bob);
+        while rx.try_recv().is_ok() {}
+
+        let thumbs = urlencoding::encode("👍").into_owned();
+        let steps = [
+            (
+                Method::PUT,
+                alice,
+                format!("{reactions_uri}/{thumbs}"),
+                None,
+            ),
+            (Method::PUT, bob, format!("{reactions_uri}/{thumbs}"), None),
+            (
+                Method::POST,
+                alice,
+                format!("{reactions_uri}/toggle"),
+                Some(serde_json::json!({ "emoji": "👍" })),
+            ),
+            (
+                Method::DELETE,
+                bob,
+                format!("{reactions_uri}/{thumbs}"),
+                None,
+            ),
+        ];
+        let expected = [
+            ("add", alice, 1),
+            ("add", bob, 2),
+            ("remove", alice, 1),
+            ("remove", bob, 0),
+        ];
+        for ((method, uid, uri, body), (op, delta_uid, count)) in steps.into_iter().zip(expected) {
+            let (status, response) = app.request(method, &uri, uid, body).await;
+            assert!(status.is_success(), "{uri}: {status} {response}");
+
+            let frame = rx.try_recv().expect("one delta per change");
+            let event: serde_json::Value = serde_json::from_str(frame.as_str()).unwrap();
+            assert_eq!(event["type"], "reactionDelta");
+            let delta = &event["payload"];
+            assert_eq!(delta["messageId"], message_id.as_str());
+            assert_eq!(delta["emoji"], "👍");
+            assert_eq!(delta["op"], op);
+            assert_eq!(delta["uid"], delta_uid);
+            assert_eq!(delta["newCount"], count);
+
+            let (status, details) = app.request(Method::GET, &reactions_uri, bob, None).await;
+            assert_eq!(status, StatusCode::OK, "{details}");
+            let stored = details["reactions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|group| group["emoji"] == "👍")
+                .map_or(0, |group| group["count"].as_i64().unwrap());
+            assert_eq!(delta["newCount"], stored);
+        }
+
+        // Adding a reaction the user already has changes nothing and sends nothing.
+        let again = format!("{reactions_uri}/{thumbs}");
+        app.request(Method::PUT, &again, alice, None).await;
+        assert!(rx.try_recv().is_ok());
+        app.request(Method::PUT, &again, alice, None).await;
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn toggling_twice_restores_the_original_reactions() {
+        use axum::http::{Method, StatusCode};
+        use diesel::prelude::*;
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (alice, bob) = (901_211, 901_212);
+        app.seed_user(alice);
+        app.seed_user(bob);
+        let chat_id = app.seed_chat("Toggles").await;
+        app.seed_membership(chat_id, alice, crate::models::GroupRole::Member);
+        app.seed_membership(chat_id, bob, crate::models::GroupRole::Member);
+
+        let (status, sent) = app
+            .request(
+                Method::POST,
+                &format!("/chats/{chat_id}/messages"),
+                alice,
+                Some(serde_json::json!({
+                    "message": "toggle me",
+                    "messageType": "text",
+                    "clientGeneratedId": "toggles-1",
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{sent}");
+        let message_id = sent["id"].as_str().unwrap().to_string();
+        let reactions_uri = format!("/chats/{chat_id}/messages/{message_id}/reactions");
+        let heart = urlencoding::encode("❤️").into_owned();
+        let (status, body) = app
+            .request(Method::PUT, &format!("{reactions_uri}/{heart}"), bob, None)
+            .await;
+        assert!(status.is_success(), "{status}: {body}");
+
+        let app = &app;
+        let listed_reactions = || async move {
+            let (status, page) = app
+                .request(
+                    Method::GET,
+                    &format!("/chats/{chat_id}/messages"),
+                    alice,
+                    None,
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{page}");
+            page["messages"][0]["reactions"].clone()
+        };
+        let toggle_uri = &format!("{reactions_uri}/toggle");
+        let toggle = || async move {
+            let (status, body) = app
+                .request(
+                    Method::POST,
+                    toggle_uri,
+                    alice,
+                    Some(serde_json::json!({ "emoji": "👍" })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            body
+        };
+        let before = listed_reactions().await;
+        assert_eq!(before.as_array().map(Vec::len), Some(1), "{before}");
+
+        let added = toggle().await;
+        assert_eq!(added["reacted"], true);
+        let thumbs = added["reactions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|summary| summary["emoji"] == "👍")
+            .expect("the toggle added a thumbs up");
+        assert_eq!(thumbs["count"], 1);
+        assert_eq!(thumbs["reactedByMe"], true);
+
+        let removed = toggle().await;
+        assert_eq!(removed["reacted"], false);
+        assert_eq!(removed["reactions"], before);
+        assert_eq!(listed_reactions().await, before);
+        let has_reactions: bool = crate::schema::messages::table
+            .find(message_id.parse::<i64>().unwrap())
+            .select(crate::schema::messages::has_reactions)
+            .get_result(&mut app.conn())
+            .unwrap();
+        assert!(has_reactions, "bob's heart is still there");
+    }
 }
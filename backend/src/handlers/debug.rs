@@ -0,0 +1,180 @@
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa_axum::router::OpenApiRouter;
+
+use crate::errors::AppError;
+use crate::extractors::{DbConn, Path, Query};
+use crate::services::authz::{Action as AuthzAction, Resource as AuthzResource};
+use crate::services::ws_registry::{AppPresenceState, ConnStat};
+use crate::utils::{auth::CurrentUid, ids};
+use crate::AppState;
+
+#[derive(serde::Deserialize)]
+struct IdPath {
+    id: i64,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct DecodedIdResponse {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    id: i64,
+    timestamp: DateTime<Utc>,
+    machine_id: u64,
+    sequence: u64,
+}
+
+impl DecodedIdResponse {
+    fn new(id: i64) -> Self {
+        let decoded = ids::decode(id);
+        Self {
+            id,
+            timestamp: decoded.timestamp,
+            machine_id: decoded.machine_id,
+            sequence: decoded.sequence,
+        }
+    }
+}
+
+/// GET /debug/id/:id — Decode a snowflake id into its creation time, node and
+/// sequence, to line ids up with logs without a database lookup.
+#[utoipa::path(
+    get,
+    path = "/debug/id/{id}",
+    tag = "debug",
+    params(
+        ("id" = String, Path, description = "Any generated id (chat, message, attachment, ...)"),
+    ),
+    responses(
+        (status = OK, body = DecodedIdResponse),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn get_decoded_id(
+    CurrentUid(_uid): CurrentUid,
+    Path(IdPath { id }): Path<IdPath>,
+) -> Result<Json<DecodedIdResponse>, AppError> {
+    Ok(Json(DecodedIdResponse::new(id)))
+}
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+struct ConnectionsQuery {
+    /// User whose live WebSocket connections to list.
+    uid: i32,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ConnectionStatResponse {
+    conn_id: u64,
+    age_secs: u64,
+    messages_sent: u64,
+    last_send_at: Option<DateTime<Utc>>,
+    last_ping_at: Option<DateTime<Utc>>,
+    app_active: bool,
+}
+
+fn unix_secs_to_datetime(secs: u64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(i64::try_from(secs).ok()?, 0)
+}
+
+impl From<ConnStat> for ConnectionStatResponse {
+    fn from(stat: ConnStat) -> Self {
+        Self {
+            conn_id: stat.conn_id,
+            age_secs: stat.age_secs,
+            messages_sent: stat.messages_sent,
+            last_send_at: stat.last_send_at.and_then(unix_secs_to_datetime),
+            last_ping_at: unix_secs_to_datetime(stat.last_ping_at),
+            app_active: stat.app_state == AppPresenceState::Active,
+        }
+    }
+}
+
+/// GET /debug/connections?uid= — Delivery counters for a user's live sockets.
+///
+/// Requires the global `permission.all` permission. Shows how many frames
+/// each connection has written and when, to tell a stuck socket from one
+/// that simply received nothing.
+#[utoipa::path(
+    get,
+    path = "/debug/connections",
+    tag = "debug",
+    params(ConnectionsQuery),
+    responses(
+        (status = OK, body = Vec<ConnectionStatResponse>),
+        (status = FORBIDDEN, description = "Permission required"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn get_connections(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    mut conn: DbConn,
+    Query(query): Query<ConnectionsQuery>,
+) -> Result<Json<Vec<ConnectionStatResponse>>, AppError> {
+    let conn = &mut *conn;
+
+    state.authz_service.require_permission(
+        conn,
+        uid,
+        AuthzAction::PermissionAll,
+        AuthzResource::Global,
+    )?;
+
+    Ok(Json(
+        state
+            .ws_registry
+            .connection_stats(query.uid)
+            .into_iter()
+            .map(ConnectionStatResponse::from)
+            .collect(),
+    ))
+}
+
+/// Operator diagnostics; only built with the `debug-endpoints` feature.
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(utoipa_axum::routes!(get_decoded_id))
+        .routes(utoipa_axum::routes!(get_connections))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionStatResponse, DecodedIdResponse};
+    use crate::services::ws_registry::{AppPresenceState, ConnStat};
+    use serde_json::json;
+
+    #[test]
+    fn decoded_id_response_uses_string_id_and_rfc3339_time() {
+        // 2023-11-14T22:13:20.123Z on node 5, sequence 42.
+        let id = (1_700_000_000_123_i64 << 16) | (5 << 12) | 42;
+        let value = serde_json::to_value(DecodedIdResponse::new(id)).expect("serialize");
+
+        assert_eq!(value["id"], json!(id.to_string()));
+        assert_eq!(value["timestamp"], json!("2023-11-14T22:13:20.123Z"));
+        assert_eq!(value["machineId"], json!(5));
+        assert_eq!(value["sequence"], json!(42));
+    }
+
+    #[test]
+    fn connection_stat_reports_unsent_connections_without_last_send() {
+        let value = serde_json::to_value(ConnectionStatResponse::from(ConnStat {
+            conn_id: 3,
+            age_secs: 90,
+            messages_sent: 0,
+            last_send_at: None,
+            last_ping_at: 1_700_000_000,
+            app_state: AppPresenceState::Inactive,
+        }))
+        .expect("serialize");
+
+        assert_eq!(value["connId"], json!(3));
+        assert_eq!(value["messagesSent"], json!(0));
+        assert_eq!(value["lastSendAt"], json!(null));
+        assert_eq!(value["lastPingAt"], json!("2023-11-14T22:13:20Z"));
+        assert_eq!(value["appActive"], json!(false));
+    }
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Json, Path, Query, State},
+    extract::{Json, State},
     http::StatusCode,
     response::IntoResponse,
 };
@@ -12,13 +12,21 @@ use std::collections::BTreeMap;
 use utoipa_axum::router::OpenApiRouter;
 
 use crate::errors::AppError;
-use crate::extractors::DbConn;
-use crate::handlers::members::{check_membership, require_admin_role};
+use crate::extractors::{DbConn, JsonBody, Path, Query};
+use crate::handlers::chats::{moderate_message_text, validate_message_text};
+use crate::handlers::members::{
+    broadcast_member_event, check_membership, is_banned, map_membership_conflict,
+    member_limit_reached, require_admin_role, send_welcome_message, ALREADY_JOINED,
+    MEMBER_LIMIT_REACHED, USER_BANNED,
+};
+use crate::handlers::ws::messages::{
+    ChatDeletedPayload, ChatUpdatedPayload, MemberUpdatePayload, ServerWsMessage,
+};
 use crate::models::{
-    GroupJoinReason, GroupRole, GroupVisibility, Media, MediaPurpose, NewGroup, NewGroupMembership,
-    NewMedia, UpdateGroup,
+    ChatKind, GroupJoinReason, GroupRole, GroupVisibility, Media, MediaPurpose, ModerationPolicy,
+    NewGroup, NewGroupMembership, NewMedia, UpdateGroup,
 };
-use crate::schema::{group_membership, groups, media};
+use crate::schema::{group_membership, groups, invites, media};
 use crate::services::authz::{Action as AuthzAction, Resource as AuthzResource};
 use crate::services::media::{build_public_object_url, build_storage_key, presign_public_upload};
 use crate::utils::ids;
@@ -27,6 +35,9 @@ use crate::AppState;
 
 /// Maximum mute duration: 7 days in seconds.
 const MAX_MUTE_DURATION_SECS: i64 = 7 * 24 * 3600;
+const MAX_SLOW_MODE_SECS: i32 = 6 * 3600;
+/// Ten years; longer windows are indistinguishable from keeping forever.
+const MAX_RETENTION_DAYS: i32 = 3650;
 const MAX_GROUP_AVATAR_BYTES: i64 = 10 * 1024 * 1024;
 const MAX_GROUP_SELECTOR_LIMIT: i64 = 50;
 
@@ -39,6 +50,8 @@ fn indefinite_mute_until() -> DateTime<Utc> {
 #[serde(rename_all = "camelCase")]
 pub(super) struct CreateChatBody {
     name: Option<String>,
+    /// Defaults to public.
+    visibility: Option<GroupVisibility>,
 }
 
 #[derive(Serialize, utoipa::ToSchema)]
@@ -48,6 +61,7 @@ pub(super) struct CreateChatResponse {
     #[schema(value_type = String)]
     id: i64,
     name: Option<String>,
+    #[serde(with = "crate::serde_timestamp")]
     created_at: DateTime<Utc>,
 }
 
@@ -69,11 +83,38 @@ pub(super) struct GroupInfoResponse {
     avatar_image_id: Option<i64>,
     avatar: Option<String>,
     visibility: GroupVisibility,
+    kind: ChatKind,
+    moderation_policy: ModerationPolicy,
+    welcome_message: Option<String>,
+    welcome_message_as_dm: bool,
+    slow_mode_secs: i32,
+    retention_days: i32,
+    member_count: i64,
+    #[serde(with = "crate::serde_timestamp")]
     created_at: DateTime<Utc>,
     muted_until: Option<DateTime<Utc>>,
     my_role: Option<GroupRole>,
 }
 
+impl GroupInfoResponse {
+    /// The chat-wide fields, shared by every member's view of the chat.
+    fn chat_updated_payload(&self) -> ChatUpdatedPayload {
+        ChatUpdatedPayload {
+            chat_id: self.id,
+            name: self.name.clone(),
+            description: self.description.clone(),
+            avatar_image_id: self.avatar_image_id,
+            avatar: self.avatar.clone(),
+            visibility: self.visibility,
+            moderation_policy: self.moderation_policy,
+            welcome_message: self.welcome_message.clone(),
+            welcome_message_as_dm: self.welcome_message_as_dm,
+            slow_mode_secs: self.slow_mode_secs,
+            retention_days: self.retention_days,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, serde::Deserialize, PartialEq, Eq, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
 enum GroupSearchMode {
@@ -119,6 +160,7 @@ struct GroupSelectorItem {
     avatar: Option<String>,
     visibility: GroupVisibility,
     role: Option<GroupRole>,
+    member_count: i64,
 }
 
 #[derive(Serialize, utoipa::ToSchema)]
@@ -148,6 +190,30 @@ pub(super) struct UpdateChatBody {
     #[schema(value_type = Option<String>)]
     avatar_image_id: Option<Option<i64>>,
     visibility: Option<GroupVisibility>,
+    moderation_policy: Option<ModerationPolicy>,
+    /// Posted as a system message when a member joins; `{username}` is
+    /// substituted. Empty disables it. Moderated and length-checked like a
+    /// message when saved.
+    welcome_message: Option<String>,
+    /// Send the welcome message to the new member's direct chat with the
+    /// chat's longest-standing admin instead of posting it in the chat.
+    welcome_message_as_dm: Option<bool>,
+    /// Seconds members must wait between messages; 0 turns slow mode off.
+    slow_mode_secs: Option<i32>,
+    /// Days to keep messages before the retention sweeper deletes them; 0 or
+    /// null keeps them forever.
+    #[serde(default, deserialize_with = "deserialize_retention_days")]
+    retention_days: Option<i32>,
+}
+
+/// An explicit `null` turns retention off, same as 0; an absent field leaves it unchanged.
+fn deserialize_retention_days<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Some(
+        <Option<i32> as serde::Deserialize>::deserialize(deserializer)?.unwrap_or(0),
+    ))
 }
 
 #[derive(serde::Deserialize, utoipa::ToSchema)]
@@ -226,10 +292,11 @@ pub(super) fn load_group_info(
 
     let group: crate::models::Group = groups::table
         .filter(groups_dsl::id.eq(chat_id))
+        .filter(groups_dsl::deleted_at.is_null())
         .select(crate::models::Group::as_select())
         .first(conn)
         .optional()?
-        .ok_or(AppError::NotFound("Chat not found"))?;
+        .ok_or(AppError::CHAT_NOT_FOUND)?;
 
     let avatar_image = match group.avatar_image_id {
         Some(avatar_image_id) => media::table
@@ -244,18 +311,25 @@ pub(super) fn load_group_info(
         None => None,
     };
 
-    let my_role = load_requester_group_role(conn, chat_id, requester_uid)?;
-
-    let muted_until: Option<DateTime<Utc>> = group_membership::table
+    // The requester's own row: their role and mute state, read together.
+    let membership: Option<(GroupRole, Option<DateTime<Utc>>)> = group_membership::table
         .filter(
             group_membership::chat_id
                 .eq(chat_id)
                 .and(group_membership::uid.eq(requester_uid)),
         )
-        .select(group_membership::muted_until)
+        .select((group_membership::role, group_membership::muted_until))
         .first(conn)
-        .optional()?
-        .flatten();
+        .optional()?;
+    let (my_role, muted_until) = match membership {
+        Some((role, muted_until)) => (Some(role), muted_until),
+        None => (None, None),
+    };
+
+    let member_count: i64 = group_membership::table
+        .filter(group_membership::chat_id.eq(chat_id))
+        .count()
+        .get_result(conn)?;
 
     Ok(GroupInfoResponse {
         id: group.id,
@@ -267,6 +341,13 @@ pub(super) fn load_group_info(
             .filter(|image| image.deleted_at.is_none())
             .map(|image| build_public_object_url(state, &image.storage_key)),
         visibility: group.visibility,
+        kind: group.kind,
+        moderation_policy: group.moderation_policy,
+        welcome_message: group.welcome_message,
+        welcome_message_as_dm: group.welcome_message_as_dm,
+        slow_mode_secs: group.slow_mode_secs,
+        retention_days: group.retention_days,
+        member_count,
         created_at: group.created_at,
         muted_until,
         my_role,
@@ -288,7 +369,7 @@ async fn post_group(
     CurrentUid(uid): CurrentUid,
     State(state): State<AppState>,
     mut conn: DbConn,
-    Json(body): Json<CreateChatBody>,
+    JsonBody(body): JsonBody<CreateChatBody>,
 ) -> Result<impl IntoResponse, AppError> {
     let conn = &mut *conn;
 
@@ -310,27 +391,30 @@ async fn post_group(
         .filter(|s| !s.trim().is_empty())
         .unwrap_or_else(String::new);
 
-    diesel::insert_into(groups::table)
-        .values(&NewGroup {
-            id,
-            name: name.clone(),
-            description: None,
-            avatar_image_id: None,
-            created_at: now,
-            visibility: GroupVisibility::Public,
-        })
-        .execute(conn)?;
-
-    diesel::insert_into(group_membership::table)
-        .values(&NewGroupMembership {
-            chat_id: id,
-            uid,
-            role: GroupRole::Admin,
-            joined_at: now,
-            join_reason: GroupJoinReason::Creator,
-            join_reason_extra: None,
-        })
-        .execute(conn)?;
+    // The creator's membership must land with the group, or nobody can ever
+    // administer the chat.
+    conn.transaction::<_, AppError, _>(|conn| {
+        diesel::insert_into(groups::table)
+            .values(&NewGroup {
+                visibility: body.visibility.unwrap_or(GroupVisibility::Public),
+                ..NewGroup::new(id, name.clone(), now)
+            })
+            .execute(conn)?;
+
+        map_membership_conflict(
+            diesel::insert_into(group_membership::table)
+                .values(&NewGroupMembership {
+                    chat_id: id,
+                    uid,
+                    role: GroupRole::Admin,
+                    joined_at: now,
+                    join_reason: GroupJoinReason::Creator,
+                    join_reason_extra: None,
+                })
+                .execute(conn),
+        )?;
+        Ok(())
+    })?;
 
     Ok((
         StatusCode::CREATED,
@@ -381,6 +465,11 @@ async fn get_groups(
         Option<String>,
         GroupVisibility,
         Option<GroupRole>,
+        i64,
+    );
+
+    let member_count_sq = diesel::dsl::sql::<diesel::sql_types::BigInt>(
+        "(SELECT count(*) FROM group_membership AS counted WHERE counted.chat_id = groups.id)",
     );
 
     let mut query = groups::table
@@ -401,13 +490,16 @@ async fn get_groups(
             media::storage_key.nullable(),
             groups::visibility,
             group_membership::role.nullable(),
+            member_count_sq,
         ))
         .into_boxed();
 
     query = match scope {
         GroupSelectorScope::Joined => query.filter(group_membership::uid.is_not_null()),
         GroupSelectorScope::Manageable => query.filter(group_membership::role.eq(GroupRole::Admin)),
-        GroupSelectorScope::Public => query.filter(groups::visibility.eq(GroupVisibility::Public)),
+        GroupSelectorScope::Public => query
+            .filter(groups::visibility.eq(GroupVisibility::Public))
+            .filter(groups::deleted_at.is_null()),
     };
 
     if let Some(after) = q.after {
@@ -430,19 +522,22 @@ async fn get_groups(
     let has_more = rows.len() as i64 > limit;
     let page_rows: Vec<Row> = rows.into_iter().take(limit as usize).collect();
     let next_cursor = has_more
-        .then(|| page_rows.last().map(|(id, _, _, _, _, _)| *id))
+        .then(|| page_rows.last().map(|(id, ..)| *id))
         .flatten();
 
     let groups = page_rows
         .into_iter()
         .map(
-            |(id, name, description, avatar_key, visibility, role)| GroupSelectorItem {
-                id,
-                name,
-                description,
-                avatar: avatar_key.map(|key| build_public_object_url(&state, &key)),
-                visibility,
-                role,
+            |(id, name, description, avatar_key, visibility, role, member_count)| {
+                GroupSelectorItem {
+                    id,
+                    name,
+                    description,
+                    avatar: avatar_key.map(|key| build_public_object_url(&state, &key)),
+                    visibility,
+                    role,
+                    member_count,
+                }
             },
         )
         .collect();
@@ -498,7 +593,7 @@ async fn post_avatar_upload_url(
     State(state): State<AppState>,
     Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
     mut conn: DbConn,
-    Json(payload): Json<AvatarUploadUrlRequest>,
+    JsonBody(payload): JsonBody<AvatarUploadUrlRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     let conn = &mut *conn;
 
@@ -525,6 +620,7 @@ async fn post_avatar_upload_url(
         &state.s3_bucket_name,
         &storage_key,
         &payload.content_type,
+        payload.size,
         chrono::Duration::minutes(15),
     )
     .await?;
@@ -555,7 +651,27 @@ async fn post_avatar_upload_url(
     ))
 }
 
+fn validate_slow_mode_secs(slow_mode_secs: i32) -> Result<(), AppError> {
+    if !(0..=MAX_SLOW_MODE_SECS).contains(&slow_mode_secs) {
+        return Err(AppError::BadRequest(
+            "Slow mode must be between 0 and 21600 seconds",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_retention_days(retention_days: i32) -> Result<(), AppError> {
+    if !(0..=MAX_RETENTION_DAYS).contains(&retention_days) {
+        return Err(AppError::BadRequest(
+            "Retention must be between 0 and 3650 days",
+        ));
+    }
+    Ok(())
+}
+
 /// PATCH /group/:chat_id — Update chat metadata (admin only).
+///
+/// Members get a `chatUpdated` event with the new chat-wide fields.
 #[utoipa::path(
     patch,
     path = "/{chat_id}",
@@ -574,7 +690,7 @@ async fn patch_group(
     State(state): State<AppState>,
     Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
     mut conn: DbConn,
-    Json(body): Json<UpdateChatBody>,
+    JsonBody(body): JsonBody<UpdateChatBody>,
 ) -> Result<Json<GroupInfoResponse>, AppError> {
     let conn = &mut *conn;
 
@@ -585,7 +701,7 @@ async fn patch_group(
         .select(crate::models::Group::as_select())
         .first(conn)
         .optional()?
-        .ok_or(AppError::NotFound("Chat not found"))?;
+        .ok_or(AppError::CHAT_NOT_FOUND)?;
 
     if let Some(Some(image_id)) = body.avatar_image_id {
         let owned_image_exists = media::table
@@ -602,23 +718,52 @@ async fn patch_group(
         }
     }
 
+    if let Some(slow_mode_secs) = body.slow_mode_secs {
+        validate_slow_mode_secs(slow_mode_secs)?;
+    }
+    if let Some(retention_days) = body.retention_days {
+        validate_retention_days(retention_days)?;
+    }
+
     use crate::schema::groups::dsl as groups_dsl;
     let changeset = UpdateGroup {
         name: body.name,
         description: body.description,
         visibility: body.visibility,
+        moderation_policy: body.moderation_policy,
+        welcome_message: body.welcome_message,
+        welcome_message_as_dm: body.welcome_message_as_dm,
+        slow_mode_secs: body.slow_mode_secs,
+        retention_days: body.retention_days,
     };
     let has_metadata_changes = changeset.name.is_some()
         || changeset.description.is_some()
-        || changeset.visibility.is_some();
-
-    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        || changeset.visibility.is_some()
+        || changeset.moderation_policy.is_some()
+        || changeset.welcome_message.is_some()
+        || changeset.welcome_message_as_dm.is_some()
+        || changeset.slow_mode_secs.is_some()
+        || changeset.retention_days.is_some();
+
+    conn.transaction::<_, AppError, _>(|conn| {
         if has_metadata_changes {
             diesel::update(groups::table.filter(groups_dsl::id.eq(chat_id)))
                 .set(&changeset)
                 .execute(conn)?;
         }
 
+        // Checked after the update so a policy changed in the same request
+        // applies. A masked template is stored masked.
+        if let Some(template) = changeset.welcome_message.filter(|t| !t.trim().is_empty()) {
+            validate_message_text(Some(&template), false, state.max_message_length)?;
+            let moderated = moderate_message_text(conn, &state, chat_id, Some(template.clone()))?;
+            if moderated.as_deref() != Some(template.as_str()) {
+                diesel::update(groups::table.filter(groups_dsl::id.eq(chat_id)))
+                    .set(groups_dsl::welcome_message.eq(moderated))
+                    .execute(conn)?;
+            }
+        }
+
         if let Some(next_avatar_image_id) = body.avatar_image_id {
             diesel::update(groups::table.filter(groups_dsl::id.eq(chat_id)))
                 .set(groups_dsl::avatar_image_id.eq(next_avatar_image_id))
@@ -636,7 +781,125 @@ async fn patch_group(
         Ok(())
     })?;
 
-    Ok(Json(load_group_info(conn, &state, chat_id, uid)?))
+    let info = load_group_info(conn, &state, chat_id, uid)?;
+    let member_uids = crate::services::chat::member_uids_or_log(conn, &state.ws_registry, chat_id);
+    state.ws_registry.broadcast_to_chat(
+        chat_id,
+        &member_uids,
+        std::sync::Arc::new(ServerWsMessage::ChatUpdated(info.chat_updated_payload())),
+    );
+
+    Ok(Json(info))
+}
+
+/// The locked row's `deleted_at`: 404 when the chat is missing or already
+/// deleted by a concurrent request.
+fn check_group_deletable(deleted_at: Option<Option<DateTime<Utc>>>) -> Result<(), AppError> {
+    match deleted_at {
+        Some(None) => Ok(()),
+        _ => Err(AppError::CHAT_NOT_FOUND),
+    }
+}
+
+/// DELETE /group/:chat_id — Soft-delete a chat (admin only).
+///
+/// Marks the chat deleted, revokes its invites and clears the roster so it
+/// drops out of every listing. Messages are kept for audit but are no longer
+/// served, since reads require a membership.
+#[utoipa::path(
+    delete,
+    path = "/{chat_id}",
+    tag = "groups",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+    ),
+    responses(
+        (status = NO_CONTENT, description = "Chat deleted"),
+        (status = FORBIDDEN, description = "Not an admin of the chat"),
+        (status = NOT_FOUND, description = "Chat not found"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn delete_group(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+) -> Result<StatusCode, AppError> {
+    soft_delete_chat(&mut conn, &state, chat_id, uid)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /chats/:chat_id — Soft-delete a chat (admin only); the same as
+/// `DELETE /group/:chat_id`.
+#[utoipa::path(
+    delete,
+    path = "/",
+    tag = "chats",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+    ),
+    responses(
+        (status = NO_CONTENT, description = "Chat deleted"),
+        (status = FORBIDDEN, description = "Not an admin of the chat"),
+        (status = NOT_FOUND, description = "Chat not found"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+pub(super) async fn delete_chat(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+) -> Result<StatusCode, AppError> {
+    soft_delete_chat(&mut conn, &state, chat_id, uid)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn soft_delete_chat(
+    conn: &mut PgConnection,
+    state: &AppState,
+    chat_id: i64,
+    uid: i32,
+) -> Result<(), AppError> {
+    require_admin_role(conn, chat_id, uid)?;
+    let member_uids = conn.transaction::<_, AppError, _>(|conn| {
+        let deleted_at: Option<Option<DateTime<Utc>>> = groups::table
+            .filter(groups::id.eq(chat_id))
+            .select(groups::deleted_at)
+            .for_update()
+            .first(conn)
+            .optional()?;
+        check_group_deletable(deleted_at)?;
+
+        let now = Utc::now();
+        diesel::update(groups::table.filter(groups::id.eq(chat_id)))
+            .set(groups::deleted_at.eq(Some(now)))
+            .execute(conn)?;
+        diesel::update(
+            invites::table.filter(
+                invites::chat_id
+                    .eq(chat_id)
+                    .and(invites::revoked_at.is_null()),
+            ),
+        )
+        .set(invites::revoked_at.eq(Some(now)))
+        .execute(conn)?;
+
+        let member_uids: Vec<i32> =
+            diesel::delete(group_membership::table.filter(group_membership::chat_id.eq(chat_id)))
+                .returning(group_membership::uid)
+                .get_results(conn)?;
+        Ok(member_uids)
+    })?;
+    state.ws_registry.invalidate_chat_members(chat_id);
+
+    state.ws_registry.broadcast_to_uids(
+        &member_uids,
+        std::sync::Arc::new(ServerWsMessage::ChatDeleted(ChatDeletedPayload { chat_id })),
+    );
+
+    Ok(())
 }
 
 /// PUT /group/:chat_id/mute — Mute notifications for a chat.
@@ -655,9 +918,10 @@ async fn patch_group(
 )]
 async fn put_mute(
     CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
     Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
     mut conn: DbConn,
-    Json(body): Json<MuteBody>,
+    JsonBody(body): JsonBody<MuteBody>,
 ) -> Result<Json<MuteResponse>, AppError> {
     let conn = &mut *conn;
 
@@ -679,6 +943,7 @@ async fn put_mute(
     )
     .set(gm_dsl::muted_until.eq(muted_until))
     .execute(conn)?;
+    state.ws_registry.invalidate_chat_members(chat_id);
 
     Ok(Json(MuteResponse { muted_until }))
 }
@@ -698,6 +963,7 @@ async fn put_mute(
 )]
 async fn delete_mute(
     CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
     Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
     mut conn: DbConn,
 ) -> Result<StatusCode, AppError> {
@@ -714,15 +980,484 @@ async fn delete_mute(
         gm_dsl::archived.eq(false),
     ))
     .execute(conn)?;
+    state.ws_registry.invalidate_chat_members(chat_id);
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Why the caller may not join a chat on their own, if anything stops them.
+fn join_error(visibility: GroupVisibility, banned: bool, already_member: bool) -> Option<AppError> {
+    if already_member {
+        Some(ALREADY_JOINED)
+    } else if visibility != GroupVisibility::Public {
+        Some(AppError::Forbidden("Chat is not public"))
+    } else if banned {
+        Some(USER_BANNED)
+    } else {
+        None
+    }
+}
+
+/// POST /group/:chat_id/join — Join a public chat as a member.
+#[utoipa::path(
+    post,
+    path = "/{chat_id}/join",
+    tag = "groups",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+    ),
+    responses(
+        (status = CREATED, body = GroupInfoResponse),
+        (status = FORBIDDEN, description = "Chat is not public, or the caller is banned"),
+        (status = NOT_FOUND, description = "Chat not found"),
+        (status = CONFLICT, description = "Already a member, or the chat is full"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn post_join_group(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+) -> Result<(StatusCode, Json<GroupInfoResponse>), AppError> {
+    let conn = &mut *conn;
+
+    let visibility: GroupVisibility = groups::table
+        .filter(groups::id.eq(chat_id))
+        .filter(groups::deleted_at.is_null())
+        .filter(groups::kind.eq(ChatKind::Group))
+        .select(groups::visibility)
+        .first(conn)
+        .optional()?
+        .ok_or(AppError::CHAT_NOT_FOUND)?;
+    let already_member = load_requester_group_role(conn, chat_id, uid)?.is_some();
+    let banned = is_banned(conn, chat_id, uid)?;
+    if let Some(err) = join_error(visibility, banned, already_member) {
+        return Err(err);
+    }
+    if member_limit_reached(conn, &state, chat_id, 1)? {
+        return Err(MEMBER_LIMIT_REACHED);
+    }
+
+    let inserted = diesel::insert_into(group_membership::table)
+        .values(&NewGroupMembership {
+            chat_id,
+            uid,
+            role: GroupRole::Member,
+            joined_at: Utc::now(),
+            join_reason: GroupJoinReason::PublicJoin,
+            join_reason_extra: None,
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    // A concurrent join from another tab won the insert.
+    if inserted == 0 {
+        return Err(ALREADY_JOINED);
+    }
+
+    broadcast_member_event(
+        conn,
+        &state,
+        chat_id,
+        None,
+        ServerWsMessage::MemberAdded(MemberUpdatePayload {
+            chat_id,
+            uid,
+            role: Some(GroupRole::Member),
+        }),
+    );
+    if let Ok(send_result) = crate::handlers::chats::send_prepared_message(
+        conn,
+        &state,
+        crate::handlers::chats::PreparedMessageSend {
+            chat_id,
+            sender_uid: uid,
+            message: Some("joined the chat".to_string()),
+            message_type: crate::models::MessageType::System,
+            sticker_id: None,
+            reply_to_id: None,
+            reply_root_id: None,
+            client_generated_id: uuid::Uuid::new_v4().to_string(),
+            attachment_ids: vec![],
+            update_group_last_message: true,
+            publish_immediately: true,
+            forwarded_from_message_id: None,
+        },
+    )
+    .await
+    {
+        send_result.side_effects.fire(&state);
+    }
+    send_welcome_message(conn, &state, chat_id, uid).await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(load_group_info(conn, &state, chat_id, uid)?),
+    ))
+}
+
 pub fn router() -> OpenApiRouter<crate::AppState> {
     OpenApiRouter::new()
         .routes(utoipa_axum::routes!(get_groups, post_group))
-        .routes(utoipa_axum::routes!(get_group, patch_group))
+        .routes(utoipa_axum::routes!(get_group, patch_group, delete_group))
         .routes(utoipa_axum::routes!(post_avatar_upload_url))
         .routes(utoipa_axum::routes!(put_mute, delete_mute))
+        .routes(utoipa_axum::routes!(post_join_group))
         .nest("/{chat_id}/members", crate::handlers::members::router())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+
+    #[test]
+    fn public_chat_can_be_joined() {
+        assert!(join_error(GroupVisibility::Public, false, false).is_none());
+    }
+
+    #[test]
+    fn private_chat_join_is_forbidden() {
+        assert!(matches!(
+            join_error(GroupVisibility::Private, false, false),
+            Some(AppError::Forbidden("Chat is not public"))
+        ));
+    }
+
+    #[test]
+    fn banned_user_cannot_join_public_chat() {
+        assert_eq!(
+            join_error(GroupVisibility::Public, true, false).map(|e| e.code()),
+            Some(ErrorCode::Banned)
+        );
+    }
+
+    #[test]
+    fn existing_member_join_conflicts() {
+        assert_eq!(
+            join_error(GroupVisibility::Public, false, true).map(|e| e.code()),
+            Some(ErrorCode::AlreadyMember)
+        );
+    }
+
+    #[test]
+    fn retention_days_accepts_zero_up_to_ten_years() {
+        assert!(validate_retention_days(0).is_ok());
+        assert!(validate_retention_days(MAX_RETENTION_DAYS).is_ok());
+        assert!(validate_retention_days(-1).is_err());
+        assert!(validate_retention_days(MAX_RETENTION_DAYS + 1).is_err());
+    }
+
+    #[test]
+    fn unknown_visibility_is_rejected_on_create_and_update() {
+        assert!(serde_json::from_str::<CreateChatBody>(r#"{"visibility":"hidden"}"#).is_err());
+        assert!(serde_json::from_str::<UpdateChatBody>(r#"{"visibility":"Public"}"#).is_err());
+
+        let create: CreateChatBody =
+            serde_json::from_str(r#"{"name":"x","visibility":"private"}"#).unwrap();
+        assert_eq!(create.visibility, Some(GroupVisibility::Private));
+        let update: UpdateChatBody =
+            serde_json::from_str(r#"{"visibility":"semi_public"}"#).unwrap();
+        assert_eq!(update.visibility, Some(GroupVisibility::SemiPublic));
+    }
+
+    #[test]
+    fn null_retention_days_turns_retention_off() {
+        let parse = |json: &str| {
+            serde_json::from_str::<UpdateChatBody>(json)
+                .unwrap()
+                .retention_days
+        };
+        assert_eq!(parse(r#"{"retentionDays":30}"#), Some(30));
+        assert_eq!(parse(r#"{"retentionDays":null}"#), Some(0));
+        assert_eq!(parse("{}"), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn delete_group_requires_admin_role() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 900_602;
+        app.seed_user(uid);
+        let chat_id = app.seed_chat("Undeletable").await;
+        app.seed_membership(chat_id, uid, GroupRole::Member);
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::DELETE,
+                &format!("/group/{chat_id}"),
+                uid,
+                None,
+            )
+            .await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN, "{body}");
+        assert_eq!(body["error"]["code"], "admin_required");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn a_failed_creator_membership_leaves_no_chat_behind() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_091;
+        app.seed_user(uid);
+        app.grant(uid, AuthzAction::ChatCreate).await;
+        // Rolled back with the test transaction.
+        diesel::connection::SimpleConnection::batch_execute(
+            &mut *app.conn(),
+            "CREATE FUNCTION reject_test_membership() RETURNS trigger AS $$ \
+             BEGIN RAISE EXCEPTION 'membership insert failed'; END $$ LANGUAGE plpgsql; \
+             CREATE TRIGGER reject_test_membership BEFORE INSERT ON group_membership \
+             FOR EACH ROW WHEN (NEW.uid = 901091) EXECUTE FUNCTION reject_test_membership();",
+        )
+        .unwrap();
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::POST,
+                "/group",
+                uid,
+                Some(serde_json::json!({ "name": "Orphan candidate" })),
+            )
+            .await;
+        assert_eq!(
+            status,
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            "{body}"
+        );
+        let orphans: i64 = groups::table
+            .filter(groups::name.eq("Orphan candidate"))
+            .count()
+            .get_result(&mut app.conn())
+            .unwrap();
+        assert_eq!(orphans, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn members_read_chat_details_and_see_admin_renames_live() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, member, outsider) = (901_131, 901_132, 901_133);
+        for uid in [admin, member, outsider] {
+            app.seed_user(uid);
+        }
+        let chat_id = app.seed_chat("Before").await;
+        app.seed_membership(chat_id, admin, GroupRole::Admin);
+        app.seed_membership(chat_id, member, GroupRole::Member);
+        let uri = format!("/group/{chat_id}");
+
+        let (status, body) = app
+            .request(axum::http::Method::GET, &uri, member, None)
+            .await;
+        assert_eq!(status, axum::http::StatusCode::OK, "{body}");
+        assert_eq!(body["name"], "Before");
+        let (status, _) = app
+            .request(axum::http::Method::GET, &uri, outsider, None)
+            .await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN);
+
+        let rename = Some(serde_json::json!({ "name": "After" }));
+        let (status, body) = app
+            .request(axum::http::Method::PATCH, &uri, member, rename.clone())
+            .await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN, "{body}");
+
+        let (_entry, mut rx, _) = app.state.ws_registry.register(member);
+        let (status, body) = app
+            .request(axum::http::Method::PATCH, &uri, admin, rename)
+            .await;
+        assert_eq!(status, axum::http::StatusCode::OK, "{body}");
+        assert_eq!(body["name"], "After");
+        let frame = loop {
+            let frame: serde_json::Value =
+                serde_json::from_str(&rx.try_recv().expect("a queued frame")).unwrap();
+            if frame["type"] == "chatUpdated" {
+                break frame;
+            }
+        };
+        assert_eq!(frame["payload"]["name"], "After");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn only_admins_change_slow_mode() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, member) = (901_011, 901_012);
+        app.seed_user(admin);
+        app.seed_user(member);
+        let chat_id = app.seed_chat("Slow mode settings").await;
+        app.seed_membership(chat_id, admin, GroupRole::Admin);
+        app.seed_membership(chat_id, member, GroupRole::Member);
+        let app = &app;
+        let patch = |uid| async move {
+            app.request(
+                axum::http::Method::PATCH,
+                &format!("/group/{chat_id}"),
+                uid,
+                Some(serde_json::json!({ "slowModeSecs": 30 })),
+            )
+            .await
+        };
+
+        let (status, body) = patch(member).await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN, "{body}");
+        assert_eq!(body["error"]["code"], "admin_required");
+        let slow_mode_secs: i32 = groups::table
+            .find(chat_id)
+            .select(groups::slow_mode_secs)
+            .first(&mut app.conn())
+            .unwrap();
+        assert_eq!(slow_mode_secs, 0);
+
+        let (status, body) = patch(admin).await;
+        assert_eq!(status, axum::http::StatusCode::OK, "{body}");
+        assert_eq!(body["slowModeSecs"], 30);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn welcome_messages_are_checked_like_messages_when_saved() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let admin = 901_053;
+        app.seed_user(admin);
+        let chat_id = app.seed_chat("Long welcome").await;
+        app.seed_membership(chat_id, admin, GroupRole::Admin);
+        let too_long = "x".repeat(app.state.max_message_length + 1);
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::PATCH,
+                &format!("/group/{chat_id}"),
+                admin,
+                Some(serde_json::json!({ "welcomeMessage": too_long })),
+            )
+            .await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST, "{body}");
+        let welcome: Option<String> = groups::table
+            .find(chat_id)
+            .select(groups::welcome_message)
+            .first(&mut app.conn())
+            .unwrap();
+        assert_eq!(welcome, None);
+    }
+
+    #[test]
+    fn delete_group_rejects_missing_or_already_deleted_chats() {
+        assert!(check_group_deletable(Some(None)).is_ok());
+        assert_eq!(
+            check_group_deletable(None).unwrap_err().code(),
+            ErrorCode::ChatNotFound
+        );
+        assert_eq!(
+            check_group_deletable(Some(Some(Utc::now())))
+                .unwrap_err()
+                .code(),
+            ErrorCode::ChatNotFound
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn deleted_chats_drop_out_of_listings_and_stop_serving_messages() {
+        use axum::http::Method;
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, member, outsider) = (901_231, 901_232, 901_233);
+        for uid in [admin, member, outsider] {
+            app.seed_user(uid);
+        }
+        let chat_id = app.seed_chat("Going away").await;
+        app.seed_membership(chat_id, admin, GroupRole::Admin);
+        app.seed_membership(chat_id, member, GroupRole::Member);
+        diesel::update(groups::table.find(chat_id))
+            .set(groups::visibility.eq(GroupVisibility::Public))
+            .execute(&mut app.conn())
+            .unwrap();
+        let app = &app;
+        let chat_key = chat_id.to_string();
+        let chat_key = chat_key.as_str();
+        let listed = |uri: &'static str, key: &'static str, uid: i32| async move {
+            let (status, body) = app.request(Method::GET, uri, uid, None).await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            body[key]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|chat| chat["id"] == chat_key)
+        };
+        assert!(listed("/chats", "chats", member).await);
+        assert!(listed("/group?scope=public", "groups", outsider).await);
+
+        let uri = format!("/chats/{chat_id}");
+        let (status, _) = app.request(Method::DELETE, &uri, member, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, body) = app.request(Method::DELETE, &uri, admin, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT, "{body}");
+
+        assert!(!listed("/chats", "chats", member).await);
+        assert!(!listed("/chats", "chats", admin).await);
+        assert!(!listed("/group?scope=public", "groups", outsider).await);
+        let (status, _) = app
+            .request(
+                Method::GET,
+                &format!("/chats/{chat_id}/messages"),
+                member,
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = app.request(Method::DELETE, &uri, admin, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    fn group_info(my_role: Option<GroupRole>) -> GroupInfoResponse {
+        GroupInfoResponse {
+            id: 7,
+            name: "Renamed".to_string(),
+            description: Some("About".to_string()),
+            avatar_image_id: Some(9),
+            avatar: Some("https://cdn.example/a.png".to_string()),
+            visibility: GroupVisibility::Public,
+            kind: ChatKind::Group,
+            moderation_policy: ModerationPolicy::Reject,
+            welcome_message: None,
+            welcome_message_as_dm: false,
+            slow_mode_secs: 30,
+            retention_days: 0,
+            member_count: 3,
+            created_at: Utc::now(),
+            muted_until: Some(Utc::now()),
+            my_role,
+        }
+    }
+
+    #[test]
+    fn chat_detail_reports_the_callers_role_and_member_count() {
+        let admin = serde_json::to_value(group_info(Some(GroupRole::Admin))).unwrap();
+        let member = serde_json::to_value(group_info(Some(GroupRole::Member))).unwrap();
+
+        assert_eq!(admin["myRole"], "admin");
+        assert_eq!(member["myRole"], "member");
+        assert_eq!(member["memberCount"], 3);
+    }
+
+    #[test]
+    fn chat_updated_payload_omits_the_requesters_own_state() {
+        let info = group_info(Some(GroupRole::Admin));
+
+        let value = serde_json::to_value(info.chat_updated_payload()).unwrap();
+
+        assert_eq!(value["chatId"], "7");
+        assert_eq!(value["name"], "Renamed");
+        assert_eq!(value["avatarImageId"], "9");
+        assert_eq!(value["moderationPolicy"], "reject");
+        assert_eq!(value["slowModeSecs"], 30);
+        assert!(value.get("myRole").is_none());
+        assert!(value.get("mutedUntil").is_none());
+    }
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, Json};
+use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::{PgConnection, RunQueryDsl};
+use serde::Serialize;
+use utoipa_axum::router::OpenApiRouter;
+
+use crate::AppState;
+
+/// How long the readiness probe waits for a pooled connection. Kept short so a
+/// saturated pool reports itself instead of queueing probes behind real traffic.
+const READINESS_DB_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, utoipa::ToSchema)]
+struct HealthResponse {
+    status: &'static str,
+}
+
+/// GET /health — Liveness: the process is up and serving requests.
+///
+/// Deliberately skips the database so an outage there does not get every
+/// instance restarted at once; use `/ready` to take instances out of rotation.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = OK, body = HealthResponse),
+    ),
+)]
+async fn health() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "ok" })
+}
+
+/// GET /ready — Readiness: a pooled connection can run `SELECT 1`.
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    responses(
+        (status = OK, body = HealthResponse),
+        (status = SERVICE_UNAVAILABLE, description = "Database unreachable or pool exhausted", body = HealthResponse),
+    ),
+)]
+async fn ready(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let pool = state.db.clone();
+    let reachable = tokio::task::spawn_blocking(move || database_is_reachable(&pool))
+        .await
+        .unwrap_or(false);
+    readiness_response(reachable)
+}
+
+fn database_is_reachable(pool: &Pool<ConnectionManager<PgConnection>>) -> bool {
+    let mut conn = match pool.get_timeout(READINESS_DB_TIMEOUT) {
+        Ok(conn) => conn,
+        Err(e) => {
+            tracing::warn!(error = %e, "readiness: no database connection");
+            return false;
+        }
+    };
+    match diesel::sql_query("SELECT 1").execute(&mut conn) {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::warn!(error = %e, "readiness: database query failed");
+            false
+        }
+    }
+}
+
+fn readiness_response(reachable: bool) -> (StatusCode, Json<HealthResponse>) {
+    if reachable {
+        (StatusCode::OK, Json(HealthResponse { status: "ok" }))
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(HealthResponse { status: "degraded" }),
+        )
+    }
+}
+
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(utoipa_axum::routes!(health))
+        .routes(utoipa_axum::routes!(ready))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreachable_database_reports_degraded() {
+        let pool = Pool::builder()
+            .connection_timeout(Duration::from_millis(200))
+            .build_unchecked(ConnectionManager::<PgConnection>::new(
+                "postgres://wetty@127.0.0.1:1/wetty",
+            ));
+
+        let reachable = database_is_reachable(&pool);
+        assert!(!reachable);
+
+        let (status, Json(body)) = readiness_response(reachable);
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body.status, "degraded");
+    }
+
+    #[test]
+    fn reachable_database_reports_ok() {
+        let (status, Json(body)) = readiness_response(true);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.status, "ok");
+    }
+}
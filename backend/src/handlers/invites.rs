@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Json, Path, Query, State},
+    extract::{Json, State},
     http::StatusCode,
 };
 use chrono::{DateTime, Utc};
@@ -14,10 +14,14 @@ use uuid::Uuid;
 use diesel::PgConnection;
 
 use crate::errors::AppError;
-use crate::extractors::DbConn;
+use crate::extractors::{DbConn, JsonBody, Path, Query};
 use crate::handlers::chats::{send_prepared_message, MessageResponse, PreparedMessageSend};
 use crate::handlers::groups::{load_group_info, GroupInfoResponse};
-use crate::handlers::members::{check_membership, require_admin_role};
+use crate::handlers::members::{
+    broadcast_member_event, check_membership, is_banned, member_limit_reached, require_admin_role,
+    ALREADY_JOINED, MEMBER_LIMIT_REACHED, USER_BANNED,
+};
+use crate::handlers::ws::messages::{MemberUpdatePayload, ServerWsMessage};
 use crate::models::{
     GroupJoinReason, GroupRole, Invite, InviteType, MessageType, NewGroupMembership, NewInvite,
 };
@@ -53,6 +57,8 @@ struct CreateInviteBody {
     #[schema(value_type = Option<String>)]
     required_chat_id: Option<i64>,
     expires_at: Option<DateTime<Utc>>,
+    /// Redemptions allowed before the invite stops working; omit for unlimited.
+    max_uses: Option<i32>,
 }
 
 #[derive(Deserialize, ToSchema, utoipa::IntoParams)]
@@ -121,10 +127,13 @@ struct InviteResponse {
     #[serde(with = "crate::serde_i64_string::opt")]
     #[schema(value_type = Option<String>)]
     required_chat_id: Option<i64>,
+    #[serde(with = "crate::serde_timestamp")]
     created_at: DateTime<Utc>,
     expires_at: Option<DateTime<Utc>>,
     revoked_at: Option<DateTime<Utc>>,
     used_at: Option<DateTime<Utc>>,
+    max_uses: Option<i32>,
+    uses: i32,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -156,6 +165,8 @@ struct SendInviteMessageResponse {
 
 enum RedeemInviteError {
     InvalidCode,
+    MemberLimit,
+    Banned,
     Db(diesel::result::Error),
 }
 
@@ -224,6 +235,8 @@ fn invite_to_response(invite: Invite) -> InviteResponse {
         expires_at: invite.expires_at,
         revoked_at: invite.revoked_at,
         used_at: invite.used_at,
+        max_uses: invite.max_uses,
+        uses: invite.uses,
     }
 }
 
@@ -273,6 +286,9 @@ fn validate_create_body(body: &CreateInviteBody) -> Result<(), AppError> {
             }
         }
     }
+    if body.max_uses.is_some_and(|max_uses| max_uses < 1) {
+        return Err(AppError::BadRequest("max_uses must be at least 1"));
+    }
 
     Ok(())
 }
@@ -294,7 +310,28 @@ fn load_invite_by_id(conn: &mut PgConnection, invite_id: i64) -> Result<Invite,
 }
 
 fn validate_invite_is_active(invite: &Invite, now: DateTime<Utc>) -> bool {
-    invite.revoked_at.is_none() && invite.expires_at.is_none_or(|expires_at| expires_at > now)
+    invite.revoked_at.is_none()
+        && invite.expires_at.is_none_or(|expires_at| expires_at > now)
+        && invite
+            .max_uses
+            .is_none_or(|max_uses| invite.uses < max_uses)
+}
+
+/// Count one redemption, unless a concurrent redeem already took the last use.
+/// Returns whether the use was claimed.
+fn claim_invite_use(conn: &mut PgConnection, invite_id: i64) -> QueryResult<bool> {
+    let updated = diesel::update(
+        invites::table.filter(
+            invites::id.eq(invite_id).and(
+                invites::max_uses
+                    .is_null()
+                    .or(invites::uses.nullable().lt(invites::max_uses)),
+            ),
+        ),
+    )
+    .set(invites::uses.eq(invites::uses + 1))
+    .execute(conn)?;
+    Ok(updated == 1)
 }
 
 async fn create_generic_invite(
@@ -325,6 +362,7 @@ async fn create_generic_invite(
             expires_at,
             revoked_at: None,
             used_at: None,
+            max_uses: None,
         };
 
         match diesel::insert_into(invites::table)
@@ -377,6 +415,7 @@ async fn create_invite_from_body(
             expires_at: body.expires_at,
             revoked_at: None,
             used_at: None,
+            max_uses: body.max_uses,
         };
 
         match diesel::insert_into(invites::table)
@@ -465,7 +504,7 @@ async fn post_invite(
     CurrentUid(uid): CurrentUid,
     State(state): State<AppState>,
     mut conn: DbConn,
-    Json(body): Json<CreateInviteBody>,
+    JsonBody(body): JsonBody<CreateInviteBody>,
 ) -> Result<(StatusCode, Json<InviteResponse>), AppError> {
     let conn = &mut *conn;
 
@@ -491,7 +530,7 @@ async fn post_send_invite_message(
     CurrentUid(uid): CurrentUid,
     State(state): State<AppState>,
     mut conn: DbConn,
-    Json(body): Json<SendInviteMessageBody>,
+    JsonBody(body): JsonBody<SendInviteMessageBody>,
 ) -> Result<(StatusCode, Json<SendInviteMessageResponse>), AppError> {
     let conn = &mut *conn;
 
@@ -530,6 +569,7 @@ async fn post_send_invite_message(
             attachment_ids: vec![],
             update_group_last_message: true,
             publish_immediately: true,
+            forwarded_from_message_id: None,
         },
     )
     .await?;
@@ -676,7 +716,7 @@ async fn patch_invite(
     CurrentUid(uid): CurrentUid,
     Path(InviteIdPath { invite_id }): Path<InviteIdPath>,
     mut conn: DbConn,
-    Json(body): Json<PatchInviteBody>,
+    JsonBody(body): JsonBody<PatchInviteBody>,
 ) -> Result<Json<InviteResponse>, AppError> {
     let conn = &mut *conn;
 
@@ -730,7 +770,9 @@ async fn delete_invite(
     tag = "invites",
     request_body = RedeemInviteBody,
     responses(
-        (status = 200, description = "Invite redeemed", body = RedeemInviteResponse)
+        (status = 200, description = "Invite redeemed", body = RedeemInviteResponse),
+        (status = 403, description = "Banned from the chat"),
+        (status = 409, description = "Already a member or the chat is full")
     ),
     security(("uid_header" = []), ("bearer_jwt" = []))
 )]
@@ -738,7 +780,7 @@ async fn post_redeem_invite(
     CurrentUid(uid): CurrentUid,
     State(state): State<AppState>,
     mut conn: DbConn,
-    Json(body): Json<RedeemInviteBody>,
+    JsonBody(body): JsonBody<RedeemInviteBody>,
 ) -> Result<Json<RedeemInviteResponse>, AppError> {
     let conn = &mut *conn;
 
@@ -800,6 +842,13 @@ async fn post_redeem_invite(
                 }
             }
 
+            if is_banned(conn, invite.chat_id, uid)? {
+                return Err(RedeemInviteError::Banned);
+            }
+            if member_limit_reached(conn, &state, invite.chat_id, 1)? {
+                return Err(RedeemInviteError::MemberLimit);
+            }
+
             match diesel::insert_into(group_membership::table)
                 .values(&NewGroupMembership {
                     chat_id: invite.chat_id,
@@ -825,6 +874,11 @@ async fn post_redeem_invite(
                 Err(other) => return Err(RedeemInviteError::Db(other)),
             }
 
+            // Checked after the insert so a rejected claim rolls the join back.
+            if !claim_invite_use(conn, invite.id)? {
+                return Err(RedeemInviteError::InvalidCode);
+            }
+
             if invite.invite_type == InviteType::Targeted {
                 let updated = diesel::update(
                     invites::table
@@ -842,6 +896,8 @@ async fn post_redeem_invite(
         })
         .map_err(|error| match error {
             RedeemInviteError::InvalidCode => AppError::BadRequest(INVALID_INVITE_CODE_MESSAGE),
+            RedeemInviteError::MemberLimit => MEMBER_LIMIT_REACHED,
+            RedeemInviteError::Banned => USER_BANNED,
             RedeemInviteError::Db(other) => {
                 tracing::error!("redeem invite: {:?}", other);
                 AppError::Internal("Failed to redeem invite")
@@ -851,10 +907,22 @@ async fn post_redeem_invite(
     let chat_id = match outcome {
         RedeemInviteOutcome::Joined(chat_id) => chat_id,
         RedeemInviteOutcome::AlreadyMember => {
-            return Err(AppError::Conflict("Already a member of this chat"));
+            return Err(ALREADY_JOINED);
         }
     };
 
+    broadcast_member_event(
+        conn,
+        &state,
+        chat_id,
+        None,
+        ServerWsMessage::MemberAdded(MemberUpdatePayload {
+            chat_id,
+            uid,
+            role: Some(GroupRole::Member),
+        }),
+    );
+
     if let Ok(send_result) = crate::handlers::chats::send_prepared_message(
         conn,
         &state,
@@ -870,12 +938,14 @@ async fn post_redeem_invite(
             attachment_ids: vec![],
             update_group_last_message: true,
             publish_immediately: true,
+            forwarded_from_message_id: None,
         },
     )
     .await
     {
         send_result.side_effects.fire(&state);
     }
+    crate::handlers::members::send_welcome_message(conn, &state, chat_id, uid).await;
 
     let chat = load_group_info(conn, &state, chat_id, uid)?;
     Ok(Json(RedeemInviteResponse { chat }))
@@ -889,3 +959,117 @@ pub fn router() -> OpenApiRouter<crate::AppState> {
         .routes(routes!(get_invite_by_code))
         .routes(routes!(get_invite, patch_invite, delete_invite))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invite(max_uses: Option<i32>, uses: i32, expires_at: Option<DateTime<Utc>>) -> Invite {
+        Invite {
+            id: 1,
+            code: "ABCDEFGHJK".to_string(),
+            chat_id: 7,
+            invite_type: InviteType::Generic,
+            creator_uid: Some(3),
+            target_uid: None,
+            required_chat_id: None,
+            created_at: Utc::now(),
+            expires_at,
+            revoked_at: None,
+            used_at: None,
+            max_uses,
+            uses,
+        }
+    }
+
+    #[test]
+    fn expired_invites_are_inactive() {
+        let now = Utc::now();
+        let hour = chrono::Duration::hours(1);
+        assert!(validate_invite_is_active(&invite(None, 0, None), now));
+        assert!(validate_invite_is_active(
+            &invite(None, 0, Some(now + hour)),
+            now
+        ));
+        assert!(!validate_invite_is_active(
+            &invite(None, 0, Some(now - hour)),
+            now
+        ));
+    }
+
+    #[test]
+    fn exhausted_invites_are_inactive() {
+        let now = Utc::now();
+        assert!(validate_invite_is_active(&invite(Some(2), 1, None), now));
+        assert!(!validate_invite_is_active(&invite(Some(2), 2, None), now));
+        assert!(validate_invite_is_active(&invite(None, 1_000, None), now));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn redeeming_stops_at_the_use_cap_and_at_expiry() {
+        use axum::http::Method;
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, first, second) = (901_181, 901_182, 901_183);
+        for uid in [admin, first, second] {
+            app.seed_user(uid);
+        }
+        let chat_id = app.seed_chat("Invited").await;
+        app.seed_membership(chat_id, admin, GroupRole::Admin);
+        let app = &app;
+        let create = || async move {
+            let (status, invite) = app
+                .request(
+                    Method::POST,
+                    "/invites",
+                    admin,
+                    Some(json!({
+                        "chatId": chat_id.to_string(),
+                        "inviteType": "generic",
+                        "maxUses": 1,
+                    })),
+                )
+                .await;
+            assert_eq!(status, StatusCode::CREATED, "{invite}");
+            invite["code"].as_str().unwrap().to_string()
+        };
+        let redeem = |uid: i32, code: String| async move {
+            app.request(
+                Method::POST,
+                "/invites/redeem",
+                uid,
+                Some(json!({ "code": code })),
+            )
+            .await
+        };
+
+        let single_use = create().await;
+        let (_entry, mut rx, _) = app.state.ws_registry.register(admin);
+        let (status, body) = redeem(first, single_use.clone()).await;
+        assert!(status.is_success(), "{status}: {body}");
+        let added = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|frame| serde_json::from_str::<serde_json::Value>(&frame).unwrap())
+            .find(|frame| frame["type"] == "memberAdded")
+            .expect("a memberAdded frame");
+        assert_eq!(added["payload"]["uid"], first);
+        let (status, body) = redeem(second, single_use).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+
+        let expiring = create().await;
+        diesel::update(invites::table.filter(invites::code.eq(&expiring)))
+            .set(invites::expires_at.eq(Some(Utc::now() - chrono::Duration::minutes(1))))
+            .execute(&mut app.conn())
+            .unwrap();
+        let (status, body) = redeem(second, expiring).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        let joined: i64 = group_membership::table
+            .filter(group_membership::chat_id.eq(chat_id))
+            .filter(group_membership::uid.eq(second))
+            .count()
+            .get_result(&mut app.conn())
+            .unwrap();
+        assert_eq!(joined, 0);
+    }
+}
@@ -1,8 +1,4 @@
-use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::State, http::StatusCode, Json};
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use serde::Serialize;
@@ -12,13 +8,14 @@ use utoipa_axum::router::OpenApiRouter;
 
 use diesel::PgConnection;
 
-use crate::errors::AppError;
-use crate::extractors::DbConn;
+use crate::errors::{AppError, ErrorCode};
+use crate::extractors::{DbConn, JsonBody, Path, Query};
 use crate::handlers::groups::load_requester_group_role;
+use crate::handlers::ws::messages::{MemberUpdatePayload, ServerWsMessage};
 use crate::models::{
-    GroupJoinReason, GroupMembership, GroupRole, NewGroupMembership, UserGroupInfo,
+    ChatBan, GroupJoinReason, GroupMembership, GroupRole, NewGroupMembership, UserGroupInfo,
 };
-use crate::schema::{self, group_membership};
+use crate::schema::{self, chat_bans, group_membership};
 
 use crate::services::user::{
     lookup_user_avatars, lookup_user_profiles, parse_user_search_query, search_group_member_uids,
@@ -52,22 +49,54 @@ pub struct UpdateMemberBody {
     role: GroupRole,
 }
 
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BanMemberBody {
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatBanResponse {
+    uid: i32,
+    banned_by: i32,
+    #[serde(with = "crate::serde_timestamp")]
+    banned_at: DateTime<Utc>,
+    reason: Option<String>,
+}
+
+impl From<ChatBan> for ChatBanResponse {
+    fn from(ban: ChatBan) -> Self {
+        Self {
+            uid: ban.uid,
+            banned_by: ban.banned_by,
+            banned_at: ban.banned_at,
+            reason: ban.reason,
+        }
+    }
+}
+
 #[derive(Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MemberResponse {
     uid: i32,
     role: GroupRole,
+    #[serde(with = "crate::serde_timestamp")]
     joined_at: DateTime<Utc>,
     username: Option<String>,
     avatar_url: Option<String>,
     gender: i16,
     user_group: Option<UserGroupInfo>,
+    /// Whether the member currently has a live WebSocket connection.
+    online: bool,
 }
 
 #[derive(serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct ListMembersQuery {
     limit: Option<i64>,
+    #[serde(alias = "after_uid", alias = "afterUid")]
     after: Option<i32>,
     q: Option<String>,
     mode: Option<UserSearchMode>,
@@ -78,9 +107,20 @@ struct ListMembersQuery {
 struct ListMembersResponse {
     members: Vec<MemberResponse>,
     next_cursor: Option<i32>,
+    /// Number of members in the chat, regardless of paging or search.
+    total: i64,
     can_manage_members: bool,
 }
 
+/// Split a `limit + 1` uid lookahead into the page and the cursor for the
+/// next one, which is only set when more members follow.
+fn split_member_page(mut uids: Vec<i32>, limit: i64) -> (Vec<i32>, Option<i32>) {
+    let has_more = uids.len() as i64 > limit;
+    uids.truncate(limit as usize);
+    let next_cursor = has_more.then(|| uids.last().copied()).flatten();
+    (uids, next_cursor)
+}
+
 fn build_member_responses(
     conn: &mut diesel::PgConnection,
     state: &AppState,
@@ -102,12 +142,38 @@ fn build_member_responses(
                 username: profile.and_then(|profile| profile.username.clone()),
                 gender: profile.map(|profile| profile.gender).unwrap_or(0),
                 user_group: profile.and_then(|profile| profile.user_group.clone()),
+                online: state.ws_registry.is_online(uid),
             }
         })
         .collect())
 }
 
-/// Check if user is a member of the chat; return 403 if not.
+/// Error for a caller without a membership row: 404 when the chat itself is
+/// missing (or deleted), so a bad chat id is distinguishable from a permission
+/// problem, and 403 otherwise. Only queried on the failure path.
+fn not_a_member_error(conn: &mut PgConnection, chat_id: i64) -> AppError {
+    let chat_exists = schema::groups::table
+        .filter(schema::groups::id.eq(chat_id))
+        .filter(schema::groups::deleted_at.is_null())
+        .count()
+        .get_result::<i64>(conn)
+        .map(|count| count > 0);
+    match chat_exists {
+        Ok(chat_exists) => membership_error(chat_exists),
+        Err(e) => e.into(),
+    }
+}
+
+fn membership_error(chat_exists: bool) -> AppError {
+    if chat_exists {
+        AppError::NOT_MEMBER
+    } else {
+        AppError::CHAT_NOT_FOUND
+    }
+}
+
+/// Check if user is a member of the chat; return 404 if the chat does not
+/// exist and 403 if the user is not in it.
 pub(super) fn check_membership(
     conn: &mut PgConnection,
     chat_id: i64,
@@ -121,13 +187,14 @@ pub(super) fn check_membership(
         .get_result::<i64>(conn)?;
 
     if exists == 0 {
-        return Err(AppError::Forbidden("Not a member of this chat"));
+        return Err(not_a_member_error(conn, chat_id));
     }
 
     Ok(())
 }
 
-/// Check if user is an admin of the chat; return 403 if not a member or not admin.
+/// Check if user is an admin of the chat; return 404 if the chat does not
+/// exist and 403 if the user is not a member or not an admin.
 pub(super) fn require_admin_role(
     conn: &mut PgConnection,
     chat_id: i64,
@@ -143,8 +210,308 @@ pub(super) fn require_admin_role(
 
     match role {
         Some(GroupRole::Admin) => Ok(()),
-        Some(_) => Err(AppError::Forbidden("Admin role required")),
-        None => Err(AppError::Forbidden("Not a member of this chat")),
+        Some(_) => Err(AppError::ADMIN_REQUIRED),
+        None => Err(not_a_member_error(conn, chat_id)),
+    }
+}
+
+pub(super) const MEMBER_LIMIT_REACHED: AppError = AppError::Coded(
+    StatusCode::CONFLICT,
+    ErrorCode::MemberLimitReached,
+    "Chat member limit reached",
+);
+
+/// Whether `adding` more members would take a chat of `current` past `max`.
+fn exceeds_member_limit(current: i64, adding: i64, max: i64) -> bool {
+    current + adding > max
+}
+
+/// Whether adding `adding` members to the chat would exceed the configured
+/// per-chat member limit.
+pub(super) fn member_limit_reached(
+    conn: &mut PgConnection,
+    state: &AppState,
+    chat_id: i64,
+    adding: i64,
+) -> QueryResult<bool> {
+    let current: i64 = group_membership::table
+        .filter(group_membership::chat_id.eq(chat_id))
+        .count()
+        .get_result(conn)?;
+    Ok(exceeds_member_limit(
+        current,
+        adding,
+        state.max_members_per_chat,
+    ))
+}
+
+pub(super) const ALREADY_MEMBER: AppError = AppError::Coded(
+    StatusCode::CONFLICT,
+    ErrorCode::AlreadyMember,
+    "User is already a member",
+);
+/// `ALREADY_MEMBER` for a caller joining a chat they are already in.
+pub(super) const ALREADY_JOINED: AppError = AppError::Coded(
+    StatusCode::CONFLICT,
+    ErrorCode::AlreadyMember,
+    "Already a member of this chat",
+);
+
+/// Turn the `(chat_id, uid)` primary-key violation from a membership insert
+/// into 409. The "already a member" pre-checks cannot see a concurrent add
+/// that commits between the check and the insert.
+pub(super) fn map_membership_conflict<T>(result: QueryResult<T>) -> Result<T, AppError> {
+    match result {
+        Err(diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            _,
+        )) => Err(ALREADY_MEMBER),
+        other => Ok(other?),
+    }
+}
+
+pub(super) const USER_BANNED: AppError = AppError::Coded(
+    StatusCode::FORBIDDEN,
+    ErrorCode::Banned,
+    "User is banned from this chat",
+);
+const MAX_BAN_REASON_CHARS: usize = 500;
+
+/// Whether `uid` is banned from the chat. Every path that adds a member must
+/// check this before inserting the membership row.
+pub(super) fn is_banned(conn: &mut PgConnection, chat_id: i64, uid: i32) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::exists(
+        chat_bans::table.filter(chat_bans::chat_id.eq(chat_id).and(chat_bans::uid.eq(uid))),
+    ))
+    .get_result(conn)
+}
+
+/// Trim a ban reason, treating blank as none; 400 when it is too long.
+fn normalize_ban_reason(reason: Option<String>) -> Result<Option<String>, AppError> {
+    let Some(reason) = reason else {
+        return Ok(None);
+    };
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Ok(None);
+    }
+    if reason.chars().count() > MAX_BAN_REASON_CHARS {
+        return Err(AppError::BadRequest("Ban reason is too long"));
+    }
+    Ok(Some(reason.to_string()))
+}
+
+const LAST_ADMIN_REQUIRED: AppError = AppError::Coded(
+    StatusCode::CONFLICT,
+    ErrorCode::LastAdmin,
+    "Chat must have at least one admin",
+);
+
+/// Whether removing admin rights from `target_uid` still leaves an admin.
+fn other_admin_remains(admin_uids: &[i32], target_uid: i32) -> bool {
+    admin_uids.iter().any(|&admin_uid| admin_uid != target_uid)
+}
+
+/// Lock the chat's admin rows, lowest uid first, and return their uids.
+///
+/// Every transaction that changes roles or removes members takes these locks
+/// before any other membership row, always in this order, so two admins
+/// demoting or removing each other queue behind one another instead of
+/// deadlocking. Holding them until commit also means two concurrent
+/// demotions cannot both count the other admin.
+fn lock_admin_uids(conn: &mut PgConnection, chat_id: i64) -> QueryResult<Vec<i32>> {
+    use crate::schema::group_membership::dsl as gm_dsl;
+
+    group_membership::table
+        .filter(
+            gm_dsl::chat_id
+                .eq(chat_id)
+                .and(gm_dsl::role.eq(GroupRole::Admin)),
+        )
+        .order(gm_dsl::uid.asc())
+        .select(gm_dsl::uid)
+        .for_update()
+        .load(conn)
+}
+
+/// Return 409 if `target_uid` is the only one of the locked `admin_uids`.
+fn ensure_other_admin_remains(admin_uids: &[i32], target_uid: i32) -> Result<(), AppError> {
+    if other_admin_remains(admin_uids, target_uid) {
+        Ok(())
+    } else {
+        Err(LAST_ADMIN_REQUIRED)
+    }
+}
+
+/// Delete `target_uid`'s membership, refusing to remove the chat's last admin.
+/// Returns whether they were a member. Must run inside a transaction.
+fn delete_membership(
+    conn: &mut PgConnection,
+    chat_id: i64,
+    target_uid: i32,
+) -> Result<bool, AppError> {
+    use crate::schema::group_membership::dsl as gm_dsl;
+    let admin_uids = lock_admin_uids(conn, chat_id)?;
+    let target_role: Option<GroupRole> = group_membership::table
+        .filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(target_uid)))
+        .select(gm_dsl::role)
+        .for_update()
+        .first(conn)
+        .optional()?;
+
+    let Some(target_role) = target_role else {
+        return Ok(false);
+    };
+    if target_role == GroupRole::Admin {
+        ensure_other_admin_remains(&admin_uids, target_uid)?;
+    }
+
+    diesel::delete(
+        group_membership::table.filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(target_uid))),
+    )
+    .execute(conn)?;
+    Ok(true)
+}
+
+/// Send a roster change to everyone currently in the chat, plus `also_notify`
+/// (a member who was just removed and no longer has a membership row).
+/// Call after the change commits: this also drops the chat's cached members.
+pub(super) fn broadcast_member_event(
+    conn: &mut PgConnection,
+    state: &AppState,
+    chat_id: i64,
+    also_notify: Option<i32>,
+    event: ServerWsMessage,
+) {
+    state.ws_registry.invalidate_chat_members(chat_id);
+    let mut member_uids =
+        crate::services::chat::member_uids_or_log(conn, &state.ws_registry, chat_id);
+    member_uids.extend(also_notify);
+
+    state
+        .ws_registry
+        .broadcast_to_uids(&member_uids, std::sync::Arc::new(event));
+}
+
+/// Substitute template variables in a chat's welcome message. Returns `None`
+/// when the template is blank so callers can skip posting.
+fn render_welcome_message(template: &str, username: &str) -> Option<String> {
+    let template = template.trim();
+    if template.is_empty() {
+        return None;
+    }
+    Some(template.replace("{username}", username))
+}
+
+/// The admin whose direct chat with a new member carries the welcome message
+/// when it goes by DM: the chat's longest-standing admin.
+fn welcome_dm_peer(conn: &mut PgConnection, chat_id: i64) -> QueryResult<Option<i32>> {
+    group_membership::table
+        .filter(group_membership::chat_id.eq(chat_id))
+        .filter(group_membership::role.eq(GroupRole::Admin))
+        .order((
+            group_membership::joined_at.asc(),
+            group_membership::uid.asc(),
+        ))
+        .select(group_membership::uid)
+        .first(conn)
+        .optional()
+}
+
+/// Where the welcome for `member_uid` goes: the chat itself or, with
+/// `as_dm`, the member's direct chat with `welcome_dm_peer`. `None` skips it.
+async fn welcome_target(
+    conn: &mut PgConnection,
+    state: &AppState,
+    chat_id: i64,
+    member_uid: i32,
+    as_dm: bool,
+) -> Result<Option<i64>, AppError> {
+    if !as_dm {
+        return Ok(Some(chat_id));
+    }
+    match welcome_dm_peer(conn, chat_id)? {
+        Some(admin_uid) if admin_uid != member_uid => {
+            crate::handlers::chats::direct_chat_reaching(conn, state, admin_uid, member_uid).await
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Post the chat's welcome message (if configured) after `member_uid` joins,
+/// as a system message with no sender, in the chat or in a direct chat with
+/// the member. Failures are logged rather than surfaced so they never undo
+/// the join.
+pub(crate) async fn send_welcome_message(
+    conn: &mut PgConnection,
+    state: &AppState,
+    chat_id: i64,
+    member_uid: i32,
+) {
+    let (template, as_dm): (Option<String>, bool) = match schema::groups::table
+        .filter(schema::groups::id.eq(chat_id))
+        .select((
+            schema::groups::welcome_message,
+            schema::groups::welcome_message_as_dm,
+        ))
+        .first(conn)
+    {
+        Ok(welcome) => welcome,
+        Err(e) => {
+            tracing::warn!(chat_id, ?e, "load welcome message");
+            return;
+        }
+    };
+    let Some(template) = template else {
+        return;
+    };
+
+    let username = lookup_user_profiles(conn, &[member_uid])
+        .ok()
+        .and_then(|mut profiles| profiles.remove(&member_uid))
+        .and_then(|profile| profile.username)
+        .unwrap_or_else(|| "Someone".to_string());
+    let Some(message) = render_welcome_message(&template, &username) else {
+        return;
+    };
+    let target_chat_id = match welcome_target(conn, state, chat_id, member_uid, as_dm).await {
+        Ok(Some(target_chat_id)) => target_chat_id,
+        Ok(None) => {
+            tracing::debug!(
+                chat_id,
+                member_uid,
+                "no direct chat for the welcome message"
+            );
+            return;
+        }
+        Err(e) => {
+            tracing::warn!(chat_id, member_uid, ?e, "open welcome direct chat");
+            return;
+        }
+    };
+
+    match crate::handlers::chats::send_prepared_message(
+        conn,
+        state,
+        crate::handlers::chats::PreparedMessageSend {
+            chat_id: target_chat_id,
+            sender_uid: crate::models::SYSTEM_SENDER_UID,
+            message: Some(message),
+            message_type: crate::models::MessageType::System,
+            sticker_id: None,
+            reply_to_id: None,
+            reply_root_id: None,
+            client_generated_id: uuid::Uuid::new_v4().to_string(),
+            attachment_ids: vec![],
+            update_group_last_message: true,
+            publish_immediately: true,
+            forwarded_from_message_id: None,
+        },
+    )
+    .await
+    {
+        Ok(send_result) => send_result.side_effects.fire(state),
+        Err(e) => tracing::warn!(chat_id, member_uid, ?e, "send welcome message"),
     }
 }
 
@@ -156,7 +523,7 @@ pub(super) fn require_admin_role(
     params(
         ("chat_id" = i64, Path, description = "Chat ID"),
         ("limit" = Option<i64>, Query, description = "Page size limit"),
-        ("after" = Option<i32>, Query, description = "Cursor for pagination"),
+        ("after" = Option<i32>, Query, description = "Return members with a uid above this cursor (alias: after_uid)"),
         ("q" = Option<String>, Query, description = "Search query"),
         ("mode" = Option<UserSearchMode>, Query, description = "Search mode"),
     ),
@@ -190,8 +557,11 @@ async fn get_members(
 
     let member_uids = search_group_member_uids(conn, chat_id, q.after, limit + 1, search.as_ref())?;
 
-    let has_more = member_uids.len() as i64 > limit;
-    let page_uids: Vec<i32> = member_uids.into_iter().take(limit as usize).collect();
+    let (page_uids, next_cursor) = split_member_page(member_uids, limit);
+    let total = group_membership::table
+        .filter(gm_dsl::chat_id.eq(chat_id))
+        .count()
+        .get_result::<i64>(conn)?;
     let rows: Vec<GroupMembership> = group_membership::table
         .filter(
             gm_dsl::chat_id
@@ -213,13 +583,10 @@ async fn get_members(
         .collect();
     let members = build_member_responses(conn, &state, page_rows)?;
 
-    let next_cursor = has_more
-        .then(|| members.last().map(|member| member.uid))
-        .flatten();
-
     Ok(Json(ListMembersResponse {
         members,
         next_cursor,
+        total,
         can_manage_members: requester_is_admin,
     }))
 }
@@ -235,6 +602,8 @@ async fn get_members(
     request_body = AddMemberBody,
     responses(
         (status = CREATED, body = MemberResponse),
+        (status = FORBIDDEN, description = "Admin role required, or the user is banned"),
+        (status = CONFLICT, description = "User is already a member or the chat is full"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
@@ -243,7 +612,7 @@ async fn post_add_member(
     State(state): State<AppState>,
     Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
     mut conn: DbConn,
-    Json(body): Json<AddMemberBody>,
+    JsonBody(body): JsonBody<AddMemberBody>,
 ) -> Result<(StatusCode, Json<MemberResponse>), AppError> {
     let conn = &mut *conn;
 
@@ -254,7 +623,14 @@ async fn post_add_member(
     let profile = profiles.get(&body.uid);
 
     if profile.is_none() {
-        return Err(AppError::BadRequest("User not found"));
+        return Err(AppError::Coded(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::UserNotFound,
+            "User not found",
+        ));
+    }
+    if is_banned(conn, chat_id, body.uid)? {
+        return Err(USER_BANNED);
     }
 
     // Check if already a member
@@ -267,7 +643,10 @@ async fn post_add_member(
     };
 
     if already_member > 0 {
-        return Err(AppError::Conflict("User is already a member"));
+        return Err(ALREADY_MEMBER);
+    }
+    if member_limit_reached(conn, &state, chat_id, 1)? {
+        return Err(MEMBER_LIMIT_REACHED);
     }
 
     let role = body.role.unwrap_or(GroupRole::Member);
@@ -282,9 +661,22 @@ async fn post_add_member(
         join_reason_extra: Some(json!({ "inviter_uid": uid })),
     };
 
-    diesel::insert_into(group_membership::table)
-        .values(&new_membership)
-        .execute(conn)?;
+    map_membership_conflict(
+        diesel::insert_into(group_membership::table)
+            .values(&new_membership)
+            .execute(conn),
+    )?;
+    broadcast_member_event(
+        conn,
+        &state,
+        chat_id,
+        None,
+        ServerWsMessage::MemberAdded(MemberUpdatePayload {
+            chat_id,
+            uid: body.uid,
+            role: Some(role.clone()),
+        }),
+    );
 
     let target_username = profile
         .and_then(|p| p.username.clone())
@@ -305,12 +697,14 @@ async fn post_add_member(
             attachment_ids: vec![],
             update_group_last_message: true,
             publish_immediately: true,
+            forwarded_from_message_id: None,
         },
     )
     .await
     {
         send_result.side_effects.fire(&state);
     }
+    send_welcome_message(conn, &state, chat_id, body.uid).await;
 
     let avatar_url = lookup_user_avatars(&state, &[body.uid])
         .remove(&body.uid)
@@ -326,6 +720,7 @@ async fn post_add_member(
             avatar_url,
             gender: profile.map(|profile| profile.gender).unwrap_or(0),
             user_group: profile.and_then(|profile| profile.user_group.clone()),
+            online: state.ws_registry.is_online(body.uid),
         }),
     ))
 }
@@ -350,6 +745,8 @@ struct RemoveMemberQuery {
     ),
     responses(
         (status = NO_CONTENT),
+        (status = NOT_FOUND, description = "Member not found"),
+        (status = CONFLICT, description = "Chat must have at least one admin"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
@@ -373,45 +770,31 @@ async fn delete_remove_member(
         check_membership(conn, chat_id, uid)?;
     }
 
-    // Check if target is a member and whether deleting it would remove the final admin.
-    use crate::schema::group_membership::dsl as gm_dsl;
-    let target_role: Option<GroupRole> = group_membership::table
-        .filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(target_uid)))
-        .select(gm_dsl::role)
-        .first(conn)
-        .optional()?;
-
-    let Some(target_role) = target_role else {
-        return Err(AppError::NotFound("Member not found"));
-    };
-
     let target_username = crate::services::user::lookup_user_profiles(conn, &[target_uid])
         .ok()
         .and_then(|mut profiles| profiles.remove(&target_uid))
         .and_then(|p| p.username)
         .unwrap_or_else(|| "Someone".to_string());
 
-    if matches!(target_role, GroupRole::Admin) {
-        let admin_count = group_membership::table
-            .filter(
-                gm_dsl::chat_id
-                    .eq(chat_id)
-                    .and(gm_dsl::role.eq(GroupRole::Admin)),
-            )
-            .count()
-            .get_result::<i64>(conn)?;
-
-        if admin_count <= 1 {
-            return Err(AppError::BadRequest(
-                "Cannot remove the last admin from the group",
-            ));
+    conn.transaction::<_, AppError, _>(|conn| {
+        if delete_membership(conn, chat_id, target_uid)? {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("Member not found"))
         }
-    }
+    })?;
 
-    diesel::delete(
-        group_membership::table.filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(target_uid))),
-    )
-    .execute(conn)?;
+    broadcast_member_event(
+        conn,
+        &state,
+        chat_id,
+        Some(target_uid),
+        ServerWsMessage::MemberRemoved(MemberUpdatePayload {
+            chat_id,
+            uid: target_uid,
+            role: None,
+        }),
+    );
 
     let (sys_sender_uid, sys_msg) = if is_admin_removing_other {
         (uid, format!("removed {}", target_username))
@@ -434,6 +817,7 @@ async fn delete_remove_member(
             attachment_ids: vec![],
             update_group_last_message: true,
             publish_immediately: true,
+            forwarded_from_message_id: None,
         },
     )
     .await
@@ -465,6 +849,155 @@ async fn delete_remove_member(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// POST /group/:chat_id/members/:uid/ban — Remove a member (if present) and
+/// bar them from being re-added or rejoining until the ban is lifted (admin only).
+#[utoipa::path(
+    post,
+    path = "/{uid}/ban",
+    tag = "members",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("uid" = i32, Path, description = "User ID to ban"),
+    ),
+    request_body = BanMemberBody,
+    responses(
+        (status = CREATED, body = ChatBanResponse),
+        (status = BAD_REQUEST, description = "Banning yourself, or the reason is too long"),
+        (status = FORBIDDEN, description = "Admin role required"),
+        (status = CONFLICT, description = "Chat must have at least one admin"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn post_ban_member(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(MemberPath {
+        chat_id,
+        uid: target_uid,
+    }): Path<MemberPath>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<BanMemberBody>,
+) -> Result<(StatusCode, Json<ChatBanResponse>), AppError> {
+    let conn = &mut *conn;
+
+    require_admin_role(conn, chat_id, uid)?;
+    if target_uid == uid {
+        return Err(AppError::BadRequest("Cannot ban yourself"));
+    }
+    let reason = normalize_ban_reason(body.reason)?;
+
+    let ban = ChatBan {
+        chat_id,
+        uid: target_uid,
+        banned_by: uid,
+        banned_at: Utc::now(),
+        reason,
+    };
+    // Banning an already banned user refreshes who banned them and why.
+    let (was_member, ban) = conn.transaction::<_, AppError, _>(|conn| {
+        let was_member = delete_membership(conn, chat_id, target_uid)?;
+        let ban: ChatBan = diesel::insert_into(chat_bans::table)
+            .values(&ban)
+            .on_conflict((chat_bans::chat_id, chat_bans::uid))
+            .do_update()
+            .set((
+                chat_bans::banned_by.eq(ban.banned_by),
+                chat_bans::banned_at.eq(ban.banned_at),
+                chat_bans::reason.eq(ban.reason.clone()),
+            ))
+            .returning(ChatBan::as_returning())
+            .get_result(conn)?;
+        Ok((was_member, ban))
+    })?;
+
+    if was_member {
+        broadcast_member_event(
+            conn,
+            &state,
+            chat_id,
+            Some(target_uid),
+            ServerWsMessage::MemberRemoved(MemberUpdatePayload {
+                chat_id,
+                uid: target_uid,
+                role: None,
+            }),
+        );
+
+        let target_username = lookup_user_profiles(conn, &[target_uid])
+            .ok()
+            .and_then(|mut profiles| profiles.remove(&target_uid))
+            .and_then(|p| p.username)
+            .unwrap_or_else(|| "Someone".to_string());
+        if let Ok(send_result) = crate::handlers::chats::send_prepared_message(
+            conn,
+            &state,
+            crate::handlers::chats::PreparedMessageSend {
+                chat_id,
+                sender_uid: uid,
+                message: Some(format!("banned {}", target_username)),
+                message_type: crate::models::MessageType::System,
+                sticker_id: None,
+                reply_to_id: None,
+                reply_root_id: None,
+                client_generated_id: uuid::Uuid::new_v4().to_string(),
+                attachment_ids: vec![],
+                update_group_last_message: true,
+                publish_immediately: true,
+                forwarded_from_message_id: None,
+            },
+        )
+        .await
+        {
+            send_result.side_effects.fire(&state);
+        }
+    }
+
+    Ok((StatusCode::CREATED, Json(ban.into())))
+}
+
+/// DELETE /group/:chat_id/members/:uid/ban — Lift a ban (admin only). The user
+/// is not re-added; they may be added or join again.
+#[utoipa::path(
+    delete,
+    path = "/{uid}/ban",
+    tag = "members",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("uid" = i32, Path, description = "User ID to unban"),
+    ),
+    responses(
+        (status = NO_CONTENT),
+        (status = FORBIDDEN, description = "Admin role required"),
+        (status = NOT_FOUND, description = "User is not banned"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn delete_ban_member(
+    CurrentUid(uid): CurrentUid,
+    Path(MemberPath {
+        chat_id,
+        uid: target_uid,
+    }): Path<MemberPath>,
+    mut conn: DbConn,
+) -> Result<StatusCode, AppError> {
+    let conn = &mut *conn;
+
+    require_admin_role(conn, chat_id, uid)?;
+    let lifted = diesel::delete(
+        chat_bans::table.filter(
+            chat_bans::chat_id
+                .eq(chat_id)
+                .and(chat_bans::uid.eq(target_uid)),
+        ),
+    )
+    .execute(conn)?;
+    if lifted == 0 {
+        return Err(AppError::NotFound("Ban not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// PATCH /group/:chat_id/members/:uid — Update member role (admin only).
 #[utoipa::path(
     patch,
@@ -477,6 +1010,8 @@ async fn delete_remove_member(
     request_body = UpdateMemberBody,
     responses(
         (status = OK, body = MemberResponse),
+        (status = NOT_FOUND, description = "Member not found"),
+        (status = CONFLICT, description = "Chat must have at least one admin"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
@@ -488,7 +1023,7 @@ async fn patch_member(
         uid: target_uid,
     }): Path<MemberPath>,
     mut conn: DbConn,
-    Json(body): Json<UpdateMemberBody>,
+    JsonBody(body): JsonBody<UpdateMemberBody>,
 ) -> Result<Json<MemberResponse>, AppError> {
     let conn = &mut *conn;
 
@@ -500,29 +1035,44 @@ async fn patch_member(
         return Err(AppError::BadRequest("Cannot change your own role"));
     }
 
-    // Check if target is a member
-    use crate::schema::group_membership::dsl as gm_dsl;
-    let is_member = group_membership::table
-        .filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(target_uid)))
-        .count()
-        .get_result::<i64>(conn)?;
-
-    if is_member == 0 {
-        return Err(AppError::NotFound("Member not found"));
-    }
+    let (role, joined_at) = conn.transaction::<_, AppError, _>(|conn| {
+        use crate::schema::group_membership::dsl as gm_dsl;
+        let admin_uids = lock_admin_uids(conn, chat_id)?;
+        let current_role: Option<GroupRole> = group_membership::table
+            .filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(target_uid)))
+            .select(gm_dsl::role)
+            .for_update()
+            .first(conn)
+            .optional()?;
+
+        let Some(current_role) = current_role else {
+            return Err(AppError::NotFound("Member not found"));
+        };
+        if current_role == GroupRole::Admin && body.role != GroupRole::Admin {
+            ensure_other_admin_remains(&admin_uids, target_uid)?;
+        }
 
-    // Update role
-    diesel::update(
-        group_membership::table.filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(target_uid))),
-    )
-    .set(gm_dsl::role.eq(&body.role))
-    .execute(conn)?;
+        diesel::update(
+            group_membership::table
+                .filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(target_uid))),
+        )
+        .set(gm_dsl::role.eq(&body.role))
+        .returning((gm_dsl::role, gm_dsl::joined_at))
+        .get_result::<(GroupRole, DateTime<Utc>)>(conn)
+        .map_err(AppError::from)
+    })?;
 
-    // Get updated member info
-    let (role, joined_at): (GroupRole, DateTime<Utc>) = group_membership::table
-        .filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(target_uid)))
-        .select((gm_dsl::role, gm_dsl::joined_at))
-        .first(conn)?;
+    broadcast_member_event(
+        conn,
+        &state,
+        chat_id,
+        None,
+        ServerWsMessage::RoleChanged(MemberUpdatePayload {
+            chat_id,
+            uid: target_uid,
+            role: Some(role.clone()),
+        }),
+    );
 
     let profiles = lookup_user_profiles(conn, &[target_uid])?;
     let profile = profiles.get(&target_uid);
@@ -539,11 +1089,590 @@ async fn patch_member(
         avatar_url,
         gender: profile.map(|profile| profile.gender).unwrap_or(0),
         user_group: profile.and_then(|profile| profile.user_group.clone()),
+        online: state.ws_registry.is_online(target_uid),
     }))
 }
 
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaveChatBody {
+    /// Member who takes over as admin when the caller is the only one.
+    /// Defaults to the longest-standing member.
+    #[serde(default)]
+    new_admin_uid: Option<i32>,
+}
+
+struct LeaveOutcome {
+    promoted_uid: Option<i32>,
+    chat_deleted: bool,
+}
+
+/// Pick who inherits admin rights from a departing sole admin: the requested
+/// member, or else the first of `candidates` (ordered by join time).
+fn choose_successor(candidates: &[i32], requested: Option<i32>) -> Result<Option<i32>, AppError> {
+    match requested {
+        Some(uid) if candidates.contains(&uid) => Ok(Some(uid)),
+        Some(_) => Err(AppError::BadRequest(
+            "New admin must be a member of this chat",
+        )),
+        None => Ok(candidates.first().copied()),
+    }
+}
+
+/// POST /chats/:chat_id/leave — Leave a chat.
+///
+/// A sole admin hands their role to `newAdminUid` or, without one, to the
+/// longest-standing member. When the last member leaves the chat is deleted.
+#[utoipa::path(
+    post,
+    path = "/leave",
+    tag = "members",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+    ),
+    request_body = Option<LeaveChatBody>,
+    responses(
+        (status = NO_CONTENT),
+        (status = BAD_REQUEST, description = "New admin must be a member of this chat"),
+        (status = NOT_FOUND, description = "Not a member of this chat"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+pub(crate) async fn post_leave_chat(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+    body: Option<JsonBody<LeaveChatBody>>,
+) -> Result<StatusCode, AppError> {
+    let conn = &mut *conn;
+    let requested_admin = body.and_then(|JsonBody(body)| body.new_admin_uid);
+
+    let outcome = conn.transaction::<_, AppError, _>(|conn| {
+        use crate::schema::group_membership::dsl as gm_dsl;
+
+        // Lock the whole roster so concurrent leaves and demotions see each
+        // other's effects: admins first and then everyone by uid, the order
+        // every membership change locks in. Successors go oldest first.
+        lock_admin_uids(conn, chat_id)?;
+        let mut roster: Vec<(i32, GroupRole, DateTime<Utc>)> = group_membership::table
+            .filter(gm_dsl::chat_id.eq(chat_id))
+            .order(gm_dsl::uid.asc())
+            .select((gm_dsl::uid, gm_dsl::role, gm_dsl::joined_at))
+            .for_update()
+            .load(conn)?;
+        roster.sort_by_key(|(member_uid, _, joined_at)| (*joined_at, *member_uid));
+        let roster: Vec<(i32, GroupRole)> = roster
+            .into_iter()
+            .map(|(member_uid, role, _)| (member_uid, role))
+            .collect();
+
+        let Some(leaver_role) = roster
+            .iter()
+            .find(|(member_uid, _)| *member_uid == uid)
+            .map(|(_, role)| role.clone())
+        else {
+            return Err(AppError::NotFound("Not a member of this chat"));
+        };
+
+        diesel::delete(
+            group_membership::table.filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(uid))),
+        )
+        .execute(conn)?;
+
+        let remaining: Vec<&(i32, GroupRole)> = roster
+            .iter()
+            .filter(|(member_uid, _)| *member_uid != uid)
+            .collect();
+
+        if remaining.is_empty() {
+            let now = Utc::now();
+            diesel::update(schema::groups::table.filter(schema::groups::id.eq(chat_id)))
+                .set(schema::groups::deleted_at.eq(Some(now)))
+                .execute(conn)?;
+            diesel::update(
+                schema::invites::table.filter(
+                    schema::invites::chat_id
+                        .eq(chat_id)
+                        .and(schema::invites::revoked_at.is_null()),
+                ),
+            )
+            .set(schema::invites::revoked_at.eq(Some(now)))
+            .execute(conn)?;
+            return Ok(LeaveOutcome {
+                promoted_uid: None,
+                chat_deleted: true,
+            });
+        }
+
+        let admin_remains = remaining.iter().any(|(_, role)| *role == GroupRole::Admin);
+        let promoted_uid = if leaver_role == GroupRole::Admin && !admin_remains {
+            let candidates: Vec<i32> = remaining
+                .iter()
+                .map(|(member_uid, _)| *member_uid)
+                .collect();
+            let successor = choose_successor(&candidates, requested_admin)?;
+            if let Some(successor) = successor {
+                diesel::update(
+                    group_membership::table
+                        .filter(gm_dsl::chat_id.eq(chat_id).and(gm_dsl::uid.eq(successor))),
+                )
+                .set(gm_dsl::role.eq(GroupRole::Admin))
+                .execute(conn)?;
+            }
+            successor
+        } else {
+            None
+        };
+
+        Ok(LeaveOutcome {
+            promoted_uid,
+            chat_deleted: false,
+        })
+    })?;
+
+    if let Some(promoted_uid) = outcome.promoted_uid {
+        broadcast_member_event(
+            conn,
+            &state,
+            chat_id,
+            None,
+            ServerWsMessage::RoleChanged(MemberUpdatePayload {
+                chat_id,
+                uid: promoted_uid,
+                role: Some(GroupRole::Admin),
+            }),
+        );
+    }
+    broadcast_member_event(
+        conn,
+        &state,
+        chat_id,
+        Some(uid),
+        ServerWsMessage::MemberRemoved(MemberUpdatePayload {
+            chat_id,
+            uid,
+            role: None,
+        }),
+    );
+
+    if !outcome.chat_deleted {
+        if let Ok(send_result) = crate::handlers::chats::send_prepared_message(
+            conn,
+            &state,
+            crate::handlers::chats::PreparedMessageSend {
+                chat_id,
+                sender_uid: uid,
+                message: Some("left the chat".to_string()),
+                message_type: crate::models::MessageType::System,
+                sticker_id: None,
+                reply_to_id: None,
+                reply_root_id: None,
+                client_generated_id: uuid::Uuid::new_v4().to_string(),
+                attachment_ids: vec![],
+                update_group_last_message: true,
+                publish_immediately: true,
+                forwarded_from_message_id: None,
+            },
+        )
+        .await
+        {
+            send_result.side_effects.fire(&state);
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 pub fn router() -> OpenApiRouter<crate::AppState> {
     OpenApiRouter::new()
         .routes(utoipa_axum::routes!(get_members, post_add_member))
         .routes(utoipa_axum::routes!(delete_remove_member, patch_member))
+        .routes(utoipa_axum::routes!(post_ban_member, delete_ban_member))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        choose_successor, exceeds_member_limit, map_membership_conflict, membership_error,
+        normalize_ban_reason, other_admin_remains, render_welcome_message, split_member_page,
+    };
+    use crate::errors::{AppError, ErrorCode};
+
+    #[test]
+    fn duplicate_membership_insert_is_a_conflict() {
+        let duplicate = Err::<usize, _>(diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            Box::new("duplicate key value violates \"group_membership_pkey\"".to_string()),
+        ));
+        assert_eq!(
+            map_membership_conflict(duplicate).unwrap_err().code(),
+            ErrorCode::AlreadyMember
+        );
+        assert!(matches!(map_membership_conflict(Ok(1)), Ok(1)));
+        assert!(matches!(
+            map_membership_conflict::<usize>(Err(diesel::result::Error::NotFound)),
+            Err(AppError::DbQuery(_))
+        ));
+    }
+
+    #[test]
+    fn missing_chat_is_not_found_and_foreign_chat_is_forbidden() {
+        assert_eq!(membership_error(false).code(), ErrorCode::ChatNotFound);
+        assert_eq!(membership_error(true).code(), ErrorCode::NotMember);
+    }
+
+    #[test]
+    fn member_pages_walk_every_uid_once() {
+        let all: Vec<i32> = (1..=7).map(|n| n * 10).collect();
+        let limit = 3;
+        let mut after = None;
+        let mut seen = Vec::new();
+        let mut pages = 0;
+        loop {
+            // Mirrors the uid-ordered `uid > after LIMIT limit + 1` lookup.
+            let lookahead: Vec<i32> = all
+                .iter()
+                .copied()
+                .filter(|uid| after.is_none_or(|after| *uid > after))
+                .take(limit as usize + 1)
+                .collect();
+            let (page, next_cursor) = split_member_page(lookahead, limit);
+            assert!(page.len() <= limit as usize);
+            seen.extend(page);
+            pages += 1;
+            match next_cursor {
+                Some(cursor) => after = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(pages, 3);
+        assert_eq!(seen, all);
+    }
+
+    #[test]
+    fn full_last_page_has_no_cursor() {
+        assert_eq!(split_member_page(vec![1, 2], 2), (vec![1, 2], None));
+        assert_eq!(split_member_page(vec![1, 2, 3], 2), (vec![1, 2], Some(2)));
+    }
+
+    #[test]
+    fn sole_admin_successor_defaults_to_longest_standing_member() {
+        assert_eq!(choose_successor(&[5, 9], None).unwrap(), Some(5));
+        assert_eq!(choose_successor(&[5, 9], Some(9)).unwrap(), Some(9));
+        assert!(matches!(
+            choose_successor(&[5, 9], Some(3)),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn demoting_one_of_two_admins_is_allowed() {
+        assert!(other_admin_remains(&[1, 2], 2));
+    }
+
+    #[test]
+    fn demoting_the_only_admin_is_rejected() {
+        assert!(!other_admin_remains(&[2], 2));
+        assert!(!other_admin_remains(&[], 2));
+    }
+
+    #[test]
+    fn welcome_message_substitutes_username() {
+        assert_eq!(
+            render_welcome_message("Welcome, {username}! Say hi {username}.", "alice").as_deref(),
+            Some("Welcome, alice! Say hi alice.")
+        );
+        assert_eq!(
+            render_welcome_message("  Read the rules  ", "alice").as_deref(),
+            Some("Read the rules")
+        );
+    }
+
+    #[test]
+    fn blank_welcome_message_is_skipped() {
+        assert_eq!(render_welcome_message("", "alice"), None);
+        assert_eq!(render_welcome_message(" \n\t", "alice"), None);
+    }
+
+    #[test]
+    fn ban_reason_is_trimmed_and_blank_means_none() {
+        assert_eq!(
+            normalize_ban_reason(Some("  spam  ".to_string())).unwrap(),
+            Some("spam".to_string())
+        );
+        assert_eq!(normalize_ban_reason(Some("   ".to_string())).unwrap(), None);
+        assert_eq!(normalize_ban_reason(None).unwrap(), None);
+    }
+
+    #[test]
+    fn overlong_ban_reason_is_rejected() {
+        let at_limit = "好".repeat(super::MAX_BAN_REASON_CHARS);
+        assert_eq!(
+            normalize_ban_reason(Some(at_limit.clone())).unwrap(),
+            Some(at_limit)
+        );
+        assert!(matches!(
+            normalize_ban_reason(Some("a".repeat(super::MAX_BAN_REASON_CHARS + 1))),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn an_admin_can_be_demoted_while_another_remains_but_not_the_last() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (first, second) = (901_041, 901_042);
+        app.seed_user(first);
+        app.seed_user(second);
+        let chat_id = app.seed_chat("Two admins").await;
+        app.seed_membership(chat_id, first, crate::models::GroupRole::Admin);
+        app.seed_membership(chat_id, second, crate::models::GroupRole::Admin);
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::PATCH,
+                &format!("/group/{chat_id}/members/{second}"),
+                first,
+                Some(serde_json::json!({ "role": "member" })),
+            )
+            .await;
+        assert_eq!(status, axum::http::StatusCode::OK, "{body}");
+        assert_eq!(body["role"], "member");
+
+        // `first` is now the only admin and cannot leave the chat without one.
+        let (status, body) = app
+            .request(
+                axum::http::Method::DELETE,
+                &format!("/group/{chat_id}/members/{first}"),
+                first,
+                None,
+            )
+            .await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT, "{body}");
+        assert_eq!(body["error"]["code"], "last_admin");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn joining_posts_the_welcome_message_once_as_a_system_message() {
+        use diesel::prelude::*;
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, joiner) = (900_401, 900_402);
+        app.seed_user(admin);
+        app.seed_user(joiner);
+        let chat_id = app.seed_chat("Welcoming").await;
+        app.seed_membership(chat_id, admin, crate::models::GroupRole::Admin);
+        diesel::update(crate::schema::groups::table.find(chat_id))
+            .set(crate::schema::groups::welcome_message.eq("Welcome, {username}!"))
+            .execute(&mut app.conn())
+            .unwrap();
+
+        let join = format!("/group/{chat_id}/join");
+        let (status, body) = app
+            .request(axum::http::Method::POST, &join, joiner, None)
+            .await;
+        assert_eq!(status, axum::http::StatusCode::CREATED, "{body}");
+        let (status, _) = app
+            .request(axum::http::Method::POST, &join, joiner, None)
+            .await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+
+        let (status, listed) = app
+            .request(
+                axum::http::Method::GET,
+                &format!("/chats/{chat_id}/messages"),
+                joiner,
+                None,
+            )
+            .await;
+        assert_eq!(status, axum::http::StatusCode::OK, "{listed}");
+        let welcomes: Vec<_> = listed["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|m| m["message"] == format!("Welcome, user{joiner}!").as_str())
+            .collect();
+        assert_eq!(welcomes.len(), 1, "{listed}");
+        assert_eq!(
+            welcomes[0]["sender"]["uid"],
+            crate::models::SYSTEM_SENDER_UID
+        );
+        assert_eq!(welcomes[0]["messageType"], "system");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn a_dm_welcome_lands_in_the_direct_chat_with_the_admin() {
+        use diesel::prelude::*;
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, joiner) = (901_051, 901_052);
+        app.seed_user(admin);
+        app.seed_user(joiner);
+        let chat_id = app.seed_chat("Whispering").await;
+        app.seed_membership(chat_id, admin, crate::models::GroupRole::Admin);
+        diesel::update(crate::schema::groups::table.find(chat_id))
+            .set((
+                crate::schema::groups::welcome_message.eq("Welcome, {username}!"),
+                crate::schema::groups::welcome_message_as_dm.eq(true),
+            ))
+            .execute(&mut app.conn())
+            .unwrap();
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::POST,
+                &format!("/group/{chat_id}/join"),
+                joiner,
+                None,
+            )
+            .await;
+        assert_eq!(status, axum::http::StatusCode::CREATED, "{body}");
+
+        let welcome = format!("Welcome, user{joiner}!");
+        let welcomes_in = |chat: i64| {
+            let welcome = welcome.clone();
+            let app = &app;
+            async move {
+                let (status, listed) = app
+                    .request(
+                        axum::http::Method::GET,
+                        &format!("/chats/{chat}/messages"),
+                        joiner,
+                        None,
+                    )
+                    .await;
+                assert_eq!(status, axum::http::StatusCode::OK, "{listed}");
+                listed["messages"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .filter(|m| m["message"] == welcome.as_str())
+                    .map(|m| m["messageType"].clone())
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert!(welcomes_in(chat_id).await.is_empty());
+
+        let (status, direct) = app
+            .request(
+                axum::http::Method::POST,
+                "/chats/direct",
+                joiner,
+                Some(serde_json::json!({ "uid": admin })),
+            )
+            .await;
+        assert!(status.is_success(), "{direct}");
+        let direct_id = direct["id"].as_str().unwrap().parse::<i64>().unwrap();
+        assert_eq!(welcomes_in(direct_id).await, vec!["system"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn concurrent_adds_of_one_user_yield_one_member_and_one_conflict() {
+        use crate::schema::group_membership;
+        use axum::http::{Method, StatusCode};
+        use diesel::prelude::*;
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, other_admin, newcomer) = (901_191, 901_192, 901_193);
+        for uid in [admin, other_admin, newcomer] {
+            app.seed_user(uid);
+        }
+        let chat_id = app.seed_chat("Double add").await;
+        app.seed_membership(chat_id, admin, crate::models::GroupRole::Admin);
+        app.seed_membership(chat_id, other_admin, crate::models::GroupRole::Admin);
+        let members = format!("/group/{chat_id}/members");
+        let add = |uid| {
+            app.request(
+                Method::POST,
+                &members,
+                uid,
+                Some(serde_json::json!({ "uid": newcomer })),
+            )
+        };
+
+        // The harness has one connection, so the two adds take turns on it.
+        let (first, second) = tokio::join!(add(admin), add(other_admin));
+        let (created, conflict) = if first.0 == StatusCode::CREATED {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        assert_eq!(created.0, StatusCode::CREATED, "{}", created.1);
+        assert_eq!(conflict.0, StatusCode::CONFLICT, "{}", conflict.1);
+        assert_eq!(conflict.1["error"]["code"], "already_member");
+        let rows: i64 = group_membership::table
+            .filter(group_membership::chat_id.eq(chat_id))
+            .filter(group_membership::uid.eq(newcomer))
+            .count()
+            .get_result(&mut app.conn())
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn a_banned_user_stays_out_until_unbanned() {
+        use axum::http::{Method, StatusCode};
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, troll) = (901_161, 901_162);
+        app.seed_user(admin);
+        app.seed_user(troll);
+        let chat_id = app.seed_chat("Moderated").await;
+        app.seed_membership(chat_id, admin, crate::models::GroupRole::Admin);
+        app.seed_membership(chat_id, troll, crate::models::GroupRole::Member);
+        let ban = format!("/group/{chat_id}/members/{troll}/ban");
+        let members = format!("/group/{chat_id}/members");
+        let add = Some(serde_json::json!({ "uid": troll }));
+
+        let (status, body) = app
+            .request(
+                Method::POST,
+                &ban,
+                admin,
+                Some(serde_json::json!({ "reason": "spam" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{body}");
+        let (status, _) = app
+            .request(
+                Method::GET,
+                &format!("/chats/{chat_id}/messages"),
+                troll,
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, body) = app
+            .request(Method::POST, &members, admin, add.clone())
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+        let (status, body) = app
+            .request(Method::POST, &format!("/group/{chat_id}/join"), troll, None)
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+
+        let (status, body) = app.request(Method::DELETE, &ban, admin, None).await;
+        assert!(status.is_success(), "{status}: {body}");
+        let (status, body) = app.request(Method::POST, &members, admin, add).await;
+        assert_eq!(status, StatusCode::CREATED, "{body}");
+    }
+
+    #[test]
+    fn member_limit_allows_filling_the_chat_but_not_exceeding_it() {
+        assert!(!exceeds_member_limit(499, 1, 500));
+        assert!(exceeds_member_limit(500, 1, 500));
+        assert!(!exceeds_member_limit(0, 2, 2));
+        assert!(exceeds_member_limit(1, 2, 2));
+    }
 }
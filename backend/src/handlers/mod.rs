@@ -1,6 +1,10 @@
+pub mod admin;
 pub mod attachments;
 pub mod chats;
+#[cfg(feature = "debug-endpoints")]
+pub mod debug;
 pub mod groups;
+pub mod health;
 pub mod invites;
 pub mod members;
 pub mod pins;
@@ -8,6 +12,7 @@ pub mod push;
 pub mod stickers;
 pub mod threads;
 pub mod users;
+pub mod webhooks;
 pub mod ws;
 
 use crate::AppState;
@@ -15,6 +20,7 @@ use utoipa_axum::router::OpenApiRouter;
 
 pub fn api_router() -> OpenApiRouter<AppState> {
     OpenApiRouter::new()
+        .merge(health::router())
         .nest("/ws", ws::router())
         .nest("/chats", chats::router())
         .nest("/threads", threads::router())
@@ -24,4 +30,17 @@ pub fn api_router() -> OpenApiRouter<AppState> {
         .nest("/stickers", stickers::router())
         .nest("/users", users::router())
         .nest("/attachments", attachments::router())
+        .nest("/admin", admin::router())
+        .merge(debug_router())
+}
+
+#[cfg(feature = "debug-endpoints")]
+fn debug_router() -> OpenApiRouter<AppState> {
+    debug::router()
+}
+
+/// Without the `debug-endpoints` feature no `/debug` routes are mounted.
+#[cfg(not(feature = "debug-endpoints"))]
+fn debug_router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
 }
@@ -1,8 +1,4 @@
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::State, http::StatusCode, Json};
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -10,7 +6,7 @@ use utoipa_axum::router::OpenApiRouter;
 use uuid::Uuid;
 
 use crate::errors::AppError;
-use crate::extractors::DbConn;
+use crate::extractors::{DbConn, JsonBody, Path};
 use crate::handlers::chats::{attach_metadata, MessageResponse, PreparedMessageSend};
 use crate::handlers::members::{check_membership, require_admin_role};
 use crate::handlers::ws::messages::{PinUpdatePayload, ServerWsMessage};
@@ -149,7 +145,7 @@ async fn create_pin(
     Path(path): Path<ChatIdPath>,
     CurrentUid(uid): CurrentUid,
     mut conn: DbConn,
-    Json(body): Json<CreatePinBody>,
+    JsonBody(body): JsonBody<CreatePinBody>,
 ) -> Result<(StatusCode, Json<PinResponse>), AppError> {
     let conn = &mut *conn;
 
@@ -166,7 +162,7 @@ async fn create_pin(
         )
         .first(conn)
         .optional()?
-        .ok_or(AppError::NotFound("Message not found"))?;
+        .ok_or(AppError::MESSAGE_NOT_FOUND)?;
 
     // Check pin count
     let now = Utc::now();
@@ -243,6 +239,7 @@ async fn create_pin(
             attachment_ids: vec![],
             update_group_last_message: false,
             publish_immediately: true,
+            forwarded_from_message_id: None,
         },
     )
     .await
@@ -319,6 +316,7 @@ async fn delete_pin(
             attachment_ids: vec![],
             update_group_last_message: false,
             publish_immediately: true,
+            forwarded_from_message_id: None,
         },
     )
     .await
@@ -1,9 +1,4 @@
-use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    Json,
-};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use chrono::Utc;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -12,7 +7,7 @@ use utoipa_axum::router::OpenApiRouter;
 use utoipa_axum::routes;
 
 use crate::errors::AppError;
-use crate::extractors::DbConn;
+use crate::extractors::{DbConn, JsonBody, Query};
 use crate::models::{
     ApnsSubscriptionData, NewPushSubscription, PushEnvironment, PushProvider,
     WebPushSubscriptionData,
@@ -149,7 +144,7 @@ async fn post_subscribe(
     ClientId(client_id): ClientId,
     State(state): State<AppState>,
     mut conn: DbConn,
-    Json(body): Json<SubscribeBody>,
+    JsonBody(body): JsonBody<SubscribeBody>,
 ) -> Result<impl IntoResponse, AppError> {
     let conn = &mut *conn;
     let validated = body.validate()?;
@@ -295,7 +290,7 @@ async fn post_unsubscribe(
     CurrentUid(uid): CurrentUid,
     ClientId(client_id): ClientId,
     mut conn: DbConn,
-    Json(body): Json<UnsubscribeBody>,
+    JsonBody(body): JsonBody<UnsubscribeBody>,
 ) -> Result<impl IntoResponse, AppError> {
     let conn = &mut *conn;
     let provider: PushProvider = body.provider.into();
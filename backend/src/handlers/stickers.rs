@@ -1,6 +1,6 @@
 use aws_sdk_s3::primitives::ByteStream;
 use axum::{
-    extract::{Json, Multipart, Path, State},
+    extract::{Multipart, State},
     http::StatusCode,
     response::IntoResponse,
     Json as AxumJson,
@@ -13,7 +13,7 @@ use std::collections::{HashMap, HashSet};
 use utoipa_axum::router::OpenApiRouter;
 
 use crate::errors::AppError;
-use crate::extractors::DbConn;
+use crate::extractors::{DbConn, JsonBody, Path};
 use crate::{
     models::{
         Media, MediaPurpose, NewMedia, NewSticker, NewStickerPack, Sticker, StickerPack,
@@ -74,6 +74,7 @@ struct StickerSummary {
     emoji: String,
     name: Option<String>,
     description: Option<String>,
+    #[serde(with = "crate::serde_timestamp")]
     created_at: DateTime<Utc>,
     is_favorited: bool,
 }
@@ -98,7 +99,9 @@ struct StickerPackSummary {
     owner_name: Option<String>,
     name: String,
     description: Option<String>,
+    #[serde(with = "crate::serde_timestamp")]
     created_at: DateTime<Utc>,
+    #[serde(with = "crate::serde_timestamp")]
     updated_at: DateTime<Utc>,
     sticker_count: i64,
     is_subscribed: bool,
@@ -382,7 +385,7 @@ async fn post_pack(
     CurrentUid(uid): CurrentUid,
     State(state): State<AppState>,
     mut conn: DbConn,
-    Json(body): Json<CreateStickerPackBody>,
+    JsonBody(body): JsonBody<CreateStickerPackBody>,
 ) -> Result<AxumJson<StickerPackSummary>, AppError> {
     let conn = &mut *conn;
 
@@ -445,7 +448,7 @@ async fn patch_pack(
     State(state): State<AppState>,
     Path(pack_id): Path<i64>,
     mut conn: DbConn,
-    Json(body): Json<UpdateStickerPackBody>,
+    JsonBody(body): JsonBody<UpdateStickerPackBody>,
 ) -> Result<AxumJson<StickerPackSummary>, AppError> {
     let conn = &mut *conn;
     let _pack = require_pack_owner(conn, pack_id, uid)?;
@@ -1,8 +1,4 @@
-use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::State, http::StatusCode, Json};
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use serde::Serialize;
@@ -10,7 +6,7 @@ use utoipa_axum::router::OpenApiRouter;
 
 use crate::{
     errors::AppError,
-    extractors::DbConn,
+    extractors::{DbConn, JsonBody, Path, Query},
     handlers::members::check_membership,
     models::Message,
     schema::messages,
@@ -124,7 +120,7 @@ async fn mark_thread_read(
     CurrentUid(uid): CurrentUid,
     Path(ThreadRootIdPath { thread_root_id }): Path<ThreadRootIdPath>,
     mut conn: DbConn,
-    Json(body): Json<MarkThreadReadBody>,
+    JsonBody(body): JsonBody<MarkThreadReadBody>,
 ) -> Result<Json<MarkThreadReadResponse>, AppError> {
     let conn = &mut *conn;
 
@@ -1,6 +1,6 @@
 use axum::{
-    extract::{Query, State},
-    http::HeaderMap,
+    extract::State,
+    http::{HeaderMap, StatusCode},
     Json,
 };
 use serde::{Deserialize, Serialize};
@@ -9,13 +9,14 @@ use utoipa_axum::router::OpenApiRouter;
 use utoipa_axum::routes;
 
 use crate::errors::AppError;
-use crate::extractors::DbConn;
+use crate::extractors::{DbConn, JsonBody, Query};
 use crate::handlers::ws::messages::{ServerWsMessage, StickerPackOrderUpdatePayload};
 use crate::models::{NewUserExtra, UserExtra, UserGroupInfo};
 use crate::schema::{group_membership, sticker_packs, user_extra, user_sticker_pack_subscriptions};
 use crate::services::authz::{Action as AuthzAction, Resource as AuthzResource};
 use crate::services::user::{
-    lookup_user_avatars, lookup_user_profiles, search_user_uids_by_prefix,
+    find_user_conflicts, insert_user, lookup_user_avatars, lookup_user_profiles,
+    search_user_uids_by_prefix,
 };
 use crate::utils::auth::{
     encode_auth_token, extract_auth_context, required_client_id, AuthClaims, AuthSource, CurrentUid,
@@ -28,6 +29,128 @@ use std::sync::Arc;
 const DEFAULT_USER_SEARCH_LIMIT: i64 = 20;
 const MAX_USER_SEARCH_LIMIT: i64 = 50;
 
+/// `common_member.username` is a `CHAR(15)`.
+const MAX_USERNAME_CHARS: usize = 15;
+const MAX_EMAIL_CHARS: usize = 255;
+/// Characters Discuz refuses in usernames.
+const DISALLOWED_USERNAME_CHARS: &[char] = &[
+    '\\', '\'', '"', '<', '>', '&', ',', '*', '%', '#', '?', '/', ':', ';',
+];
+
+#[derive(serde::Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateUserBody {
+    /// Discuz uid of the account being provisioned.
+    uid: i32,
+    username: String,
+    #[serde(default)]
+    email: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateUserResponse {
+    uid: i32,
+    username: String,
+}
+
+/// Trim and check a username for a new account.
+fn validate_new_username(raw: &str) -> Result<String, AppError> {
+    let username = raw.trim();
+    if username.is_empty() {
+        return Err(AppError::BadRequest("Username cannot be empty"));
+    }
+    if username.chars().count() > MAX_USERNAME_CHARS {
+        return Err(AppError::BadRequest(
+            "Username too long (maximum of 15 characters)",
+        ));
+    }
+    if username
+        .chars()
+        .any(|c| c.is_control() || DISALLOWED_USERNAME_CHARS.contains(&c))
+    {
+        return Err(AppError::BadRequest(
+            "Username contains disallowed characters",
+        ));
+    }
+    Ok(username.to_string())
+}
+
+fn user_conflict_error(uid_taken: bool, username_taken: bool) -> Option<AppError> {
+    if uid_taken {
+        Some(AppError::Conflict("User already exists"))
+    } else if username_taken {
+        Some(AppError::Conflict("Username already taken"))
+    } else {
+        None
+    }
+}
+
+/// POST /users — Provision a user account (requires `user.create`).
+///
+/// Accounts normally come from Discuz; this lets deployments without it, and
+/// test setups, create the `common_member` row the rest of the API reads.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "users",
+    request_body = CreateUserBody,
+    responses(
+        (status = 201, description = "User created", body = CreateUserResponse),
+        (status = 400, description = "Invalid uid, username or email"),
+        (status = 403, description = "Permission required"),
+        (status = 409, description = "Uid or username already in use"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = []))
+)]
+async fn post_user(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<CreateUserBody>,
+) -> Result<(StatusCode, Json<CreateUserResponse>), AppError> {
+    let conn = &mut *conn;
+
+    state.authz_service.require_permission(
+        conn,
+        uid,
+        AuthzAction::UserCreate,
+        AuthzResource::Global,
+    )?;
+
+    if body.uid <= 0 {
+        return Err(AppError::BadRequest("Invalid uid"));
+    }
+    let username = validate_new_username(&body.username)?;
+    let email = body.email.trim();
+    if email.chars().count() > MAX_EMAIL_CHARS {
+        return Err(AppError::BadRequest("Email too long"));
+    }
+
+    let (uid_taken, username_taken) = find_user_conflicts(conn, body.uid, &username)?;
+    if let Some(err) = user_conflict_error(uid_taken, username_taken) {
+        return Err(err);
+    }
+    match insert_user(conn, body.uid, &username, email) {
+        Ok(()) => {}
+        // Lost a race with another request for the same uid or username.
+        Err(diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            _,
+        )) => return Err(AppError::Conflict("Uid or username already in use")),
+        Err(e) => return Err(e.into()),
+    }
+    state.ws_registry.forget_user(body.uid);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateUserResponse {
+            uid: body.uid,
+            username,
+        }),
+    ))
+}
+
 #[derive(Debug, Clone, serde::Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct StickerPackOrderItem {
@@ -63,7 +186,7 @@ async fn put_stickerpack_order(
     CurrentUid(uid): CurrentUid,
     State(state): State<AppState>,
     mut conn: DbConn,
-    Json(req): Json<UpdateStickerPackOrderRequest>,
+    JsonBody(req): JsonBody<UpdateStickerPackOrderRequest>,
 ) -> Result<Json<()>, AppError> {
     let conn = &mut *conn;
     let requested_order = req.order;
@@ -456,6 +579,7 @@ async fn get_auth_token(
             uid: auth.uid,
             cid: client_id,
             gen: 0,
+            exp: None,
         },
         &state.jwt_signing_key,
     )?;
@@ -465,6 +589,7 @@ async fn get_auth_token(
 
 pub fn router() -> OpenApiRouter<crate::AppState> {
     OpenApiRouter::new()
+        .routes(routes!(post_user))
         .routes(routes!(get_me))
         .routes(routes!(get_user_search))
         .routes(routes!(get_auth_token))
@@ -500,7 +625,11 @@ fn load_accessible_sticker_pack_ids(
 
 #[cfg(test)]
 mod tests {
-    use super::{normalize_user_search_limit, split_excluded_member_summaries, MemberSummary};
+    use super::{
+        normalize_user_search_limit, split_excluded_member_summaries, user_conflict_error,
+        validate_new_username, MemberSummary,
+    };
+    use crate::errors::AppError;
     use std::collections::HashSet;
 
     fn make_summary(uid: i32) -> MemberSummary {
@@ -544,4 +673,50 @@ mod tests {
             vec![2, 3]
         );
     }
+
+    #[test]
+    fn new_usernames_are_trimmed_and_bounded() {
+        assert_eq!(validate_new_username("  alice ").unwrap(), "alice");
+        assert_eq!(
+            validate_new_username("fifteen_chars_x").unwrap(),
+            "fifteen_chars_x"
+        );
+        assert_eq!(
+            validate_new_username("十五个汉字十五个汉字十五个汉字")
+                .unwrap()
+                .chars()
+                .count(),
+            15
+        );
+
+        assert!(matches!(
+            validate_new_username("   "),
+            Err(AppError::BadRequest("Username cannot be empty"))
+        ));
+        assert!(matches!(
+            validate_new_username("sixteen_chars_xx"),
+            Err(AppError::BadRequest(msg)) if msg.starts_with("Username too long")
+        ));
+        for bad in ["a<b", "quote'", "new\nline", "semi;colon"] {
+            assert!(matches!(
+                validate_new_username(bad),
+                Err(AppError::BadRequest(
+                    "Username contains disallowed characters"
+                ))
+            ));
+        }
+    }
+
+    #[test]
+    fn taken_uid_or_username_is_a_conflict() {
+        assert!(user_conflict_error(false, false).is_none());
+        assert!(matches!(
+            user_conflict_error(true, false),
+            Some(AppError::Conflict("User already exists"))
+        ));
+        assert!(matches!(
+            user_conflict_error(false, true),
+            Some(AppError::Conflict("Username already taken"))
+        ));
+    }
 }
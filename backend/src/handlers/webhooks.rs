@@ -0,0 +1,692 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa_axum::router::OpenApiRouter;
+
+use crate::errors::AppError;
+use crate::extractors::{DbConn, JsonBody, Path};
+use crate::handlers::members::require_admin_role;
+use crate::models::{NewAdminAuditLog, NewWebhook, UpdateWebhook, Webhook, WebhookDelivery};
+use crate::schema::{admin_audit_log, groups, webhook_deliveries, webhooks};
+use crate::services::authz::{Action as AuthzAction, Resource as AuthzResource};
+use crate::services::webhooks::{WebhookEvent, DELIVERY_LOG_LIMIT};
+use crate::utils::{auth::CurrentUid, ids};
+use crate::AppState;
+
+const AUDIT_ACTION_CREATE_WEBHOOK: &str = "chat.webhook.create";
+const AUDIT_ACTION_UPDATE_WEBHOOK: &str = "chat.webhook.update";
+const AUDIT_ACTION_DELETE_WEBHOOK: &str = "chat.webhook.delete";
+const MAX_WEBHOOK_URL_LEN: usize = 2048;
+/// Short secrets make the `X-Signature` HMAC easy to brute-force.
+const MIN_WEBHOOK_SECRET_LEN: usize = 16;
+
+#[derive(Deserialize)]
+struct ChatIdPath {
+    chat_id: i64,
+}
+
+#[derive(Deserialize)]
+struct WebhookPath {
+    chat_id: i64,
+    webhook_id: i64,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct CreateWebhookBody {
+    url: String,
+    /// HMAC-SHA256 key for the `X-Signature` header; never returned.
+    secret: String,
+    /// Any of `message.created`, `message.updated`, `message.deleted`.
+    events: Vec<String>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct UpdateWebhookBody {
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    secret: Option<String>,
+    #[serde(default)]
+    events: Option<Vec<String>>,
+    /// `true` re-enables a webhook disabled after failed deliveries.
+    #[serde(default)]
+    enabled: Option<bool>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct WebhookResponse {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    id: i64,
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    chat_id: i64,
+    url: String,
+    events: Vec<String>,
+    created_by: i32,
+    #[serde(with = "crate::serde_timestamp")]
+    created_at: DateTime<Utc>,
+    #[serde(with = "crate::serde_timestamp")]
+    updated_at: DateTime<Utc>,
+    /// Deliveries in a row that gave up.
+    consecutive_failures: i32,
+    /// Set while the webhook gets no events, after too many failed deliveries.
+    #[serde(with = "crate::serde_timestamp::opt")]
+    disabled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListWebhooksResponse {
+    webhooks: Vec<WebhookResponse>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct WebhookDeliveryResponse {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    id: i64,
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    webhook_id: i64,
+    event: String,
+    /// Counts from 1 within one event's delivery.
+    attempt: i32,
+    /// `null` when no response arrived.
+    status_code: Option<i32>,
+    /// Transport error when no response arrived.
+    error: Option<String>,
+    succeeded: bool,
+    #[serde(with = "crate::serde_timestamp")]
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListWebhookDeliveriesResponse {
+    deliveries: Vec<WebhookDeliveryResponse>,
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryResponse {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            webhook_id: delivery.webhook_id,
+            event: delivery.event,
+            attempt: delivery.attempt,
+            status_code: delivery.status_code,
+            error: delivery.error,
+            succeeded: delivery.succeeded,
+            created_at: delivery.created_at,
+        }
+    }
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(hook: Webhook) -> Self {
+        Self {
+            id: hook.id,
+            chat_id: hook.chat_id,
+            url: hook.url,
+            events: hook.events.into_iter().flatten().collect(),
+            created_by: hook.created_by,
+            created_at: hook.created_at,
+            updated_at: hook.updated_at,
+            consecutive_failures: hook.consecutive_failures,
+            disabled_at: hook.disabled_at,
+        }
+    }
+}
+
+fn validate_webhook_url(url: &str) -> Result<reqwest::Url, AppError> {
+    if url.len() > MAX_WEBHOOK_URL_LEN {
+        return Err(AppError::BadRequest("Webhook URL is too long"));
+    }
+    let parsed =
+        reqwest::Url::parse(url).map_err(|_| AppError::BadRequest("Invalid webhook URL"))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host().is_none() {
+        return Err(AppError::BadRequest("Webhook URL must be http(s)"));
+    }
+    Ok(parsed)
+}
+
+/// A well-formed URL whose host resolves to addresses webhooks may reach.
+async fn check_webhook_url(state: &AppState, url: &str) -> Result<(), AppError> {
+    let parsed = validate_webhook_url(url)?;
+    state
+        .webhook_service
+        .destinations
+        .check(&parsed)
+        .await
+        .map_err(AppError::BadRequest)
+}
+
+fn validate_webhook_secret(secret: &str) -> Result<(), AppError> {
+    if secret.chars().count() < MIN_WEBHOOK_SECRET_LEN {
+        return Err(AppError::BadRequest(
+            "Webhook secret must be at least 16 characters",
+        ));
+    }
+    Ok(())
+}
+
+/// Known event names, de-duplicated and in a stable order.
+fn parse_webhook_events(events: &[String]) -> Result<Vec<String>, AppError> {
+    let mut parsed = Vec::with_capacity(events.len());
+    for event in events {
+        let event =
+            WebhookEvent::parse(event).ok_or(AppError::BadRequest("Unknown webhook event"))?;
+        if !parsed.contains(&event) {
+            parsed.push(event);
+        }
+    }
+    if parsed.is_empty() {
+        return Err(AppError::BadRequest(
+            "At least one webhook event is required",
+        ));
+    }
+    Ok(WebhookEvent::ALL
+        .into_iter()
+        .filter(|event| parsed.contains(event))
+        .map(|event| event.as_str().to_string())
+        .collect())
+}
+
+/// Chat admins manage their own chat's webhooks; holders of the global
+/// `WebhookManage` permission manage any chat's.
+fn require_webhook_admin(
+    conn: &mut PgConnection,
+    state: &AppState,
+    chat_id: i64,
+    uid: i32,
+) -> Result<(), AppError> {
+    if state.authz_service.has_permission(
+        conn,
+        uid,
+        AuthzAction::WebhookManage,
+        AuthzResource::Global,
+    )? {
+        return Ok(());
+    }
+    require_admin_role(conn, chat_id, uid)
+}
+
+fn require_live_chat(conn: &mut PgConnection, chat_id: i64) -> Result<(), AppError> {
+    let exists = groups::table
+        .filter(groups::id.eq(chat_id))
+        .filter(groups::deleted_at.is_null())
+        .count()
+        .get_result::<i64>(conn)?;
+    if exists == 0 {
+        return Err(AppError::CHAT_NOT_FOUND);
+    }
+    Ok(())
+}
+
+async fn record_audit(
+    conn: &mut PgConnection,
+    state: &AppState,
+    uid: i32,
+    action: &str,
+    chat_id: i64,
+    metadata: serde_json::Value,
+) -> Result<(), AppError> {
+    let audit_id = ids::next_id(state.id_gen.as_ref()).await.map_err(|e| {
+        tracing::error!("next_id for audit log: {:?}", e);
+        AppError::Internal("ID generation failed")
+    })?;
+    diesel::insert_into(admin_audit_log::table)
+        .values(&NewAdminAuditLog {
+            id: audit_id,
+            actor_uid: uid,
+            action: action.to_string(),
+            chat_id: Some(chat_id),
+            metadata,
+            created_at: Utc::now(),
+        })
+        .execute(conn)?;
+    Ok(())
+}
+
+/// GET /chats/:chat_id/webhooks — List a chat's webhooks.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "webhooks",
+    params(("chat_id" = i64, Path, description = "Chat ID")),
+    responses(
+        (status = OK, body = ListWebhooksResponse),
+        (status = FORBIDDEN, description = "Chat admin or webhook permission required"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn list_webhooks(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+) -> Result<Json<ListWebhooksResponse>, AppError> {
+    let conn = &mut *conn;
+
+    require_webhook_admin(conn, &state, chat_id, uid)?;
+
+    let hooks: Vec<Webhook> = webhooks::table
+        .filter(webhooks::chat_id.eq(chat_id))
+        .order(webhooks::id.asc())
+        .select(Webhook::as_select())
+        .load(conn)?;
+
+    Ok(Json(ListWebhooksResponse {
+        webhooks: hooks.into_iter().map(WebhookResponse::from).collect(),
+    }))
+}
+
+/// POST /chats/:chat_id/webhooks — Register a webhook for a chat.
+///
+/// Subscribed events are POSTed as JSON with an `X-Signature` header of
+/// `sha256=<hex HMAC-SHA256 of the body>`, retried with backoff on non-2xx.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "webhooks",
+    params(("chat_id" = i64, Path, description = "Chat ID")),
+    request_body = CreateWebhookBody,
+    responses(
+        (status = CREATED, body = WebhookResponse),
+        (status = BAD_REQUEST, description = "Invalid or non-public URL, secret or events"),
+        (status = FORBIDDEN, description = "Chat admin or webhook permission required"),
+        (status = NOT_FOUND, description = "Chat not found"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn create_webhook(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(ChatIdPath { chat_id }): Path<ChatIdPath>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<CreateWebhookBody>,
+) -> Result<(StatusCode, Json<WebhookResponse>), AppError> {
+    let conn = &mut *conn;
+
+    require_webhook_admin(conn, &state, chat_id, uid)?;
+    require_live_chat(conn, chat_id)?;
+    check_webhook_url(&state, &body.url).await?;
+    validate_webhook_secret(&body.secret)?;
+    let events = parse_webhook_events(&body.events)?;
+
+    let id = ids::next_id(state.id_gen.as_ref()).await.map_err(|e| {
+        tracing::error!("next_id for webhook: {:?}", e);
+        AppError::Internal("ID generation failed")
+    })?;
+    let now = Utc::now();
+    let hook = diesel::insert_into(webhooks::table)
+        .values(&NewWebhook {
+            id,
+            chat_id,
+            url: body.url,
+            secret: body.secret,
+            events,
+            created_by: uid,
+            created_at: now,
+            updated_at: now,
+        })
+        .returning(Webhook::as_returning())
+        .get_result::<Webhook>(conn)?;
+
+    record_audit(
+        conn,
+        &state,
+        uid,
+        AUDIT_ACTION_CREATE_WEBHOOK,
+        chat_id,
+        json!({ "webhookId": hook.id.to_string(), "url": hook.url }),
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(hook.into())))
+}
+
+/// PATCH /chats/:chat_id/webhooks/:webhook_id — Change a webhook's URL, secret or events,
+/// or enable or disable it.
+#[utoipa::path(
+    patch,
+    path = "/{webhook_id}",
+    tag = "webhooks",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("webhook_id" = i64, Path, description = "Webhook ID"),
+    ),
+    request_body = UpdateWebhookBody,
+    responses(
+        (status = OK, body = WebhookResponse),
+        (status = BAD_REQUEST, description = "Invalid or non-public URL, secret or events"),
+        (status = FORBIDDEN, description = "Chat admin or webhook permission required"),
+        (status = NOT_FOUND, description = "Webhook not found"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn patch_webhook(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(WebhookPath {
+        chat_id,
+        webhook_id,
+    }): Path<WebhookPath>,
+    mut conn: DbConn,
+    JsonBody(body): JsonBody<UpdateWebhookBody>,
+) -> Result<Json<WebhookResponse>, AppError> {
+    let conn = &mut *conn;
+
+    require_webhook_admin(conn, &state, chat_id, uid)?;
+    if let Some(url) = &body.url {
+        check_webhook_url(&state, url).await?;
+    }
+    if let Some(secret) = &body.secret {
+        validate_webhook_secret(secret)?;
+    }
+    let events = body
+        .events
+        .as_deref()
+        .map(parse_webhook_events)
+        .transpose()?;
+    let secret_rotated = body.secret.is_some();
+    let now = Utc::now();
+    // Either way the failure count starts over.
+    let (consecutive_failures, disabled_at) = match body.enabled {
+        Some(true) => (Some(0), Some(None)),
+        Some(false) => (Some(0), Some(Some(now))),
+        None => (None, None),
+    };
+
+    let hook = diesel::update(
+        webhooks::table
+            .filter(webhooks::id.eq(webhook_id))
+            .filter(webhooks::chat_id.eq(chat_id)),
+    )
+    .set(&UpdateWebhook {
+        url: body.url,
+        secret: body.secret,
+        events,
+        consecutive_failures,
+        disabled_at,
+        updated_at: now,
+    })
+    .returning(Webhook::as_returning())
+    .get_result::<Webhook>(conn)
+    .optional()?
+    .ok_or(AppError::NotFound("Webhook not found"))?;
+
+    record_audit(
+        conn,
+        &state,
+        uid,
+        AUDIT_ACTION_UPDATE_WEBHOOK,
+        chat_id,
+        json!({
+            "webhookId": hook.id.to_string(),
+            "url": hook.url,
+            "secretRotated": secret_rotated,
+            "enabled": body.enabled,
+        }),
+    )
+    .await?;
+
+    Ok(Json(hook.into()))
+}
+
+/// DELETE /chats/:chat_id/webhooks/:webhook_id — Stop delivering to a webhook.
+#[utoipa::path(
+    delete,
+    path = "/{webhook_id}",
+    tag = "webhooks",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("webhook_id" = i64, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = NO_CONTENT, description = "Webhook deleted"),
+        (status = FORBIDDEN, description = "Chat admin or webhook permission required"),
+        (status = NOT_FOUND, description = "Webhook not found"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn delete_webhook(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(WebhookPath {
+        chat_id,
+        webhook_id,
+    }): Path<WebhookPath>,
+    mut conn: DbConn,
+) -> Result<StatusCode, AppError> {
+    let conn = &mut *conn;
+
+    require_webhook_admin(conn, &state, chat_id, uid)?;
+
+    let deleted = diesel::delete(
+        webhooks::table
+            .filter(webhooks::id.eq(webhook_id))
+            .filter(webhooks::chat_id.eq(chat_id)),
+    )
+    .execute(conn)?;
+    if deleted == 0 {
+        return Err(AppError::NotFound("Webhook not found"));
+    }
+
+    record_audit(
+        conn,
+        &state,
+        uid,
+        AUDIT_ACTION_DELETE_WEBHOOK,
+        chat_id,
+        json!({ "webhookId": webhook_id.to_string() }),
+    )
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /chats/:chat_id/webhooks/:webhook_id/deliveries — Recent delivery attempts, newest first.
+#[utoipa::path(
+    get,
+    path = "/{webhook_id}/deliveries",
+    tag = "webhooks",
+    params(
+        ("chat_id" = i64, Path, description = "Chat ID"),
+        ("webhook_id" = i64, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = OK, body = ListWebhookDeliveriesResponse),
+        (status = FORBIDDEN, description = "Chat admin or webhook permission required"),
+        (status = NOT_FOUND, description = "Webhook not found"),
+    ),
+    security(("uid_header" = []), ("bearer_jwt" = [])),
+)]
+async fn list_webhook_deliveries(
+    CurrentUid(uid): CurrentUid,
+    State(state): State<AppState>,
+    Path(WebhookPath {
+        chat_id,
+        webhook_id,
+    }): Path<WebhookPath>,
+    mut conn: DbConn,
+) -> Result<Json<ListWebhookDeliveriesResponse>, AppError> {
+    let conn = &mut *conn;
+
+    require_webhook_admin(conn, &state, chat_id, uid)?;
+    let exists = webhooks::table
+        .filter(webhooks::id.eq(webhook_id))
+        .filter(webhooks::chat_id.eq(chat_id))
+        .count()
+        .get_result::<i64>(conn)?;
+    if exists == 0 {
+        return Err(AppError::NotFound("Webhook not found"));
+    }
+
+    let deliveries: Vec<WebhookDelivery> = webhook_deliveries::table
+        .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+        .order(webhook_deliveries::id.desc())
+        .limit(DELIVERY_LOG_LIMIT)
+        .select(WebhookDelivery::as_select())
+        .load(conn)?;
+
+    Ok(Json(ListWebhookDeliveriesResponse {
+        deliveries: deliveries
+            .into_iter()
+            .map(WebhookDeliveryResponse::from)
+            .collect(),
+    }))
+}
+
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(utoipa_axum::routes!(list_webhooks, create_webhook))
+        .routes(utoipa_axum::routes!(patch_webhook, delete_webhook))
+        .routes(utoipa_axum::routes!(list_webhook_deliveries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn accepts_http_and_https_urls() {
+        assert!(validate_webhook_url("https://example.com/hook").is_ok());
+        assert!(validate_webhook_url("http://example.com:8080/in").is_ok());
+    }
+
+    #[tokio::test]
+    async fn internal_destinations_need_an_explicit_allowance() {
+        use crate::services::webhooks::DestinationPolicy;
+
+        let check = |policy: DestinationPolicy, url: &'static str| async move {
+            policy.check(&validate_webhook_url(url).unwrap()).await
+        };
+        for url in [
+            "http://10.0.0.5:8080/in",
+            "http://127.0.0.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[::ffff:192.168.1.1]/hook",
+            "http://localhost/hook",
+        ] {
+            assert!(
+                check(DestinationPolicy::default(), url).await.is_err(),
+                "{url}"
+            );
+        }
+        assert!(
+            check(DestinationPolicy::default(), "https://93.184.215.14/hook")
+                .await
+                .is_ok()
+        );
+        let allowed = DestinationPolicy::new(["10.0.0.5", " LOCALHOST "]);
+        assert!(check(allowed.clone(), "http://10.0.0.5:8080/in")
+            .await
+            .is_ok());
+        assert!(check(allowed.clone(), "http://localhost/hook")
+            .await
+            .is_ok());
+        assert!(check(allowed, "http://10.0.0.6/in").await.is_err());
+    }
+
+    #[test]
+    fn rejects_non_http_urls() {
+        for url in ["ftp://example.com/x", "file:///etc/passwd", "not a url", ""] {
+            assert!(
+                matches!(validate_webhook_url(url), Err(AppError::BadRequest(_))),
+                "{url}"
+            );
+        }
+        let long = format!("https://example.com/{}", "a".repeat(MAX_WEBHOOK_URL_LEN));
+        assert!(validate_webhook_url(&long).is_err());
+    }
+
+    #[test]
+    fn rejects_short_secrets() {
+        assert!(validate_webhook_secret("too-short").is_err());
+        assert!(validate_webhook_secret("sixteen-chars-ok").is_ok());
+    }
+
+    #[test]
+    fn events_are_deduplicated_in_stable_order() {
+        assert_eq!(
+            parse_webhook_events(&events(&[
+                "message.deleted",
+                "message.created",
+                "message.deleted",
+            ]))
+            .unwrap(),
+            events(&["message.created", "message.deleted"])
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn chat_admins_manage_webhooks_under_the_chat() {
+        use axum::http::Method;
+
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, member) = (901_221, 901_222);
+        app.seed_user(admin);
+        app.seed_user(member);
+        let chat_id = app.seed_chat("Integrations").await;
+        app.seed_membership(chat_id, admin, crate::models::GroupRole::Admin);
+        app.seed_membership(chat_id, member, crate::models::GroupRole::Member);
+        let hooks_uri = format!("/chats/{chat_id}/webhooks");
+        let body = json!({
+            "url": "https://93.184.215.14/hook",
+            "secret": "sixteen-chars-ok",
+            "events": ["message.created"],
+        });
+
+        let (status, _) = app
+            .request(Method::POST, &hooks_uri, member, Some(body.clone()))
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, created) = app
+            .request(Method::POST, &hooks_uri, admin, Some(body))
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{created}");
+        let webhook_id = created["id"].as_str().unwrap();
+
+        let (status, listed) = app.request(Method::GET, &hooks_uri, admin, None).await;
+        assert_eq!(status, StatusCode::OK, "{listed}");
+        assert_eq!(listed["webhooks"][0]["id"], webhook_id);
+        let deliveries_uri = format!("{hooks_uri}/{webhook_id}/deliveries");
+        let (status, deliveries) = app.request(Method::GET, &deliveries_uri, admin, None).await;
+        assert_eq!(status, StatusCode::OK, "{deliveries}");
+        assert_eq!(deliveries["deliveries"], json!([]));
+        let (status, _) = app
+            .request(Method::GET, &deliveries_uri, member, None)
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = app
+            .request(
+                Method::GET,
+                &format!("/admin/chats/{chat_id}/webhooks"),
+                admin,
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn rejects_unknown_or_missing_events() {
+        assert!(parse_webhook_events(&events(&["message.read"])).is_err());
+        assert!(parse_webhook_events(&[]).is_err());
+    }
+}
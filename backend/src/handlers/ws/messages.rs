@@ -1,7 +1,9 @@
-use crate::handlers::chats::{MessageResponse, ReactionSummary};
+use crate::handlers::chats::MessageResponse;
 use crate::handlers::pins::PinResponse;
+use crate::models::{GroupRole, GroupVisibility, ModerationPolicy};
 use chrono::{DateTime, Utc};
 use serde::Serialize;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -14,48 +16,110 @@ pub struct BulkDeletedPayload {
 #[serde(tag = "type", content = "payload", rename_all = "camelCase")]
 pub enum ServerWsMessage {
     Message(MessageResponse),
+    Mention(MentionPayload),
     MessageUpdated(MessageResponse),
     MessageDeleted(MessageResponse),
+    MessageRestored(MessageResponse),
     MessagesBulkDeleted(BulkDeletedPayload),
-    ReactionUpdated(ReactionUpdatePayload),
+    ReactionDelta(ReactionDeltaPayload),
+    ReadStateUpdated(ReadStateUpdatedPayload),
     PresenceUpdate(PresenceUpdatePayload),
+    Presence(UserPresencePayload),
     ThreadUpdate(ThreadUpdatePayload),
     ThreadMembershipChanged(ThreadMembershipChangedPayload),
     ChatArchiveStateChanged(ChatArchiveStateChangedPayload),
+    ChatUpdated(ChatUpdatedPayload),
+    ChatDeleted(ChatDeletedPayload),
     PinAdded(PinUpdatePayload),
     PinRemoved(PinUpdatePayload),
+    MemberAdded(MemberUpdatePayload),
+    MemberRemoved(MemberUpdatePayload),
+    RoleChanged(MemberUpdatePayload),
     StickerPackOrderUpdated(StickerPackOrderUpdatePayload),
+    CatchUpComplete(CatchUpCompletePayload),
+    Connected(ConnectedPayload),
+    Subscriptions(SubscriptionsPayload),
 }
 
 impl ServerWsMessage {
     pub fn message_type(&self) -> &'static str {
         match self {
             Self::Message(_) => "message",
+            Self::Mention(_) => "mention",
             Self::MessageUpdated(_) => "messageUpdated",
             Self::MessageDeleted(_) => "messageDeleted",
+            Self::MessageRestored(_) => "messageRestored",
             Self::MessagesBulkDeleted(_) => "messagesBulkDeleted",
-            Self::ReactionUpdated(_) => "reactionUpdated",
+            Self::ReactionDelta(_) => "reactionDelta",
+            Self::ReadStateUpdated(_) => "readStateUpdated",
             Self::PresenceUpdate(_) => "presenceUpdate",
+            Self::Presence(_) => "presence",
             Self::ThreadUpdate(_) => "threadUpdate",
             Self::ThreadMembershipChanged(_) => "threadMembershipChanged",
             Self::ChatArchiveStateChanged(_) => "chatArchiveStateChanged",
+            Self::ChatUpdated(_) => "chatUpdated",
+            Self::ChatDeleted(_) => "chatDeleted",
             Self::PinAdded(_) => "pinAdded",
             Self::PinRemoved(_) => "pinRemoved",
+            Self::MemberAdded(_) => "memberAdded",
+            Self::MemberRemoved(_) => "memberRemoved",
+            Self::RoleChanged(_) => "roleChanged",
             Self::StickerPackOrderUpdated(_) => "stickerPackOrderUpdated",
+            Self::CatchUpComplete(_) => "catchUpComplete",
+            Self::Connected(_) => "connected",
+            Self::Subscriptions(_) => "subscriptions",
         }
     }
 }
 
+/// Sent only to members mentioned in a new message, alongside the regular
+/// `message` event, so clients can badge mentions separately.
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct ReactionUpdatePayload {
+pub struct MentionPayload {
     #[serde(with = "crate::serde_i64_string")]
     #[schema(value_type = String)]
     pub message_id: i64,
     #[serde(with = "crate::serde_i64_string")]
     #[schema(value_type = String)]
     pub chat_id: i64,
-    pub reactions: Vec<ReactionSummary>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReactionOp {
+    Add,
+    Remove,
+}
+
+/// One reaction added or removed. `new_count` is the emoji's count after the
+/// change as read back from the database, so clients apply it as-is; one that
+/// missed a delta resyncs from `GET /chats/:chat_id/messages/:message_id/reactions`.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReactionDeltaPayload {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    pub message_id: i64,
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    pub chat_id: i64,
+    pub emoji: String,
+    pub uid: i32,
+    pub op: ReactionOp,
+    pub new_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadStateUpdatedPayload {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    pub chat_id: i64,
+    pub uid: i32,
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    pub last_read_message_id: i64,
 }
 
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
@@ -64,6 +128,14 @@ pub struct PresenceUpdatePayload {
     pub active_connections: u32,
 }
 
+/// Sent to a user's chat co-members when they come online or go offline.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPresencePayload {
+    pub uid: i32,
+    pub online: bool,
+}
+
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ThreadUpdatePayload {
@@ -99,6 +171,39 @@ pub struct ChatArchiveStateChangedPayload {
     pub muted_until: Option<DateTime<Utc>>,
 }
 
+/// Sent to members after an admin edits chat metadata so open clients refresh
+/// the chat header. Carries only chat-wide fields, never a member's own role
+/// or mute state.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatUpdatedPayload {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    pub chat_id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(with = "crate::serde_i64_string::opt")]
+    #[schema(value_type = Option<String>)]
+    pub avatar_image_id: Option<i64>,
+    pub avatar: Option<String>,
+    pub visibility: GroupVisibility,
+    pub moderation_policy: ModerationPolicy,
+    pub welcome_message: Option<String>,
+    pub welcome_message_as_dm: bool,
+    pub slow_mode_secs: i32,
+    pub retention_days: i32,
+}
+
+/// Sent to everyone who was a member when an admin deleted the chat; clients
+/// drop it from their chat list.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatDeletedPayload {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    pub chat_id: i64,
+}
+
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PinUpdatePayload {
@@ -115,11 +220,128 @@ pub struct PinUpdatePayload {
     pub pin: Option<PinResponse>,
 }
 
+/// Roster change in a chat. `role` is the member's role after the change and
+/// is omitted when they were removed.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberUpdatePayload {
+    #[serde(with = "crate::serde_i64_string")]
+    #[schema(value_type = String)]
+    pub chat_id: i64,
+    pub uid: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<GroupRole>,
+}
+
+/// Ends the replay of `message` events missed since the `since` cursor given to
+/// `/ws`. When `truncated`, more messages were missed than are replayed and the
+/// client should page the rest over REST.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CatchUpCompletePayload {
+    pub truncated: bool,
+}
+
+/// First frame on an authenticated socket: which co-members of each of the
+/// user's chats are online right now, keyed by string chat id. Chats without
+/// anyone else online are left out. When `snapshot_truncated`, the user is in
+/// too many chats for a snapshot, the map is empty and clients fall back to
+/// per-user `presence` events.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectedPayload {
+    pub uid: i32,
+    /// This socket's id, which a client sends as `X-Skip-Echo` to not be sent
+    /// its own messages back.
+    pub conn_id: u64,
+    pub online_members_by_chat: BTreeMap<String, Vec<i32>>,
+    pub snapshot_truncated: bool,
+}
+
+/// Reply to a client `subscriptions` frame, sent only to the asking socket.
+/// `chat_ids` is `null` until the first `subscribe`, while the socket still
+/// gets events from every chat.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionsPayload {
+    pub chat_ids: Option<Vec<String>>,
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{PresenceUpdatePayload, ServerWsMessage, ThreadMembershipChangedPayload};
+    use super::{
+        BulkDeletedPayload, CatchUpCompletePayload, ChatArchiveStateChangedPayload,
+        ChatDeletedPayload, ChatUpdatedPayload, ConnectedPayload, MemberUpdatePayload,
+        MentionPayload, PresenceUpdatePayload, ReadStateUpdatedPayload, ServerWsMessage,
+        ThreadMembershipChangedPayload, UserPresencePayload,
+    };
+    use crate::models::{GroupVisibility, ModerationPolicy};
     use serde_json::json;
 
+    fn chat_updated() -> ChatUpdatedPayload {
+        ChatUpdatedPayload {
+            chat_id: 7,
+            name: "Renamed".to_string(),
+            description: None,
+            avatar_image_id: Some(9),
+            avatar: None,
+            visibility: GroupVisibility::Public,
+            moderation_policy: ModerationPolicy::Off,
+            welcome_message: None,
+            welcome_message_as_dm: false,
+            slow_mode_secs: 30,
+            retention_days: 0,
+        }
+    }
+
+    #[test]
+    fn message_type_matches_the_serialized_type_tag() {
+        let member = || MemberUpdatePayload {
+            chat_id: 7,
+            uid: 3,
+            role: None,
+        };
+        let events = [
+            ServerWsMessage::Mention(MentionPayload {
+                message_id: 1,
+                chat_id: 7,
+            }),
+            ServerWsMessage::MessagesBulkDeleted(BulkDeletedPayload {
+                chat_id: "7".to_string(),
+                message_ids: vec!["1".to_string()],
+            }),
+            ServerWsMessage::PresenceUpdate(PresenceUpdatePayload {
+                active_connections: 1,
+            }),
+            ServerWsMessage::Presence(UserPresencePayload {
+                uid: 3,
+                online: false,
+            }),
+            ServerWsMessage::ChatArchiveStateChanged(ChatArchiveStateChangedPayload {
+                chat_id: 7,
+                archived: true,
+                muted_until: None,
+            }),
+            ServerWsMessage::ChatUpdated(chat_updated()),
+            ServerWsMessage::ChatDeleted(ChatDeletedPayload { chat_id: 7 }),
+            ServerWsMessage::MemberAdded(member()),
+            ServerWsMessage::MemberRemoved(member()),
+            ServerWsMessage::RoleChanged(member()),
+            ServerWsMessage::CatchUpComplete(CatchUpCompletePayload { truncated: false }),
+            ServerWsMessage::Connected(ConnectedPayload {
+                uid: 3,
+                conn_id: 1,
+                online_members_by_chat: Default::default(),
+                snapshot_truncated: false,
+            }),
+        ];
+
+        for event in events {
+            let value = serde_json::to_value(&event).expect("serialize ws event");
+            assert_eq!(value["type"], json!(event.message_type()));
+        }
+    }
+
     #[test]
     fn serializes_ws_event_types_and_payload_keys_as_camel_case() {
         let value = serde_json::to_value(ServerWsMessage::PresenceUpdate(PresenceUpdatePayload {
@@ -146,6 +368,111 @@ mod tests {
         assert_eq!(value["payload"]["threadRootId"], json!("42"));
         assert_eq!(value["payload"]["chatId"], json!("7"));
     }
+
+    #[test]
+    fn serializes_read_state_updated_with_string_ids() {
+        let value =
+            serde_json::to_value(ServerWsMessage::ReadStateUpdated(ReadStateUpdatedPayload {
+                chat_id: 7,
+                uid: 3,
+                last_read_message_id: 42,
+            }))
+            .expect("serialize read state event");
+
+        assert_eq!(value["type"], json!("readStateUpdated"));
+        assert_eq!(value["payload"]["chatId"], json!("7"));
+        assert_eq!(value["payload"]["uid"], json!(3));
+        assert_eq!(value["payload"]["lastReadMessageId"], json!("42"));
+    }
+
+    #[test]
+    fn serializes_connected_snapshot_keyed_by_string_chat_id() {
+        let value = serde_json::to_value(ServerWsMessage::Connected(ConnectedPayload {
+            uid: 3,
+            conn_id: 11,
+            online_members_by_chat: [("42".to_string(), vec![5, 9])].into_iter().collect(),
+            snapshot_truncated: false,
+        }))
+        .expect("serialize connected event");
+
+        assert_eq!(value["type"], json!("connected"));
+        assert_eq!(value["payload"]["uid"], json!(3));
+        assert_eq!(value["payload"]["connId"], json!(11));
+        assert_eq!(value["payload"]["onlineMembersByChat"]["42"], json!([5, 9]));
+        assert_eq!(value["payload"]["snapshotTruncated"], json!(false));
+    }
+
+    #[test]
+    fn serializes_chat_updated_without_member_fields() {
+        let value = serde_json::to_value(ServerWsMessage::ChatUpdated(chat_updated()))
+            .expect("serialize chat updated event");
+
+        assert_eq!(value["type"], json!("chatUpdated"));
+        assert_eq!(value["payload"]["chatId"], json!("7"));
+        assert_eq!(value["payload"]["avatarImageId"], json!("9"));
+        assert_eq!(value["payload"]["slowModeSecs"], json!(30));
+        assert!(value["payload"].get("myRole").is_none());
+        assert!(value["payload"].get("mutedUntil").is_none());
+    }
+
+    #[test]
+    fn serializes_chat_deleted_with_string_chat_id() {
+        let value = serde_json::to_value(ServerWsMessage::ChatDeleted(ChatDeletedPayload {
+            chat_id: 42,
+        }))
+        .expect("serialize chat deleted event");
+
+        assert_eq!(value["type"], json!("chatDeleted"));
+        assert_eq!(value["payload"]["chatId"], json!("42"));
+    }
+
+    #[test]
+    fn serializes_user_presence_as_presence_event() {
+        let value = serde_json::to_value(ServerWsMessage::Presence(UserPresencePayload {
+            uid: 3,
+            online: true,
+        }))
+        .expect("serialize presence event");
+
+        assert_eq!(value["type"], json!("presence"));
+        assert_eq!(value["payload"]["uid"], json!(3));
+        assert_eq!(value["payload"]["online"], json!(true));
+    }
+
+    #[test]
+    fn serializes_mention_with_string_ids() {
+        let value = serde_json::to_value(ServerWsMessage::Mention(MentionPayload {
+            message_id: 42,
+            chat_id: 7,
+        }))
+        .expect("serialize mention event");
+
+        assert_eq!(value["type"], json!("mention"));
+        assert_eq!(value["payload"]["messageId"], json!("42"));
+        assert_eq!(value["payload"]["chatId"], json!("7"));
+    }
+
+    #[test]
+    fn serializes_member_events_with_role_only_when_present() {
+        let value = serde_json::to_value(ServerWsMessage::RoleChanged(MemberUpdatePayload {
+            chat_id: 7,
+            uid: 3,
+            role: Some(crate::models::GroupRole::Admin),
+        }))
+        .expect("serialize role change event");
+        assert_eq!(value["type"], json!("roleChanged"));
+        assert_eq!(value["payload"]["chatId"], json!("7"));
+        assert_eq!(value["payload"]["role"], json!("admin"));
+
+        let value = serde_json::to_value(ServerWsMessage::MemberRemoved(MemberUpdatePayload {
+            chat_id: 7,
+            uid: 3,
+            role: None,
+        }))
+        .expect("serialize member removed event");
+        assert_eq!(value["type"], json!("memberRemoved"));
+        assert!(value["payload"].get("role").is_none());
+    }
 }
 
 use crate::handlers::users::StickerPackOrderItem;
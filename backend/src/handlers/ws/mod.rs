@@ -1,23 +1,37 @@
-//! WebSocket handler: auth handshake, lifecycle-aware presence updates, ping/pong keepalive,
-//! connection registry, 300s stale timeout.
+//! WebSocket handler: auth handshake, lifecycle-aware presence updates, ping/pong keepalive
+//! (client text pings plus server protocol pings),
+//! per-chat subscribe/unsubscribe with a `subscriptions` query, an online co-member snapshot on connect,
+//! delivery acks with catch-up replay on reconnect, JSON or CBOR frames,
+//! connection registry, configurable stale timeout (300s by default).
 
 pub mod messages;
 
-use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::ws::{close_code, CloseFrame, Message, Utf8Bytes, WebSocket, WebSocketUpgrade};
 use axum::extract::State;
-use axum::response::Response;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde::{Deserialize, Serialize};
+use diesel::prelude::*;
+use diesel::PgConnection;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::time::timeout;
 use tracing::{debug, trace};
 use utoipa_axum::router::OpenApiRouter;
 
+use crate::errors::{error_response, AppError, ErrorCode};
+use crate::extractors::{DbConn, Query};
+use crate::handlers::chats::attach_metadata;
+use crate::models::Message as ChatMessage;
+use crate::schema::{self, group_membership};
+use crate::services::background::BackgroundJob;
 use crate::services::ws_registry;
 use crate::utils::auth::{decode_auth_token, encode_auth_token, AuthClaims, ClientId, CurrentUid};
 use crate::AppState;
-use messages::ServerWsMessage;
+use messages::{CatchUpCompletePayload, ConnectedPayload, ServerWsMessage, SubscriptionsPayload};
 use ws_registry::AppPresenceState;
 
 #[derive(Serialize, utoipa::ToSchema)]
@@ -26,12 +40,23 @@ pub struct TicketResponse {
     pub ticket: String,
 }
 
+/// Tickets only bridge the gap between fetching one and opening the socket.
+const WS_TICKET_TTL_SECS: u64 = 60;
+
+const UNKNOWN_USER: AppError = AppError::Coded(
+    StatusCode::UNAUTHORIZED,
+    ErrorCode::UnknownUser,
+    "Unknown user",
+);
+
+/// GET /ws/ticket — Issue a short-lived ticket for the WebSocket auth handshake.
 #[utoipa::path(
     get,
     path = "/ticket",
     tag = "websocket",
     responses(
         (status = OK, body = TicketResponse),
+        (status = UNAUTHORIZED, description = "No account has this uid"),
     ),
     security(("uid_header" = []), ("bearer_jwt" = [])),
 )]
@@ -39,11 +64,16 @@ async fn get_ws_ticket(
     CurrentUid(uid): CurrentUid,
     ClientId(client_id): ClientId,
     State(state): State<AppState>,
-) -> Result<Json<TicketResponse>, (axum::http::StatusCode, &'static str)> {
+    mut conn: DbConn,
+) -> Result<Json<TicketResponse>, AppError> {
+    if !is_known_user(&state, &mut conn, uid)? {
+        return Err(UNKNOWN_USER);
+    }
     let claims = AuthClaims {
         uid,
         cid: client_id,
         gen: 0,
+        exp: Some(jsonwebtoken::get_current_timestamp() + WS_TICKET_TTL_SECS),
     };
     let ticket = encode_auth_token(&claims, &state.jwt_signing_key)?;
 
@@ -64,8 +94,93 @@ struct WsMessage {
     #[serde(rename = "type")]
     type_: String,
     state: Option<WsAppState>,
+    /// Target of `subscribe` / `unsubscribe` / `ack`.
+    #[serde(
+        default,
+        deserialize_with = "crate::serde_i64_string::opt::deserialize"
+    )]
+    chat_id: Option<i64>,
+    /// `ack`: id of the newest message received in `chat_id`. Message ids are
+    /// monotonic per chat, so they double as delivery sequence numbers.
+    #[serde(
+        default,
+        deserialize_with = "crate::serde_i64_string::opt::deserialize"
+    )]
+    up_to_seq: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WsQuery {
+    /// Newest message id the client already has; missed messages after it are
+    /// replayed once the socket is authenticated.
+    #[serde(
+        default,
+        deserialize_with = "crate::serde_i64_string::opt::deserialize"
+    )]
+    since: Option<i64>,
+    #[serde(default)]
+    encoding: WsEncoding,
+}
+
+/// Wire format of a socket, chosen with `?encoding=` on connect.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum WsEncoding {
+    /// Text frames holding JSON.
+    #[default]
+    Json,
+    /// Binary frames holding CBOR, for bandwidth-sensitive clients.
+    Cbor,
+}
+
+impl WsEncoding {
+    /// Wrap a queued event for the socket. Broadcasts are encoded to JSON once
+    /// for every recipient, so CBOR sockets transcode their copy here.
+    fn outbound(self, frame: Utf8Bytes) -> Option<Message> {
+        match self {
+            Self::Json => Some(Message::Text(frame)),
+            Self::Cbor => match json_to_cbor(frame.as_str()) {
+                Ok(bytes) => Some(Message::Binary(bytes.into())),
+                Err(e) => {
+                    tracing::error!(error = %e, "ws frame CBOR transcoding failed");
+                    None
+                }
+            },
+        }
+    }
+}
+
+fn json_to_cbor(frame: &str) -> Result<Vec<u8>, String> {
+    let value: serde_json::Value = serde_json::from_str(frame).map_err(|e| e.to_string())?;
+    let mut bytes = Vec::with_capacity(frame.len());
+    ciborium::into_writer(&value, &mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes)
 }
 
+/// Client frames are JSON in text frames or CBOR in binary frames, whichever
+/// encoding the socket was opened with.
+fn decode_client_frame<T: DeserializeOwned>(message: &Message) -> Option<T> {
+    match message {
+        Message::Text(text) => serde_json::from_str(text).ok(),
+        Message::Binary(bytes) => ciborium::from_reader(bytes.as_ref()).ok(),
+        _ => None,
+    }
+}
+
+/// Most `message` events replayed on connect; clients page older gaps over REST.
+const CATCH_UP_LIMIT: i64 = 500;
+/// Most `message` events replayed per chat, so one busy chat cannot use up the
+/// whole replay budget and starve the others.
+const CATCH_UP_PER_CHAT_LIMIT: i64 = 100;
+/// Longest a replayed frame may wait for room in a connection's send buffer
+/// before the replay gives up on a client that stopped reading.
+const CATCH_UP_SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most chats summarized in the `connected` frame; beyond this the snapshot is
+/// omitted rather than loading every co-member of every chat.
+const CONNECTED_SNAPSHOT_MAX_CHATS: i64 = 200;
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 enum WsAppState {
@@ -84,27 +199,223 @@ impl From<WsAppState> for AppPresenceState {
 
 const PONG_JSON: &str = r#"{"type":"pong"}"#;
 
+/// Env var for how often the server pings each socket, in seconds.
+pub const WS_PING_INTERVAL_ENV: &str = "WS_PING_INTERVAL_SECS";
+/// Env var for how long a socket may stay silent before it is closed, in seconds.
+pub const WS_PONG_TIMEOUT_ENV: &str = "WS_PONG_TIMEOUT_SECS";
+/// Env var for how long a connection may go without a client ping before the
+/// registry prunes it, in seconds.
+pub const WS_PING_TIMEOUT_ENV: &str = "WS_PING_TIMEOUT_SECS";
+/// Env var for how often the registry is swept for stale connections, in seconds.
+pub const WS_PRUNE_INTERVAL_ENV: &str = "WS_PRUNE_INTERVAL_SECS";
+
+const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(25);
+const DEFAULT_PONG_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_STALE_TIMEOUT: Duration = Duration::from_secs(300);
+const DEFAULT_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Server-side keepalive: a protocol ping every `ping_interval`, and the socket
+/// is closed once nothing at all (pong or any other frame) arrived for
+/// `pong_timeout`. Catches dead TCP connections long before `prune_stale` does.
+///
+/// Separately, every `prune_interval` the registry drops connections whose
+/// last client ping is older than `stale_timeout`.
+#[derive(Debug, Clone, Copy)]
+pub struct Keepalive {
+    pub ping_interval: Duration,
+    pub pong_timeout: Duration,
+    pub stale_timeout: Duration,
+    pub prune_interval: Duration,
+}
+
+impl Keepalive {
+    /// Keepalive configured from `WS_PING_INTERVAL_SECS`, `WS_PONG_TIMEOUT_SECS`,
+    /// `WS_PING_TIMEOUT_SECS` and `WS_PRUNE_INTERVAL_SECS`.
+    pub fn from_env() -> Self {
+        let keepalive = Self {
+            ping_interval: read_secs(WS_PING_INTERVAL_ENV, DEFAULT_PING_INTERVAL),
+            pong_timeout: read_secs(WS_PONG_TIMEOUT_ENV, DEFAULT_PONG_TIMEOUT),
+            stale_timeout: read_secs(WS_PING_TIMEOUT_ENV, DEFAULT_STALE_TIMEOUT),
+            prune_interval: read_secs(WS_PRUNE_INTERVAL_ENV, DEFAULT_PRUNE_INTERVAL),
+        };
+        assert!(
+            keepalive.pong_timeout > keepalive.ping_interval,
+            "{WS_PONG_TIMEOUT_ENV} must be longer than {WS_PING_INTERVAL_ENV}"
+        );
+        keepalive
+    }
+
+    /// Prune connections that missed the stale timeout; returns the users left offline.
+    pub fn prune_stale(&self, registry: &ws_registry::ConnectionRegistry) -> Vec<i32> {
+        registry.prune_stale(self.stale_timeout.as_secs())
+    }
+}
+
+/// Env var that, set to `false`, also rejects upgrades without an `Origin`
+/// header. Browsers always send one, so omitting it marks a native client.
+pub const WS_ALLOW_MISSING_ORIGIN_ENV: &str = "WS_ALLOW_MISSING_ORIGIN";
+/// Env var naming a subprotocol clients must offer in `Sec-WebSocket-Protocol`.
+pub const WS_REQUIRED_SUBPROTOCOL_ENV: &str = "WS_REQUIRED_SUBPROTOCOL";
+
+const ORIGIN_NOT_ALLOWED: &str = "WebSocket origin not allowed";
+const SUBPROTOCOL_REQUIRED: &str = "Required WebSocket subprotocol not offered";
+const SERVER_AT_CAPACITY: &str = "Too many WebSocket connections, retry later";
+/// Sent with 503 when the registry is at its server-wide connection limit.
+const AT_CAPACITY_RETRY_AFTER_SECS: u64 = 5;
+
+/// Checks run on the upgrade request before any socket is opened, so a page
+/// on another site cannot ride a user's browser into a WebSocket session.
+#[derive(Debug, Clone)]
+pub struct UpgradePolicy {
+    /// Origins browsers may connect from; `None` accepts only the server's own host.
+    allowed_origins: Option<Vec<HeaderValue>>,
+    allow_missing_origin: bool,
+    required_subprotocol: Option<String>,
+}
+
+impl UpgradePolicy {
+    /// Policy allowing `allowed_origins` (the CORS list), configured from
+    /// `WS_ALLOW_MISSING_ORIGIN` (default `true`) and `WS_REQUIRED_SUBPROTOCOL`.
+    pub fn from_env(allowed_origins: Option<Vec<HeaderValue>>) -> Self {
+        let allow_missing_origin = std::env::var(WS_ALLOW_MISSING_ORIGIN_ENV)
+            .ok()
+            .map(|value| {
+                value.parse::<bool>().unwrap_or_else(|_| {
+                    panic!("{WS_ALLOW_MISSING_ORIGIN_ENV} must be true or false")
+                })
+            })
+            .unwrap_or(true);
+        let required_subprotocol = std::env::var(WS_REQUIRED_SUBPROTOCOL_ENV)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        Self {
+            allowed_origins,
+            allow_missing_origin,
+            required_subprotocol,
+        }
+    }
+
+    /// 403 unless the request's `Origin` is allowed, or absent and permitted to be.
+    fn check_origin(&self, headers: &HeaderMap) -> Result<(), AppError> {
+        let Some(origin) = headers.get(header::ORIGIN) else {
+            return if self.allow_missing_origin {
+                Ok(())
+            } else {
+                Err(AppError::Forbidden(ORIGIN_NOT_ALLOWED))
+            };
+        };
+        let allowed = match &self.allowed_origins {
+            Some(allowed) => allowed.iter().any(|allowed| allowed == origin),
+            None => is_same_origin(origin, headers.get(header::HOST)),
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(ORIGIN_NOT_ALLOWED))
+        }
+    }
+}
+
+/// Whether `origin` (`scheme://host[:port]`) names the host the request was sent to.
+fn is_same_origin(origin: &HeaderValue, host: Option<&HeaderValue>) -> bool {
+    let (Ok(origin), Some(Ok(host))) = (origin.to_str(), host.map(HeaderValue::to_str)) else {
+        return false;
+    };
+    origin
+        .split_once("://")
+        .is_some_and(|(_, authority)| authority.eq_ignore_ascii_case(host))
+}
+
+fn read_secs(var_name: &str, default: Duration) -> Duration {
+    std::env::var(var_name)
+        .ok()
+        .map(|value| {
+            value
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or_else(|| panic!("{var_name} must be a positive number of seconds"))
+        })
+        .unwrap_or(default)
+}
+
 /// Upgrades the connection to WebSocket and initiates auth handshake.
 #[utoipa::path(
     get,
     path = "/",
     tag = "websocket",
     description = "WebSocket upgrade endpoint",
+    params(
+        ("since" = Option<String>, Query, description = "Replay messages newer than this message ID"),
+        ("encoding" = Option<String>, Query, description = "`json` (default, text frames) or `cbor` (binary frames)"),
+    ),
     responses(
         (status = 101, description = "Switching Protocols"),
+        (status = 400, description = "Required subprotocol not offered"),
+        (status = 403, description = "Origin not allowed"),
+        (status = 503, description = "Server at its WebSocket connection limit"),
     ),
 )]
-async fn ws_handler(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
-    ws.on_upgrade(move |socket| handle_auth_and_socket(socket, state))
+async fn ws_handler(
+    State(state): State<AppState>,
+    Query(query): Query<WsQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let policy = &state.ws_upgrade_policy;
+    if let Err(e) = policy.check_origin(&headers) {
+        debug!(origin = ?headers.get(header::ORIGIN), "ws upgrade rejected");
+        return e.into_response();
+    }
+    if !state.ws_registry.has_capacity() {
+        debug!(
+            total = state.ws_registry.total_connections(),
+            "ws upgrade rejected, server at connection limit"
+        );
+        return at_capacity_response();
+    }
+    let ws = match &policy.required_subprotocol {
+        Some(protocol) => {
+            let ws = ws.protocols([protocol.clone()]);
+            if ws.selected_protocol().is_none() {
+                return AppError::BadRequest(SUBPROTOCOL_REQUIRED).into_response();
+            }
+            ws
+        }
+        None => ws,
+    };
+    ws.on_upgrade(move |socket| handle_auth_and_socket(socket, state, query.since, query.encoding))
+}
+
+fn at_capacity_response() -> Response {
+    (
+        [(
+            header::RETRY_AFTER,
+            AT_CAPACITY_RETRY_AFTER_SECS.to_string(),
+        )],
+        error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::AtCapacity,
+            SERVER_AT_CAPACITY,
+        ),
+    )
+        .into_response()
 }
 
-async fn handle_auth_and_socket(mut socket: WebSocket, state: AppState) {
+async fn handle_auth_and_socket(
+    mut socket: WebSocket,
+    state: AppState,
+    since: Option<i64>,
+    encoding: WsEncoding,
+) {
     // Wait for auth message, timeout after 5 seconds
     let auth_result = timeout(std::time::Duration::from_secs(5), socket.recv()).await;
 
     let uid = match auth_result {
-        Ok(Some(Ok(Message::Text(text)))) => {
-            if let Ok(parsed) = serde_json::from_str::<WsAuthMessage>(&text) {
+        Ok(Some(Ok(message))) => {
+            if let Some(parsed) = decode_client_frame::<WsAuthMessage>(&message) {
                 if parsed.type_ == "auth" {
                     match decode_auth_token(&parsed.ticket, &state.jwt_signing_key) {
                         Ok(claims) => claims.uid,
@@ -117,47 +428,179 @@ async fn handle_auth_and_socket(mut socket: WebSocket, state: AppState) {
                     return; // First message not auth
                 }
             } else {
-                return; // Invalid JSON or wrong structure
+                return; // Undecodable frame or wrong structure
             }
         }
-        _ => return, // Timeout, connection closed, or non-text message
+        _ => return, // Timeout or connection closed
     };
 
+    if let Err(close) = accept_uid(&state, uid) {
+        let _ = socket.send(Message::Close(Some(close))).await;
+        return;
+    }
+
     let registry = state.ws_registry.clone();
-    let (entry, rx) = registry.register(uid);
-    let conn_id = entry.conn_id;
+    let (entry, rx, came_online) = match registry.try_register(uid) {
+        Ok(registration) => registration,
+        Err(ws_registry::ConnectionLimitReached) => {
+            debug!("ws connection rejected, too many connections uid={}", uid);
+            let _ = socket
+                .send(Message::Close(Some(CloseFrame {
+                    code: close_code::POLICY,
+                    reason: "too many connections".into(),
+                })))
+                .await;
+            return;
+        }
+    };
+    if came_online {
+        state
+            .background_service
+            .enqueue(BackgroundJob::BroadcastPresence { uid });
+    }
+    tokio::spawn(send_initial_frames(
+        state.clone(),
+        uid,
+        entry.clone(),
+        since,
+    ));
+
+    handle_socket(socket, state, uid, registry, entry, rx, encoding).await;
+}
 
-    handle_socket(socket, state, uid, conn_id, registry, entry, rx).await;
+/// Whether `uid` names an account, cached in the registry so a burst of
+/// connects does not query the users table for each one.
+fn is_known_user(state: &AppState, conn: &mut PgConnection, uid: i32) -> QueryResult<bool> {
+    state
+        .ws_registry
+        .user_exists(uid, || crate::services::user::user_exists(conn, uid))
+}
+
+/// Refuse a ticket whose uid has no account, so nothing is registered or
+/// broadcast for it. Tickets are signed for accounts that existed when
+/// issued, but outlive a deletion by up to `WS_TICKET_TTL_SECS`.
+fn accept_uid(state: &AppState, uid: i32) -> Result<(), CloseFrame> {
+    let known = state
+        .db
+        .get()
+        .map_err(|e| e.to_string())
+        .and_then(|mut conn| is_known_user(state, &mut conn, uid).map_err(|e| e.to_string()));
+    match known {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            debug!("ws auth rejected, unknown uid={}", uid);
+            Err(CloseFrame {
+                code: close_code::POLICY,
+                reason: "unknown user".into(),
+            })
+        }
+        Err(e) => {
+            tracing::warn!(uid, error = %e, "ws auth: user lookup failed");
+            Err(CloseFrame {
+                code: close_code::AGAIN,
+                reason: "try again later".into(),
+            })
+        }
+    }
 }
 
 async fn handle_socket(
-    mut socket: WebSocket,
+    socket: WebSocket,
     state: AppState,
     uid: i32,
-    conn_id: u64,
     registry: Arc<ws_registry::ConnectionRegistry>,
     entry: Arc<ws_registry::ConnectionEntry>,
-    mut rx: tokio::sync::mpsc::Receiver<Arc<ServerWsMessage>>,
+    rx: tokio::sync::mpsc::Receiver<Utf8Bytes>,
+    encoding: WsEncoding,
 ) {
     let started_at = Instant::now();
+    run_socket(
+        socket,
+        uid,
+        &registry,
+        &entry,
+        rx,
+        state.ws_keepalive,
+        encoding,
+    )
+    .await;
+    if registry.remove_connection(uid, entry.conn_id) {
+        state
+            .background_service
+            .enqueue(BackgroundJob::BroadcastPresence { uid });
+    }
+    state
+        .metrics
+        .record_ws_connection_duration(started_at.elapsed().as_secs_f64());
+}
+
+/// Pumps frames both ways until the socket closes, fails, goes silent past the
+/// keepalive timeout, or the registry evicts the connection.
+async fn run_socket<S>(
+    mut socket: S,
+    uid: i32,
+    registry: &ws_registry::ConnectionRegistry,
+    entry: &ws_registry::ConnectionEntry,
+    mut rx: tokio::sync::mpsc::Receiver<Utf8Bytes>,
+    keepalive: Keepalive,
+    encoding: WsEncoding,
+) where
+    S: Stream<Item = Result<Message, axum::Error>> + Sink<Message> + Unpin,
+{
+    let conn_id = entry.conn_id;
+    let mut ping_timer = tokio::time::interval_at(
+        tokio::time::Instant::now() + keepalive.ping_interval,
+        keepalive.ping_interval,
+    );
+    ping_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_activity = tokio::time::Instant::now();
     loop {
         tokio::select! {
+            reason = entry.evicted() => {
+                debug!("ws connection evicted uid={} conn_id={} reason={:?}", uid, conn_id, reason);
+                let close = match reason {
+                    ws_registry::Eviction::TooFarBehind => CloseFrame {
+                        code: close_code::AGAIN,
+                        reason: "too far behind, reconnect to resync".into(),
+                    },
+                    ws_registry::Eviction::ConnectionLimit => CloseFrame {
+                        code: close_code::POLICY,
+                        reason: "replaced by a newer connection".into(),
+                    },
+                };
+                let _ = socket.send(Message::Close(Some(close))).await;
+                break;
+            }
+            _ = tokio::time::sleep_until(last_activity + keepalive.pong_timeout) => {
+                debug!("ws connection silent, closing uid={} conn_id={}", uid, conn_id);
+                break;
+            }
+            _ = ping_timer.tick() => {
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    break;
+                }
+            }
             msg = rx.recv() => {
                 match msg {
-                    Some(ws_msg) => {
-                        if let Ok(text) = serde_json::to_string(&*ws_msg) {
-                            if socket.send(Message::Text(text.into())).await.is_err() {
-                                break;
-                            }
+                    Some(frame) => {
+                        let Some(message) = encoding.outbound(frame) else {
+                            continue;
+                        };
+                        if socket.send(message).await.is_err() {
+                            break;
                         }
+                        entry.record_frame_sent();
                     }
                     None => break,
                 }
             }
-            msg = socket.recv() => {
+            msg = socket.next() => {
+                if let Some(Ok(_)) = &msg {
+                    last_activity = tokio::time::Instant::now();
+                }
                 match msg {
-                    Some(Ok(Message::Text(text))) => {
-                        if let Ok(parsed) = serde_json::from_str::<WsMessage>(&text) {
+                    Some(Ok(message @ (Message::Text(_) | Message::Binary(_)))) => {
+                        if let Some(parsed) = decode_client_frame::<WsMessage>(&message) {
                             if parsed.type_ == "ping" {
                                 let state = parsed
                                     .state
@@ -166,8 +609,31 @@ async fn handle_socket(
                                 entry.update_ping(state);
                                 registry.refresh_metrics();
                                 trace!("ws ping received uid={} conn_id={}", uid, conn_id);
-                                if socket.send(Message::Text(PONG_JSON.into())).await.is_err() {
-                                    break;
+                                if let Some(pong) = encoding.outbound(PONG_JSON.into()) {
+                                    if socket.send(pong).await.is_err() {
+                                        break;
+                                    }
+                                }
+                            } else if parsed.type_ == "subscribe" {
+                                if let Some(chat_id) = parsed.chat_id {
+                                    entry.subscribe(chat_id);
+                                }
+                            } else if parsed.type_ == "unsubscribe" {
+                                if let Some(chat_id) = parsed.chat_id {
+                                    entry.unsubscribe(chat_id);
+                                }
+                            } else if parsed.type_ == "subscriptions" {
+                                let reply = subscriptions_frame(entry).and_then(|frame| encoding.outbound(frame));
+                                if let Some(reply) = reply {
+                                    if socket.send(reply).await.is_err() {
+                                        break;
+                                    }
+                                }
+                            } else if parsed.type_ == "ack" {
+                                if let (Some(chat_id), Some(up_to_seq)) =
+                                    (parsed.chat_id, parsed.up_to_seq)
+                                {
+                                    registry.record_ack(uid, chat_id, up_to_seq);
                                 }
                             } else if parsed.type_ == "appState" {
                                 let state = parsed
@@ -191,10 +657,259 @@ async fn handle_socket(
             }
         }
     }
-    registry.remove_connection(uid, conn_id);
-    state
-        .metrics
-        .record_ws_connection_duration(started_at.elapsed().as_secs_f64());
+}
+
+/// The `subscriptions` reply listing the chats `entry` gets events from.
+fn subscriptions_frame(entry: &ws_registry::ConnectionEntry) -> Option<Utf8Bytes> {
+    let chat_ids = entry
+        .subscriptions()
+        .map(|chat_ids| chat_ids.iter().map(i64::to_string).collect());
+    ws_registry::encode_frame(&ServerWsMessage::Subscriptions(SubscriptionsPayload {
+        chat_ids,
+    }))
+}
+
+/// Send the `connected` snapshot, then the catch-up replay when `since` is given,
+/// so clients always see the snapshot before any replayed message.
+async fn send_initial_frames(
+    state: AppState,
+    uid: i32,
+    entry: Arc<ws_registry::ConnectionEntry>,
+    since: Option<i64>,
+) {
+    if !send_connected(&state, uid, &entry).await {
+        return;
+    }
+    if let Some(since) = since {
+        send_catch_up(state, uid, entry, since).await;
+    }
+}
+
+/// Returns false once the connection is gone.
+async fn send_connected(state: &AppState, uid: i32, entry: &ws_registry::ConnectionEntry) -> bool {
+    let rows = state
+        .db
+        .get()
+        .map_err(|e| e.to_string())
+        .and_then(|mut conn| load_co_members(&mut conn, uid).map_err(|e| e.to_string()));
+    let payload = match rows {
+        Ok(rows) => connected_payload(uid, entry.conn_id, rows, |member| {
+            state.ws_registry.is_online(member)
+        }),
+        Err(e) => {
+            tracing::warn!(uid, error = %e, "ws connected snapshot: loading members failed");
+            connected_payload(uid, entry.conn_id, None, |_| false)
+        }
+    };
+    entry.send(&ServerWsMessage::Connected(payload)).await
+}
+
+/// `(chat_id, uid)` of everyone else in the user's chats, or `None` when the
+/// user is in more than `CONNECTED_SNAPSHOT_MAX_CHATS` chats.
+fn load_co_members(conn: &mut PgConnection, uid: i32) -> QueryResult<Option<Vec<(i64, i32)>>> {
+    let chat_ids: Vec<i64> = group_membership::table
+        .filter(group_membership::uid.eq(uid))
+        .select(group_membership::chat_id)
+        .limit(CONNECTED_SNAPSHOT_MAX_CHATS + 1)
+        .load(conn)?;
+    if chat_ids.len() as i64 > CONNECTED_SNAPSHOT_MAX_CHATS {
+        return Ok(None);
+    }
+    group_membership::table
+        .filter(group_membership::chat_id.eq_any(&chat_ids))
+        .filter(group_membership::uid.ne(uid))
+        .select((group_membership::chat_id, group_membership::uid))
+        .load(conn)
+        .map(Some)
+}
+
+fn connected_payload(
+    uid: i32,
+    conn_id: u64,
+    co_members: Option<Vec<(i64, i32)>>,
+    is_online: impl Fn(i32) -> bool,
+) -> ConnectedPayload {
+    let Some(co_members) = co_members else {
+        return ConnectedPayload {
+            uid,
+            conn_id,
+            online_members_by_chat: BTreeMap::new(),
+            snapshot_truncated: true,
+        };
+    };
+    let mut online_members_by_chat: BTreeMap<String, Vec<i32>> = BTreeMap::new();
+    for (chat_id, member) in co_members {
+        if is_online(member) {
+            online_members_by_chat
+                .entry(chat_id.to_string())
+                .or_default()
+                .push(member);
+        }
+    }
+    for members in online_members_by_chat.values_mut() {
+        members.sort_unstable();
+    }
+    ConnectedPayload {
+        uid,
+        conn_id,
+        online_members_by_chat,
+        snapshot_truncated: false,
+    }
+}
+
+/// Replay published messages newer than `since` in the user's chats, skipping
+/// any an earlier connection acked, then send `catchUpComplete`. Runs beside the
+/// socket loop, whose draining of the channel lets the replay make progress.
+/// A client that stops reading gets no further replay after
+/// `CATCH_UP_SEND_TIMEOUT`, and no database connection is held while sending.
+async fn send_catch_up(
+    state: AppState,
+    uid: i32,
+    entry: Arc<ws_registry::ConnectionEntry>,
+    since: i64,
+) {
+    let Some((missed, truncated)) = load_catch_up(&state, uid, since).await else {
+        return;
+    };
+
+    for response in missed {
+        if !entry.wants_chat(response.chat_id) {
+            continue;
+        }
+        let frame = ServerWsMessage::Message(response);
+        if !matches!(
+            timeout(CATCH_UP_SEND_TIMEOUT, entry.send(&frame)).await,
+            Ok(true)
+        ) {
+            tracing::debug!(uid, "ws catch-up: client stopped reading, replay abandoned");
+            return;
+        }
+    }
+    let complete = ServerWsMessage::CatchUpComplete(CatchUpCompletePayload { truncated });
+    let _ = timeout(CATCH_UP_SEND_TIMEOUT, entry.send(&complete)).await;
+}
+
+/// The messages `send_catch_up` replays and whether the replay was truncated.
+/// The pooled connection is returned before any frame is sent.
+async fn load_catch_up(
+    state: &AppState,
+    uid: i32,
+    since: i64,
+) -> Option<(Vec<crate::handlers::chats::MessageResponse>, bool)> {
+    let mut conn = match state.db.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            tracing::warn!(uid, error = %e, "ws catch-up: no database connection");
+            return None;
+        }
+    };
+    let rows = match load_missed_messages(&mut conn, uid, since) {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::warn!(uid, error = %e, "ws catch-up: loading messages failed");
+            return None;
+        }
+    };
+    let (replay_ids, truncated) = select_replay(
+        &rows,
+        CATCH_UP_PER_CHAT_LIMIT as usize,
+        CATCH_UP_LIMIT as usize,
+    );
+    let registry = &state.ws_registry;
+    let missed = match load_messages_by_id(&mut conn, &replay_ids) {
+        Ok(missed) => missed
+            .into_iter()
+            .filter(|m| is_unacked(m.id, registry.acked_up_to(uid, m.chat_id)))
+            .collect(),
+        Err(e) => {
+            tracing::warn!(uid, error = %e, "ws catch-up: loading messages failed");
+            return None;
+        }
+    };
+    Some((
+        attach_metadata(&mut conn, missed, state, uid).await,
+        truncated,
+    ))
+}
+
+#[derive(QueryableByName)]
+struct MissedMessageRow {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    id: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    chat_id: i64,
+}
+
+/// Oldest first: up to one more than `CATCH_UP_PER_CHAT_LIMIT` per chat and one
+/// more than `CATCH_UP_LIMIT` overall, so both kinds of truncation show up.
+fn load_missed_messages(
+    conn: &mut PgConnection,
+    uid: i32,
+    since: i64,
+) -> QueryResult<Vec<MissedMessageRow>> {
+    use diesel::sql_types::{BigInt, Integer};
+
+    // LATERAL walks each chat's (chat_id, id) index separately, so an old
+    // `since` costs at most the per-chat limit per chat.
+    diesel::sql_query(
+        "SELECT m.id, m.chat_id
+         FROM group_membership gm
+         CROSS JOIN LATERAL (
+             SELECT id, chat_id FROM messages
+             WHERE chat_id = gm.chat_id
+               AND id > $2
+               AND is_published
+               AND deleted_at IS NULL
+             ORDER BY id
+             LIMIT $3
+         ) m
+         WHERE gm.uid = $1
+         ORDER BY m.id
+         LIMIT $4",
+    )
+    .bind::<Integer, _>(uid)
+    .bind::<BigInt, _>(since)
+    .bind::<BigInt, _>(CATCH_UP_PER_CHAT_LIMIT + 1)
+    .bind::<BigInt, _>(CATCH_UP_LIMIT + 1)
+    .load(conn)
+}
+
+/// Ids to replay, oldest first, and whether any missed message was left out
+/// because a chat or the whole replay hit its limit.
+fn select_replay(rows: &[MissedMessageRow], per_chat: usize, total: usize) -> (Vec<i64>, bool) {
+    let mut per_chat_counts: std::collections::HashMap<i64, usize> =
+        std::collections::HashMap::new();
+    let mut ids = Vec::new();
+    let mut truncated = false;
+    for row in rows {
+        let count = per_chat_counts.entry(row.chat_id).or_default();
+        *count += 1;
+        if *count > per_chat {
+            truncated = true;
+            continue;
+        }
+        if ids.len() == total {
+            truncated = true;
+            break;
+        }
+        ids.push(row.id);
+    }
+    (ids, truncated)
+}
+
+fn load_messages_by_id(conn: &mut PgConnection, ids: &[i64]) -> QueryResult<Vec<ChatMessage>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    schema::messages::table
+        .filter(schema::messages::id.eq_any(ids))
+        .order(schema::messages::id.asc())
+        .select(ChatMessage::as_select())
+        .load(conn)
+}
+
+fn is_unacked(message_id: i64, acked_up_to: Option<i64>) -> bool {
+    acked_up_to.is_none_or(|acked| message_id > acked)
 }
 
 pub fn router() -> OpenApiRouter<crate::AppState> {
@@ -202,3 +917,482 @@ pub fn router() -> OpenApiRouter<crate::AppState> {
         .routes(utoipa_axum::routes!(ws_handler))
         .routes(utoipa_axum::routes!(get_ws_ticket))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    fn policy(allowed: Option<&[&str]>, allow_missing_origin: bool) -> UpgradePolicy {
+        UpgradePolicy {
+            allowed_origins: allowed.map(|origins| {
+                origins
+                    .iter()
+                    .map(|origin| HeaderValue::from_str(origin).unwrap())
+                    .collect()
+            }),
+            allow_missing_origin,
+            required_subprotocol: None,
+        }
+    }
+
+    fn upgrade_headers(origin: Option<&str>, host: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, HeaderValue::from_str(host).unwrap());
+        if let Some(origin) = origin {
+            headers.insert(header::ORIGIN, HeaderValue::from_str(origin).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn listed_origins_may_upgrade() {
+        let policy = policy(Some(&["https://chat.example.com"]), false);
+        let headers = upgrade_headers(Some("https://chat.example.com"), "api.example.com");
+        assert!(policy.check_origin(&headers).is_ok());
+    }
+
+    #[test]
+    fn unlisted_origins_are_forbidden() {
+        let policy = policy(Some(&["https://chat.example.com"]), true);
+        for origin in ["https://evil.example", "http://chat.example.com", "null"] {
+            let headers = upgrade_headers(Some(origin), "api.example.com");
+            assert!(matches!(
+                policy.check_origin(&headers),
+                Err(AppError::Forbidden(ORIGIN_NOT_ALLOWED))
+            ));
+        }
+    }
+
+    #[test]
+    fn missing_origin_follows_the_config_flag() {
+        let headers = upgrade_headers(None, "api.example.com");
+        assert!(policy(None, true).check_origin(&headers).is_ok());
+        assert!(matches!(
+            policy(None, false).check_origin(&headers),
+            Err(AppError::Forbidden(ORIGIN_NOT_ALLOWED))
+        ));
+    }
+
+    #[test]
+    fn without_an_allowlist_only_the_own_host_may_upgrade() {
+        let policy = policy(None, false);
+        let own = upgrade_headers(
+            Some("https://Chat.Example.com:8443"),
+            "chat.example.com:8443",
+        );
+        let other = upgrade_headers(Some("https://evil.example"), "chat.example.com:8443");
+        assert!(policy.check_origin(&own).is_ok());
+        assert!(policy.check_origin(&other).is_err());
+    }
+
+    /// A peer that accepts every frame but, past any `incoming` frames, never
+    /// sends one back, like a socket whose TCP connection died without a FIN.
+    #[derive(Default)]
+    struct SilentSocket {
+        incoming: std::collections::VecDeque<Message>,
+        sent: Vec<Message>,
+    }
+
+    impl Stream for SilentSocket {
+        type Item = Result<Message, axum::Error>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            match self.incoming.pop_front() {
+                Some(message) => Poll::Ready(Some(Ok(message))),
+                None => Poll::Pending,
+            }
+        }
+    }
+
+    impl Sink<Message> for SilentSocket {
+        type Error = axum::Error;
+
+        fn poll_ready(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+            self.sent.push(item);
+            Ok(())
+        }
+
+        fn poll_flush(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn ack_frame_carries_string_encoded_ids() {
+        let parsed: WsMessage =
+            serde_json::from_str(r#"{"type":"ack","chatId":"12","upToSeq":"9007199254740993"}"#)
+                .unwrap();
+        assert_eq!(parsed.type_, "ack");
+        assert_eq!(parsed.chat_id, Some(12));
+        assert_eq!(parsed.up_to_seq, Some(9_007_199_254_740_993));
+
+        let query: WsQuery = serde_json::from_str(r#"{"since":"42"}"#).unwrap();
+        assert_eq!(query.since, Some(42));
+    }
+
+    fn missed(rows: &[(i64, i64)]) -> Vec<MissedMessageRow> {
+        rows.iter()
+            .map(|&(id, chat_id)| MissedMessageRow { id, chat_id })
+            .collect()
+    }
+
+    #[test]
+    fn replay_caps_each_chat_and_flags_truncation() {
+        // Chat 1 is over its per-chat cap of 2; chat 2 still gets replayed.
+        let rows = missed(&[(1, 1), (2, 1), (3, 1), (4, 2), (5, 2)]);
+        assert_eq!(select_replay(&rows, 2, 10), (vec![1, 2, 4, 5], true));
+    }
+
+    #[test]
+    fn replay_caps_total_and_flags_truncation() {
+        let rows = missed(&[(1, 1), (2, 2), (3, 3)]);
+        assert_eq!(select_replay(&rows, 5, 2), (vec![1, 2], true));
+    }
+
+    #[test]
+    fn replay_within_limits_is_not_truncated() {
+        let rows = missed(&[(1, 1), (2, 2), (3, 1)]);
+        assert_eq!(select_replay(&rows, 2, 3), (vec![1, 2, 3], false));
+        assert_eq!(select_replay(&[], 2, 3), (vec![], false));
+    }
+
+    #[test]
+    fn catch_up_skips_messages_already_acked() {
+        let registry = ws_registry::ConnectionRegistry::default();
+        registry.record_ack(7, 10, 100);
+
+        assert!(!is_unacked(100, registry.acked_up_to(7, 10)));
+        assert!(is_unacked(101, registry.acked_up_to(7, 10)));
+        assert!(is_unacked(1, registry.acked_up_to(7, 20)));
+    }
+
+    #[test]
+    fn connected_snapshot_lists_online_co_members_by_chat() {
+        let registry = ws_registry::ConnectionRegistry::default();
+        let _online_five = registry.register(5);
+        let _online_nine = registry.register(9);
+
+        let payload = connected_payload(
+            7,
+            1,
+            Some(vec![(10, 9), (10, 5), (10, 6), (20, 6), (30, 9)]),
+            |member| registry.is_online(member),
+        );
+
+        assert_eq!(payload.uid, 7);
+        assert!(!payload.snapshot_truncated);
+        assert_eq!(
+            payload.online_members_by_chat,
+            BTreeMap::from([("10".to_string(), vec![5, 9]), ("30".to_string(), vec![9])])
+        );
+    }
+
+    #[test]
+    fn connected_snapshot_is_omitted_for_too_many_chats() {
+        let payload = connected_payload(7, 1, None, |_| true);
+
+        assert!(payload.snapshot_truncated);
+        assert!(payload.online_members_by_chat.is_empty());
+    }
+
+    #[test]
+    fn short_stale_timeout_prunes_an_idle_connection() {
+        let registry = ws_registry::ConnectionRegistry::default();
+        let (idle, _idle_rx, _) = registry.register(7);
+        let (_fresh, _fresh_rx, _) = registry.register(8);
+        let keepalive = Keepalive {
+            ping_interval: DEFAULT_PING_INTERVAL,
+            pong_timeout: DEFAULT_PONG_TIMEOUT,
+            stale_timeout: Duration::from_secs(1),
+            prune_interval: Duration::from_secs(1),
+        };
+
+        assert!(keepalive.prune_stale(&registry).is_empty());
+
+        let two_seconds_ago = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            - 2;
+        idle.last_ping_at
+            .store(two_seconds_ago, std::sync::atomic::Ordering::Relaxed);
+
+        assert_eq!(keepalive.prune_stale(&registry), vec![7]);
+        assert!(!registry.is_online(7));
+        assert!(registry.is_online(8));
+    }
+
+    #[tokio::test]
+    async fn silent_socket_is_pinged_then_closed() {
+        let registry = ws_registry::ConnectionRegistry::default();
+        let (entry, mut rx, _) = registry.register(7);
+        while rx.try_recv().is_ok() {}
+        let keepalive = Keepalive {
+            ping_interval: Duration::from_millis(20),
+            pong_timeout: Duration::from_millis(70),
+            stale_timeout: DEFAULT_STALE_TIMEOUT,
+            prune_interval: DEFAULT_PRUNE_INTERVAL,
+        };
+        let mut socket = SilentSocket::default();
+
+        let started = Instant::now();
+        timeout(
+            Duration::from_secs(2),
+            run_socket(
+                &mut socket,
+                7,
+                &registry,
+                &entry,
+                rx,
+                keepalive,
+                WsEncoding::Json,
+            ),
+        )
+        .await
+        .expect("silent socket is closed after the pong timeout");
+
+        assert!(started.elapsed() >= keepalive.pong_timeout);
+        let pings = socket
+            .sent
+            .iter()
+            .filter(|msg| matches!(msg, Message::Ping(_)))
+            .count();
+        assert!(pings >= 2, "expected repeated pings, got {pings}");
+    }
+
+    #[tokio::test]
+    async fn broadcasts_written_to_the_socket_are_counted() {
+        let registry = ws_registry::ConnectionRegistry::default();
+        let (entry, rx, _) = registry.register(7);
+        let event = || {
+            Arc::new(ServerWsMessage::PresenceUpdate(
+                messages::PresenceUpdatePayload {
+                    active_connections: 1,
+                },
+            ))
+        };
+        registry.broadcast_to_uids(&[7], event());
+        registry.broadcast_to_uids(&[7], event());
+        let keepalive = Keepalive {
+            ping_interval: Duration::from_secs(60),
+            pong_timeout: Duration::from_millis(50),
+            stale_timeout: DEFAULT_STALE_TIMEOUT,
+            prune_interval: DEFAULT_PRUNE_INTERVAL,
+        };
+        let mut socket = SilentSocket::default();
+
+        timeout(
+            Duration::from_secs(2),
+            run_socket(
+                &mut socket,
+                7,
+                &registry,
+                &entry,
+                rx,
+                keepalive,
+                WsEncoding::Json,
+            ),
+        )
+        .await
+        .expect("silent socket is closed after the pong timeout");
+
+        // The presence frame from `register` plus both broadcasts.
+        let stats = registry.connection_stats(7);
+        assert_eq!(stats[0].messages_sent, 3);
+        assert!(stats[0].last_send_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn subscriptions_reply_follows_subscribe_and_unsubscribe() {
+        let registry = ws_registry::ConnectionRegistry::default();
+        let (entry, mut rx, _) = registry.register(7);
+        while rx.try_recv().is_ok() {}
+        let keepalive = Keepalive {
+            ping_interval: Duration::from_secs(60),
+            pong_timeout: Duration::from_millis(50),
+            stale_timeout: DEFAULT_STALE_TIMEOUT,
+            prune_interval: DEFAULT_PRUNE_INTERVAL,
+        };
+        let frames = [
+            r#"{"type":"subscriptions"}"#,
+            r#"{"type":"subscribe","chatId":"20"}"#,
+            r#"{"type":"subscribe","chatId":"10"}"#,
+            r#"{"type":"subscriptions"}"#,
+            r#"{"type":"unsubscribe","chatId":"20"}"#,
+            r#"{"type":"subscriptions"}"#,
+        ];
+        let mut socket = SilentSocket {
+            incoming: frames
+                .iter()
+                .map(|frame| Message::Text((*frame).into()))
+                .collect(),
+            ..Default::default()
+        };
+
+        timeout(
+            Duration::from_secs(2),
+            run_socket(
+                &mut socket,
+                7,
+                &registry,
+                &entry,
+                rx,
+                keepalive,
+                WsEncoding::Json,
+            ),
+        )
+        .await
+        .expect("socket is closed after the pong timeout");
+
+        let replies: Vec<serde_json::Value> = socket
+            .sent
+            .iter()
+            .filter_map(|msg| match msg {
+                Message::Text(text) => serde_json::from_str(text.as_str()).ok(),
+                _ => None,
+            })
+            .filter(|frame: &serde_json::Value| frame["type"] == "subscriptions")
+            .map(|frame| frame["payload"]["chatIds"].clone())
+            .collect();
+        assert_eq!(
+            replies,
+            vec![
+                serde_json::Value::Null,
+                serde_json::json!(["10", "20"]),
+                serde_json::json!(["10"]),
+            ]
+        );
+    }
+
+    fn message_event() -> ServerWsMessage {
+        ServerWsMessage::Message(crate::handlers::chats::MessageResponse {
+            id: 9_007_199_254_740_993,
+            message: Some("hello".to_string()),
+            message_type: crate::models::MessageType::Text,
+            sticker: None,
+            reply_root_id: None,
+            forwarded_from_message_id: None,
+            client_generated_id: "cgid".to_string(),
+            sender: crate::models::Sender {
+                uid: 7,
+                avatar_url: None,
+                name: Some("Alice".to_string()),
+                gender: 0,
+                user_group: None,
+            },
+            chat_id: 10,
+            created_at: chrono::Utc::now(),
+            is_edited: false,
+            edit_count: 0,
+            is_deleted: false,
+            has_attachments: false,
+            thread_info: None,
+            reply_to_message: None,
+            reply_root_message: None,
+            attachments: Vec::new(),
+            reactions: Vec::new(),
+            mentions: Vec::new(),
+            priority: None,
+            muted: false,
+        })
+    }
+
+    #[test]
+    fn json_sockets_get_the_queued_text_frame() {
+        let frame: Utf8Bytes = serde_json::to_string(&message_event()).unwrap().into();
+
+        match WsEncoding::Json.outbound(frame.clone()) {
+            Some(Message::Text(text)) => assert_eq!(text, frame),
+            other => panic!("expected a text frame, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn cbor_sockets_get_the_same_event_as_binary() {
+        let expected = serde_json::to_value(message_event()).unwrap();
+        let frame: Utf8Bytes = expected.to_string().into();
+
+        let Some(Message::Binary(bytes)) = WsEncoding::Cbor.outbound(frame) else {
+            panic!("expected a binary frame");
+        };
+        let decoded: serde_json::Value = ciborium::from_reader(bytes.as_ref()).unwrap();
+        assert_eq!(decoded, expected);
+        assert_eq!(decoded["type"], "message");
+        assert_eq!(decoded["payload"]["id"], "9007199254740993");
+    }
+
+    #[test]
+    fn client_frames_decode_from_json_text_and_cbor_binary() {
+        let ack = serde_json::json!({"type": "ack", "chatId": "12", "upToSeq": "42"});
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&ack, &mut cbor).unwrap();
+
+        for frame in [
+            Message::Text(ack.to_string().into()),
+            Message::Binary(cbor.into()),
+        ] {
+            let parsed: WsMessage = decode_client_frame(&frame).expect("decodes");
+            assert_eq!(parsed.type_, "ack");
+            assert_eq!(parsed.chat_id, Some(12));
+            assert_eq!(parsed.up_to_seq, Some(42));
+        }
+        assert!(decode_client_frame::<WsMessage>(&Message::Binary(vec![0xff].into())).is_none());
+
+        let query: WsQuery = serde_json::from_str(r#"{"encoding":"cbor"}"#).unwrap();
+        assert_eq!(query.encoding, WsEncoding::Cbor);
+        let query: WsQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(query.encoding, WsEncoding::Json);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unknown_uids_cannot_open_a_socket() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (known, unknown) = (900_201, 900_202);
+        app.seed_user(known);
+
+        let ticket_request = |uid: i32| {
+            axum::http::Request::get("/ws/ticket")
+                .header(crate::utils::auth::X_USER_ID, uid.to_string())
+                .header(crate::utils::auth::X_CLIENT_ID, "ws-test-client")
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let (status, body) = app.request_with(ticket_request(unknown)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{body}");
+        let close = accept_uid(&app.state, unknown).expect_err("unknown uid accepted");
+        assert_eq!(close.code, close_code::POLICY);
+
+        let (status, body) = app.request_with(ticket_request(known)).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert!(body["ticket"].is_string());
+        assert!(accept_uid(&app.state, known).is_ok());
+
+        // The miss is cached until the account is created and forgotten.
+        app.seed_user(unknown);
+        assert!(accept_uid(&app.state, unknown).is_err());
+        app.state.ws_registry.forget_user(unknown);
+        assert!(accept_uid(&app.state, unknown).is_ok());
+    }
+}
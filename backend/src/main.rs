@@ -1,4 +1,5 @@
 use axum::body::Body;
+use axum::extract::DefaultBodyLimit;
 use axum::http::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, ORIGIN};
 use axum::http::{HeaderValue, Method, Request};
 use axum::{middleware, routing::get, Router};
@@ -19,7 +20,6 @@ use tower_http::ServiceBuilderExt;
 use tracing::{debug_span, info, Level};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 use utils::auth::{X_APP_VERSION, X_CLIENT_ID, X_USER_ID};
-use utoipa::OpenApi;
 
 mod db_tracing;
 pub(crate) mod errors;
@@ -30,7 +30,10 @@ mod models;
 mod openapi;
 mod schema;
 mod serde_i64_string;
+mod serde_timestamp;
 mod services;
+#[cfg(test)]
+mod test_support;
 mod utils;
 
 const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
@@ -49,10 +52,18 @@ impl MakeRequestId for RequestIdMaker {
 }
 
 pub(crate) const MAX_AUTO_SORT_LIMIT: usize = 20;
-pub(crate) const MAX_CHATS_LIMIT: i64 = 100;
-pub(crate) const MAX_MESSAGES_LIMIT: i64 = 100;
 pub(crate) const MAX_MEMBERS_LIMIT: i64 = 100;
+/// Hard ceiling for any request body, sized for sticker multipart uploads.
 const MAX_REQUEST_BODY_BYTES: usize = 50 * 1024 * 1024;
+/// Default cap on bodies read through extractors; routers that take uploads
+/// raise it for themselves with their own `DefaultBodyLimit`.
+const DEFAULT_MAX_JSON_BODY_BYTES: u32 = 256 * 1024;
+const DEFAULT_MAX_ATTACHMENT_SIZE_BYTES: i64 = 100 * 1024 * 1024;
+const DEFAULT_MAX_MESSAGE_LENGTH: u32 = 4000;
+const DEFAULT_MESSAGE_RESTORE_WINDOW_SECS: u32 = 5 * 60;
+/// Comfortably above the ~5K members expected in the largest chats.
+const DEFAULT_MAX_MEMBERS_PER_CHAT: u32 = 10_000;
+const DEFAULT_RETENTION_SWEEP_INTERVAL_SECS: u32 = 60 * 60;
 
 #[derive(Clone, Deserialize, Default)]
 pub(crate) enum AuthMethod {
@@ -68,13 +79,24 @@ pub(crate) struct AppState {
     metrics: Arc<metrics::Metrics>,
     authz_service: Arc<services::authz::AuthorizationService>,
     ws_registry: Arc<services::ws_registry::ConnectionRegistry>,
+    ws_keepalive: handlers::ws::Keepalive,
+    ws_upgrade_policy: Arc<handlers::ws::UpgradePolicy>,
     push_service: Arc<services::push::PushService>,
     client_tracking: Arc<services::client_tracking::ClientTrackingService>,
     background_service: Arc<services::background::BackgroundService>,
+    webhook_service: Arc<services::webhooks::WebhookService>,
+    keyword_filter: Arc<utils::moderation::KeywordFilter>,
+    message_rate_limiter: Arc<utils::rate_limit::RateLimiter>,
     s3_client: aws_sdk_s3::Client,
     s3_bucket_name: String,
+    attachment_storage: Arc<dyn services::media::AttachmentStorage>,
     s3_attachment_prefix: String,
     s3_base_url: Option<String>,
+    max_attachment_size_bytes: i64,
+    max_message_length: usize,
+    message_restore_window: chrono::Duration,
+    max_members_per_chat: i64,
+    page_limits: utils::pagination::PageLimits,
     pub auth_method: AuthMethod,
     pub discuz_cookie_prefix: String,
     pub discuz_authkey: String,
@@ -101,6 +123,11 @@ async fn main() {
 
     // TODO: consider deadpool for pool
     let pool = Pool::builder()
+        .max_size(read_positive_u32("DB_POOL_MAX_SIZE").unwrap_or(10))
+        .min_idle(read_positive_u32("DB_POOL_MIN_IDLE"))
+        .connection_timeout(std::time::Duration::from_secs(
+            read_positive_u32("DB_POOL_CONNECTION_TIMEOUT_SECS").unwrap_or(30) as u64,
+        ))
         .build(manager)
         .expect("Failed to create pool");
 
@@ -112,9 +139,31 @@ async fn main() {
 
     let metrics = Arc::new(metrics::Metrics::new());
     let authz_service = services::authz::AuthorizationService::start();
-    let ws_registry = Arc::new(services::ws_registry::ConnectionRegistry::new(
+    let mut ws_registry = services::ws_registry::ConnectionRegistry::with_buffer(
         metrics.clone(),
-    ));
+        read_positive_u32("WS_CONNECTION_BUFFER_SIZE")
+            .map_or(services::ws_registry::DEFAULT_CONNECTION_BUFFER, |size| {
+                size as usize
+            }),
+    )
+    .with_connection_limit(
+        read_positive_u32("WS_MAX_CONNECTIONS_PER_USER").map_or(
+            services::ws_registry::DEFAULT_MAX_CONNECTIONS_PER_USER,
+            |max| max as usize,
+        ),
+        std::env::var("WS_CONNECTION_LIMIT_POLICY").map_or(
+            services::ws_registry::ConnectionLimitPolicy::Reject,
+            |value| {
+                services::ws_registry::ConnectionLimitPolicy::parse(&value).unwrap_or_else(|| {
+                    panic!("WS_CONNECTION_LIMIT_POLICY must be reject or evict_oldest")
+                })
+            },
+        ),
+    );
+    if let Some(max) = read_positive_u32("WS_MAX_TOTAL_CONNECTIONS") {
+        ws_registry = ws_registry.with_total_connection_limit(max as usize);
+    }
+    let ws_registry = Arc::new(ws_registry);
 
     let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
     let mut s3_config_builder = aws_sdk_s3::config::Builder::from(&aws_config);
@@ -130,6 +179,33 @@ async fn main() {
     let s3_attachment_prefix =
         std::env::var("ATTACHMENTS_PREFIX").unwrap_or_else(|_| "attachments".to_string());
     let s3_base_url = std::env::var("S3_BASE_URL").ok();
+    let max_attachment_size_bytes = std::env::var("MAX_ATTACHMENT_SIZE_BYTES")
+        .ok()
+        .map(|value| {
+            value
+                .parse::<i64>()
+                .ok()
+                .filter(|bytes| *bytes > 0)
+                .expect("MAX_ATTACHMENT_SIZE_BYTES must be a positive integer")
+        })
+        .unwrap_or(DEFAULT_MAX_ATTACHMENT_SIZE_BYTES);
+    let max_json_body_bytes =
+        read_positive_u32("MAX_JSON_BODY_BYTES").unwrap_or(DEFAULT_MAX_JSON_BODY_BYTES) as usize;
+    let max_message_length =
+        read_positive_u32("MAX_MESSAGE_LENGTH").unwrap_or(DEFAULT_MAX_MESSAGE_LENGTH) as usize;
+    let message_restore_window = chrono::Duration::seconds(
+        read_positive_u32("MESSAGE_RESTORE_WINDOW_SECS")
+            .unwrap_or(DEFAULT_MESSAGE_RESTORE_WINDOW_SECS)
+            .into(),
+    );
+    let max_members_per_chat =
+        read_positive_u32("MAX_MEMBERS_PER_CHAT").unwrap_or(DEFAULT_MAX_MEMBERS_PER_CHAT);
+    // Direct chats always hold two members.
+    assert!(
+        max_members_per_chat >= 2,
+        "MAX_MEMBERS_PER_CHAT must be at least 2"
+    );
+    let max_members_per_chat = i64::from(max_members_per_chat);
 
     let auth_method_str = std::env::var("AUTH_METHOD").unwrap_or_else(|_| "UIDHeader".to_string());
     let auth_method = match auth_method_str.as_str() {
@@ -169,6 +245,10 @@ async fn main() {
         metrics: metrics.clone(),
         authz_service,
         ws_registry: ws_registry.clone(),
+        ws_keepalive: handlers::ws::Keepalive::from_env(),
+        ws_upgrade_policy: Arc::new(handlers::ws::UpgradePolicy::from_env(
+            cors_allowed_origins.clone(),
+        )),
         push_service: services::push::PushService::start(
             pool.clone(),
             ws_registry.clone(),
@@ -183,10 +263,22 @@ async fn main() {
             ws_registry.clone(),
             metrics.clone(),
         ),
+        webhook_service: services::webhooks::WebhookService::from_env(),
+        keyword_filter: Arc::new(utils::moderation::KeywordFilter::from_env()),
+        message_rate_limiter: Arc::new(utils::rate_limit::RateLimiter::messages_from_env()),
+        attachment_storage: Arc::new(services::media::S3AttachmentStorage {
+            client: s3_client.clone(),
+            bucket: s3_bucket_name.clone(),
+        }),
         s3_client,
         s3_bucket_name,
         s3_attachment_prefix,
         s3_base_url,
+        max_attachment_size_bytes,
+        max_message_length,
+        message_restore_window,
+        max_members_per_chat,
+        page_limits: utils::pagination::PageLimits::from_env(),
         auth_method,
         discuz_cookie_prefix,
         discuz_authkey,
@@ -196,71 +288,59 @@ async fn main() {
     };
 
     services::audio_transcode::start(state.clone());
+    services::webhooks::start(state.clone());
 
     let registry = state.ws_registry.clone();
+    let background_service = state.background_service.clone();
+    let keepalive = state.ws_keepalive;
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        let mut interval = tokio::time::interval(keepalive.prune_interval);
         loop {
             interval.tick().await;
-            registry.prune_stale(300);
+            for uid in keepalive.prune_stale(&registry) {
+                background_service
+                    .enqueue(services::background::BackgroundJob::BroadcastPresence { uid });
+            }
+        }
+    });
+
+    let retention_sweep_interval = std::time::Duration::from_secs(
+        read_positive_u32("RETENTION_SWEEP_INTERVAL_SECS")
+            .unwrap_or(DEFAULT_RETENTION_SWEEP_INTERVAL_SECS)
+            .into(),
+    );
+    let background_service = state.background_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(retention_sweep_interval);
+        loop {
+            interval.tick().await;
+            background_service.enqueue(services::background::BackgroundJob::EnforceRetention);
         }
     });
 
     // --- Sub-routers ---
     // Sub-routers are mounted via handlers::api_router()
 
-    let trace_layer = TraceLayer::new_for_http()
-        .make_span_with(|request: &Request<Body>| {
-            let request_id = request
-                .extensions()
-                .get::<RequestId>()
-                .map(|id| id.header_value().to_str().unwrap_or("").to_string())
-                .unwrap_or_else(|| "".to_string());
-            debug_span!(
-                "request",
-                method = %request.method(),
-                uri = %request.uri(),
-                request_id = %request_id,
-            )
-        })
-        .on_request(DefaultOnRequest::new().level(Level::DEBUG))
-        .on_response(
-            DefaultOnResponse::new()
-                .level(Level::DEBUG)
-                .latency_unit(LatencyUnit::Micros),
-        );
-
     let metrics_registry = state.metrics.clone();
-    let client_tracking_state = state.clone();
 
     let (api_router, api_openapi) = handlers::api_router().split_for_parts();
-    let mut openapi_doc = openapi::ApiDoc::openapi();
-    openapi_doc.merge(api_openapi);
+    let openapi_doc = openapi::build(api_openapi);
+    let openapi_json = openapi_doc.clone();
 
-    let app = Router::new()
-        .merge(api_router)
-        // Keep enough headroom for sticker multipart uploads; per-feature logic still
-        // enforces tighter file-size checks where needed.
-        .layer(RequestBodyLimitLayer::new(MAX_REQUEST_BODY_BYTES))
-        .layer(
-            ServiceBuilder::new()
-                .set_x_request_id(RequestIdMaker)
-                .propagate_x_request_id()
-                .layer(trace_layer),
-        )
-        .layer(middleware::from_fn_with_state(
-            client_tracking_state,
-            services::client_tracking::track_client_activity,
-        ))
-        .layer(middleware::from_fn_with_state(
-            metrics_registry.clone(),
-            metrics::track_http_metrics,
-        ))
-        .with_state(state);
+    let app = build_app(state, api_router, max_json_body_bytes);
 
-    let app = app.merge(
-        utoipa_swagger_ui::SwaggerUi::new("/docs").url("/api-docs/openapi.json", openapi_doc),
-    );
+    let app = app
+        .merge(
+            utoipa_swagger_ui::SwaggerUi::new("/docs").url("/api-docs/openapi.json", openapi_doc),
+        )
+        // Plain spec path for SDK generators that expect it at the root.
+        .route(
+            "/openapi.json",
+            get(move || {
+                let doc = openapi_json.clone();
+                async move { axum::Json(doc) }
+            }),
+        );
     let app = if let Some(allowed_origins) = cors_allowed_origins {
         info!(
             allowed_origins = ?allowed_origins,
@@ -315,6 +395,65 @@ async fn main() {
     }
 }
 
+/// The API routes wrapped in the middleware every request passes through.
+/// Shared with the integration test harness so tests exercise the same stack.
+fn build_app(state: AppState, api_router: Router<AppState>, max_json_body_bytes: usize) -> Router {
+    let trace_layer = TraceLayer::new_for_http()
+        .make_span_with(|request: &Request<Body>| {
+            let request_id = request
+                .extensions()
+                .get::<RequestId>()
+                .map(|id| id.header_value().to_str().unwrap_or("").to_string())
+                .unwrap_or_else(|| "".to_string());
+            debug_span!(
+                "request",
+                method = %request.method(),
+                uri = %request.uri(),
+                request_id = %request_id,
+            )
+        })
+        .on_request(DefaultOnRequest::new().level(Level::DEBUG))
+        .on_response(
+            DefaultOnResponse::new()
+                .level(Level::DEBUG)
+                .latency_unit(LatencyUnit::Micros),
+        );
+
+    let client_tracking_state = state.clone();
+
+    Router::new()
+        .merge(api_router)
+        .layer(DefaultBodyLimit::max(max_json_body_bytes))
+        // Keep enough headroom for sticker multipart uploads; per-feature logic still
+        // enforces tighter file-size checks where needed.
+        .layer(RequestBodyLimitLayer::new(MAX_REQUEST_BODY_BYTES))
+        .layer(
+            ServiceBuilder::new()
+                .set_x_request_id(RequestIdMaker)
+                .propagate_x_request_id()
+                .layer(trace_layer),
+        )
+        .layer(middleware::from_fn_with_state(
+            client_tracking_state,
+            services::client_tracking::track_client_activity,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.metrics.clone(),
+            metrics::track_http_metrics,
+        ))
+        .with_state(state)
+}
+
+fn read_positive_u32(var_name: &str) -> Option<u32> {
+    std::env::var(var_name).ok().map(|value| {
+        value
+            .parse()
+            .ok()
+            .filter(|parsed| *parsed > 0)
+            .unwrap_or_else(|| panic!("{var_name} must be a positive integer"))
+    })
+}
+
 fn read_socket_addr(var_name: &str, default: SocketAddr) -> SocketAddr {
     std::env::var(var_name)
         .ok()
@@ -354,3 +493,49 @@ fn read_cors_allowed_origins(var_name: &str) -> Option<Vec<HeaderValue>> {
 
     Some(origins)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    /// The app's own layer stack from `build_app`, over a route that echoes
+    /// any JSON body it is let through.
+    fn echo_app(state: AppState, limit: usize) -> Router {
+        let api_router = Router::new().route(
+            "/echo",
+            post(|axum::Json(body): axum::Json<serde_json::Value>| async move { axum::Json(body) }),
+        );
+        build_app(state, api_router, limit)
+    }
+
+    fn json_request(len: usize) -> Request<Body> {
+        let body = format!("\"{}\"", "a".repeat(len - 2));
+        Request::post("/echo")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn json_bodies_over_the_default_limit_get_413() {
+        let Some(app) = test_support::TestApp::start().await else {
+            return;
+        };
+        let limit = DEFAULT_MAX_JSON_BODY_BYTES as usize;
+
+        let response = echo_app(app.state.clone(), limit)
+            .oneshot(json_request(limit))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = echo_app(app.state.clone(), limit)
+            .oneshot(json_request(limit + 1))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
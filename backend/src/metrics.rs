@@ -46,6 +46,7 @@ pub(crate) struct Metrics {
     push_notification_jobs_total: IntCounterVec,
     push_notification_job_duration_seconds: HistogramVec,
     push_notifications_suppressed_total: IntCounter,
+    webhook_deliveries_total: IntCounterVec,
     ws_connected_users: IntGauge,
     ws_active_connections: IntGauge,
     ws_inactive_connections: IntGauge,
@@ -145,6 +146,14 @@ impl Metrics {
             "Total number of push notifications skipped because a user had active websocket presence"
         ))
         .expect("push_notifications_suppressed_total metric should be valid");
+        let webhook_deliveries_total = IntCounterVec::new(
+            opts!(
+                "webhook_deliveries_total",
+                "Total number of webhook deliveries, counted once after retries"
+            ),
+            &["result"],
+        )
+        .expect("webhook_deliveries_total metric should be valid");
         let ws_connected_users = IntGauge::with_opts(opts!(
             "ws_connected_users",
             "Current number of users with at least one active websocket connection"
@@ -369,6 +378,9 @@ impl Metrics {
         registry
             .register(Box::new(push_notifications_suppressed_total.clone()))
             .expect("push_notifications_suppressed_total registration should succeed");
+        registry
+            .register(Box::new(webhook_deliveries_total.clone()))
+            .expect("webhook_deliveries_total registration should succeed");
         registry
             .register(Box::new(ws_connected_users.clone()))
             .expect("ws_connected_users registration should succeed");
@@ -467,6 +479,7 @@ impl Metrics {
             push_notification_jobs_total,
             push_notification_job_duration_seconds,
             push_notifications_suppressed_total,
+            webhook_deliveries_total,
             ws_connected_users,
             ws_active_connections,
             ws_inactive_connections,
@@ -606,6 +619,13 @@ impl Metrics {
         self.push_notifications_suppressed_total.inc();
     }
 
+    pub(crate) fn record_webhook_delivery(&self, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        self.webhook_deliveries_total
+            .with_label_values(&[result])
+            .inc();
+    }
+
     pub(crate) fn record_ws_connection_open(&self) {
         self.ws_connections_total.inc();
     }
@@ -803,6 +823,7 @@ mod tests {
         metrics.record_push_notification("web_push", true);
         metrics.record_push_job("success", 0.002);
         metrics.record_push_suppressed();
+        metrics.record_webhook_delivery(true);
         metrics.set_ws_connected_users(2);
         metrics.set_ws_connection_states(1, 1);
         metrics.record_ws_connection_open();
@@ -854,6 +875,7 @@ mod tests {
         assert!(body.contains("push_notification_jobs_total"));
         assert!(body.contains("push_notification_job_duration_seconds"));
         assert!(body.contains("push_notifications_suppressed_total"));
+        assert!(body.contains("webhook_deliveries_total"));
         assert!(body.contains("ws_connected_users"));
         assert!(body.contains("ws_active_connections"));
         assert!(body.contains("ws_inactive_connections"));
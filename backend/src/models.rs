@@ -22,6 +22,49 @@ pub enum GroupVisibility {
     Private,
 }
 
+/// Distinguishes ordinary group chats from 1:1 direct chats.
+#[derive(
+    diesel_derive_enum::DbEnum,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    utoipa::ToSchema,
+)]
+#[ExistingTypePath = "crate::schema::sql_types::ChatKind"]
+#[serde(rename_all = "snake_case")]
+pub enum ChatKind {
+    #[default]
+    Group,
+    Direct,
+}
+
+/// How messages matching the keyword blocklist are handled in a chat.
+#[derive(
+    diesel_derive_enum::DbEnum,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    utoipa::ToSchema,
+)]
+#[ExistingTypePath = "crate::schema::sql_types::ModerationPolicy"]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationPolicy {
+    #[default]
+    Off,
+    Reject,
+    Redact,
+}
+
 #[derive(
     diesel_derive_enum::DbEnum,
     Debug,
@@ -59,6 +102,8 @@ pub enum GroupJoinReason {
     Creator,
     InviteCode,
     DirectInvite,
+    /// Joined a public chat on their own.
+    PublicJoin,
 }
 
 #[derive(
@@ -115,8 +160,13 @@ pub enum MessageType {
     Sticker,
     Invite,
     System,
+    Announcement,
 }
 
+/// `sender_uid` of system messages no member sent, such as welcome messages.
+/// No account has this uid, so clients show the text without a sender name.
+pub const SYSTEM_SENDER_UID: i32 = 0;
+
 #[derive(
     diesel_derive_enum::DbEnum,
     Debug,
@@ -239,13 +289,29 @@ pub struct Group {
     pub name: String,
     pub description: Option<String>,
     pub avatar_image_id: Option<i64>,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
     pub visibility: GroupVisibility,
     pub last_message_id: Option<i64>,
     pub last_message_at: Option<DateTime<Utc>>,
+    pub moderation_policy: ModerationPolicy,
+    pub welcome_message: Option<String>,
+    pub kind: ChatKind,
+    pub direct_key: Option<String>,
+    #[serde(with = "crate::serde_timestamp::opt")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Minimum seconds between a member's messages; 0 turns slow mode off.
+    pub slow_mode_secs: i32,
+    /// Messages older than this many days are soft-deleted by the retention
+    /// sweeper; 0 keeps them forever.
+    pub retention_days: i32,
+    /// Send `welcome_message` to the new member's direct chat with an admin
+    /// instead of posting it in the chat.
+    pub welcome_message_as_dm: bool,
 }
 
-/// For inserting a group. Set `id` and `created_at` (e.g. `Utc::now()`) when not relying on DB defaults.
+/// For inserting a group. Start from `NewGroup::new` or `NewGroup::direct` and
+/// override fields with struct update syntax, so new columns get one default.
 #[derive(Debug, Clone, Insertable)]
 #[diesel(table_name = schema::groups)]
 pub struct NewGroup {
@@ -255,6 +321,34 @@ pub struct NewGroup {
     pub avatar_image_id: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub visibility: GroupVisibility,
+    pub kind: ChatKind,
+    pub direct_key: Option<String>,
+}
+
+impl NewGroup {
+    /// A public group chat without description or avatar.
+    pub fn new(id: i64, name: String, created_at: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            name,
+            description: None,
+            avatar_image_id: None,
+            created_at,
+            visibility: GroupVisibility::Public,
+            kind: ChatKind::Group,
+            direct_key: None,
+        }
+    }
+
+    /// An unnamed private direct chat, unique by `direct_key`.
+    pub fn direct(id: i64, direct_key: String, created_at: DateTime<Utc>) -> Self {
+        Self {
+            visibility: GroupVisibility::Private,
+            kind: ChatKind::Direct,
+            direct_key: Some(direct_key),
+            ..Self::new(id, String::new(), created_at)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Queryable, Selectable, Serialize, Insertable)]
@@ -263,7 +357,9 @@ pub struct Policy {
     pub id: i64,
     pub name: String,
     pub metadata: serde_json::Value,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::serde_timestamp")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -285,6 +381,7 @@ pub struct PolicyPermission {
     pub action: String,
     pub resource_type: PermissionResourceType,
     pub resource_id: Option<i64>,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -306,7 +403,9 @@ pub struct PolicyAssignment {
     pub subject_type: PolicySubjectType,
     pub subject_id: i64,
     pub policy_id: i64,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::serde_timestamp")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -328,7 +427,9 @@ pub struct Media {
     pub content_type: String,
     pub storage_key: String,
     pub size: i64,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::serde_timestamp::opt")]
     pub deleted_at: Option<DateTime<Utc>>,
     pub file_name: String,
     pub width: Option<i32>,
@@ -360,7 +461,9 @@ pub struct StickerPack {
     pub owner_uid: i32,
     pub name: String,
     pub description: Option<String>,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::serde_timestamp")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -391,6 +494,7 @@ pub struct Sticker {
     pub emoji: String,
     pub name: Option<String>,
     pub description: Option<String>,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -435,6 +539,7 @@ pub struct GroupMembership {
     pub chat_id: i64,
     pub uid: i32,
     pub role: GroupRole,
+    #[serde(with = "crate::serde_timestamp")]
     pub joined_at: DateTime<Utc>,
     pub last_read_message_id: Option<i64>,
     pub muted_until: Option<DateTime<Utc>>,
@@ -464,10 +569,14 @@ pub struct Invite {
     pub creator_uid: Option<i32>,
     pub target_uid: Option<i32>,
     pub required_chat_id: Option<i64>,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
     pub revoked_at: Option<DateTime<Utc>>,
     pub used_at: Option<DateTime<Utc>>,
+    /// Redemptions allowed before the invite stops working; `None` is unlimited.
+    pub max_uses: Option<i32>,
+    pub uses: i32,
 }
 
 #[derive(Debug, Clone, Insertable)]
@@ -484,6 +593,7 @@ pub struct NewInvite {
     pub expires_at: Option<DateTime<Utc>>,
     pub revoked_at: Option<DateTime<Utc>>,
     pub used_at: Option<DateTime<Utc>>,
+    pub max_uses: Option<i32>,
 }
 
 #[derive(Debug, Clone, Queryable, Selectable, Serialize)]
@@ -497,8 +607,11 @@ pub struct Message {
     pub client_generated_id: String,
     pub sender_uid: i32,
     pub chat_id: i64,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::serde_timestamp::opt")]
     pub updated_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::serde_timestamp::opt")]
     pub deleted_at: Option<DateTime<Utc>>,
     pub has_attachments: bool,
     pub has_thread: bool,
@@ -506,6 +619,13 @@ pub struct Message {
     pub sticker_id: Option<i64>,
     pub is_published: bool,
     pub transcode_status: TranscodeStatus,
+    /// Who soft-deleted the message through the API; `None` for moderation
+    /// bulk deletes, which the sender cannot undo.
+    pub deleted_by: Option<i32>,
+    pub forwarded_from_message_id: Option<i64>,
+    /// Set once the sender edits the content; server-side changes such as
+    /// linking attachments bump `updated_at` without it.
+    pub edited_by_user: bool,
 }
 
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
@@ -534,6 +654,17 @@ pub struct NewMessage {
     pub sticker_id: Option<i64>,
     pub is_published: bool,
     pub transcode_status: TranscodeStatus,
+    pub forwarded_from_message_id: Option<i64>,
+}
+
+/// The body a message had before one edit replaced it.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = schema::message_edits)]
+pub struct MessageEdit {
+    pub id: i64,
+    pub message_id: i64,
+    pub previous_text: Option<String>,
+    pub edited_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Queryable, Selectable, Insertable)]
@@ -569,7 +700,33 @@ pub struct NewPinnedMessage {
 
 #[cfg(test)]
 mod tests {
-    use super::{MessageType, TranscodeStatus};
+    use super::{ChatKind, GroupVisibility, MessageType, NewGroup, TranscodeStatus};
+    use chrono::Utc;
+
+    #[test]
+    fn new_group_defaults_to_a_bare_public_group() {
+        let now = Utc::now();
+        let group = NewGroup::new(1, "General".to_string(), now);
+
+        assert_eq!(group.id, 1);
+        assert_eq!(group.name, "General");
+        assert_eq!(group.created_at, now);
+        assert_eq!(group.description, None);
+        assert_eq!(group.avatar_image_id, None);
+        assert_eq!(group.visibility, GroupVisibility::Public);
+        assert_eq!(group.kind, ChatKind::Group);
+        assert_eq!(group.direct_key, None);
+    }
+
+    #[test]
+    fn direct_group_is_private_and_keyed() {
+        let group = NewGroup::direct(2, "3:5".to_string(), Utc::now());
+
+        assert_eq!(group.name, "");
+        assert_eq!(group.visibility, GroupVisibility::Private);
+        assert_eq!(group.kind, ChatKind::Direct);
+        assert_eq!(group.direct_key.as_deref(), Some("3:5"));
+    }
 
     #[test]
     fn message_type_serializes_as_snake_case() {
@@ -615,11 +772,16 @@ pub struct Attachment {
     pub kind: String,
     pub external_reference: String,
     pub size: i64,
+    #[serde(with = "crate::serde_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::serde_timestamp::opt")]
     pub deleted_at: Option<DateTime<Utc>>,
     pub width: Option<i32>,
     pub height: Option<i32>,
     pub order: i16,
+    /// The row this one was copied from when its message was forwarded.
+    /// Copies share the original's stored object.
+    pub forwarded_from_attachment_id: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
@@ -650,6 +812,7 @@ pub struct NewAttachment {
     pub width: Option<i32>,
     pub height: Option<i32>,
     pub order: i16,
+    pub forwarded_from_attachment_id: Option<i64>,
 }
 
 #[derive(Debug, Clone, AsChangeset)]
@@ -658,6 +821,11 @@ pub struct UpdateGroup {
     pub name: Option<String>,
     pub description: Option<String>,
     pub visibility: Option<GroupVisibility>,
+    pub moderation_policy: Option<ModerationPolicy>,
+    pub welcome_message: Option<String>,
+    pub welcome_message_as_dm: Option<bool>,
+    pub slow_mode_secs: Option<i32>,
+    pub retention_days: Option<i32>,
 }
 
 #[derive(Debug, Clone, Queryable, Selectable, Serialize, Insertable)]
@@ -688,6 +856,97 @@ pub struct NewActivityDailyMetric {
     pub updated_at: chrono::NaiveDateTime,
 }
 
+/// One row per privileged operator access, kept for accountability.
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = schema::admin_audit_log)]
+pub struct NewAdminAuditLog {
+    pub id: i64,
+    pub actor_uid: i32,
+    pub action: String,
+    pub chat_id: Option<i64>,
+    pub metadata: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An integrator endpoint that receives signed message events for one chat.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = schema::webhooks)]
+pub struct Webhook {
+    pub id: i64,
+    pub chat_id: i64,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<Option<String>>,
+    pub created_by: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Deliveries in a row that gave up; reset by the next success.
+    pub consecutive_failures: i32,
+    /// Set once too many deliveries fail in a row; disabled webhooks get no events.
+    pub disabled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = schema::webhooks)]
+pub struct NewWebhook {
+    pub id: i64,
+    pub chat_id: i64,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<String>,
+    pub created_by: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, AsChangeset)]
+#[diesel(table_name = schema::webhooks)]
+pub struct UpdateWebhook {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub consecutive_failures: Option<i32>,
+    pub disabled_at: Option<Option<DateTime<Utc>>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One HTTP attempt at delivering a webhook event.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = schema::webhook_deliveries)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event: String,
+    pub attempt: i32,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub succeeded: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = schema::webhook_deliveries)]
+pub struct NewWebhookDelivery {
+    pub webhook_id: i64,
+    pub event: String,
+    pub attempt: i32,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub succeeded: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A user barred from a chat until an admin lifts the ban.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = schema::chat_bans)]
+pub struct ChatBan {
+    pub chat_id: i64,
+    pub uid: i32,
+    pub banned_by: i32,
+    pub banned_at: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
 #[derive(Debug, Clone, Queryable, Selectable, Serialize, Insertable)]
 #[diesel(table_name = schema::clients)]
 pub struct ClientRecord {
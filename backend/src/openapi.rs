@@ -1,6 +1,8 @@
 use crate::handlers::ws::messages::{
-    ChatArchiveStateChangedPayload, PinUpdatePayload, PresenceUpdatePayload, ReactionUpdatePayload,
-    ServerWsMessage, ThreadMembershipChangedPayload, ThreadUpdatePayload,
+    CatchUpCompletePayload, ChatArchiveStateChangedPayload, ChatDeletedPayload, ChatUpdatedPayload,
+    ConnectedPayload, MemberUpdatePayload, MentionPayload, PinUpdatePayload, PresenceUpdatePayload,
+    ReactionDeltaPayload, ReactionOp, ReadStateUpdatedPayload, ServerWsMessage,
+    SubscriptionsPayload, ThreadMembershipChangedPayload, ThreadUpdatePayload, UserPresencePayload,
 };
 use utoipa::openapi::security::{ApiKey, ApiKeyValue, Http, HttpAuthScheme, SecurityScheme};
 use utoipa::OpenApi;
@@ -16,18 +18,35 @@ use utoipa::OpenApi;
     components(
         schemas(
             ServerWsMessage,
-            ReactionUpdatePayload,
+            MentionPayload,
+            ReactionDeltaPayload,
+            ReactionOp,
+            ReadStateUpdatedPayload,
             PresenceUpdatePayload,
+            UserPresencePayload,
             ThreadUpdatePayload,
             ThreadMembershipChangedPayload,
             ChatArchiveStateChangedPayload,
+            ChatUpdatedPayload,
+            ChatDeletedPayload,
             PinUpdatePayload,
+            MemberUpdatePayload,
+            CatchUpCompletePayload,
+            ConnectedPayload,
+            SubscriptionsPayload,
         )
     ),
     modifiers(&SecurityAddon),
 )]
 pub struct ApiDoc;
 
+/// Combine the static document with the paths collected from the API router.
+pub fn build(api: utoipa::openapi::OpenApi) -> utoipa::openapi::OpenApi {
+    let mut doc = ApiDoc::openapi();
+    doc.merge(api);
+    doc
+}
+
 struct SecurityAddon;
 
 impl utoipa::Modify for SecurityAddon {
@@ -43,3 +62,22 @@ impl utoipa::Modify for SecurityAddon {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::build;
+
+    #[test]
+    fn documents_core_endpoints_with_status_codes() {
+        let (_, api) = crate::handlers::api_router().split_for_parts();
+        let doc = serde_json::to_value(build(api)).expect("serialize openapi");
+        let paths = &doc["paths"];
+
+        assert!(paths["/chats/{chat_id}/messages"]["post"]["responses"]["201"].is_object());
+        assert!(paths["/chats/{chat_id}/messages"]["get"]["responses"]["200"].is_object());
+        assert!(paths["/chats"]["get"]["responses"]["200"].is_object());
+        assert!(paths["/group/{chat_id}/members"]["get"]["responses"]["200"].is_object());
+        assert!(doc["components"]["schemas"]["MessageResponse"].is_object());
+        assert!(doc["components"]["securitySchemes"]["bearer_jwt"].is_object());
+    }
+}
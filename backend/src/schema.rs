@@ -6,11 +6,11 @@ pub mod primary;
 use discuz::discuz::{common_member, common_usergroup};
 use discuz_manual::discuz::common_member_profile;
 pub use primary::{
-    activity_daily_metrics, attachments, clients, group_membership, groups, invites, media,
-    message_reactions, messages, pinned_messages, policies, policy_assignments, policy_permissions,
-    push_subscriptions, sql_types, sticker_pack_stickers, sticker_packs, stickers, thread_meta,
-    thread_subscriptions, user_extra, user_favorite_stickers, user_sticker_pack_subscriptions,
-    usergroup_extra,
+    activity_daily_metrics, admin_audit_log, attachments, chat_bans, clients, group_membership,
+    groups, invites, media, message_edits, message_reactions, messages, pinned_messages, policies,
+    policy_assignments, policy_permissions, push_subscriptions, sql_types, sticker_pack_stickers,
+    sticker_packs, stickers, thread_meta, thread_subscriptions, user_extra, user_favorite_stickers,
+    user_sticker_pack_subscriptions, usergroup_extra, webhook_deliveries, webhooks,
 };
 
 diesel::allow_tables_to_appear_in_same_query!(group_membership, common_member);
@@ -1,6 +1,10 @@
 // @generated automatically by Diesel CLI.
 
 pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "chat_kind"))]
+    pub struct ChatKind;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "group_join_reason"))]
     pub struct GroupJoinReason;
@@ -25,6 +29,10 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "message_type"))]
     pub struct MessageType;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "moderation_policy"))]
+    pub struct ModerationPolicy;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "permission_resource_type"))]
     pub struct PermissionResourceType;
@@ -60,6 +68,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    admin_audit_log (id) {
+        id -> Int8,
+        actor_uid -> Int4,
+        #[max_length = 64]
+        action -> Varchar,
+        chat_id -> Nullable<Int8>,
+        metadata -> Jsonb,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     attachments (id) {
         id -> Int8,
@@ -75,6 +95,17 @@ diesel::table! {
         width -> Nullable<Int4>,
         height -> Nullable<Int4>,
         order -> Int2,
+        forwarded_from_attachment_id -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    chat_bans (chat_id, uid) {
+        chat_id -> Int8,
+        uid -> Int4,
+        banned_by -> Int4,
+        banned_at -> Timestamptz,
+        reason -> Nullable<Text>,
     }
 }
 
@@ -109,6 +140,8 @@ diesel::table! {
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::GroupVisibility;
+    use super::sql_types::ModerationPolicy;
+    use super::sql_types::ChatKind;
 
     groups (id) {
         id -> Int8,
@@ -120,6 +153,15 @@ diesel::table! {
         last_message_id -> Nullable<Int8>,
         last_message_at -> Nullable<Timestamptz>,
         avatar_image_id -> Nullable<Int8>,
+        moderation_policy -> ModerationPolicy,
+        welcome_message -> Nullable<Text>,
+        kind -> ChatKind,
+        #[max_length = 32]
+        direct_key -> Nullable<Varchar>,
+        deleted_at -> Nullable<Timestamptz>,
+        slow_mode_secs -> Int4,
+        retention_days -> Int4,
+        welcome_message_as_dm -> Bool,
     }
 }
 
@@ -140,6 +182,8 @@ diesel::table! {
         expires_at -> Nullable<Timestamptz>,
         revoked_at -> Nullable<Timestamptz>,
         used_at -> Nullable<Timestamptz>,
+        max_uses -> Nullable<Int4>,
+        uses -> Int4,
     }
 }
 
@@ -165,6 +209,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    message_edits (id) {
+        id -> Int8,
+        message_id -> Int8,
+        previous_text -> Nullable<Text>,
+        edited_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     message_reactions (message_id, user_uid, emoji) {
         message_id -> Int8,
@@ -198,6 +251,9 @@ diesel::table! {
         sticker_id -> Nullable<Int8>,
         is_published -> Bool,
         transcode_status -> TranscodeStatus,
+        deleted_by -> Nullable<Int4>,
+        forwarded_from_message_id -> Nullable<Int8>,
+        edited_by_user -> Bool,
     }
 }
 
@@ -357,9 +413,39 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    webhooks (id) {
+        id -> Int8,
+        chat_id -> Int8,
+        url -> Text,
+        secret -> Text,
+        events -> Array<Nullable<Text>>,
+        created_by -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        consecutive_failures -> Int4,
+        disabled_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (id) {
+        id -> Int8,
+        webhook_id -> Int8,
+        event -> Text,
+        attempt -> Int4,
+        status_code -> Nullable<Int4>,
+        error -> Nullable<Text>,
+        succeeded -> Bool,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::joinable!(attachments -> messages (message_id));
+diesel::joinable!(chat_bans -> groups (chat_id));
 diesel::joinable!(group_membership -> groups (chat_id));
 diesel::joinable!(groups -> media (avatar_image_id));
+diesel::joinable!(message_edits -> messages (message_id));
 diesel::joinable!(message_reactions -> messages (message_id));
 diesel::joinable!(messages -> stickers (sticker_id));
 diesel::joinable!(pinned_messages -> groups (chat_id));
@@ -375,15 +461,20 @@ diesel::joinable!(thread_subscriptions -> groups (chat_id));
 diesel::joinable!(thread_subscriptions -> messages (thread_root_id));
 diesel::joinable!(user_favorite_stickers -> stickers (sticker_id));
 diesel::joinable!(user_sticker_pack_subscriptions -> sticker_packs (pack_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhooks -> groups (chat_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     activity_daily_metrics,
+    admin_audit_log,
     attachments,
+    chat_bans,
     clients,
     group_membership,
     groups,
     invites,
     media,
+    message_edits,
     message_reactions,
     messages,
     pinned_messages,
@@ -400,4 +491,6 @@ diesel::allow_tables_to_appear_in_same_query!(
     user_favorite_stickers,
     user_sticker_pack_subscriptions,
     usergroup_extra,
+    webhook_deliveries,
+    webhooks,
 );
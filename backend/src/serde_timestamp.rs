@@ -0,0 +1,106 @@
+//! Serialize `DateTime<Utc>` as RFC3339 with millisecond precision and a trailing `Z`
+//! (e.g. `2023-11-14T22:13:20.123Z`); deserialize from any RFC3339 timestamp.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+fn format(value: &DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    format(value).serialize(serializer)
+}
+
+// No request body takes a timestamp yet; kept so `with` also works on `Deserialize` structs.
+#[allow(dead_code)]
+pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(serde::de::Error::custom)
+}
+
+pub mod opt {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(v) => super::format(v).serialize(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(s) => DateTime::parse_from_rfc3339(&s)
+                .map(|dt| Some(dt.with_timezone(&Utc)))
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Stamped {
+        #[serde(with = "super")]
+        at: DateTime<Utc>,
+        #[serde(with = "super::opt", default)]
+        maybe: Option<DateTime<Utc>>,
+    }
+
+    fn at_micros(micros: i64) -> DateTime<Utc> {
+        Utc.timestamp_micros(micros).unwrap()
+    }
+
+    #[test]
+    fn serializes_millis_with_trailing_z() {
+        let value = Stamped {
+            at: at_micros(1_700_000_000_123_456),
+            maybe: Some(at_micros(1_700_000_000_000_000)),
+        };
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"{"at":"2023-11-14T22:13:20.123Z","maybe":"2023-11-14T22:13:20.000Z"}"#
+        );
+    }
+
+    #[test]
+    fn serializes_missing_optional_as_null() {
+        let value = Stamped {
+            at: at_micros(0),
+            maybe: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"{"at":"1970-01-01T00:00:00.000Z","maybe":null}"#
+        );
+    }
+
+    #[test]
+    fn deserializes_offsets_into_utc() {
+        let parsed: Stamped =
+            serde_json::from_str(r#"{"at":"2023-11-15T00:13:20.123+02:00"}"#).unwrap();
+        assert_eq!(parsed.at, at_micros(1_700_000_000_123_000));
+        assert_eq!(parsed.maybe, None);
+    }
+}
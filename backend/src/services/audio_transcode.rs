@@ -13,6 +13,7 @@ use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use crate::errors::AppError;
+use crate::extractors::get_conn;
 use crate::handlers::chats::{
     attach_metadata, build_message_side_effects, recalculate_group_last_message,
 };
@@ -286,13 +287,13 @@ fn refill_pending_jobs(service: &AudioTranscodeService, state: &AppState) {
 async fn process_message(state: AppState, message_id: i64) -> Result<(), AppError> {
     let started_at = std::time::Instant::now();
     let (message, current_attachment) = {
-        let conn = &mut state.db.get()?;
+        let conn = &mut get_conn(&state)?;
         let message: Message = messages::table
             .filter(messages::id.eq(message_id))
             .select(Message::as_select())
             .first(conn)
             .optional()?
-            .ok_or(AppError::NotFound("Message not found"))?;
+            .ok_or(AppError::MESSAGE_NOT_FOUND)?;
         let current_attachment = load_primary_attachment(conn, message.id)?;
         (message, current_attachment)
     };
@@ -338,7 +339,7 @@ async fn process_message(state: AppState, message_id: i64) -> Result<(), AppErro
     };
 
     let updated_message = {
-        let conn = &mut state.db.get()?;
+        let conn = &mut get_conn(&state)?;
         conn.transaction::<_, AppError, _>(|conn| {
             use crate::schema::attachments::dsl as a_dsl;
             use crate::schema::messages::dsl as m_dsl;
@@ -393,7 +394,7 @@ async fn process_message(state: AppState, message_id: i64) -> Result<(), AppErro
         })?
     };
 
-    let conn = &mut state.db.get()?;
+    let conn = &mut get_conn(&state)?;
     let response = attach_metadata(conn, vec![updated_message], &state, message.sender_uid)
         .await
         .into_iter()
@@ -419,7 +420,7 @@ async fn process_message(state: AppState, message_id: i64) -> Result<(), AppErro
         return Ok(());
     }
 
-    let conn = &mut state.db.get()?;
+    let conn = &mut get_conn(&state)?;
     let side_effects = build_message_side_effects(
         conn,
         &response,
@@ -504,7 +505,9 @@ fn broadcast_message_update(state: &AppState, response: &crate::handlers::chats:
         }
     };
     let ws_msg = std::sync::Arc::new(ServerWsMessage::MessageUpdated(response.clone()));
-    state.ws_registry.broadcast_to_uids(&member_uids, ws_msg);
+    state
+        .ws_registry
+        .broadcast_to_chat(response.chat_id, &member_uids, ws_msg);
 }
 
 fn load_primary_attachment(
@@ -586,6 +589,7 @@ async fn transcode_attachment(
         width: None,
         height: None,
         order: attachment.order,
+        forwarded_from_attachment_id: None,
     })
 }
 
@@ -6,7 +6,9 @@ use dashmap::DashMap;
 use diesel::prelude::*;
 use diesel::PgConnection;
 
-use crate::errors::AppError;
+use axum::http::StatusCode;
+
+use crate::errors::{AppError, ErrorCode};
 use crate::models::{PermissionResourceType, PolicySubjectType};
 use crate::schema::discuz::discuz::common_member;
 use crate::schema::{policy_assignments, policy_permissions};
@@ -17,7 +19,10 @@ const CACHE_TTL: Duration = Duration::from_secs(60);
 pub enum Action {
     ChatCreate,
     MemberViewAll,
+    UserCreate,
+    MessageViewAll,
     PermissionAll,
+    WebhookManage,
 }
 
 impl Action {
@@ -25,7 +30,10 @@ impl Action {
         match self {
             Self::ChatCreate => "chat.create",
             Self::MemberViewAll => "member.viewAll",
+            Self::UserCreate => "user.create",
+            Self::MessageViewAll => "message.viewAll",
             Self::PermissionAll => "permission.all",
+            Self::WebhookManage => "webhook.manage",
         }
     }
 }
@@ -135,7 +143,11 @@ impl AuthorizationService {
             return Ok(());
         }
 
-        Err(AppError::Forbidden("Permission required"))
+        Err(AppError::Coded(
+            StatusCode::FORBIDDEN,
+            ErrorCode::PermissionRequired,
+            "Permission required",
+        ))
     }
 
     fn lookup_discuz_group_id(
@@ -275,6 +287,9 @@ mod tests {
     fn action_strings_match_reserved_names() {
         assert_eq!(Action::ChatCreate.as_str(), "chat.create");
         assert_eq!(Action::PermissionAll.as_str(), "permission.all");
+        assert_eq!(Action::MessageViewAll.as_str(), "message.viewAll");
+        assert_eq!(Action::UserCreate.as_str(), "user.create");
+        assert_eq!(Action::WebhookManage.as_str(), "webhook.manage");
     }
 
     #[test]
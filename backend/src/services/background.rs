@@ -1,7 +1,7 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool};
 use diesel::PgConnection;
@@ -10,9 +10,9 @@ use std::collections::HashSet;
 use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 
-use crate::handlers::ws::messages::{BulkDeletedPayload, ServerWsMessage};
+use crate::handlers::ws::messages::{BulkDeletedPayload, ServerWsMessage, UserPresencePayload};
 use crate::metrics::Metrics;
-use crate::schema::{attachments, group_membership, messages};
+use crate::schema::{attachments, group_membership, groups, messages};
 use crate::services::ws_registry::ConnectionRegistry;
 
 const CHANNEL_BUFFER: usize = 64;
@@ -37,6 +37,10 @@ pub enum BackgroundJob {
         target_uid: i32,
         scope: DeleteScope,
     },
+    /// Tell a user's chat co-members that they came online or went offline.
+    BroadcastPresence { uid: i32 },
+    /// Soft-delete messages older than each chat's retention window.
+    EnforceRetention,
     // Future variants: CleanupStaleUploads, CompressMedia, etc.
 }
 
@@ -45,6 +49,8 @@ impl BackgroundJob {
     fn kind(&self) -> &'static str {
         match self {
             BackgroundJob::BulkDeleteMessages { .. } => "bulk_delete_messages",
+            BackgroundJob::BroadcastPresence { .. } => "broadcast_presence",
+            BackgroundJob::EnforceRetention => "enforce_retention",
         }
     }
 }
@@ -134,6 +140,10 @@ async fn run_worker(
                 target_uid,
                 scope,
             } => process_bulk_delete(*chat_id, *target_uid, *scope, db, ws_registry),
+            BackgroundJob::BroadcastPresence { uid } => {
+                process_presence_broadcast(*uid, db, ws_registry)
+            }
+            BackgroundJob::EnforceRetention => process_retention_sweep(db, ws_registry),
         };
 
         let duration = started_at.elapsed().as_secs_f64();
@@ -153,6 +163,51 @@ async fn run_worker(
 // Job handlers
 // ---------------------------------------------------------------------------
 
+/// Broadcast a user's presence to everyone sharing a chat with them.
+///
+/// The online flag is read from the registry when the job runs rather than
+/// when it was enqueued, so racing connect/disconnect jobs still settle on
+/// the user's actual state.
+fn process_presence_broadcast(
+    uid: i32,
+    db: &Pool<ConnectionManager<PgConnection>>,
+    ws_registry: &Arc<ConnectionRegistry>,
+) -> Result<(), String> {
+    use crate::schema::group_membership::dsl as gm_dsl;
+
+    let conn = &mut db.get().map_err(|e| format!("pool error: {e}"))?;
+
+    let chat_ids: Vec<i64> = group_membership::table
+        .filter(gm_dsl::uid.eq(uid))
+        .select(gm_dsl::chat_id)
+        .load(conn)
+        .map_err(|e| format!("db error: {e}"))?;
+    let co_member_uids: Vec<i32> = group_membership::table
+        .filter(gm_dsl::chat_id.eq_any(&chat_ids))
+        .filter(gm_dsl::uid.ne(uid))
+        .select(gm_dsl::uid)
+        .distinct()
+        .load(conn)
+        .map_err(|e| format!("db error: {e}"))?;
+
+    let ws_msg = Arc::new(ServerWsMessage::Presence(UserPresencePayload {
+        uid,
+        online: ws_registry.is_online(uid),
+    }));
+    ws_registry.broadcast_to_uids(&co_member_uids, ws_msg);
+
+    Ok(())
+}
+
+/// Which messages of a chat a batched soft-delete targets.
+#[derive(Debug, Clone, Copy)]
+enum DeleteTarget {
+    /// Everything one member sent, optionally limited to the last 24 hours.
+    Sender { uid: i32, scope: DeleteScope },
+    /// Everything created before the cutoff.
+    OlderThan(DateTime<Utc>),
+}
+
 /// Soft-delete messages from a user in a chat, in batches of BATCH_SIZE.
 fn process_bulk_delete(
     chat_id: i64,
@@ -161,12 +216,76 @@ fn process_bulk_delete(
     db: &Pool<ConnectionManager<PgConnection>>,
     ws_registry: &Arc<ConnectionRegistry>,
 ) -> Result<(), String> {
+    let conn = &mut db.get().map_err(|e| format!("pool error: {e}"))?;
+
+    let target = DeleteTarget::Sender {
+        uid: target_uid,
+        scope,
+    };
+    let total_deleted = soft_delete_in_batches(conn, ws_registry, chat_id, target)?;
+    if total_deleted > 0 {
+        info!(
+            chat_id,
+            target_uid, total_deleted, "Bulk message deletion completed"
+        );
+    }
+
+    Ok(())
+}
+
+/// Start of the window a chat keeps, or `None` when it keeps messages forever.
+fn retention_cutoff(now: DateTime<Utc>, retention_days: i32) -> Option<DateTime<Utc>> {
+    (retention_days > 0).then(|| now - chrono::Duration::days(retention_days.into()))
+}
+
+/// Soft-delete expired messages in every chat with a retention window.
+///
+/// A failing chat is logged and skipped so it cannot hold back the rest;
+/// it is retried on the next sweep.
+fn process_retention_sweep(
+    db: &Pool<ConnectionManager<PgConnection>>,
+    ws_registry: &Arc<ConnectionRegistry>,
+) -> Result<(), String> {
+    let conn = &mut db.get().map_err(|e| format!("pool error: {e}"))?;
+
+    let chats: Vec<(i64, i32)> = groups::table
+        .filter(groups::retention_days.gt(0))
+        .filter(groups::deleted_at.is_null())
+        .select((groups::id, groups::retention_days))
+        .load(conn)
+        .map_err(|e| format!("db error: {e}"))?;
+
+    let now = Utc::now();
+    for (chat_id, retention_days) in chats {
+        let Some(cutoff) = retention_cutoff(now, retention_days) else {
+            continue;
+        };
+        match soft_delete_in_batches(conn, ws_registry, chat_id, DeleteTarget::OlderThan(cutoff)) {
+            Ok(0) => {}
+            Ok(total_deleted) => info!(
+                chat_id,
+                retention_days, total_deleted, "Retention sweep deleted expired messages"
+            ),
+            Err(e) => warn!(chat_id, "Retention sweep failed: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Soft-delete a chat's targeted messages and their attachments in batches of
+/// BATCH_SIZE, broadcasting `messagesBulkDeleted` per batch. Returns how many
+/// messages were deleted.
+fn soft_delete_in_batches(
+    conn: &mut PgConnection,
+    ws_registry: &Arc<ConnectionRegistry>,
+    chat_id: i64,
+    target: DeleteTarget,
+) -> Result<usize, String> {
     use crate::schema::attachments::dsl as a_dsl;
     use crate::schema::group_membership::dsl as gm_dsl;
     use crate::schema::messages::dsl;
 
-    let conn = &mut db.get().map_err(|e| format!("pool error: {e}"))?;
-
     let map_db = |e: diesel::result::Error| format!("db error: {e}");
 
     // 1. Collect member UIDs for WS broadcast (once before the loop)
@@ -182,13 +301,23 @@ fn process_bulk_delete(
     loop {
         let mut query = messages::table
             .filter(dsl::chat_id.eq(chat_id))
-            .filter(dsl::sender_uid.eq(target_uid))
             .filter(dsl::deleted_at.is_null())
             .into_boxed();
 
-        if let DeleteScope::Last24Hours = scope {
-            let cutoff = Utc::now() - chrono::Duration::hours(24);
-            query = query.filter(dsl::created_at.ge(cutoff));
+        match target {
+            DeleteTarget::Sender { uid, scope } => {
+                query = query.filter(dsl::sender_uid.eq(uid));
+                if let DeleteScope::Last24Hours = scope {
+                    let cutoff = Utc::now() - chrono::Duration::hours(24);
+                    query = query.filter(dsl::created_at.ge(cutoff));
+                }
+            }
+            DeleteTarget::OlderThan(cutoff) => {
+                // Ids are snowflakes, so the bound starts the (chat_id, id)
+                // index walk at the cutoff instead of scanning down through
+                // the whole retention window on every batch.
+                query = query.filter(dsl::id.lt(crate::utils::ids::first_id_at(cutoff)));
+            }
         }
 
         let batch_ids: Vec<i64> = query
@@ -254,7 +383,7 @@ fn process_bulk_delete(
                     chat_id,
                     thread_root_id,
                     ?e,
-                    "recalculate thread_meta after bulk delete"
+                    "recalculate thread_meta after soft delete"
                 );
                 continue;
             }
@@ -269,16 +398,116 @@ fn process_bulk_delete(
                     chat_id,
                     thread_root_id,
                     ?e,
-                    "broadcast thread update after bulk delete"
+                    "broadcast thread update after soft delete"
                 );
             }
         }
+    }
 
-        info!(
-            chat_id,
-            target_uid, total_deleted, "Bulk message deletion completed"
+    Ok(total_deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retention_cutoff_is_disabled_by_zero() {
+        let now = Utc::now();
+        assert_eq!(retention_cutoff(now, 0), None);
+        assert_eq!(retention_cutoff(now, -1), None);
+        assert_eq!(
+            retention_cutoff(now, 30),
+            Some(now - chrono::Duration::days(30))
         );
     }
 
-    Ok(())
+    #[tokio::test(flavor = "multi_thread")]
+    async fn retention_sweep_deletes_only_expired_messages_in_chats_that_expire() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let uid = 901_171;
+        app.seed_user(uid);
+        let expiring = app.seed_chat("Expiring").await;
+        let forever = app.seed_chat("Forever").await;
+        app.seed_membership(expiring, uid, crate::models::GroupRole::Member);
+        app.seed_membership(forever, uid, crate::models::GroupRole::Member);
+        let app = &app;
+        let send = |chat_id: i64, client_generated_id: &'static str| async move {
+            let (status, body) = app
+                .request(
+                    axum::http::Method::POST,
+                    &format!("/chats/{chat_id}/messages"),
+                    uid,
+                    Some(serde_json::json!({
+                        "message": client_generated_id,
+                        "messageType": "text",
+                        "clientGeneratedId": client_generated_id,
+                    })),
+                )
+                .await;
+            assert_eq!(status, axum::http::StatusCode::CREATED, "{body}");
+            body["id"].as_str().unwrap().parse::<i64>().unwrap()
+        };
+        let recent = send(expiring, "retention-new").await;
+        // Backdated rows need ids from the same time, as the sweep bounds by id.
+        let month_ago = Utc::now() - chrono::Duration::days(30);
+        let old_message =
+            |id: i64, chat_id: i64, client_generated_id: &str| crate::models::NewMessage {
+                id,
+                message: Some(client_generated_id.to_string()),
+                message_type: crate::models::MessageType::Text,
+                reply_to_id: None,
+                reply_root_id: None,
+                client_generated_id: client_generated_id.to_string(),
+                sender_uid: uid,
+                chat_id,
+                created_at: month_ago,
+                updated_at: None,
+                deleted_at: None,
+                has_attachments: false,
+                has_thread: false,
+                has_reactions: false,
+                sticker_id: None,
+                is_published: true,
+                transcode_status: crate::models::TranscodeStatus::None,
+                forwarded_from_message_id: None,
+            };
+        let expired = crate::utils::ids::first_id_at(month_ago) + 1;
+        let kept = expired + 1;
+        {
+            let conn = &mut app.conn();
+            diesel::update(groups::table.find(expiring))
+                .set(groups::retention_days.eq(7))
+                .execute(conn)
+                .unwrap();
+            diesel::insert_into(messages::table)
+                .values(&vec![
+                    old_message(expired, expiring, "retention-old"),
+                    old_message(kept, forever, "retention-kept"),
+                ])
+                .execute(conn)
+                .unwrap();
+        }
+        let (_entry, mut rx, _) = app.state.ws_registry.register(uid);
+
+        process_retention_sweep(&app.state.db, &app.state.ws_registry).unwrap();
+
+        let deleted: Vec<(i64, bool)> = messages::table
+            .filter(messages::id.eq_any([expired, recent, kept]))
+            .order(messages::id.asc())
+            .select((messages::id, messages::deleted_at.is_not_null()))
+            .load(&mut app.conn())
+            .unwrap();
+        assert_eq!(
+            deleted,
+            vec![(expired, true), (kept, false), (recent, false)]
+        );
+        let bulk_deleted = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|frame| serde_json::from_str::<serde_json::Value>(&frame).unwrap())
+            .find(|frame| frame["type"] == "messagesBulkDeleted")
+            .expect("a messagesBulkDeleted frame");
+        assert_eq!(bulk_deleted["payload"]["chatId"], expiring.to_string());
+    }
 }
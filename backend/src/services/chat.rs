@@ -3,9 +3,11 @@ use diesel::prelude::*;
 use diesel::sql_query;
 use diesel::PgConnection;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::warn;
 
 use crate::schema::group_membership;
+use crate::services::ws_registry::{ChatMember, ConnectionRegistry};
 
 pub const MAX_UNREAD_COUNT: i64 = 100;
 const UNREAD_COUNT_CHUNK_SIZE: usize = 50;
@@ -93,6 +95,7 @@ fn get_unread_counts_batch(
              WHERE gm.uid = input_uids.uid
                AND gm.archived = $2
                AND m.id > COALESCE(gm.last_read_message_id, 0)
+               AND m.sender_uid <> gm.uid
                AND m.deleted_at IS NULL
                AND m.is_published = TRUE
                AND m.reply_root_id IS NULL
@@ -118,9 +121,12 @@ fn get_unread_counts_batch(
     }
 }
 
+/// Capped unread count for one chat as seen by `viewer_uid`; the viewer's own
+/// messages never count as unread.
 pub fn get_chat_unread_count(
     conn: &mut PgConnection,
     chat_id: i64,
+    viewer_uid: i32,
     last_read_message_id: Option<i64>,
 ) -> Result<i64, diesel::result::Error> {
     let query = sql_query(
@@ -132,11 +138,13 @@ pub fn get_chat_unread_count(
                AND reply_root_id IS NULL
                AND deleted_at IS NULL
                AND is_published = TRUE
-               AND id > COALESCE($2, 0)
+               AND sender_uid <> $2
+               AND id > COALESCE($3, 0)
              LIMIT 100
          ) AS unread_messages",
     )
     .bind::<diesel::sql_types::BigInt, _>(chat_id)
+    .bind::<diesel::sql_types::Integer, _>(viewer_uid)
     .bind::<diesel::sql_types::Nullable<diesel::sql_types::BigInt>, _>(last_read_message_id);
 
     query
@@ -166,3 +174,120 @@ pub fn mark_chat_as_read(
 
     Ok(updated > 0)
 }
+
+/// Members of `chat_id` with their mute deadlines, served from the registry's
+/// short-lived cache so a busy chat does not hit `group_membership` for every
+/// broadcast.
+pub fn chat_members(
+    conn: &mut PgConnection,
+    registry: &ConnectionRegistry,
+    chat_id: i64,
+) -> Result<Arc<[ChatMember]>, diesel::result::Error> {
+    registry.chat_members(chat_id, || {
+        group_membership::table
+            .filter(group_membership::chat_id.eq(chat_id))
+            .select((group_membership::uid, group_membership::muted_until))
+            .load(conn)
+    })
+}
+
+/// Uids of everyone in `chat_id`, for broadcasting an event to the chat.
+pub fn member_uids(
+    conn: &mut PgConnection,
+    registry: &ConnectionRegistry,
+    chat_id: i64,
+) -> Result<Vec<i32>, diesel::result::Error> {
+    Ok(chat_members(conn, registry, chat_id)?
+        .iter()
+        .map(|(uid, _)| *uid)
+        .collect())
+}
+
+/// [`member_uids`] for a broadcast sent after its change has committed: a
+/// failed load is logged and the broadcast skipped, since failing the request
+/// would report a saved change as lost.
+pub fn member_uids_or_log(
+    conn: &mut PgConnection,
+    registry: &ConnectionRegistry,
+    chat_id: i64,
+) -> Vec<i32> {
+    member_uids(conn, registry, chat_id).unwrap_or_else(|e| {
+        tracing::warn!(chat_id, error = ?e, "Change saved but loading its broadcast recipients failed");
+        Vec::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GroupRole;
+    use crate::test_support::TestApp;
+    use axum::http::{Method, StatusCode};
+
+    fn sorted_member_uids(app: &TestApp, chat_id: i64) -> Vec<i32> {
+        let mut uids = member_uids(&mut app.conn(), &app.state.ws_registry, chat_id).unwrap();
+        uids.sort_unstable();
+        uids
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn member_uids_are_cached_until_invalidated() {
+        let Some(app) = TestApp::start().await else {
+            return;
+        };
+        let chat_id = app.seed_chat("Cached roster").await;
+        app.seed_membership(chat_id, 900_101, GroupRole::Admin);
+        app.seed_membership(chat_id, 900_102, GroupRole::Member);
+        assert_eq!(sorted_member_uids(&app, chat_id), vec![900_101, 900_102]);
+
+        // A write that skips the handlers is not seen until invalidation.
+        app.seed_membership(chat_id, 900_103, GroupRole::Member);
+        assert_eq!(sorted_member_uids(&app, chat_id), vec![900_101, 900_102]);
+
+        app.state.ws_registry.invalidate_chat_members(chat_id);
+        assert_eq!(
+            sorted_member_uids(&app, chat_id),
+            vec![900_101, 900_102, 900_103]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn joining_and_muting_refresh_cached_members() {
+        let Some(app) = TestApp::start().await else {
+            return;
+        };
+        let (admin, joiner) = (900_111, 900_112);
+        app.seed_user(admin);
+        app.seed_user(joiner);
+        let chat_id = app.seed_chat("Joinable").await;
+        app.seed_membership(chat_id, admin, GroupRole::Admin);
+        assert_eq!(sorted_member_uids(&app, chat_id), vec![admin]);
+
+        let (status, body) = app
+            .request(
+                Method::POST,
+                &format!("/group/{chat_id}/join"),
+                joiner,
+                None,
+            )
+            .await;
+        assert!(status.is_success(), "{status}: {body}");
+        assert_eq!(sorted_member_uids(&app, chat_id), vec![admin, joiner]);
+
+        let (status, body) = app
+            .request(
+                Method::PUT,
+                &format!("/group/{chat_id}/mute"),
+                joiner,
+                Some(serde_json::json!({ "durationSeconds": 3600 })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let members = chat_members(&mut app.conn(), &app.state.ws_registry, chat_id).unwrap();
+        let joiner_mute = members
+            .iter()
+            .find(|(uid, _)| *uid == joiner)
+            .and_then(|(_, muted_until)| *muted_until);
+        assert!(joiner_mute.is_some_and(|until| until > Utc::now()));
+    }
+}
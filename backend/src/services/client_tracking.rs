@@ -14,6 +14,7 @@ use diesel::r2d2::{ConnectionManager, Pool};
 use diesel::PgConnection;
 use tracing::{error, info, warn};
 
+use crate::errors::AppError;
 use crate::metrics::{ActivityTodaySnapshot, Metrics};
 use crate::models::{
     ActivityDailyMetric, ClientRecord, NewActivityDailyMetric, NewClientRecord, NewUserExtra,
@@ -450,10 +451,8 @@ pub async fn track_client_activity(
             .or_else(|| optional_client_id(request.headers()).ok().flatten());
         if let Some(client_id) = client_id {
             resolved_client_id = Some(client_id.clone());
-            if let Err((status, message)) =
-                state.client_tracking.record_activity(auth.uid, &client_id)
-            {
-                return (status, message).into_response();
+            if let Err(err) = state.client_tracking.record_activity(auth.uid, &client_id) {
+                return AppError::from(err).into_response();
             }
         }
     }
@@ -2,6 +2,7 @@ use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::primitives::ByteStream;
 use axum::http::StatusCode;
 use chrono::Duration;
+use futures::future::BoxFuture;
 use std::collections::BTreeMap;
 
 use crate::AppState;
@@ -38,11 +39,14 @@ pub fn build_storage_key(prefix: &str, filename: &str, object_id: &str) -> Strin
     format!("{}/{}.{}", prefix, object_id, extension)
 }
 
+/// Sign a public PUT of exactly `content_length` bytes, so storage refuses a
+/// body of any other size than the one the caller was allowed to upload.
 pub async fn presign_public_upload(
     s3_client: &aws_sdk_s3::Client,
     bucket: &str,
     storage_key: &str,
     content_type: &str,
+    content_length: i64,
     expires_in: Duration,
 ) -> Result<PresignedUpload, (StatusCode, &'static str)> {
     let presigning_config =
@@ -59,6 +63,7 @@ pub async fn presign_public_upload(
         .bucket(bucket)
         .key(storage_key)
         .content_type(content_type)
+        .content_length(content_length)
         .cache_control(PUBLIC_MEDIA_CACHE_CONTROL)
         .acl(aws_sdk_s3::types::ObjectCannedAcl::PublicRead)
         .presigned(presigning_config)
@@ -77,6 +82,90 @@ pub async fn presign_public_upload(
     })
 }
 
+/// What storage holds under a key, as opposed to what a client claims it uploaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredObject {
+    pub size: i64,
+    pub content_type: Option<String>,
+}
+
+pub async fn head_object(
+    s3_client: &aws_sdk_s3::Client,
+    bucket: &str,
+    storage_key: &str,
+) -> Result<Option<StoredObject>, (StatusCode, &'static str)> {
+    match s3_client
+        .head_object()
+        .bucket(bucket)
+        .key(storage_key)
+        .send()
+        .await
+    {
+        Ok(head) => Ok(Some(StoredObject {
+            size: head.content_length().unwrap_or(0),
+            content_type: head.content_type().map(str::to_string),
+        })),
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+        Err(e) => {
+            tracing::error!("Failed to look up uploaded object: {:?}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to check uploaded file",
+            ))
+        }
+    }
+}
+
+/// Object store that clients upload attachment bytes to directly. Handlers
+/// sign uploads through this rather than the S3 client so tests can fake it.
+pub trait AttachmentStorage: Send + Sync {
+    fn presign_put<'a>(
+        &'a self,
+        storage_key: &'a str,
+        content_type: &'a str,
+        content_length: i64,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<PresignedUpload, (StatusCode, &'static str)>>;
+
+    /// The object under `storage_key`, or `None` if nothing was uploaded there.
+    fn head<'a>(
+        &'a self,
+        storage_key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<StoredObject>, (StatusCode, &'static str)>>;
+}
+
+/// The S3-compatible bucket configured by `S3_BUCKET_NAME` and `S3_ENDPOINT_URL`.
+pub struct S3AttachmentStorage {
+    pub client: aws_sdk_s3::Client,
+    pub bucket: String,
+}
+
+impl AttachmentStorage for S3AttachmentStorage {
+    fn presign_put<'a>(
+        &'a self,
+        storage_key: &'a str,
+        content_type: &'a str,
+        content_length: i64,
+        expires_in: Duration,
+    ) -> BoxFuture<'a, Result<PresignedUpload, (StatusCode, &'static str)>> {
+        Box::pin(presign_public_upload(
+            &self.client,
+            &self.bucket,
+            storage_key,
+            content_type,
+            content_length,
+            expires_in,
+        ))
+    }
+
+    fn head<'a>(
+        &'a self,
+        storage_key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<StoredObject>, (StatusCode, &'static str)>> {
+        Box::pin(head_object(&self.client, &self.bucket, storage_key))
+    }
+}
+
 pub async fn upload_public_object(
     s3_client: &aws_sdk_s3::Client,
     bucket: &str,
@@ -8,4 +8,5 @@ pub mod media;
 pub mod push;
 pub mod threads;
 pub mod user;
+pub mod webhooks;
 pub mod ws_registry;
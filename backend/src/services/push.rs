@@ -215,6 +215,38 @@ impl PushService {
         let apns_sender =
             ApnsSender::from_env().expect("invalid APNS configuration; set all vars or none");
 
+        Self::start_with(
+            db,
+            ws_registry,
+            metrics,
+            (public_key, private_key, subject),
+            apns_sender,
+        )
+    }
+
+    /// Push service for the integration test harness: no APNs sender, and
+    /// placeholder VAPID keys, which only matter once a subscription exists.
+    #[cfg(test)]
+    pub fn start_for_tests(
+        db: Pool<ConnectionManager<PgConnection>>,
+        ws_registry: Arc<ConnectionRegistry>,
+        metrics: Arc<Metrics>,
+    ) -> Arc<Self> {
+        let vapid = (
+            "test-public-key".to_string(),
+            "test-private-key".to_string(),
+            "mailto:test@example.com".to_string(),
+        );
+        Self::start_with(db, ws_registry, metrics, vapid, None)
+    }
+
+    fn start_with(
+        db: Pool<ConnectionManager<PgConnection>>,
+        ws_registry: Arc<ConnectionRegistry>,
+        metrics: Arc<Metrics>,
+        (public_key, private_key, subject): (String, String, String),
+        apns_sender: Option<ApnsSender>,
+    ) -> Arc<Self> {
         let (tx, rx) = mpsc::channel(CHANNEL_BUFFER);
 
         let service = Arc::new(Self {
@@ -662,12 +694,13 @@ async fn process_push_job(
         };
 
     // 2. Filter out the sender, muted users (unless mentioned), and users with fresh active app presence.
+    let is_announcement = matches!(job.message_preview.message_type, MessageType::Announcement);
     let target_uids: Vec<i32> = members
         .into_iter()
         .filter(|(uid, _)| *uid != job.sender_uid)
         .filter(|(uid, muted_until)| {
-            // Mentioned users bypass mute
-            if job.mentioned_uids.contains(uid) {
+            // Announcements and mentioned users bypass mute
+            if is_announcement || job.mentioned_uids.contains(uid) {
                 return true;
             }
             // Not muted, or mute has expired
@@ -862,6 +862,7 @@ mod tests {
             width: None,
             height: None,
             order,
+            forwarded_from_attachment_id: None,
         }
     }
 
@@ -216,6 +216,66 @@ pub fn lookup_user_profiles(
         .collect())
 }
 
+/// Which of `uid` and `username` already belong to someone. Usernames compare
+/// like the search helpers do: trimmed and case-insensitive.
+pub fn find_user_conflicts(
+    conn: &mut PgConnection,
+    uid: i32,
+    username: &str,
+) -> QueryResult<(bool, bool)> {
+    use crate::schema::discuz::discuz::common_member::dsl as cm_dsl;
+
+    let uid_taken = cm_dsl::common_member
+        .filter(cm_dsl::uid.eq(uid))
+        .count()
+        .get_result::<i64>(conn)?
+        > 0;
+    let username_taken = !sql_query(
+        "SELECT cm.uid
+         FROM discuz.common_member AS cm
+         WHERE LOWER(BTRIM(cm.username::text)) = LOWER($1)
+         LIMIT 1",
+    )
+    .bind::<diesel::sql_types::Text, _>(username)
+    .load::<UserUidRow>(conn)?
+    .is_empty();
+    Ok((uid_taken, username_taken))
+}
+
+/// Whether `uid` has a `common_member` row.
+pub fn user_exists(conn: &mut PgConnection, uid: i32) -> QueryResult<bool> {
+    use crate::schema::discuz::discuz::common_member::dsl as cm_dsl;
+
+    diesel::select(diesel::dsl::exists(
+        cm_dsl::common_member.filter(cm_dsl::uid.eq(uid)),
+    ))
+    .get_result(conn)
+}
+
+/// Insert a bare `common_member` row; every other column keeps its default.
+pub fn insert_user(
+    conn: &mut PgConnection,
+    uid: i32,
+    username: &str,
+    email: &str,
+) -> QueryResult<()> {
+    use crate::schema::discuz::discuz::common_member::dsl as cm_dsl;
+
+    let regdate = std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0);
+    diesel::insert_into(cm_dsl::common_member)
+        .values((
+            cm_dsl::uid.eq(uid),
+            cm_dsl::username.eq(username),
+            cm_dsl::email.eq(email),
+            cm_dsl::regdate.eq(regdate),
+        ))
+        .execute(conn)
+        .map(|_| ())
+}
+
 #[cfg(test)]
 mod tests {
     use super::normalize_discuz_username;
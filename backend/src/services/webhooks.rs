@@ -0,0 +1,1182 @@
+use std::hash::BuildHasher;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::PgConnection;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::handlers::chats::{MessageResponse, PreparedMessageSend};
+use crate::models::{MessageType, NewWebhookDelivery, Webhook, SYSTEM_SENDER_UID};
+use crate::schema::{webhook_deliveries, webhooks};
+use crate::AppState;
+
+/// Channel buffer size for pending webhook events.
+const CHANNEL_BUFFER: usize = 1024;
+/// Per-attempt timeout, so a hung integrator cannot pin a delivery task.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const WEBHOOK_MAX_ATTEMPTS_ENV: &str = "WEBHOOK_MAX_ATTEMPTS";
+pub const WEBHOOK_RETRY_BASE_DELAY_MS_ENV: &str = "WEBHOOK_RETRY_BASE_DELAY_MS";
+pub const WEBHOOK_RETRY_MAX_DELAY_MS_ENV: &str = "WEBHOOK_RETRY_MAX_DELAY_MS";
+/// Fraction in `[0, 1]` of each retry delay that may be shaved off at random.
+pub const WEBHOOK_RETRY_JITTER_ENV: &str = "WEBHOOK_RETRY_JITTER";
+pub const WEBHOOK_DISABLE_AFTER_FAILURES_ENV: &str = "WEBHOOK_DISABLE_AFTER_FAILURES";
+/// Comma-separated hosts webhooks may reach even though they resolve to
+/// loopback, private or link-local addresses, for integrations running inside
+/// the deployment.
+pub const WEBHOOK_ALLOWED_INTERNAL_HOSTS_ENV: &str = "WEBHOOK_ALLOWED_INTERNAL_HOSTS";
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+/// Doubled after every failed attempt: 1s, 2s, 4s, 8s.
+const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+/// Spreads out retries from every delivery that failed in the same outage.
+const DEFAULT_RETRY_JITTER: f64 = 0.2;
+pub const DEFAULT_DISABLE_AFTER_FAILURES: u32 = 10;
+/// Attempts kept per webhook in `webhook_deliveries`; older ones are pruned.
+pub const DELIVERY_LOG_LIMIT: i64 = 100;
+
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// Message lifecycle events a webhook can subscribe to.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "message.created")]
+    MessageCreated,
+    #[serde(rename = "message.updated")]
+    MessageUpdated,
+    #[serde(rename = "message.deleted")]
+    MessageDeleted,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 3] = [
+        WebhookEvent::MessageCreated,
+        WebhookEvent::MessageUpdated,
+        WebhookEvent::MessageDeleted,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::MessageCreated => "message.created",
+            WebhookEvent::MessageUpdated => "message.updated",
+            WebhookEvent::MessageDeleted => "message.deleted",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == value)
+    }
+}
+
+/// JSON body POSTed to every subscribed webhook.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebhookPayload<'a> {
+    event: WebhookEvent,
+    #[serde(with = "crate::serde_i64_string")]
+    chat_id: i64,
+    message: &'a MessageResponse,
+}
+
+struct WebhookJob {
+    chat_id: i64,
+    event: WebhookEvent,
+    body: Vec<u8>,
+}
+
+/// Backoff schedule for one delivery; shortened in tests.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    /// Wait after the first failed attempt, doubled after each one after it.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Up to this fraction of each wait is skipped at random.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: DEFAULT_RETRY_BASE_DELAY,
+            max_delay: DEFAULT_RETRY_MAX_DELAY,
+            jitter: DEFAULT_RETRY_JITTER,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let policy = Self {
+            max_attempts: read_positive(WEBHOOK_MAX_ATTEMPTS_ENV)
+                .map_or(defaults.max_attempts, |attempts| attempts as u32),
+            base_delay: read_positive(WEBHOOK_RETRY_BASE_DELAY_MS_ENV)
+                .map_or(defaults.base_delay, Duration::from_millis),
+            max_delay: read_positive(WEBHOOK_RETRY_MAX_DELAY_MS_ENV)
+                .map_or(defaults.max_delay, Duration::from_millis),
+            jitter: std::env::var(WEBHOOK_RETRY_JITTER_ENV)
+                .ok()
+                .map(|value| {
+                    value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|jitter| (0.0..=1.0).contains(jitter))
+                        .unwrap_or_else(|| {
+                            panic!("{WEBHOOK_RETRY_JITTER_ENV} must be between 0 and 1")
+                        })
+                })
+                .unwrap_or(defaults.jitter),
+        };
+        assert!(
+            policy.max_delay >= policy.base_delay,
+            "{WEBHOOK_RETRY_MAX_DELAY_MS_ENV} must not be below {WEBHOOK_RETRY_BASE_DELAY_MS_ENV}"
+        );
+        policy
+    }
+
+    /// Wait after failed attempt `attempt` (from 1), given `roll` uniform in
+    /// `[0, 1)`: the doubled base delay, capped, less up to `jitter` of it.
+    fn delay(&self, attempt: u32, roll: f64) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(31);
+        self.base_delay
+            .saturating_mul(1 << doublings)
+            .min(self.max_delay)
+            .mul_f64(1.0 - self.jitter * roll)
+    }
+}
+
+fn read_positive(var_name: &str) -> Option<u64> {
+    std::env::var(var_name).ok().map(|value| {
+        value
+            .parse::<u64>()
+            .ok()
+            .filter(|parsed| *parsed > 0 && *parsed <= u64::from(u32::MAX))
+            .unwrap_or_else(|| panic!("{var_name} must be a positive integer"))
+    })
+}
+
+/// Uniform in `[0, 1)`; good enough to spread retries, not for anything secret.
+fn jitter_roll() -> f64 {
+    let bits = std::collections::hash_map::RandomState::new().hash_one(Instant::now());
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Which hosts webhooks may be delivered to. Every address a host resolves to
+/// must be public unless the host is allowed in
+/// `WEBHOOK_ALLOWED_INTERNAL_HOSTS`, so a chat admin cannot point signed
+/// requests at the server's own network.
+#[derive(Debug, Clone, Default)]
+pub struct DestinationPolicy {
+    /// Lowercased, as URL hosts are.
+    allowed_internal_hosts: Vec<String>,
+}
+
+impl DestinationPolicy {
+    pub fn new<I, S>(allowed_internal_hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            allowed_internal_hosts: allowed_internal_hosts
+                .into_iter()
+                .map(|host| host.as_ref().trim().to_ascii_lowercase())
+                .filter(|host| !host.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var(WEBHOOK_ALLOWED_INTERNAL_HOSTS_ENV)
+                .unwrap_or_default()
+                .split(','),
+        )
+    }
+
+    fn allows_internal(&self, host: &str) -> bool {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        self.allowed_internal_hosts
+            .iter()
+            .any(|allowed| allowed.trim_start_matches('[').trim_end_matches(']') == host)
+    }
+
+    /// Resolve `url`'s host and refuse it unless every address is public or
+    /// the host is allowed to be internal.
+    pub async fn check(&self, url: &reqwest::Url) -> Result<(), &'static str> {
+        let host = url.host_str().ok_or("Webhook URL must have a host")?;
+        if self.allows_internal(host) {
+            return Ok(());
+        }
+        let port = url.port_or_known_default().unwrap_or(0);
+        let lookup_host = host.trim_start_matches('[').trim_end_matches(']');
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((lookup_host, port))
+            .await
+            .map_err(|_| "Webhook host could not be resolved")?
+            .collect();
+        if addrs.is_empty() || !addrs.iter().all(|addr| is_public_ip(addr.ip())) {
+            return Err("Webhook URL must resolve to a public address");
+        }
+        Ok(())
+    }
+}
+
+/// Whether `ip` is reachable on the public internet: not loopback, private,
+/// link-local (which includes cloud metadata at 169.254.169.254), shared,
+/// multicast, documentation or otherwise reserved.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => is_public_ipv6(ip),
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Shared address space (carrier-grade NAT), 100.64.0.0/10.
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments, 192.0.0.0/24.
+        || ip.octets()[..3] == [192, 0, 0]
+        // Benchmarking, 198.18.0.0/15.
+        || (a == 198 && (18..20).contains(&b))
+        // Reserved, 240.0.0.0/4.
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_public_ipv4(v4);
+    }
+    let segments = ip.segments();
+    // NAT64, 64:ff9b::/96, reaches the embedded IPv4 address.
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [.., hi, lo] = segments;
+        return is_public_ipv4(Ipv4Addr::from((u32::from(hi) << 16) | u32::from(lo)));
+    }
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local, fc00::/7.
+        || (segments[0] & 0xfe00) == 0xfc00
+        // Link-local, fe80::/10.
+        || (segments[0] & 0xffc0) == 0xfe80
+        // Documentation, 2001:db8::/32.
+        || segments[..2] == [0x2001, 0xdb8])
+}
+
+/// Resolver for the delivery client that drops addresses a webhook may not
+/// reach, so a host that re-resolves to an internal address after it was
+/// registered still cannot be connected to.
+struct PublicOnlyResolver {
+    policy: DestinationPolicy,
+}
+
+impl reqwest::dns::Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        let allow_internal = self.policy.allows_internal(&host);
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| allow_internal || is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} resolves to no public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// One POST of a delivery, as reported to `deliver`'s caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryAttempt {
+    /// Counts from 1 within the delivery.
+    pub attempt: u32,
+    /// `None` when no response arrived.
+    pub status_code: Option<u16>,
+    /// Transport error when no response arrived.
+    pub error: Option<String>,
+    pub succeeded: bool,
+}
+
+pub struct WebhookService {
+    job_tx: mpsc::Sender<WebhookJob>,
+    /// Taken by `start`.
+    job_rx: Mutex<Option<mpsc::Receiver<WebhookJob>>>,
+    retry: RetryPolicy,
+    /// Failed deliveries in a row after which a webhook is disabled.
+    disable_after_failures: u32,
+    pub destinations: DestinationPolicy,
+}
+
+impl WebhookService {
+    /// Create the webhook service; events queue until `start` spawns its dispatcher.
+    pub fn new(
+        retry: RetryPolicy,
+        disable_after_failures: u32,
+        destinations: DestinationPolicy,
+    ) -> Arc<Self> {
+        let (tx, rx) = mpsc::channel(CHANNEL_BUFFER);
+        Arc::new(Self {
+            job_tx: tx,
+            job_rx: Mutex::new(Some(rx)),
+            retry,
+            disable_after_failures,
+            destinations,
+        })
+    }
+
+    pub fn from_env() -> Arc<Self> {
+        Self::new(
+            RetryPolicy::from_env(),
+            read_positive(WEBHOOK_DISABLE_AFTER_FAILURES_ENV)
+                .map_or(DEFAULT_DISABLE_AFTER_FAILURES, |failures| failures as u32),
+            DestinationPolicy::from_env(),
+        )
+    }
+
+    /// Enqueue a message event. Non-blocking; logs a warning if the channel is full.
+    pub fn enqueue(&self, event: WebhookEvent, message: &MessageResponse) {
+        let payload = WebhookPayload {
+            event,
+            chat_id: message.chat_id,
+            message,
+        };
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+        let job = WebhookJob {
+            chat_id: message.chat_id,
+            event,
+            body,
+        };
+        if let Err(e) = self.job_tx.try_send(job) {
+            warn!("Webhook job channel full, dropping event: {}", e);
+        }
+    }
+}
+
+/// Spawn the dispatcher for `state.webhook_service`. It needs the app state,
+/// not just the pool, because disabling a webhook posts to its chat.
+///
+/// The dispatcher looks up the chat's subscribed webhooks for each event
+/// and spawns an independent delivery per endpoint, so one slow or failing
+/// integrator never delays another.
+pub fn start(state: AppState) {
+    let rx = state
+        .webhook_service
+        .job_rx
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take();
+    let Some(rx) = rx else {
+        warn!("webhook dispatcher already started");
+        return;
+    };
+    // Redirects could lead anywhere, including past the destination policy.
+    let client = reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(PublicOnlyResolver {
+            policy: state.webhook_service.destinations.clone(),
+        })
+        .build()
+        .expect("Failed to build webhook HTTP client");
+
+    tokio::spawn(async move {
+        run_dispatcher(rx, state, client).await;
+    });
+}
+
+async fn run_dispatcher(
+    mut rx: mpsc::Receiver<WebhookJob>,
+    state: AppState,
+    client: reqwest::Client,
+) {
+    while let Some(job) = rx.recv().await {
+        let hooks = match load_subscribed_webhooks(&state.db, job.chat_id, job.event) {
+            Ok(hooks) => hooks,
+            Err(e) => {
+                warn!(chat_id = job.chat_id, "Failed to load webhooks: {}", e);
+                continue;
+            }
+        };
+        let body: Arc<[u8]> = job.body.into();
+        for hook in hooks {
+            let state = state.clone();
+            let client = client.clone();
+            let body = body.clone();
+            tokio::spawn(async move {
+                let service = &state.webhook_service;
+                deliver_and_record(
+                    &state,
+                    &client,
+                    &hook,
+                    job.event,
+                    &body,
+                    service.retry,
+                    service.disable_after_failures,
+                )
+                .await;
+            });
+        }
+    }
+    info!("Webhook dispatcher stopped (channel closed)");
+}
+
+/// Deliver one event to `hook`, logging each attempt to `webhook_deliveries`,
+/// and disable the webhook once `disable_after_failures` deliveries in a row
+/// have given up. Returns whether the event was delivered.
+async fn deliver_and_record(
+    state: &AppState,
+    client: &reqwest::Client,
+    hook: &Webhook,
+    event: WebhookEvent,
+    body: &[u8],
+    policy: RetryPolicy,
+    disable_after_failures: u32,
+) -> bool {
+    let record = |attempt: &DeliveryAttempt| {
+        if let Err(e) = record_attempt(&state.db, hook.id, event, attempt) {
+            warn!(
+                webhook_id = hook.id,
+                "Failed to record webhook attempt: {}", e
+            );
+        }
+    };
+    // Registration checked the URL too, but the allowlist may have changed
+    // since and literal addresses never reach the client's resolver.
+    let refused = match reqwest::Url::parse(&hook.url) {
+        Ok(url) => state.webhook_service.destinations.check(&url).await.err(),
+        Err(_) => Some("Invalid webhook URL"),
+    };
+    let delivered = match refused {
+        Some(reason) => {
+            warn!(
+                webhook_id = hook.id,
+                "Webhook destination refused: {}", reason
+            );
+            record(&DeliveryAttempt {
+                attempt: 1,
+                status_code: None,
+                error: Some(reason.to_string()),
+                succeeded: false,
+            });
+            false
+        }
+        None => deliver(client, &hook.url, &hook.secret, event, body, policy, record).await,
+    };
+    state.metrics.record_webhook_delivery(delivered);
+    if !delivered {
+        warn!(
+            webhook_id = hook.id,
+            chat_id = hook.chat_id,
+            "Webhook delivery gave up after {} attempts",
+            policy.max_attempts
+        );
+    }
+    match track_outcome(&state.db, hook.id, delivered, disable_after_failures) {
+        Ok(true) => notify_disabled(state, hook, disable_after_failures).await,
+        Ok(false) => {}
+        Err(e) => warn!(
+            webhook_id = hook.id,
+            "Failed to track webhook outcome: {}", e
+        ),
+    }
+    delivered
+}
+
+fn record_attempt(
+    db: &Pool<ConnectionManager<PgConnection>>,
+    webhook_id: i64,
+    event: WebhookEvent,
+    attempt: &DeliveryAttempt,
+) -> Result<(), String> {
+    let conn = &mut db.get().map_err(|e| format!("pool error: {e}"))?;
+    diesel::insert_into(webhook_deliveries::table)
+        .values(&NewWebhookDelivery {
+            webhook_id,
+            event: event.as_str().to_string(),
+            attempt: attempt.attempt as i32,
+            status_code: attempt.status_code.map(i32::from),
+            error: attempt.error.clone(),
+            succeeded: attempt.succeeded,
+            created_at: Utc::now(),
+        })
+        .execute(conn)
+        .map_err(|e| format!("db error: {e}"))?;
+    Ok(())
+}
+
+/// Reset or extend the webhook's run of failed deliveries and trim its
+/// attempt log. Returns true when this failure is the one that disabled it.
+fn track_outcome(
+    db: &Pool<ConnectionManager<PgConnection>>,
+    webhook_id: i64,
+    delivered: bool,
+    disable_after_failures: u32,
+) -> Result<bool, String> {
+    let conn = &mut db.get().map_err(|e| format!("pool error: {e}"))?;
+    track_outcome_on(conn, webhook_id, delivered, disable_after_failures)
+        .map_err(|e| format!("db error: {e}"))
+}
+
+fn track_outcome_on(
+    conn: &mut PgConnection,
+    webhook_id: i64,
+    delivered: bool,
+    disable_after_failures: u32,
+) -> QueryResult<bool> {
+    prune_delivery_log(conn, webhook_id)?;
+    let hook = webhooks::table.filter(webhooks::id.eq(webhook_id));
+    if delivered {
+        diesel::update(hook)
+            .set(webhooks::consecutive_failures.eq(0))
+            .execute(conn)?;
+        return Ok(false);
+    }
+    let failures: Option<i32> = diesel::update(hook.filter(webhooks::disabled_at.is_null()))
+        .set(webhooks::consecutive_failures.eq(webhooks::consecutive_failures + 1))
+        .returning(webhooks::consecutive_failures)
+        .get_result(conn)
+        .optional()?;
+    if failures.is_none_or(|failures| (failures as u32) < disable_after_failures) {
+        return Ok(false);
+    }
+    // Concurrent deliveries can both cross the threshold; only one disables it.
+    let disabled = diesel::update(hook.filter(webhooks::disabled_at.is_null()))
+        .set(webhooks::disabled_at.eq(Utc::now()))
+        .execute(conn)?;
+    Ok(disabled == 1)
+}
+
+fn prune_delivery_log(conn: &mut PgConnection, webhook_id: i64) -> QueryResult<usize> {
+    let oldest_kept: Option<i64> = webhook_deliveries::table
+        .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+        .order(webhook_deliveries::id.desc())
+        .offset(DELIVERY_LOG_LIMIT - 1)
+        .select(webhook_deliveries::id)
+        .first(conn)
+        .optional()?;
+    match oldest_kept {
+        Some(oldest_kept) => diesel::delete(
+            webhook_deliveries::table
+                .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+                .filter(webhook_deliveries::id.lt(oldest_kept)),
+        )
+        .execute(conn),
+        None => Ok(0),
+    }
+}
+
+/// Tell the chat a webhook was disabled, as a system message, so its admins
+/// know to fix the endpoint and re-enable it.
+async fn notify_disabled(state: &AppState, hook: &Webhook, failures: u32) {
+    warn!(
+        webhook_id = hook.id,
+        chat_id = hook.chat_id,
+        "Disabled webhook after {} failed deliveries in a row",
+        failures
+    );
+    let mut conn = match state.db.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            warn!(
+                webhook_id = hook.id,
+                "Failed to announce disabled webhook: {}", e
+            );
+            return;
+        }
+    };
+    let sent = crate::handlers::chats::send_prepared_message(
+        &mut conn,
+        state,
+        PreparedMessageSend {
+            chat_id: hook.chat_id,
+            sender_uid: SYSTEM_SENDER_UID,
+            message: Some(format!(
+                "A webhook was disabled after {failures} failed deliveries in a row"
+            )),
+            message_type: MessageType::System,
+            sticker_id: None,
+            reply_to_id: None,
+            reply_root_id: None,
+            client_generated_id: Uuid::new_v4().to_string(),
+            attachment_ids: vec![],
+            update_group_last_message: false,
+            publish_immediately: true,
+            forwarded_from_message_id: None,
+        },
+    )
+    .await;
+    match sent {
+        Ok(sent) => sent.side_effects.fire(state),
+        Err(e) => warn!(
+            webhook_id = hook.id,
+            "Failed to announce disabled webhook: {:?}", e
+        ),
+    }
+}
+
+fn load_subscribed_webhooks(
+    db: &Pool<ConnectionManager<PgConnection>>,
+    chat_id: i64,
+    event: WebhookEvent,
+) -> Result<Vec<Webhook>, String> {
+    let conn = &mut db.get().map_err(|e| format!("pool error: {e}"))?;
+    let hooks: Vec<Webhook> = webhooks::table
+        .filter(webhooks::chat_id.eq(chat_id))
+        .filter(webhooks::disabled_at.is_null())
+        .select(Webhook::as_select())
+        .load(conn)
+        .map_err(|e| format!("db error: {e}"))?;
+    Ok(hooks
+        .into_iter()
+        .filter(|hook| subscribes_to(hook, event))
+        .collect())
+}
+
+fn subscribes_to(hook: &Webhook, event: WebhookEvent) -> bool {
+    hook.events.iter().flatten().any(|e| e == event.as_str())
+}
+
+/// `sha256=<hex HMAC-SHA256 of the raw body>`, keyed by the webhook secret.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// POST one signed event, retrying non-2xx responses and transport errors
+/// with jittered exponential backoff, and passing each attempt to
+/// `on_attempt`. Returns whether any attempt succeeded.
+pub async fn deliver(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    event: WebhookEvent,
+    body: &[u8],
+    policy: RetryPolicy,
+    mut on_attempt: impl FnMut(&DeliveryAttempt),
+) -> bool {
+    let signature = sign(secret, body);
+    for attempt in 1..=policy.max_attempts {
+        let result = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .header(EVENT_HEADER, event.as_str())
+            .body(body.to_vec())
+            .send()
+            .await;
+        let outcome = match result {
+            Ok(response) => {
+                let status = response.status();
+                if !status.is_success() {
+                    warn!(url, attempt, status = %status, "Webhook rejected");
+                }
+                DeliveryAttempt {
+                    attempt,
+                    status_code: Some(status.as_u16()),
+                    error: None,
+                    succeeded: status.is_success(),
+                }
+            }
+            Err(e) => {
+                warn!(url, attempt, "Webhook request failed: {}", e);
+                DeliveryAttempt {
+                    attempt,
+                    status_code: None,
+                    error: Some(e.to_string()),
+                    succeeded: false,
+                }
+            }
+        };
+        on_attempt(&outcome);
+        if outcome.succeeded {
+            return true;
+        }
+        if attempt < policy.max_attempts {
+            tokio::time::sleep(policy.delay(attempt, jitter_roll())).await;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::HeaderMap, http::StatusCode, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    type Captured = Vec<(HeaderMap, Vec<u8>)>;
+
+    #[derive(Clone, Default)]
+    struct Received {
+        hits: Arc<AtomicUsize>,
+        /// Number of leading requests answered with 500 before accepting.
+        failures: usize,
+        requests: Arc<Mutex<Captured>>,
+    }
+
+    async fn mock_server(received: Received) -> String {
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: axum::body::Bytes| {
+                let received = received.clone();
+                async move {
+                    let hit = received.hits.fetch_add(1, Ordering::SeqCst);
+                    received
+                        .requests
+                        .lock()
+                        .unwrap()
+                        .push((headers, body.to_vec()));
+                    if hit < received.failures {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::NO_CONTENT
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{addr}/hook")
+    }
+
+    fn fast_retries(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(5),
+            max_delay: Duration::from_millis(20),
+            jitter: 0.0,
+        }
+    }
+
+    #[test]
+    fn only_public_addresses_count_as_public() {
+        for ip in [
+            "93.184.215.14",
+            "8.8.8.8",
+            "2606:4700::1111",
+            "64:ff9b::808:808",
+        ] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "0.0.0.0",
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.0.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "192.0.0.8",
+            "198.18.0.1",
+            "224.0.0.1",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "ff02::1",
+            "2001:db8::1",
+            "::ffff:10.0.0.1",
+            "64:ff9b::a00:1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn signature_is_hex_hmac_sha256() {
+        // RFC 4231 test case 2.
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn event_names_round_trip() {
+        for event in WebhookEvent::ALL {
+            assert_eq!(WebhookEvent::parse(event.as_str()), Some(event));
+            assert_eq!(
+                serde_json::to_value(event).unwrap(),
+                serde_json::json!(event.as_str())
+            );
+        }
+        assert_eq!(WebhookEvent::parse("message.read"), None);
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payload() {
+        let received = Received::default();
+        let url = mock_server(received.clone()).await;
+        let body = br#"{"event":"message.created"}"#;
+
+        let delivered = deliver(
+            &reqwest::Client::new(),
+            &url,
+            "s3cret",
+            WebhookEvent::MessageCreated,
+            body,
+            fast_retries(1),
+            |_| {},
+        )
+        .await;
+
+        assert!(delivered);
+        let requests = received.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let (headers, got) = &requests[0];
+        assert_eq!(got.as_slice(), body);
+        assert_eq!(headers[SIGNATURE_HEADER], sign("s3cret", body).as_str());
+        assert_eq!(headers[EVENT_HEADER], "message.created");
+        assert_eq!(headers["content-type"], "application/json");
+    }
+
+    #[tokio::test]
+    async fn retries_non_2xx_until_accepted() {
+        let received = Received {
+            failures: 2,
+            ..Default::default()
+        };
+        let url = mock_server(received.clone()).await;
+
+        let delivered = deliver(
+            &reqwest::Client::new(),
+            &url,
+            "s3cret",
+            WebhookEvent::MessageUpdated,
+            b"{}",
+            fast_retries(5),
+            |_| {},
+        )
+        .await;
+
+        assert!(delivered);
+        assert_eq!(received.hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let received = Received {
+            failures: usize::MAX,
+            ..Default::default()
+        };
+        let url = mock_server(received.clone()).await;
+
+        let delivered = deliver(
+            &reqwest::Client::new(),
+            &url,
+            "s3cret",
+            WebhookEvent::MessageDeleted,
+            b"{}",
+            fast_retries(3),
+            |_| {},
+        )
+        .await;
+
+        assert!(!delivered);
+        assert_eq!(received.hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_cap_less_jitter() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            jitter: 0.5,
+        };
+        assert_eq!(policy.delay(1, 0.0), Duration::from_secs(1));
+        assert_eq!(policy.delay(2, 0.0), Duration::from_secs(2));
+        assert_eq!(policy.delay(3, 0.0), Duration::from_secs(4));
+        assert_eq!(policy.delay(4, 0.0), Duration::from_secs(5));
+        assert_eq!(policy.delay(64, 0.0), Duration::from_secs(5));
+        assert_eq!(policy.delay(2, 1.0), Duration::from_secs(1));
+        for _ in 0..100 {
+            let roll = jitter_roll();
+            assert!((0.0..1.0).contains(&roll));
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_each_attempt_through_transient_failures() {
+        let received = Received {
+            failures: 2,
+            ..Default::default()
+        };
+        let url = mock_server(received.clone()).await;
+        let mut attempts = Vec::new();
+
+        let delivered = deliver(
+            &reqwest::Client::new(),
+            &url,
+            "s3cret",
+            WebhookEvent::MessageCreated,
+            b"{}",
+            fast_retries(5),
+            |attempt| attempts.push(attempt.clone()),
+        )
+        .await;
+
+        assert!(delivered);
+        let outcomes: Vec<_> = attempts
+            .iter()
+            .map(|a| (a.attempt, a.status_code, a.succeeded))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (1, Some(500), false),
+                (2, Some(500), false),
+                (3, Some(204), true)
+            ]
+        );
+    }
+
+    /// A chat with a webhook on `url`, created by `admin`, who may manage webhooks.
+    async fn seed_webhook(app: &crate::test_support::TestApp, admin: i32, url: &str) -> Webhook {
+        app.seed_user(admin);
+        app.grant(admin, crate::services::authz::Action::WebhookManage)
+            .await;
+        let chat_id = app.seed_chat("Hooked").await;
+        app.seed_membership(chat_id, admin, crate::models::GroupRole::Admin);
+        let now = Utc::now();
+        diesel::insert_into(webhooks::table)
+            .values(&crate::models::NewWebhook {
+                id: chat_id + 1,
+                chat_id,
+                url: url.to_string(),
+                secret: "s3cret".to_string(),
+                events: vec![WebhookEvent::MessageCreated.as_str().to_string()],
+                created_by: admin,
+                created_at: now,
+                updated_at: now,
+            })
+            .returning(Webhook::as_returning())
+            .get_result(&mut app.conn())
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn transient_failures_are_logged_then_reset_by_a_success() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let (admin, member) = (900_501, 900_502);
+        let failing = mock_server(Received {
+            failures: usize::MAX,
+            ..Default::default()
+        })
+        .await;
+        let hook = seed_webhook(&app, admin, &failing).await;
+        app.seed_user(member);
+        let client = reqwest::Client::new();
+
+        // One delivery that gives up, then one that recovers after a retry.
+        let delivered = deliver_and_record(
+            &app.state,
+            &client,
+            &hook,
+            WebhookEvent::MessageCreated,
+            b"{}",
+            fast_retries(2),
+            3,
+        )
+        .await;
+        assert!(!delivered);
+        let flaky = mock_server(Received {
+            failures: 1,
+            ..Default::default()
+        })
+        .await;
+        let hook = Webhook { url: flaky, ..hook };
+        let delivered = deliver_and_record(
+            &app.state,
+            &client,
+            &hook,
+            WebhookEvent::MessageCreated,
+            b"{}",
+            fast_retries(2),
+            3,
+        )
+        .await;
+        assert!(delivered);
+
+        let path = format!("/chats/{}/webhooks/{}/deliveries", hook.chat_id, hook.id);
+        let (status, body) = app
+            .request(axum::http::Method::GET, &path, admin, None)
+            .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let outcomes: Vec<_> = body["deliveries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| {
+                (
+                    d["attempt"].clone(),
+                    d["statusCode"].clone(),
+                    d["succeeded"].clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (2.into(), 204.into(), true.into()),
+                (1.into(), 500.into(), false.into()),
+                (2.into(), 500.into(), false.into()),
+                (1.into(), 500.into(), false.into()),
+            ]
+        );
+        let failures: i32 = webhooks::table
+            .find(hook.id)
+            .select(webhooks::consecutive_failures)
+            .first(&mut app.conn())
+            .unwrap();
+        assert_eq!(failures, 0);
+
+        let (status, _) = app
+            .request(axum::http::Method::GET, &path, member, None)
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn deliveries_to_internal_addresses_are_refused_and_logged() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let hook = seed_webhook(&app, 900_504, "http://127.0.0.2:9/hook").await;
+
+        let delivered = deliver_and_record(
+            &app.state,
+            &reqwest::Client::new(),
+            &hook,
+            WebhookEvent::MessageCreated,
+            b"{}",
+            fast_retries(3),
+            5,
+        )
+        .await;
+        assert!(!delivered);
+        let logged: Vec<(i32, Option<i32>, Option<String>)> = webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(hook.id))
+            .select((
+                webhook_deliveries::attempt,
+                webhook_deliveries::status_code,
+                webhook_deliveries::error,
+            ))
+            .load(&mut app.conn())
+            .unwrap();
+        assert_eq!(
+            logged,
+            vec![(
+                1,
+                None,
+                Some("Webhook URL must resolve to a public address".to_string())
+            )]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn repeated_failures_disable_the_webhook_and_tell_the_chat() {
+        let Some(app) = crate::test_support::TestApp::start().await else {
+            return;
+        };
+        let admin = 900_503;
+        let failing = mock_server(Received {
+            failures: usize::MAX,
+            ..Default::default()
+        })
+        .await;
+        let hook = seed_webhook(&app, admin, &failing).await;
+        let client = reqwest::Client::new();
+
+        for _ in 0..2 {
+            deliver_and_record(
+                &app.state,
+                &client,
+                &hook,
+                WebhookEvent::MessageCreated,
+                b"{}",
+                fast_retries(1),
+                2,
+            )
+            .await;
+        }
+
+        let subscribed =
+            load_subscribed_webhooks(&app.state.db, hook.chat_id, WebhookEvent::MessageCreated)
+                .unwrap();
+        assert!(subscribed.is_empty());
+        let (status, listed) = app
+            .request(
+                axum::http::Method::GET,
+                &format!("/chats/{}/messages", hook.chat_id),
+                admin,
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{listed}");
+        let notices: Vec<_> = listed["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|m| m["messageType"] == "system")
+            .collect();
+        assert_eq!(notices.len(), 1, "{listed}");
+        assert_eq!(
+            notices[0]["message"],
+            "A webhook was disabled after 2 failed deliveries in a row"
+        );
+        assert_eq!(notices[0]["sender"]["uid"], SYSTEM_SENDER_UID);
+
+        let (status, body) = app
+            .request(
+                axum::http::Method::PATCH,
+                &format!("/chats/{}/webhooks/{}", hook.chat_id, hook.id),
+                admin,
+                Some(serde_json::json!({ "enabled": true })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["disabledAt"], serde_json::Value::Null);
+        assert_eq!(body["consecutiveFailures"], 0);
+    }
+}
@@ -1,12 +1,122 @@
 //! WebSocket connection registry: maps user id to active connections, tracks app presence,
-//! supports broadcast and stale-connection pruning.
+//! supports broadcast, slow-connection eviction and stale-connection pruning.
 
 use crate::handlers::ws::messages::{PresenceUpdatePayload, ServerWsMessage};
 use crate::metrics::Metrics;
-use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::mpsc;
+use axum::extract::ws::Utf8Bytes;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, Notify};
+
+/// Broadcasts in a row a connection's buffer may reject before it is closed.
+/// A client that far behind has already lost events; reconnecting resyncs it.
+pub const MAX_CONSECUTIVE_FULL_SENDS: u32 = 32;
+
+/// Frames each connection may have queued when no buffer size is configured.
+pub const DEFAULT_CONNECTION_BUFFER: usize = 256;
+
+/// Live sockets one user may hold when no limit is configured. Each holds a
+/// buffer of up to `DEFAULT_CONNECTION_BUFFER` frames, so an unbounded count
+/// lets one user exhaust server memory.
+pub const DEFAULT_MAX_CONNECTIONS_PER_USER: usize = 32;
+
+/// How long a chat's member list is served from `chat_members` before being
+/// reloaded. Handlers that change a membership or mute invalidate it at once;
+/// the TTL only bounds staleness from writes made outside them.
+pub const MEMBER_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// How long `user_exists` trusts a lookup that found the account.
+pub const KNOWN_USER_TTL: Duration = Duration::from_secs(300);
+
+/// How long `user_exists` trusts a lookup that found no account. Kept short
+/// so an account created outside this server can connect soon after.
+pub const UNKNOWN_USER_TTL: Duration = Duration::from_secs(30);
+
+/// Entries a registry cache holds before expired ones are swept.
+const CACHE_SWEEP_THRESHOLD: usize = 1024;
+
+/// How long a user's acks are kept after their last one. Reconnects within
+/// it skip already-delivered messages in catch-up; later ones replay them and
+/// rely on clients dropping ids they already have.
+pub const ACK_TTL: Duration = Duration::from_secs(3600);
+
+/// How often one reader's receipts in a chat go out to the other members.
+/// The reader's own sessions get every advance; everyone else gets at most one
+/// receipt per interval, with the latest held-back cursor sent when it ends.
+pub const READ_RECEIPT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What to do with a reader's receipt for the rest of the chat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadReceiptClaim {
+    /// Send it to every member now.
+    Send,
+    /// Held back; flush it after this long with
+    /// [`ConnectionRegistry::take_held_read_receipt`].
+    FlushAfter(Duration),
+    /// Held back behind a flush that is already scheduled.
+    Held,
+}
+
+/// When a reader's last receipt went to the whole chat, and the newest
+/// cursor held back since.
+struct ReadReceiptSlot {
+    sent_at: Instant,
+    held: Option<i64>,
+}
+
+/// A chat member's uid and, if they muted the chat, when the mute ends.
+pub type ChatMember = (i32, Option<DateTime<Utc>>);
+
+/// The highest acked message id per chat for one user, and when they last acked.
+struct UserAcks {
+    chats: HashMap<i64, i64>,
+    acked_at: Instant,
+}
+
+struct CachedMembers {
+    members: Arc<[ChatMember]>,
+    cached_at: Instant,
+}
+
+/// What `try_register` does when a user already holds the maximum number of connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionLimitPolicy {
+    /// Refuse the new connection.
+    Reject,
+    /// Close the user's oldest connection to make room.
+    EvictOldest,
+}
+
+impl ConnectionLimitPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "reject" => Some(Self::Reject),
+            "evict_oldest" => Some(Self::EvictOldest),
+            _ => None,
+        }
+    }
+}
+
+/// Why the registry dropped a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eviction {
+    /// The buffer stayed full for `MAX_CONSECUTIVE_FULL_SENDS` broadcasts.
+    TooFarBehind,
+    /// A newer connection from the same user took its place.
+    ConnectionLimit,
+}
+
+/// `try_register` refused a connection because the user, or the server as a
+/// whole, is at its connection limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionLimitReached;
+
+/// The entry, the receiver for the send task, and whether this is the user's
+/// first live connection.
+pub type Registration = (Arc<ConnectionEntry>, mpsc::Receiver<Utf8Bytes>, bool);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -24,15 +134,52 @@ impl AppPresenceState {
     }
 }
 
+/// Connections a broadcast skips, so a sender that renders its own message
+/// optimistically does not receive it a second time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EchoExclusion {
+    /// A single socket of the user, identified by the `connId` of its `connected` frame.
+    Connection { uid: i32, conn_id: u64 },
+    /// Every socket of the user.
+    User(i32),
+}
+
+impl EchoExclusion {
+    fn excludes(&self, uid: i32, entry: &ConnectionEntry) -> bool {
+        match *self {
+            EchoExclusion::Connection {
+                uid: excluded,
+                conn_id,
+            } => uid == excluded && entry.conn_id == conn_id,
+            EchoExclusion::User(excluded) => uid == excluded,
+        }
+    }
+}
+
 /// Per-connection state: sender to push messages to the socket task, last ping time for timeout.
 #[derive(Debug)]
 pub struct ConnectionEntry {
     pub conn_id: u64,
-    pub tx: mpsc::Sender<Arc<ServerWsMessage>>,
+    /// Serialized frames; clones share one buffer, so a broadcast is encoded once.
+    pub tx: mpsc::Sender<Utf8Bytes>,
     /// Unix timestamp (seconds) when we last received a ping from the client.
     pub last_ping_at: AtomicU64,
+    /// Unix timestamp (seconds) when the socket was registered.
+    connected_at: u64,
+    /// Frames the socket task has written to the client.
+    messages_sent: AtomicU64,
+    /// Unix timestamp (seconds) of the last frame written, 0 before the first.
+    last_send_at: AtomicU64,
     pub app_state: AtomicU8,
     pub last_state_at: AtomicU64,
+    /// Chats this connection asked for chat-scoped events from. `None` until the
+    /// first `subscribe`, meaning every chat, so older clients keep working.
+    chat_subscriptions: Mutex<Option<HashSet<i64>>>,
+    /// Broadcasts dropped since the last one that fit in the buffer.
+    consecutive_full: AtomicU32,
+    /// Signalled when the registry evicts this connection; `eviction` says why.
+    evicted: Notify,
+    eviction: OnceLock<Eviction>,
 }
 
 impl ConnectionEntry {
@@ -53,6 +200,101 @@ impl ConnectionEntry {
     pub fn app_state(&self) -> AppPresenceState {
         AppPresenceState::from_u8(self.app_state.load(Ordering::Relaxed))
     }
+
+    pub fn subscribe(&self, chat_id: i64) {
+        self.chat_subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_with(HashSet::new)
+            .insert(chat_id);
+    }
+
+    /// No-op before the first `subscribe`: the connection still gets every chat.
+    pub fn unsubscribe(&self, chat_id: i64) {
+        if let Some(chats) = self
+            .chat_subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            chats.remove(&chat_id);
+        }
+    }
+
+    /// Chats this connection is subscribed to, ascending, or `None` while it
+    /// still gets every chat.
+    pub fn subscriptions(&self) -> Option<Vec<i64>> {
+        self.chat_subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|chats| {
+                let mut chat_ids: Vec<i64> = chats.iter().copied().collect();
+                chat_ids.sort_unstable();
+                chat_ids
+            })
+    }
+
+    /// Resolves once the registry has dropped this connection; the socket task
+    /// should then close the socket.
+    pub async fn evicted(&self) -> Eviction {
+        self.evicted.notified().await;
+        self.eviction
+            .get()
+            .copied()
+            .unwrap_or(Eviction::TooFarBehind)
+    }
+
+    fn signal_eviction(&self, reason: Eviction) {
+        let _ = self.eviction.set(reason);
+        self.evicted.notify_one();
+    }
+
+    /// Queue one event behind any pending frames, waiting for buffer space.
+    /// Returns false once the socket task is gone.
+    pub async fn send(&self, message: &ServerWsMessage) -> bool {
+        match encode_frame(message) {
+            Some(frame) => self.tx.send(frame).await.is_ok(),
+            None => true,
+        }
+    }
+
+    /// Count a frame the socket task wrote to the client.
+    pub fn record_frame_sent(&self) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        self.last_send_at.store(now_secs(), Ordering::Relaxed);
+    }
+
+    /// Track a broadcast attempt; returns true once the connection should be evicted.
+    fn record_send(&self, delivered: bool) -> bool {
+        if delivered {
+            self.consecutive_full.store(0, Ordering::Relaxed);
+            return false;
+        }
+        self.consecutive_full.fetch_add(1, Ordering::Relaxed) + 1 >= MAX_CONSECUTIVE_FULL_SENDS
+    }
+
+    pub fn wants_chat(&self, chat_id: i64) -> bool {
+        self.chat_subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_none_or(|chats| chats.contains(&chat_id))
+    }
+}
+
+/// Point-in-time counters for one connection, for diagnosing delivery problems.
+#[cfg_attr(not(feature = "debug-endpoints"), allow(dead_code))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnStat {
+    pub conn_id: u64,
+    pub age_secs: u64,
+    pub messages_sent: u64,
+    /// Unix seconds of the last frame written, `None` if nothing was sent yet.
+    pub last_send_at: Option<u64>,
+    /// Unix seconds of the last client ping.
+    pub last_ping_at: u64,
+    pub app_state: AppPresenceState,
 }
 
 static NEXT_CONN_ID: AtomicU64 = AtomicU64::new(0);
@@ -72,74 +314,412 @@ pub(crate) fn now_secs() -> u64 {
 pub struct ConnectionRegistry {
     /// uid -> list of connection entries (multiple tabs/devices per user).
     inner: dashmap::DashMap<i32, Vec<Arc<ConnectionEntry>>>,
+    /// uid -> chat id -> highest message id any of the user's connections acked.
+    /// Kept past disconnects so the next connection's catch-up can skip what
+    /// was already delivered, until `ACK_TTL` after the user's last ack.
+    acked: dashmap::DashMap<i32, UserAcks>,
     metrics: Arc<Metrics>,
+    /// Capacity of each connection's outbound channel.
+    buffer_size: usize,
+    max_connections_per_user: usize,
+    limit_policy: ConnectionLimitPolicy,
+    /// Live connections across all users, kept alongside `inner` so reading
+    /// it never walks the map.
+    total: AtomicUsize,
+    /// Server-wide cap on `total`; `None` means unlimited.
+    max_total_connections: Option<usize>,
+    /// chat id -> recently loaded members, so a busy chat does not query its
+    /// roster for every broadcast.
+    members: dashmap::DashMap<i64, CachedMembers>,
+    /// Bumped by every invalidation, so a load that overlapped one is not cached.
+    member_generation: AtomicU64,
+    /// uid -> whether the account exists and when that was looked up, so a
+    /// reconnect storm does not query the users table for every socket.
+    known_users: dashmap::DashMap<i32, (bool, Instant)>,
+    /// (chat id, reader uid) -> that reader's receipt throttle.
+    read_receipts: dashmap::DashMap<(i64, i32), ReadReceiptSlot>,
 }
 
 impl ConnectionRegistry {
     pub fn new(metrics: Arc<Metrics>) -> Self {
+        Self::with_buffer(metrics, DEFAULT_CONNECTION_BUFFER)
+    }
+
+    /// Registry whose connections queue up to `buffer_size` frames each.
+    ///
+    /// A larger buffer lets a client ride out bursts in busy chats without
+    /// broadcasts being dropped (and, after `MAX_CONSECUTIVE_FULL_SENDS`
+    /// misses, the connection evicted), but every queued frame stays in memory
+    /// until the socket drains it, so many slow or idle sockets cost more.
+    pub fn with_buffer(metrics: Arc<Metrics>, buffer_size: usize) -> Self {
+        assert!(buffer_size > 0, "connection buffer size must be positive");
         Self {
             inner: dashmap::DashMap::new(),
+            acked: dashmap::DashMap::new(),
             metrics,
+            buffer_size,
+            max_connections_per_user: DEFAULT_MAX_CONNECTIONS_PER_USER,
+            limit_policy: ConnectionLimitPolicy::Reject,
+            total: AtomicUsize::new(0),
+            max_total_connections: None,
+            members: dashmap::DashMap::new(),
+            member_generation: AtomicU64::new(0),
+            known_users: dashmap::DashMap::new(),
+            read_receipts: dashmap::DashMap::new(),
         }
     }
 
-    /// Register a new connection for the given user. Returns the entry (to update last_ping_at)
-    /// and the receiver for the send task. Caller must call `remove_connection(uid, conn_id)` when the socket closes.
-    pub fn register(
-        &self,
-        uid: i32,
-    ) -> (Arc<ConnectionEntry>, mpsc::Receiver<Arc<ServerWsMessage>>) {
+    /// Allow each user at most `max` live connections, applying `policy` past that.
+    pub fn with_connection_limit(mut self, max: usize, policy: ConnectionLimitPolicy) -> Self {
+        assert!(max > 0, "connection limit must be positive");
+        self.max_connections_per_user = max;
+        self.limit_policy = policy;
+        self
+    }
+
+    /// Allow at most `max` live connections across all users. Past that,
+    /// `try_register` refuses every new connection regardless of policy.
+    pub fn with_total_connection_limit(mut self, max: usize) -> Self {
+        assert!(max > 0, "total connection limit must be positive");
+        self.max_total_connections = Some(max);
+        self
+    }
+
+    /// Live connections across all users.
+    pub fn total_connections(&self) -> usize {
+        self.total.load(Ordering::Acquire)
+    }
+
+    /// Whether the server-wide limit leaves room for another connection.
+    /// Checked before the upgrade so a full server answers with a status
+    /// code instead of accepting the socket only to close it.
+    pub fn has_capacity(&self) -> bool {
+        self.max_total_connections
+            .is_none_or(|max| self.total_connections() < max)
+    }
+
+    /// Claim a slot in `total`, failing when the server-wide limit is reached.
+    fn reserve_slot(&self) -> bool {
+        match self.max_total_connections {
+            None => {
+                self.total.fetch_add(1, Ordering::AcqRel);
+                true
+            }
+            Some(max) => self
+                .total
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                    (n < max).then_some(n + 1)
+                })
+                .is_ok(),
+        }
+    }
+
+    fn release_slots(&self, count: usize) {
+        if count > 0 {
+            self.total.fetch_sub(count, Ordering::AcqRel);
+        }
+    }
+
+    /// Register a new connection for the given user, enforcing the per-user
+    /// and server-wide connection limits. Under `EvictOldest` a user at their
+    /// limit displaces their oldest connection, signalling its `evicted`.
+    /// Caller must call
+    /// `remove_connection(uid, conn_id)` when the socket closes.
+    pub fn try_register(&self, uid: i32) -> Result<Registration, ConnectionLimitReached> {
         let conn_id = next_conn_id();
-        let (tx, rx) = mpsc::channel(256);
+        let (tx, rx) = mpsc::channel(self.buffer_size);
         let now = now_secs();
         let entry = Arc::new(ConnectionEntry {
             conn_id,
             tx,
             last_ping_at: AtomicU64::new(now),
+            connected_at: now,
+            messages_sent: AtomicU64::new(0),
+            last_send_at: AtomicU64::new(0),
             app_state: AtomicU8::new(AppPresenceState::Active as u8),
             last_state_at: AtomicU64::new(now),
+            chat_subscriptions: Mutex::new(None),
+            consecutive_full: AtomicU32::new(0),
+            evicted: Notify::new(),
+            eviction: OnceLock::new(),
         });
-        self.inner.entry(uid).or_default().push(entry.clone());
+        // Reserve the global slot first: it is a single atomic, so concurrent
+        // connects on different shards cannot overshoot the cap either.
+        if !self.reserve_slot() {
+            return Err(ConnectionLimitReached);
+        }
+        // Check and insert under the shard lock so concurrent connects cannot
+        // both slip under the limit.
+        let (came_online, displaced) = {
+            let mut vec = self.inner.entry(uid).or_default();
+            let displaced = if vec.len() >= self.max_connections_per_user {
+                match self.limit_policy {
+                    ConnectionLimitPolicy::Reject => {
+                        self.release_slots(1);
+                        return Err(ConnectionLimitReached);
+                    }
+                    ConnectionLimitPolicy::EvictOldest => Some(vec.remove(0)),
+                }
+            } else {
+                None
+            };
+            vec.push(entry.clone());
+            (vec.len() == 1, displaced)
+        };
+        if let Some(oldest) = displaced {
+            // The new connection took the displaced one's slot.
+            self.release_slots(1);
+            tracing::info!(
+                uid,
+                conn_id = oldest.conn_id,
+                "ws connection closed to stay within the per-user connection limit"
+            );
+            oldest.signal_eviction(Eviction::ConnectionLimit);
+        }
         self.metrics.record_ws_connection_open();
         self.update_metrics();
         self.broadcast_presence_to_user(uid);
-        (entry, rx)
+        Ok((entry, rx, came_online))
+    }
+
+    #[cfg(test)]
+    pub fn register(&self, uid: i32) -> Registration {
+        self.try_register(uid)
+            .expect("test registrations stay within the connection limit")
     }
 
     /// Remove a single connection. Call when the socket closes.
-    pub fn remove_connection(&self, uid: i32, conn_id: u64) {
-        let mut empty = false;
-        if let Some(mut vec) = self.inner.get_mut(&uid) {
-            vec.retain(|e| e.conn_id != conn_id);
-            empty = vec.is_empty();
-        }
-        if empty {
-            self.inner.remove(&uid);
-        }
+    /// Returns true when this was the user's last live connection.
+    pub fn remove_connection(&self, uid: i32, conn_id: u64) -> bool {
+        // Check and remove under the shard lock so a concurrent `register`
+        // cannot land between the emptiness check and the removal.
+        let mut removed = 0;
+        let went_offline = self
+            .inner
+            .remove_if_mut(&uid, |_, vec| {
+                let before = vec.len();
+                vec.retain(|e| e.conn_id != conn_id);
+                removed = before - vec.len();
+                vec.is_empty()
+            })
+            .is_some();
+        self.release_slots(removed);
         self.update_metrics();
         self.broadcast_presence_to_user(uid);
+        went_offline
+    }
+
+    /// Record that the user received every message in `chat_id` up to `up_to_seq`.
+    /// Acks only move forward, so a late or duplicate ack is harmless.
+    pub fn record_ack(&self, uid: i32, chat_id: i64, up_to_seq: i64) {
+        let now = Instant::now();
+        let mut acks = self.acked.entry(uid).or_insert_with(|| UserAcks {
+            chats: HashMap::new(),
+            acked_at: now,
+        });
+        acks.acked_at = now;
+        let acked = acks.chats.entry(chat_id).or_insert(up_to_seq);
+        *acked = (*acked).max(up_to_seq);
+    }
+
+    /// Highest message id the user acked in `chat_id`, if any.
+    pub fn acked_up_to(&self, uid: i32, chat_id: i64) -> Option<i64> {
+        self.acked
+            .get(&uid)
+            .and_then(|acks| acks.chats.get(&chat_id).copied())
+    }
+
+    /// Forget the acks of users who have not acked anything for `ACK_TTL`.
+    fn prune_expired_acks(&self, now: Instant) {
+        self.acked
+            .retain(|_, acks| now.saturating_duration_since(acks.acked_at) < ACK_TTL);
+    }
+
+    /// Whether `uid`'s receipt for `message_id` in `chat_id` may go to every
+    /// member now, starting a new `READ_RECEIPT_INTERVAL` if so. Otherwise the
+    /// cursor is held until the interval ends.
+    pub fn claim_read_receipt(&self, chat_id: i64, uid: i32, message_id: i64) -> ReadReceiptClaim {
+        self.claim_read_receipt_at(chat_id, uid, message_id, Instant::now())
+    }
+
+    fn claim_read_receipt_at(
+        &self,
+        chat_id: i64,
+        uid: i32,
+        message_id: i64,
+        now: Instant,
+    ) -> ReadReceiptClaim {
+        let elapsed = |slot: &ReadReceiptSlot| now.saturating_duration_since(slot.sent_at);
+        if self.read_receipts.len() > CACHE_SWEEP_THRESHOLD {
+            self.read_receipts
+                .retain(|_, slot| slot.held.is_some() || elapsed(slot) < READ_RECEIPT_INTERVAL);
+        }
+        match self.read_receipts.entry((chat_id, uid)) {
+            dashmap::mapref::entry::Entry::Occupied(mut entry)
+                if elapsed(entry.get()) < READ_RECEIPT_INTERVAL =>
+            {
+                let remaining = READ_RECEIPT_INTERVAL - elapsed(entry.get());
+                let slot = entry.get_mut();
+                let first_held = slot.held.is_none();
+                slot.held = Some(slot.held.map_or(message_id, |held| held.max(message_id)));
+                if first_held {
+                    ReadReceiptClaim::FlushAfter(remaining)
+                } else {
+                    ReadReceiptClaim::Held
+                }
+            }
+            entry => {
+                entry.insert(ReadReceiptSlot {
+                    sent_at: now,
+                    held: None,
+                });
+                ReadReceiptClaim::Send
+            }
+        }
+    }
+
+    /// The cursor held back during `uid`'s interval in `chat_id`, if any. Taking
+    /// it counts as a send and starts a new interval.
+    pub fn take_held_read_receipt(&self, chat_id: i64, uid: i32) -> Option<i64> {
+        self.take_held_read_receipt_at(chat_id, uid, Instant::now())
+    }
+
+    fn take_held_read_receipt_at(&self, chat_id: i64, uid: i32, now: Instant) -> Option<i64> {
+        let mut slot = self.read_receipts.get_mut(&(chat_id, uid))?;
+        let held = slot.held.take()?;
+        slot.sent_at = now;
+        Some(held)
+    }
+
+    /// Whether the user has at least one live connection.
+    pub fn is_online(&self, uid: i32) -> bool {
+        self.inner.get(&uid).is_some_and(|vec| !vec.is_empty())
     }
 
-    /// Broadcast a JSON string to all connections for the given user ids. Each uid may have multiple connections.
-    /// Failures to send (e.g. full buffer) are logged but do not remove the connection here.
+    /// Broadcast a message to all connections for the given user ids. Each uid may have multiple connections.
+    /// The message is serialized once and every connection gets a handle to the same frame.
+    /// A connection whose buffer stays full for `MAX_CONSECUTIVE_FULL_SENDS` broadcasts is evicted.
     pub fn broadcast_to_uids(&self, uids: &[i32], message: Arc<ServerWsMessage>) {
+        self.broadcast_to_uids_except(uids, None, message);
+    }
+
+    /// `broadcast_to_uids`, minus the connections `except` names.
+    pub fn broadcast_to_uids_except(
+        &self,
+        uids: &[i32],
+        except: Option<EchoExclusion>,
+        message: Arc<ServerWsMessage>,
+    ) {
+        self.broadcast_where(uids, &message, except, |_| true);
+    }
+
+    /// Broadcast a chat-scoped event to the members' connections subscribed to `chat_id`.
+    pub fn broadcast_to_chat(
+        &self,
+        chat_id: i64,
+        member_uids: &[i32],
+        message: Arc<ServerWsMessage>,
+    ) {
+        self.broadcast_to_chat_except(chat_id, member_uids, None, message);
+    }
+
+    /// `broadcast_to_chat`, minus the connections `except` names.
+    pub fn broadcast_to_chat_except(
+        &self,
+        chat_id: i64,
+        member_uids: &[i32],
+        except: Option<EchoExclusion>,
+        message: Arc<ServerWsMessage>,
+    ) {
+        self.broadcast_where(member_uids, &message, except, |entry| {
+            entry.wants_chat(chat_id)
+        });
+    }
+
+    fn broadcast_where(
+        &self,
+        uids: &[i32],
+        message: &ServerWsMessage,
+        except: Option<EchoExclusion>,
+        wants: impl Fn(&ConnectionEntry) -> bool,
+    ) {
         let msg_type = message.message_type();
+        let Some(frame) = encode_frame(message) else {
+            return;
+        };
+        let mut slow: Vec<(i32, u64)> = Vec::new();
         for &uid in uids {
             if let Some(vec) = self.inner.get(&uid) {
-                for entry in vec.iter() {
-                    if entry.tx.try_send(message.clone()).is_err() {
+                let skipped = |entry: &ConnectionEntry| {
+                    except.is_some_and(|except| except.excludes(uid, entry))
+                };
+                for entry in vec.iter().filter(|entry| wants(entry) && !skipped(entry)) {
+                    let delivered = entry.tx.try_send(frame.clone()).is_ok();
+                    if delivered {
+                        self.metrics.record_ws_message_pushed(msg_type);
+                    } else {
                         tracing::warn!(
                             uid,
                             conn_id = entry.conn_id,
                             "ws broadcast try_send full, message dropped"
                         );
                         self.metrics.record_ws_message_dropped(msg_type);
-                    } else {
-                        self.metrics.record_ws_message_pushed(msg_type);
+                    }
+                    if entry.record_send(delivered) {
+                        slow.push((uid, entry.conn_id));
                     }
                 }
             }
         }
+        for (uid, conn_id) in slow {
+            self.evict_connection(uid, conn_id);
+        }
+    }
+
+    /// Drop a connection that stopped draining its buffer and tell its socket
+    /// task to close. The user's key stays in place even when emptied, so the
+    /// task's own `remove_connection` still reports the user going offline.
+    fn evict_connection(&self, uid: i32, conn_id: u64) {
+        let evicted = self.inner.get_mut(&uid).and_then(|mut vec| {
+            let pos = vec.iter().position(|e| e.conn_id == conn_id)?;
+            Some(vec.remove(pos))
+        });
+        let Some(entry) = evicted else {
+            return;
+        };
+        self.release_slots(1);
+        tracing::warn!(
+            uid,
+            conn_id,
+            "ws connection evicted after {MAX_CONSECUTIVE_FULL_SENDS} dropped broadcasts"
+        );
+        entry.signal_eviction(Eviction::TooFarBehind);
+        self.update_metrics();
+        self.broadcast_presence_to_user(uid);
+    }
+
+    /// Snapshot of each of the user's live connections, oldest first.
+    #[cfg_attr(not(feature = "debug-endpoints"), allow(dead_code))]
+    pub fn connection_stats(&self, uid: i32) -> Vec<ConnStat> {
+        let now = now_secs();
+        self.inner
+            .get(&uid)
+            .map(|vec| {
+                vec.iter()
+                    .map(|entry| {
+                        let last_send_at = entry.last_send_at.load(Ordering::Relaxed);
+                        ConnStat {
+                            conn_id: entry.conn_id,
+                            age_secs: now.saturating_sub(entry.connected_at),
+                            messages_sent: entry.messages_sent.load(Ordering::Relaxed),
+                            last_send_at: (last_send_at > 0).then_some(last_send_at),
+                            last_ping_at: entry.last_ping_at.load(Ordering::Relaxed),
+                            app_state: entry.app_state(),
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
     /// Returns true when at least one fresh connection is actively viewing the app.
@@ -153,9 +733,11 @@ impl ConnectionRegistry {
         })
     }
 
-    /// Remove connections that have not sent a ping in more than `max_age` seconds.
-    /// Call periodically (e.g. every 60s) from a background task.
-    pub fn prune_stale(&self, max_age_secs: u64) {
+    /// Remove connections that have not sent a ping in more than `max_age` seconds,
+    /// and acks past `ACK_TTL`. Call periodically (e.g. every 60s) from a
+    /// background task. Returns the users left without any live connection.
+    pub fn prune_stale(&self, max_age_secs: u64) -> Vec<i32> {
+        self.prune_expired_acks(Instant::now());
         let now = now_secs();
         let mut uids_to_trim: Vec<(i32, Vec<u64>)> = Vec::new();
         for ref_entry in self.inner.iter() {
@@ -172,13 +754,18 @@ impl ConnectionRegistry {
             }
         }
         let mut pruned_uids: Vec<i32> = Vec::new();
+        let mut offline_uids: Vec<i32> = Vec::new();
         for (uid, conn_ids) in uids_to_trim {
-            if let Some(mut vec) = self.inner.get_mut(&uid) {
+            let mut removed = 0;
+            let emptied = self.inner.remove_if_mut(&uid, |_, vec| {
+                let before = vec.len();
                 vec.retain(|e| !conn_ids.contains(&e.conn_id));
-                if vec.is_empty() {
-                    drop(vec);
-                    self.inner.remove(&uid);
-                }
+                removed = before - vec.len();
+                vec.is_empty()
+            });
+            self.release_slots(removed);
+            if emptied.is_some() {
+                offline_uids.push(uid);
             }
             pruned_uids.push(uid);
         }
@@ -186,17 +773,97 @@ impl ConnectionRegistry {
         for uid in pruned_uids {
             self.broadcast_presence_to_user(uid);
         }
+        offline_uids
+    }
+
+    /// Members of `chat_id`, from the cache while fresh and from `load` otherwise.
+    pub fn chat_members<E>(
+        &self,
+        chat_id: i64,
+        load: impl FnOnce() -> Result<Vec<ChatMember>, E>,
+    ) -> Result<Arc<[ChatMember]>, E> {
+        if let Some(cached) = self.members.get(&chat_id) {
+            if cached.cached_at.elapsed() < MEMBER_CACHE_TTL {
+                return Ok(cached.members.clone());
+            }
+        }
+
+        let generation = self.member_generation.load(Ordering::SeqCst);
+        let members: Arc<[ChatMember]> = load()?.into();
+        self.members.insert(
+            chat_id,
+            CachedMembers {
+                members: members.clone(),
+                cached_at: Instant::now(),
+            },
+        );
+        // An invalidation that ran during the load may have been for a change
+        // the load did not see; drop the entry rather than serve it.
+        if self.member_generation.load(Ordering::SeqCst) != generation {
+            self.members.remove(&chat_id);
+        }
+        if self.members.len() > CACHE_SWEEP_THRESHOLD {
+            self.members
+                .retain(|_, cached| cached.cached_at.elapsed() < MEMBER_CACHE_TTL);
+        }
+        Ok(members)
+    }
+
+    /// Forget the cached members of `chat_id`. Call after committing a change
+    /// to who is in the chat or to a member's mute.
+    pub fn invalidate_chat_members(&self, chat_id: i64) {
+        self.member_generation.fetch_add(1, Ordering::SeqCst);
+        self.members.remove(&chat_id);
+    }
+
+    /// Whether `uid` names an account, from the cache while the last answer is
+    /// within its TTL and from `load` otherwise.
+    pub fn user_exists<E>(
+        &self,
+        uid: i32,
+        load: impl FnOnce() -> Result<bool, E>,
+    ) -> Result<bool, E> {
+        let ttl = |exists: bool| {
+            if exists {
+                KNOWN_USER_TTL
+            } else {
+                UNKNOWN_USER_TTL
+            }
+        };
+        if let Some(cached) = self.known_users.get(&uid) {
+            let (exists, checked_at) = *cached;
+            if checked_at.elapsed() < ttl(exists) {
+                return Ok(exists);
+            }
+        }
+
+        let exists = load()?;
+        self.known_users.insert(uid, (exists, Instant::now()));
+        if self.known_users.len() > CACHE_SWEEP_THRESHOLD {
+            self.known_users
+                .retain(|_, (exists, checked_at)| checked_at.elapsed() < ttl(*exists));
+        }
+        Ok(exists)
+    }
+
+    /// Forget the cached lookup for `uid`. Call after creating an account.
+    pub fn forget_user(&self, uid: i32) {
+        self.known_users.remove(&uid);
     }
 
     /// Notify all of a user's connections about the current connection count.
     pub fn broadcast_presence_to_user(&self, uid: i32) {
         if let Some(vec) = self.inner.get(&uid) {
             let count = vec.len() as u32;
-            let msg = Arc::new(ServerWsMessage::PresenceUpdate(PresenceUpdatePayload {
-                active_connections: count,
-            }));
+            let Some(frame) =
+                encode_frame(&ServerWsMessage::PresenceUpdate(PresenceUpdatePayload {
+                    active_connections: count,
+                }))
+            else {
+                return;
+            };
             for entry in vec.iter() {
-                let _ = entry.tx.try_send(msg.clone());
+                let _ = entry.tx.try_send(frame.clone());
             }
         }
     }
@@ -218,12 +885,31 @@ impl ConnectionRegistry {
             }
         }
 
-        self.metrics.set_ws_connected_users(self.inner.len());
+        self.metrics.set_ws_connected_users(
+            self.inner
+                .iter()
+                .filter(|ref_entry| !ref_entry.is_empty())
+                .count(),
+        );
         self.metrics
             .set_ws_connection_states(active_connections, inactive_connections);
     }
 }
 
+pub(crate) fn encode_frame(message: &ServerWsMessage) -> Option<Utf8Bytes> {
+    match serde_json::to_string(message) {
+        Ok(text) => Some(text.into()),
+        Err(e) => {
+            tracing::error!(
+                msg_type = message.message_type(),
+                ?e,
+                "ws message serialization failed"
+            );
+            None
+        }
+    }
+}
+
 impl Default for ConnectionRegistry {
     fn default() -> Self {
         Self::new(Arc::new(Metrics::new()))
@@ -238,10 +924,49 @@ mod tests {
         ConnectionRegistry::new(Arc::new(Metrics::new()))
     }
 
+    #[test]
+    fn broadcast_except_skips_only_the_excluded_connections() {
+        let event = || {
+            Arc::new(ServerWsMessage::CatchUpComplete(
+                crate::handlers::ws::messages::CatchUpCompletePayload { truncated: false },
+            ))
+        };
+        let registry = registry();
+        let (origin, mut origin_rx, _) = registry.register(7);
+        let (_other_tab, mut other_tab_rx, _) = registry.register(7);
+        let (_peer, mut peer_rx, _) = registry.register(8);
+        // Registering queues presence frames; start from empty buffers.
+        for rx in [&mut origin_rx, &mut other_tab_rx, &mut peer_rx] {
+            while rx.try_recv().is_ok() {}
+        }
+
+        let except = EchoExclusion::Connection {
+            uid: 7,
+            conn_id: origin.conn_id,
+        };
+        registry.broadcast_to_uids_except(&[7, 8], Some(except), event());
+        assert!(origin_rx.try_recv().is_err());
+        assert!(other_tab_rx.try_recv().is_ok());
+        assert!(peer_rx.try_recv().is_ok());
+
+        registry.broadcast_to_uids_except(&[7, 8], Some(EchoExclusion::User(7)), event());
+        assert!(origin_rx.try_recv().is_err());
+        assert!(other_tab_rx.try_recv().is_err());
+        assert!(peer_rx.try_recv().is_ok());
+
+        // A connection id only excludes a socket of the user it was issued to.
+        let foreign = EchoExclusion::Connection {
+            uid: 8,
+            conn_id: origin.conn_id,
+        };
+        registry.broadcast_to_uids_except(&[7], Some(foreign), event());
+        assert!(origin_rx.try_recv().is_ok());
+    }
+
     #[test]
     fn suppresses_push_for_fresh_active_connection() {
         let registry = registry();
-        let (entry, _rx) = registry.register(7);
+        let (entry, _rx, _) = registry.register(7);
         entry.update_ping(AppPresenceState::Active);
 
         assert!(registry.should_suppress_push(7, 30));
@@ -250,7 +975,7 @@ mod tests {
     #[test]
     fn does_not_suppress_push_for_inactive_connection() {
         let registry = registry();
-        let (entry, _rx) = registry.register(7);
+        let (entry, _rx, _) = registry.register(7);
         entry.update_app_state(AppPresenceState::Inactive);
 
         assert!(!registry.should_suppress_push(7, 30));
@@ -259,7 +984,7 @@ mod tests {
     #[test]
     fn does_not_suppress_push_for_stale_connection() {
         let registry = registry();
-        let (entry, _rx) = registry.register(7);
+        let (entry, _rx, _) = registry.register(7);
         entry.update_ping(AppPresenceState::Active);
         entry
             .last_ping_at
@@ -271,11 +996,449 @@ mod tests {
     #[test]
     fn suppresses_push_when_any_connection_is_active() {
         let registry = registry();
-        let (inactive_entry, _rx1) = registry.register(7);
+        let (inactive_entry, _rx1, _) = registry.register(7);
         inactive_entry.update_app_state(AppPresenceState::Inactive);
-        let (active_entry, _rx2) = registry.register(7);
+        let (active_entry, _rx2, _) = registry.register(7);
         active_entry.update_ping(AppPresenceState::Active);
 
         assert!(registry.should_suppress_push(7, 30));
     }
+
+    #[test]
+    fn presence_transitions_only_on_first_and_last_connection() {
+        let registry = registry();
+        let (first, _rx1, first_online) = registry.register(7);
+        let (second, _rx2, second_online) = registry.register(7);
+        assert!(first_online);
+        assert!(!second_online);
+        assert!(registry.is_online(7));
+
+        assert!(!registry.remove_connection(7, first.conn_id));
+        assert!(registry.is_online(7));
+        assert!(registry.remove_connection(7, second.conn_id));
+        assert!(!registry.is_online(7));
+    }
+
+    #[test]
+    fn broadcast_shares_one_encoded_frame_across_connections() {
+        let registry = registry();
+        let uids: Vec<i32> = (0..1000).collect();
+        let mut receivers: Vec<_> = uids
+            .iter()
+            .map(|&uid| {
+                let (_entry, mut rx, _) = registry.register(uid);
+                // Drain the connection-count frame sent on register.
+                rx.try_recv().expect("presence frame on register");
+                rx
+            })
+            .collect();
+
+        registry.broadcast_to_uids(
+            &uids,
+            Arc::new(ServerWsMessage::PresenceUpdate(PresenceUpdatePayload {
+                active_connections: 42,
+            })),
+        );
+
+        let frames: Vec<Utf8Bytes> = receivers
+            .iter_mut()
+            .map(|rx| rx.try_recv().expect("broadcast frame"))
+            .collect();
+        let first = frames[0].as_str();
+        assert!(first.contains("\"activeConnections\":42"));
+        assert!(frames
+            .iter()
+            .all(|frame| std::ptr::eq(frame.as_str().as_ptr(), first.as_ptr())));
+    }
+
+    #[test]
+    fn chat_broadcast_skips_connections_subscribed_elsewhere() {
+        let registry = registry();
+        let (legacy, mut legacy_rx, _) = registry.register(7);
+        let (viewing, mut viewing_rx, _) = registry.register(7);
+        let (other, mut other_rx, _) = registry.register(7);
+        viewing.subscribe(10);
+        other.subscribe(20);
+        legacy.unsubscribe(10);
+        for rx in [&mut legacy_rx, &mut viewing_rx, &mut other_rx] {
+            while rx.try_recv().is_ok() {}
+        }
+
+        let event = || {
+            Arc::new(ServerWsMessage::PresenceUpdate(PresenceUpdatePayload {
+                active_connections: 3,
+            }))
+        };
+        registry.broadcast_to_chat(10, &[7], event());
+        assert!(legacy_rx.try_recv().is_ok());
+        assert!(viewing_rx.try_recv().is_ok());
+        assert!(other_rx.try_recv().is_err());
+
+        viewing.unsubscribe(10);
+        registry.broadcast_to_chat(10, &[7], event());
+        assert!(viewing_rx.try_recv().is_err());
+
+        registry.broadcast_to_uids(&[7], event());
+        assert!(other_rx.try_recv().is_ok());
+    }
+
+    fn presence_event() -> Arc<ServerWsMessage> {
+        Arc::new(ServerWsMessage::PresenceUpdate(PresenceUpdatePayload {
+            active_connections: 1,
+        }))
+    }
+
+    #[tokio::test]
+    async fn connection_that_never_drains_is_evicted() {
+        let registry = registry();
+        let (stuck, mut stuck_rx, _) = registry.register(7);
+        let (_other_tab, mut other_rx, _) = registry.register(7);
+        while stuck_rx.try_recv().is_ok() {}
+
+        // The first broadcasts fill the buffer; only misses count.
+        for _ in 0..DEFAULT_CONNECTION_BUFFER {
+            registry.broadcast_to_uids(&[7], presence_event());
+            while other_rx.try_recv().is_ok() {}
+        }
+        for _ in 0..MAX_CONSECUTIVE_FULL_SENDS - 1 {
+            registry.broadcast_to_uids(&[7], presence_event());
+        }
+        assert_eq!(registry.inner.get(&7).unwrap().len(), 2);
+
+        registry.broadcast_to_uids(&[7], presence_event());
+        let remaining = registry.inner.get(&7).unwrap().clone();
+        assert_eq!(remaining.len(), 1);
+        assert_ne!(remaining[0].conn_id, stuck.conn_id);
+        tokio::time::timeout(std::time::Duration::from_secs(1), stuck.evicted())
+            .await
+            .expect("socket task is told to close");
+
+        // The socket task's own cleanup still reports the user's status.
+        assert!(!registry.remove_connection(7, stuck.conn_id));
+        assert!(registry.is_online(7));
+    }
+
+    #[test]
+    fn delivered_broadcast_resets_the_full_count() {
+        let registry = registry();
+        let (entry, _rx, _) = registry.register(7);
+
+        for _ in 0..MAX_CONSECUTIVE_FULL_SENDS - 1 {
+            assert!(!entry.record_send(false));
+        }
+        assert!(!entry.record_send(true));
+        for _ in 0..MAX_CONSECUTIVE_FULL_SENDS - 1 {
+            assert!(!entry.record_send(false));
+        }
+        assert!(entry.record_send(false));
+    }
+
+    #[test]
+    fn evicting_the_last_connection_leaves_offline_to_the_socket_task() {
+        let registry = registry();
+        let (entry, _rx, _) = registry.register(7);
+
+        registry.evict_connection(7, entry.conn_id);
+        assert!(!registry.is_online(7));
+        assert!(registry.remove_connection(7, entry.conn_id));
+    }
+
+    #[test]
+    fn rejects_connections_past_the_limit() {
+        let registry = registry().with_connection_limit(2, ConnectionLimitPolicy::Reject);
+        let (first, _rx1, _) = registry.register(7);
+        let (_second, _rx2, _) = registry.register(7);
+
+        assert_eq!(registry.try_register(7).err(), Some(ConnectionLimitReached));
+        assert_eq!(registry.connection_stats(7).len(), 2);
+        // Other users are unaffected, and a freed slot can be reused.
+        assert!(registry.try_register(8).is_ok());
+        registry.remove_connection(7, first.conn_id);
+        assert!(registry.try_register(7).is_ok());
+    }
+
+    #[tokio::test]
+    async fn evicts_oldest_connection_past_the_limit() {
+        let registry = registry().with_connection_limit(2, ConnectionLimitPolicy::EvictOldest);
+        let (oldest, _rx1, _) = registry.register(7);
+        let (second, _rx2, _) = registry.register(7);
+        let (newest, _rx3, came_online) = registry.register(7);
+
+        assert!(!came_online);
+        let reason = tokio::time::timeout(std::time::Duration::from_secs(1), oldest.evicted())
+            .await
+            .expect("oldest connection is told to close");
+        assert_eq!(reason, Eviction::ConnectionLimit);
+        let live: Vec<u64> = registry
+            .connection_stats(7)
+            .iter()
+            .map(|stat| stat.conn_id)
+            .collect();
+        assert_eq!(live, vec![second.conn_id, newest.conn_id]);
+    }
+
+    #[test]
+    fn total_connection_limit_rejects_until_a_slot_frees() {
+        let registry = registry().with_total_connection_limit(2);
+        let (first, _rx1, _) = registry.register(7);
+        let (_second, _rx2, _) = registry.register(8);
+
+        assert_eq!(registry.total_connections(), 2);
+        assert!(!registry.has_capacity());
+        assert!(matches!(
+            registry.try_register(9),
+            Err(ConnectionLimitReached)
+        ));
+        assert_eq!(registry.total_connections(), 2);
+
+        registry.remove_connection(7, first.conn_id);
+        assert_eq!(registry.total_connections(), 1);
+        assert!(registry.has_capacity());
+        assert!(registry.try_register(9).is_ok());
+    }
+
+    #[test]
+    fn total_connections_tracks_every_way_a_connection_leaves() {
+        let registry = registry().with_connection_limit(1, ConnectionLimitPolicy::EvictOldest);
+        assert!(registry.has_capacity());
+        let (_displaced, _rx1, _) = registry.register(7);
+        // Displacing the oldest connection swaps it for the new one.
+        let (current, _rx2, _) = registry.register(7);
+        assert_eq!(registry.total_connections(), 1);
+
+        let (stale, _rx3, _) = registry.register(8);
+        stale.last_ping_at.store(0, Ordering::Relaxed);
+        let (slow, _rx4, _) = registry.register(9);
+        assert_eq!(registry.total_connections(), 3);
+
+        registry.prune_stale(300);
+        assert_eq!(registry.total_connections(), 2);
+        registry.evict_connection(9, slow.conn_id);
+        assert_eq!(registry.total_connections(), 1);
+        // Closing an already-removed socket must not count it twice.
+        registry.remove_connection(9, slow.conn_id);
+        registry.remove_connection(7, current.conn_id);
+        assert_eq!(registry.total_connections(), 0);
+    }
+
+    #[test]
+    fn connection_limit_policy_parses_config_values() {
+        assert_eq!(
+            ConnectionLimitPolicy::parse("reject"),
+            Some(ConnectionLimitPolicy::Reject)
+        );
+        assert_eq!(
+            ConnectionLimitPolicy::parse("evict_oldest"),
+            Some(ConnectionLimitPolicy::EvictOldest)
+        );
+        assert_eq!(ConnectionLimitPolicy::parse("evict"), None);
+    }
+
+    #[test]
+    fn acks_only_move_forward_per_chat() {
+        let registry = registry();
+        assert_eq!(registry.acked_up_to(7, 10), None);
+
+        registry.record_ack(7, 10, 500);
+        registry.record_ack(7, 10, 400);
+        registry.record_ack(7, 20, 100);
+        assert_eq!(registry.acked_up_to(7, 10), Some(500));
+        assert_eq!(registry.acked_up_to(7, 20), Some(100));
+        assert_eq!(registry.acked_up_to(8, 10), None);
+    }
+
+    #[test]
+    fn acks_expire_a_ttl_after_the_last_one() {
+        let registry = registry();
+        registry.record_ack(7, 10, 500);
+        registry.record_ack(8, 10, 600);
+        let acked_at = registry.acked.get(&7).unwrap().acked_at;
+
+        registry.prune_expired_acks(acked_at + ACK_TTL - Duration::from_secs(1));
+        assert_eq!(registry.acked_up_to(7, 10), Some(500));
+
+        registry.acked.get_mut(&8).unwrap().acked_at = acked_at + ACK_TTL;
+        registry.prune_expired_acks(acked_at + ACK_TTL);
+        assert_eq!(registry.acked_up_to(7, 10), None);
+        assert_eq!(registry.acked_up_to(8, 10), Some(600));
+    }
+
+    #[test]
+    fn read_receipts_reach_the_chat_once_per_interval() {
+        let registry = registry();
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+
+        assert_eq!(
+            registry.claim_read_receipt_at(10, 7, 1, start),
+            ReadReceiptClaim::Send
+        );
+        assert_eq!(
+            registry.claim_read_receipt_at(10, 7, 2, start + second),
+            ReadReceiptClaim::FlushAfter(READ_RECEIPT_INTERVAL - second)
+        );
+        assert_eq!(
+            registry.claim_read_receipt_at(10, 7, 3, start + second * 2),
+            ReadReceiptClaim::Held
+        );
+        assert_eq!(
+            registry.claim_read_receipt_at(10, 8, 1, start),
+            ReadReceiptClaim::Send
+        );
+        assert_eq!(
+            registry.claim_read_receipt_at(11, 7, 1, start),
+            ReadReceiptClaim::Send
+        );
+
+        let flushed_at = start + READ_RECEIPT_INTERVAL;
+        assert_eq!(
+            registry.take_held_read_receipt_at(10, 7, flushed_at),
+            Some(3)
+        );
+        assert_eq!(registry.take_held_read_receipt_at(10, 7, flushed_at), None);
+        assert_eq!(registry.take_held_read_receipt_at(10, 8, flushed_at), None);
+        // The flush starts a new interval.
+        assert_eq!(
+            registry.claim_read_receipt_at(10, 7, 4, flushed_at + second),
+            ReadReceiptClaim::FlushAfter(READ_RECEIPT_INTERVAL - second)
+        );
+        assert_eq!(
+            registry.claim_read_receipt_at(10, 8, 2, start + READ_RECEIPT_INTERVAL),
+            ReadReceiptClaim::Send
+        );
+    }
+
+    #[test]
+    fn prune_stale_reports_users_left_offline() {
+        let registry = registry();
+        let (stale, _rx1, _) = registry.register(7);
+        stale.last_ping_at.store(0, Ordering::Relaxed);
+        let (stale_tab, _rx2, _) = registry.register(8);
+        stale_tab.last_ping_at.store(0, Ordering::Relaxed);
+        let (_fresh_tab, _rx3, _) = registry.register(8);
+
+        assert_eq!(registry.prune_stale(300), vec![7]);
+        assert!(!registry.is_online(7));
+        assert!(registry.is_online(8));
+    }
+
+    #[test]
+    fn connection_stats_start_empty_and_count_sent_frames() {
+        let registry = registry();
+        assert!(registry.connection_stats(7).is_empty());
+        let (entry, _rx, _) = registry.register(7);
+
+        let stats = registry.connection_stats(7);
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].conn_id, entry.conn_id);
+        assert_eq!(stats[0].messages_sent, 0);
+        assert_eq!(stats[0].last_send_at, None);
+
+        entry.record_frame_sent();
+        entry.record_frame_sent();
+        let stats = registry.connection_stats(7);
+        assert_eq!(stats[0].messages_sent, 2);
+        assert!(stats[0]
+            .last_send_at
+            .is_some_and(|at| at >= stats[0].last_ping_at));
+    }
+
+    #[test]
+    fn buffer_size_bounds_simultaneous_broadcasts() {
+        for (buffer_size, expected_frames) in [(1, 1), (4, 2)] {
+            let registry = ConnectionRegistry::with_buffer(Arc::new(Metrics::new()), buffer_size);
+            let (_entry, mut rx, _) = registry.register(7);
+            while rx.try_recv().is_ok() {}
+
+            registry.broadcast_to_uids(&[7], presence_event());
+            registry.broadcast_to_uids(&[7], presence_event());
+
+            let mut received = 0;
+            while rx.try_recv().is_ok() {
+                received += 1;
+            }
+            assert_eq!(received, expected_frames, "buffer of {buffer_size}");
+        }
+    }
+
+    fn load_members(uids: &[i32]) -> impl FnOnce() -> Result<Vec<ChatMember>, ()> + '_ {
+        move || Ok(uids.iter().map(|uid| (*uid, None)).collect())
+    }
+
+    #[test]
+    fn chat_members_are_cached_until_invalidated() {
+        let registry = registry();
+        let first = registry.chat_members(1, load_members(&[1, 2])).unwrap();
+        assert_eq!(first.len(), 2);
+
+        // A fresh entry is served without calling the loader.
+        let cached = registry
+            .chat_members(1, || -> Result<Vec<ChatMember>, ()> {
+                panic!("cache miss")
+            })
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &cached));
+        assert_eq!(
+            registry.chat_members(2, load_members(&[9])).unwrap().len(),
+            1
+        );
+
+        registry.invalidate_chat_members(1);
+        let reloaded = registry.chat_members(1, load_members(&[1, 2, 3])).unwrap();
+        assert_eq!(reloaded.len(), 3);
+        // Other chats keep their entries.
+        let other = registry
+            .chat_members(2, || -> Result<Vec<ChatMember>, ()> {
+                panic!("cache miss")
+            })
+            .unwrap();
+        assert_eq!(other.len(), 1);
+    }
+
+    #[test]
+    fn members_loaded_across_an_invalidation_are_not_cached() {
+        let registry = registry();
+        let during = registry
+            .chat_members(1, || {
+                // The membership changes and is invalidated mid-load, so
+                // what this load read may already be out of date.
+                registry.invalidate_chat_members(1);
+                load_members(&[1])()
+            })
+            .unwrap();
+        assert_eq!(during.len(), 1);
+
+        let after = registry.chat_members(1, load_members(&[1, 2])).unwrap();
+        assert_eq!(after.len(), 2);
+    }
+
+    #[test]
+    fn failed_member_load_is_not_cached() {
+        let registry = registry();
+        assert!(registry
+            .chat_members(1, || -> Result<Vec<ChatMember>, ()> { Err(()) })
+            .is_err());
+        assert_eq!(
+            registry.chat_members(1, load_members(&[4])).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn user_lookups_are_cached_both_ways_until_forgotten() {
+        let registry = registry();
+        let unexpected = || -> Result<bool, ()> { panic!("cache miss") };
+
+        assert_eq!(registry.user_exists(1, || Ok::<_, ()>(true)), Ok(true));
+        assert_eq!(registry.user_exists(2, || Ok::<_, ()>(false)), Ok(false));
+        assert_eq!(registry.user_exists(1, unexpected), Ok(true));
+        assert_eq!(registry.user_exists(2, unexpected), Ok(false));
+
+        registry.forget_user(2);
+        assert_eq!(registry.user_exists(2, || Ok::<_, ()>(true)), Ok(true));
+
+        // Failed lookups are retried rather than remembered.
+        assert_eq!(registry.user_exists(3, || Err(())), Err(()));
+        assert_eq!(registry.user_exists(3, || Ok::<_, ()>(true)), Ok(true));
+    }
 }
@@ -0,0 +1,379 @@
+//! Integration test harness: the real router and `AppState` over the database
+//! named by `TEST_DATABASE_URL`, with each test confined to a transaction that
+//! is rolled back when its `TestApp` drops.
+//!
+//! Tests call `TestApp::start()` and return early on `None`, so `cargo test`
+//! still passes on machines without Postgres.
+
+use std::sync::{Arc, Once};
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool, PooledConnection};
+use diesel::PgConnection;
+use diesel_migrations::MigrationHarness;
+use tower::ServiceExt;
+
+use crate::models::{
+    GroupJoinReason, GroupRole, NewGroup, NewGroupMembership, NewPolicy, NewPolicyAssignment,
+    NewPolicyPermission, PermissionResourceType, PolicySubjectType,
+};
+use crate::schema::{
+    discuz::discuz::common_member, group_membership, groups, policies, policy_assignments,
+    policy_permissions,
+};
+use crate::services::authz::Action;
+use crate::utils::{auth::X_USER_ID, ids};
+use crate::{handlers, services, utils, AppState, AuthMethod};
+
+pub const TEST_DATABASE_URL_ENV: &str = "TEST_DATABASE_URL";
+
+/// How long a request waits for the single pooled connection. Background
+/// services share it with handlers, so waits are short but do happen.
+const POOL_TIMEOUT: Duration = Duration::from_secs(10);
+
+const MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+static MIGRATIONS: Once = Once::new();
+
+/// Opens a transaction that is never committed on every connection the pool
+/// hands out, so a test's writes vanish with the connection.
+#[derive(Debug)]
+struct TestTransaction;
+
+impl CustomizeConnection<PgConnection, diesel::r2d2::Error> for TestTransaction {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.begin_test_transaction()
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+pub struct TestApp {
+    pub state: AppState,
+    router: Router,
+}
+
+impl TestApp {
+    /// The app over `TEST_DATABASE_URL`, or `None` (with a note on stderr)
+    /// when the variable is unset.
+    ///
+    /// The pool holds exactly one connection, inside one test transaction, so
+    /// everything the test seeds and every request it sends see the same
+    /// uncommitted data. Run these tests on a multi-threaded runtime: the
+    /// pool blocks while another task holds the connection.
+    pub async fn start() -> Option<Self> {
+        let Ok(url) = std::env::var(TEST_DATABASE_URL_ENV) else {
+            eprintln!("{TEST_DATABASE_URL_ENV} is not set, skipping database test");
+            return None;
+        };
+        MIGRATIONS.call_once(|| {
+            let mut conn =
+                PgConnection::establish(&url).expect("connect to TEST_DATABASE_URL to migrate");
+            conn.run_pending_migrations(crate::MIGRATIONS)
+                .expect("run migrations on the test database");
+        });
+
+        let db = Pool::builder()
+            .max_size(1)
+            .connection_timeout(POOL_TIMEOUT)
+            // A recycled connection would silently drop the test transaction.
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connection_customizer(Box::new(TestTransaction))
+            .build(ConnectionManager::new(url))
+            .expect("connect to TEST_DATABASE_URL");
+
+        let state = test_state(db);
+        let (api_router, _) = handlers::api_router().split_for_parts();
+        let router = crate::build_app(
+            state.clone(),
+            api_router,
+            crate::DEFAULT_MAX_JSON_BODY_BYTES as usize,
+        );
+        Some(Self { state, router })
+    }
+
+    /// The test's connection, for seeding and assertions. Drop it before the
+    /// next request, which needs the same connection.
+    pub fn conn(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.state.db.get().expect("test connection")
+    }
+
+    /// Send a request as `uid` and return the status and JSON body (`Null`
+    /// when the body is empty).
+    ///
+    /// The app runs with `AuthMethod::UIDHeader`, so `X-User-Id` stands in
+    /// for a session; tests that exercise JWT or Discuz auth build their own
+    /// headers with `request_with`.
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        uid: i32,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(X_USER_ID, uid.to_string());
+        let request = match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        }
+        .expect("valid test request");
+        self.request_with(request).await
+    }
+
+    pub async fn request_with(&self, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("router is infallible");
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), MAX_RESPONSE_BYTES)
+            .await
+            .expect("read response body");
+        let body = if bytes.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_slice(&bytes).unwrap_or_else(|_| {
+                serde_json::Value::String(String::from_utf8_lossy(&bytes).into_owned())
+            })
+        };
+        (status, body)
+    }
+
+    /// Insert a forum account for `uid`; chats and profiles read names from it.
+    /// Tests run in parallel, and a second open transaction inserting the same
+    /// uid waits for the first to end, so give each test its own uids.
+    pub fn seed_user(&self, uid: i32) {
+        use common_member::dsl as cm_dsl;
+
+        diesel::insert_into(common_member::table)
+            .values((
+                cm_dsl::uid.eq(uid),
+                cm_dsl::username.eq(format!("user{uid}")),
+                cm_dsl::email.eq(format!("user{uid}@example.com")),
+            ))
+            .execute(&mut self.conn())
+            .expect("seed user");
+    }
+
+    /// Insert a public group chat with no members and return its id.
+    pub async fn seed_chat(&self, name: &str) -> i64 {
+        let id = ids::next_gid(&self.state.id_gen).await.expect("chat id");
+        diesel::insert_into(groups::table)
+            .values(&NewGroup::new(id, name.to_string(), Utc::now()))
+            .execute(&mut self.conn())
+            .expect("seed chat");
+        id
+    }
+
+    pub fn seed_membership(&self, chat_id: i64, uid: i32, role: GroupRole) {
+        diesel::insert_into(group_membership::table)
+            .values(&NewGroupMembership {
+                chat_id,
+                uid,
+                role,
+                joined_at: Utc::now(),
+                join_reason: GroupJoinReason::Other,
+                join_reason_extra: None,
+            })
+            .execute(&mut self.conn())
+            .expect("seed membership");
+    }
+
+    /// Grant `uid` a global permission through a policy of its own.
+    pub async fn grant(&self, uid: i32, action: Action) {
+        let gen = &self.state.id_gen;
+        let policy_id = ids::next_id(gen).await.expect("policy id");
+        let now = Utc::now();
+        let conn = &mut self.conn();
+        diesel::insert_into(policies::table)
+            .values(&NewPolicy {
+                id: policy_id,
+                name: format!("test-{policy_id}"),
+                metadata: serde_json::json!({}),
+                created_at: now,
+                updated_at: now,
+            })
+            .execute(conn)
+            .expect("seed policy");
+        diesel::insert_into(policy_permissions::table)
+            .values(&NewPolicyPermission {
+                id: policy_id,
+                policy_id,
+                action: action.as_str().to_string(),
+                resource_type: PermissionResourceType::Global,
+                resource_id: None,
+                created_at: now,
+            })
+            .execute(conn)
+            .expect("seed policy permission");
+        diesel::insert_into(policy_assignments::table)
+            .values(&NewPolicyAssignment {
+                id: policy_id,
+                subject_type: PolicySubjectType::User,
+                subject_id: i64::from(uid),
+                policy_id,
+                created_at: now,
+                updated_at: now,
+            })
+            .execute(conn)
+            .expect("seed policy assignment");
+    }
+}
+
+/// `AppState` as `main` builds it with an empty environment, minus anything
+/// that would reach outside the process: push has no real keys and the S3
+/// client points nowhere.
+fn test_state(db: Pool<ConnectionManager<PgConnection>>) -> AppState {
+    let metrics = Arc::new(crate::metrics::Metrics::new());
+    let ws_registry = Arc::new(services::ws_registry::ConnectionRegistry::new(
+        metrics.clone(),
+    ));
+    let s3_client = aws_sdk_s3::Client::from_conf(
+        aws_sdk_s3::Config::builder()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new("us-east-1"))
+            .build(),
+    );
+    let s3_bucket_name = "test-bucket".to_string();
+
+    AppState {
+        db: db.clone(),
+        id_gen: Arc::new(utils::ids::new_generator()),
+        metrics: metrics.clone(),
+        authz_service: services::authz::AuthorizationService::start(),
+        ws_registry: ws_registry.clone(),
+        ws_keepalive: handlers::ws::Keepalive::from_env(),
+        ws_upgrade_policy: Arc::new(handlers::ws::UpgradePolicy::from_env(None)),
+        push_service: services::push::PushService::start_for_tests(
+            db.clone(),
+            ws_registry.clone(),
+            metrics.clone(),
+        ),
+        client_tracking: services::client_tracking::ClientTrackingService::start(
+            db.clone(),
+            metrics.clone(),
+        ),
+        background_service: services::background::BackgroundService::start(
+            db.clone(),
+            ws_registry,
+            metrics.clone(),
+        ),
+        // Webhook tests deliver to mock servers on loopback.
+        webhook_service: services::webhooks::WebhookService::new(
+            services::webhooks::RetryPolicy::default(),
+            services::webhooks::DEFAULT_DISABLE_AFTER_FAILURES,
+            services::webhooks::DestinationPolicy::new(["127.0.0.1"]),
+        ),
+        keyword_filter: Arc::new(utils::moderation::KeywordFilter::default()),
+        message_rate_limiter: Arc::new(utils::rate_limit::RateLimiter::messages_from_env()),
+        attachment_storage: Arc::new(services::media::S3AttachmentStorage {
+            client: s3_client.clone(),
+            bucket: s3_bucket_name.clone(),
+        }),
+        s3_client,
+        s3_bucket_name,
+        s3_attachment_prefix: "attachments".to_string(),
+        s3_base_url: None,
+        max_attachment_size_bytes: crate::DEFAULT_MAX_ATTACHMENT_SIZE_BYTES,
+        max_message_length: crate::DEFAULT_MAX_MESSAGE_LENGTH as usize,
+        message_restore_window: chrono::Duration::seconds(
+            crate::DEFAULT_MESSAGE_RESTORE_WINDOW_SECS.into(),
+        ),
+        max_members_per_chat: crate::DEFAULT_MAX_MEMBERS_PER_CHAT.into(),
+        page_limits: utils::pagination::PageLimits::default(),
+        auth_method: AuthMethod::UIDHeader,
+        discuz_cookie_prefix: String::new(),
+        discuz_authkey: String::new(),
+        discuz_avatar_public_url: None,
+        discuz_avatar_path: None,
+        jwt_signing_key: vec![7; 32],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn created_chat_lists_the_posted_message() {
+        let Some(app) = TestApp::start().await else {
+            return;
+        };
+        let uid = 900_001;
+        app.seed_user(uid);
+        app.grant(uid, Action::ChatCreate).await;
+
+        let (status, chat) = app
+            .request(
+                Method::POST,
+                "/group",
+                uid,
+                Some(serde_json::json!({ "name": "Harness" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{chat}");
+        let chat_id = chat["id"].as_str().expect("chat id");
+
+        let (status, sent) = app
+            .request(
+                Method::POST,
+                &format!("/chats/{chat_id}/messages"),
+                uid,
+                Some(serde_json::json!({
+                    "message": "hello from the harness",
+                    "messageType": "text",
+                    "clientGeneratedId": "harness-1",
+                })),
+            )
+            .await;
+        assert!(status.is_success(), "{status}: {sent}");
+
+        let (status, listed) = app
+            .request(
+                Method::GET,
+                &format!("/chats/{chat_id}/messages"),
+                uid,
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{listed}");
+        let messages = listed["messages"].as_array().expect("messages");
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["id"], sent["id"]);
+        assert_eq!(messages[0]["message"], "hello from the harness");
+        assert_eq!(messages[0]["sender"]["uid"], uid);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn seeded_rows_do_not_outlive_the_test() {
+        let Some(app) = TestApp::start().await else {
+            return;
+        };
+        let chat_id = app.seed_chat("Rolled back").await;
+        app.seed_user(900_002);
+        app.seed_membership(chat_id, 900_002, GroupRole::Member);
+        drop(app);
+
+        let Some(app) = TestApp::start().await else {
+            return;
+        };
+        let remaining: i64 = groups::table
+            .filter(groups::id.eq(chat_id))
+            .count()
+            .get_result(&mut app.conn())
+            .expect("count chats");
+        assert_eq!(remaining, 0);
+    }
+}
@@ -11,6 +11,8 @@ use rc4::{consts::U64, Key, KeyInit, Rc4, StreamCipher};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use crate::errors::AppError;
+
 pub const X_USER_ID: &str = "x-user-id";
 pub const X_CLIENT_ID: &str = "x-client-id";
 pub const X_APP_VERSION: &str = "x-app-version";
@@ -39,6 +41,9 @@ pub struct AuthClaims {
     pub uid: i32,
     pub cid: String,
     pub gen: i32,
+    /// Expiry as a unix timestamp. Tokens without it never expire.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
 }
 
 impl fmt::Display for CurrentUid {
@@ -63,7 +68,7 @@ enum DiscuzCipherError {
 
 fn jwt_validation() -> Validation {
     let mut validation = Validation::default();
-    validation.validate_exp = false;
+    // `exp` is optional, but enforced whenever a token carries it.
     validation.required_spec_claims.clear();
     validation
 }
@@ -298,25 +303,25 @@ pub fn required_client_id(headers: &HeaderMap) -> Result<String, (StatusCode, &'
 }
 
 impl FromRequestParts<crate::AppState> for CurrentUid {
-    type Rejection = (StatusCode, &'static str);
+    type Rejection = AppError;
 
     async fn from_request_parts(
         parts: &mut Parts,
         state: &crate::AppState,
     ) -> Result<Self, Self::Rejection> {
-        extract_current_uid(&parts.headers, state).map(CurrentUid)
+        Ok(CurrentUid(extract_current_uid(&parts.headers, state)?))
     }
 }
 
 impl FromRequestParts<crate::AppState> for ClientId {
-    type Rejection = (StatusCode, &'static str);
+    type Rejection = AppError;
 
     async fn from_request_parts(
         parts: &mut Parts,
         state: &crate::AppState,
     ) -> Result<Self, Self::Rejection> {
         resolve_client_id(&parts.headers, state)?
-            .ok_or((StatusCode::BAD_REQUEST, "Missing X-Client-Id header"))
+            .ok_or(AppError::BadRequest("Missing X-Client-Id header"))
             .map(ClientId)
     }
 }
@@ -345,6 +350,7 @@ mod tests {
             uid: 42,
             cid: "client_123".to_string(),
             gen: 0,
+            exp: None,
         };
 
         let token = encode_auth_token(&claims, b"01234567890123456789012345678901").unwrap();
@@ -359,6 +365,7 @@ mod tests {
             uid: 42,
             cid: "client_123".to_string(),
             gen: 0,
+            exp: None,
         };
 
         let token = encode_auth_token(&claims, b"01234567890123456789012345678901").unwrap();
@@ -382,4 +389,22 @@ mod tests {
             Err((StatusCode::BAD_REQUEST, "X-Client-Id is invalid"))
         );
     }
+
+    #[test]
+    fn auth_token_rejects_expired_token() {
+        let claims = AuthClaims {
+            uid: 42,
+            cid: "client_123".to_string(),
+            gen: 0,
+            exp: Some(jsonwebtoken::get_current_timestamp() - 3600),
+        };
+
+        let token = encode_auth_token(&claims, b"01234567890123456789012345678901").unwrap();
+        let result = decode_auth_token(&token, b"01234567890123456789012345678901");
+
+        assert_eq!(
+            result,
+            Err((StatusCode::UNAUTHORIZED, "Invalid auth token"))
+        );
+    }
 }
@@ -1,7 +1,10 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
 use ferroid::{
     define_snowflake_id,
     futures::SnowflakeGeneratorAsyncTokioExt,
-    generator::LockSnowflakeGenerator,
+    generator::{LockSnowflakeGenerator, Poll},
     time::{MonotonicClock, UNIX_EPOCH},
 };
 
@@ -55,3 +58,121 @@ pub async fn next_gid(gen: &IdGen) -> Result<i64, ferroid::generator::Error> {
 pub async fn next_message_id(gen: &IdGen) -> Result<i64, ferroid::generator::Error> {
     next_id(gen).await
 }
+
+/// Generate `n` strictly increasing ids in one call, for inserts of many rows.
+/// Only sleeps when a millisecond's sequence runs out, instead of awaiting per id.
+pub async fn next_ids(gen: &IdGen, n: usize) -> Result<Vec<i64>, ferroid::generator::Error> {
+    let mut ids = Vec::with_capacity(n);
+    while ids.len() < n {
+        match gen.try_poll_id()? {
+            Poll::Ready { id } => ids.push(id.to_raw() as i64),
+            Poll::Pending { yield_for } => {
+                tokio::time::sleep(Duration::from_millis(yield_for)).await
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// Generate `n` message (or attachment) ids; see [`next_ids`].
+pub async fn next_message_ids(
+    gen: &IdGen,
+    n: usize,
+) -> Result<Vec<i64>, ferroid::generator::Error> {
+    next_ids(gen, n).await
+}
+
+/// Fields packed into a snowflake id.
+#[cfg_attr(not(feature = "debug-endpoints"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedId {
+    pub timestamp: DateTime<Utc>,
+    pub machine_id: u64,
+    pub sequence: u64,
+}
+
+/// Wall-clock time an id from [`next_id`] was generated at, to the millisecond.
+#[cfg_attr(not(feature = "debug-endpoints"), allow(dead_code))]
+pub fn timestamp_of(id: i64) -> DateTime<Utc> {
+    let millis = WettyChatId::from_raw(id as u64).timestamp() as i64;
+    DateTime::from_timestamp_millis(millis).expect("47-bit millisecond timestamps are in range")
+}
+
+/// Smallest id [`next_id`] can generate at or after `time`, so a time bound
+/// becomes a range on an `id` index.
+pub fn first_id_at(time: DateTime<Utc>) -> i64 {
+    let millis = time.timestamp_millis().max(0) as u64;
+    WettyChatId::from_components(millis, 0, 0).to_raw() as i64
+}
+
+/// Split an id into its timestamp, node and sequence.
+#[cfg_attr(not(feature = "debug-endpoints"), allow(dead_code))]
+pub fn decode(id: i64) -> DecodedId {
+    let raw = WettyChatId::from_raw(id as u64);
+    DecodedId {
+        timestamp: timestamp_of(id),
+        machine_id: raw.machine_id(),
+        sequence: raw.sequence(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn generated_ids_decode_to_their_creation_time() {
+        let gen = LockSnowflakeGenerator::new(3, MonotonicClock::with_epoch(UNIX_EPOCH));
+        let before = Utc::now().timestamp_millis();
+        let first = next_id(&gen).await.expect("generate id");
+        let second = next_id(&gen).await.expect("generate id");
+        let after = Utc::now().timestamp_millis();
+
+        // The ferroid clock ticks on its own thread and may trail the wall
+        // clock by a few milliseconds.
+        let decoded = decode(first);
+        assert!((before - 1_000..=after + 1_000).contains(&decoded.timestamp.timestamp_millis()));
+        assert_eq!(decoded.machine_id, 3);
+        assert_eq!(timestamp_of(first), decoded.timestamp);
+        assert!(timestamp_of(second) >= decoded.timestamp);
+    }
+
+    #[tokio::test]
+    async fn batches_are_unique_and_strictly_increasing() {
+        let gen = LockSnowflakeGenerator::new(1, MonotonicClock::with_epoch(UNIX_EPOCH));
+        assert!(next_message_ids(&gen, 0).await.unwrap().is_empty());
+
+        // More than one millisecond's worth of sequence numbers.
+        let batch = next_message_ids(&gen, 10_000).await.expect("generate ids");
+        assert_eq!(batch.len(), 10_000);
+        assert!(batch.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let after = next_message_id(&gen).await.expect("generate id");
+        assert!(after > *batch.last().unwrap());
+    }
+
+    #[test]
+    fn first_id_at_bounds_the_ids_of_that_millisecond() {
+        let time = DateTime::from_timestamp_millis(1_700_000_000_123).unwrap();
+        let first = first_id_at(time);
+        let same_ms = WettyChatId::from_components(1_700_000_000_123, 7, 9).to_raw() as i64;
+        let earlier = WettyChatId::from_components(1_700_000_000_122, 15, 4095).to_raw() as i64;
+
+        assert_eq!(timestamp_of(first), time);
+        assert!(earlier < first && first <= same_ms);
+    }
+
+    #[test]
+    fn decode_splits_every_component() {
+        let raw = WettyChatId::from_components(1_700_000_000_123, 5, 42).to_raw() as i64;
+
+        assert_eq!(
+            decode(raw),
+            DecodedId {
+                timestamp: DateTime::from_timestamp_millis(1_700_000_000_123).unwrap(),
+                machine_id: 5,
+                sequence: 42,
+            }
+        );
+    }
+}
@@ -1,3 +1,5 @@
 pub mod auth;
 pub mod ids;
+pub mod moderation;
 pub mod pagination;
+pub mod rate_limit;
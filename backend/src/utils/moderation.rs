@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use axum::http::StatusCode;
+
+use crate::errors::{AppError, ErrorCode};
+use crate::models::ModerationPolicy;
+
+const BLOCKED_WORDS: AppError = AppError::Coded(
+    StatusCode::BAD_REQUEST,
+    ErrorCode::BlockedWords,
+    "Message contains blocked words",
+);
+
+/// Env var pointing at a newline-separated keyword blocklist.
+pub const BLOCKLIST_PATH_ENV: &str = "MODERATION_BLOCKLIST_PATH";
+
+const REDACTION_CHAR: char = '*';
+
+/// Case-insensitive whole-word keyword filter.
+///
+/// Text and blocklist entries are both split into runs of alphanumeric
+/// characters, so a blocked word never matches inside a longer word, and a
+/// multi-word or hyphenated entry such as `free money` or `f-word` matches the
+/// same words in a row whatever separates them.
+#[derive(Debug, Default)]
+pub struct KeywordFilter {
+    /// Blocked entries as word sequences, keyed by their first word.
+    blocked: HashMap<String, Vec<Vec<String>>>,
+}
+
+/// Byte ranges of the alphanumeric runs in `text`.
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut word_start = None;
+    for (idx, ch) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (ch.is_alphanumeric(), word_start) {
+            (true, None) => word_start = Some(idx),
+            (false, Some(start)) => {
+                spans.push((start, idx));
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    spans
+}
+
+impl KeywordFilter {
+    pub fn new<I, S>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut blocked: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+        for entry in entries {
+            let entry = entry.as_ref().trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            let words: Vec<String> = word_spans(entry)
+                .into_iter()
+                .map(|(start, end)| entry[start..end].to_lowercase())
+                .collect();
+            let Some(first) = words.first() else {
+                tracing::warn!(
+                    entry,
+                    "Skipping blocklist entry without any letters or digits"
+                );
+                continue;
+            };
+            let sequences = blocked.entry(first.clone()).or_default();
+            if !sequences.contains(&words) {
+                sequences.push(words);
+            }
+        }
+        Self { blocked }
+    }
+
+    /// Load the blocklist from the file named by `MODERATION_BLOCKLIST_PATH`.
+    /// An unset variable yields an empty filter; an unreadable file is fatal.
+    pub fn from_env() -> Self {
+        let Ok(path) = std::env::var(BLOCKLIST_PATH_ENV) else {
+            return Self::default();
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{BLOCKLIST_PATH_ENV} ({path}) could not be read: {e}"));
+        let filter = Self::new(contents.lines());
+        tracing::info!(
+            path,
+            words = filter.blocked.values().map(Vec::len).sum::<usize>(),
+            "Loaded moderation blocklist"
+        );
+        filter
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocked.is_empty()
+    }
+
+    /// Byte ranges of blocked entries in `text`, longest first where entries
+    /// start at the same word, never overlapping.
+    fn find_matches(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        if self.blocked.is_empty() {
+            return matches;
+        }
+
+        let spans = word_spans(text);
+        let words: Vec<String> = spans
+            .iter()
+            .map(|&(start, end)| text[start..end].to_lowercase())
+            .collect();
+        let mut idx = 0;
+        while idx < words.len() {
+            let longest = self.blocked.get(&words[idx]).and_then(|sequences| {
+                sequences
+                    .iter()
+                    .filter(|sequence| words[idx..].starts_with(sequence))
+                    .map(Vec::len)
+                    .max()
+            });
+            match longest {
+                Some(len) => {
+                    matches.push((spans[idx].0, spans[idx + len - 1].1));
+                    idx += len;
+                }
+                None => idx += 1,
+            }
+        }
+
+        matches
+    }
+
+    pub fn contains_blocked(&self, text: &str) -> bool {
+        !self.find_matches(text).is_empty()
+    }
+
+    /// Replace every blocked entry with asterisks, one per character.
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = String::with_capacity(text.len());
+        let mut cursor = 0;
+        for (start, end) in self.find_matches(text) {
+            redacted.push_str(&text[cursor..start]);
+            redacted.extend(std::iter::repeat_n(
+                REDACTION_CHAR,
+                text[start..end].chars().count(),
+            ));
+            cursor = end;
+        }
+        redacted.push_str(&text[cursor..]);
+        redacted
+    }
+
+    /// Apply a chat's moderation policy to outgoing message text.
+    pub fn apply(&self, policy: ModerationPolicy, text: String) -> Result<String, AppError> {
+        match policy {
+            ModerationPolicy::Off => Ok(text),
+            ModerationPolicy::Reject if self.contains_blocked(&text) => Err(BLOCKED_WORDS),
+            ModerationPolicy::Reject => Ok(text),
+            ModerationPolicy::Redact => Ok(self.redact(&text)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter() -> KeywordFilter {
+        KeywordFilter::new(["darn", " Heck ", "", "# comment"])
+    }
+
+    #[test]
+    fn reject_mode_refuses_blocked_words() {
+        let err = filter()
+            .apply(ModerationPolicy::Reject, "well DARN it".to_string())
+            .expect_err("blocked word should be rejected");
+        assert_eq!(err.code(), ErrorCode::BlockedWords);
+
+        assert_eq!(
+            filter()
+                .apply(ModerationPolicy::Reject, "darnation is fine".to_string())
+                .unwrap(),
+            "darnation is fine"
+        );
+    }
+
+    #[test]
+    fn redact_mode_masks_each_match() {
+        assert_eq!(
+            filter()
+                .apply(ModerationPolicy::Redact, "heck, darn! Heck.".to_string())
+                .unwrap(),
+            "****, ****! ****."
+        );
+        assert_eq!(filter().redact("no matches here"), "no matches here");
+    }
+
+    #[test]
+    fn multi_word_and_hyphenated_entries_match_as_phrases() {
+        let filter = KeywordFilter::new(["free money", "f-word", "free", "!!!"]);
+
+        assert_eq!(filter.redact("Get FREE  money now"), "Get *********** now");
+        assert_eq!(filter.redact("free stuff"), "**** stuff");
+        assert_eq!(
+            filter.redact("the f-word, the F word"),
+            "the ******, the ******"
+        );
+        assert_eq!(filter.redact("money free"), "money ****");
+        assert!(!filter.contains_blocked("fword and !!!"));
+        assert!(filter
+            .apply(ModerationPolicy::Reject, "free-money".to_string())
+            .is_err());
+    }
+
+    #[test]
+    fn off_mode_and_empty_filter_pass_through() {
+        assert_eq!(
+            filter()
+                .apply(ModerationPolicy::Off, "darn".to_string())
+                .unwrap(),
+            "darn"
+        );
+        assert!(!KeywordFilter::default().contains_blocked("darn"));
+        assert!(!filter().contains_blocked("# comment"));
+    }
+}
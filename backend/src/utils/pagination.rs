@@ -1,12 +1,177 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::errors::AppError;
+
+pub const MAX_CHATS_LIMIT_ENV: &str = "MAX_CHATS_LIMIT";
+pub const MAX_MESSAGES_LIMIT_ENV: &str = "MAX_MESSAGES_LIMIT";
+const DEFAULT_PAGE_LIMIT: i64 = 100;
+
+/// Largest page the chat and message lists hand out, read once at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageLimits {
+    pub chats: i64,
+    pub messages: i64,
+}
+
+impl Default for PageLimits {
+    fn default() -> Self {
+        Self {
+            chats: DEFAULT_PAGE_LIMIT,
+            messages: DEFAULT_PAGE_LIMIT,
+        }
+    }
+}
+
+impl PageLimits {
+    /// Limits from `MAX_CHATS_LIMIT` and `MAX_MESSAGES_LIMIT`, each defaulting to 100.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let read = |name: &str| {
+            lookup(name)
+                .map(|value| {
+                    value
+                        .parse::<i64>()
+                        .ok()
+                        .filter(|limit| *limit > 0)
+                        .unwrap_or_else(|| panic!("{name} must be a positive integer"))
+                })
+                .unwrap_or(DEFAULT_PAGE_LIMIT)
+        };
+        Self {
+            chats: read(MAX_CHATS_LIMIT_ENV),
+            messages: read(MAX_MESSAGES_LIMIT_ENV),
+        }
+    }
+}
+
 /// Clamp an optional user-supplied limit to `[1, max]`, defaulting to `max`.
 pub fn validate_limit(limit: Option<i64>, max: i64) -> i64 {
     limit.map(|l| l.min(max)).unwrap_or(max).max(1)
 }
 
+/// Like [`validate_limit`], but rejects a non-positive page size with 400 instead of
+/// coercing it to 1, so clients notice the bug. Values above `max` still clamp.
+pub fn require_positive_limit(limit: Option<i64>, max: i64) -> Result<i64, AppError> {
+    match limit {
+        Some(l) if l < 1 => Err(AppError::BadRequest("Page size must be a positive integer")),
+        Some(l) => Ok(l.min(max)),
+        None => Ok(max),
+    }
+}
+
+/// Leading byte of every cursor token, so the format can change later. It
+/// also makes tokens start with `A`, which keeps them from ever being all
+/// digits and mistaken for a legacy raw id.
+const CURSOR_VERSION: u8 = 1;
+
+/// Opaque page cursor for `id`. Clients must hand it back unchanged rather
+/// than reading an id (and its timestamp) out of it.
+pub fn encode_cursor(id: i64) -> String {
+    let mut bytes = [0u8; 9];
+    bytes[0] = CURSOR_VERSION;
+    bytes[1..].copy_from_slice(&id.to_be_bytes());
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// The id behind a cursor from [`encode_cursor`]. Raw numeric ids are still
+/// accepted while clients move off them.
+pub fn decode_cursor(cursor: &str) -> Result<i64, AppError> {
+    const INVALID: AppError = AppError::BadRequest("Invalid cursor");
+    if !cursor.is_empty() && cursor.bytes().all(|b| b.is_ascii_digit() || b == b'-') {
+        return cursor.parse().map_err(|_| INVALID);
+    }
+    let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| INVALID)?;
+    match bytes.split_first() {
+        Some((&CURSOR_VERSION, id)) => id.try_into().map(i64::from_be_bytes).map_err(|_| INVALID),
+        _ => Err(INVALID),
+    }
+}
+
+/// Serde adapter for optional cursor fields: [`encode_cursor`] on the way out,
+/// [`decode_cursor`] on the way in. A bad token fails deserialization, which
+/// the `Query` extractor answers with 400.
+pub mod serde_cursor {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(id) => serializer.serialize_str(&super::encode_cursor(*id)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|cursor| {
+                super::decode_cursor(&cursor)
+                    .map_err(|_| serde::de::Error::custom("invalid cursor"))
+            })
+            .transpose()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn cursors_round_trip() {
+        for id in [0, 1, 42, 7_300_000_000_000_000_000, i64::MAX, -1, i64::MIN] {
+            let cursor = encode_cursor(id);
+            assert!(!cursor.bytes().all(|b| b.is_ascii_digit()));
+            assert_eq!(decode_cursor(&cursor).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn raw_numeric_cursors_are_still_accepted() {
+        assert_eq!(
+            decode_cursor("7300000000000000000").unwrap(),
+            7_300_000_000_000_000_000
+        );
+    }
+
+    #[test]
+    fn invalid_cursors_are_rejected() {
+        let truncated = &encode_cursor(42)[..6];
+        let wrong_version = URL_SAFE_NO_PAD.encode([9u8; 9]);
+        for cursor in ["", "not a cursor", "12-3", truncated, &wrong_version] {
+            assert!(
+                matches!(decode_cursor(cursor), Err(AppError::BadRequest(_))),
+                "{cursor:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn page_limits_default_to_100_and_follow_the_config() {
+        assert_eq!(PageLimits::from_lookup(|_| None), PageLimits::default());
+        assert_eq!(PageLimits::default().messages, 100);
+
+        let limits = PageLimits::from_lookup(|name| {
+            (name == MAX_MESSAGES_LIMIT_ENV).then(|| "250".to_string())
+        });
+        assert_eq!(limits.chats, 100);
+        assert_eq!(validate_limit(Some(1_000), limits.messages), 250);
+        assert_eq!(
+            require_positive_limit(Some(200), limits.messages).unwrap(),
+            200
+        );
+        assert_eq!(
+            require_positive_limit(Some(200), limits.chats).unwrap(),
+            100
+        );
+    }
+
     #[test]
     fn defaults_to_max_when_none() {
         assert_eq!(validate_limit(None, 50), 50);
@@ -27,4 +192,28 @@ mod tests {
     fn passes_through_valid_value() {
         assert_eq!(validate_limit(Some(25), 50), 25);
     }
+
+    #[test]
+    fn require_positive_rejects_zero_and_negative() {
+        assert!(matches!(
+            require_positive_limit(Some(0), 50),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            require_positive_limit(Some(-1), 50),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn require_positive_clamps_above_max() {
+        assert_eq!(require_positive_limit(Some(51), 50).unwrap(), 50);
+        assert_eq!(require_positive_limit(Some(50), 50).unwrap(), 50);
+        assert_eq!(require_positive_limit(Some(1), 50).unwrap(), 1);
+    }
+
+    #[test]
+    fn require_positive_defaults_to_max() {
+        assert_eq!(require_positive_limit(None, 50).unwrap(), 50);
+    }
 }
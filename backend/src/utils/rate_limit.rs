@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+use crate::errors::AppError;
+
+/// Env var for the number of messages a user may send back to back.
+pub const MESSAGE_BURST_ENV: &str = "MESSAGE_RATE_LIMIT_BURST";
+/// Env var for the sustained number of messages per second once the burst is spent.
+pub const MESSAGE_PER_SECOND_ENV: &str = "MESSAGE_RATE_LIMIT_PER_SECOND";
+
+const DEFAULT_MESSAGE_BURST: f64 = 10.0;
+const DEFAULT_MESSAGE_PER_SECOND: f64 = 2.0;
+
+/// Bucket count above which a check also drops idle buckets.
+const SWEEP_THRESHOLD: usize = 1024;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Per-user token bucket. Each user starts with `burst` tokens, spends one per
+/// action and regains `per_second` tokens every second up to the burst size.
+#[derive(Debug)]
+pub struct RateLimiter {
+    buckets: DashMap<i32, Bucket>,
+    burst: f64,
+    per_second: f64,
+}
+
+impl RateLimiter {
+    pub fn new(burst: f64, per_second: f64) -> Self {
+        assert!(burst >= 1.0, "rate limit burst must be at least 1");
+        assert!(per_second > 0.0, "rate limit refill rate must be positive");
+        Self {
+            buckets: DashMap::new(),
+            burst,
+            per_second,
+        }
+    }
+
+    /// Message-send limiter configured from `MESSAGE_RATE_LIMIT_BURST` and
+    /// `MESSAGE_RATE_LIMIT_PER_SECOND`.
+    pub fn messages_from_env() -> Self {
+        Self::new(
+            read_positive_f64(MESSAGE_BURST_ENV, DEFAULT_MESSAGE_BURST),
+            read_positive_f64(MESSAGE_PER_SECOND_ENV, DEFAULT_MESSAGE_PER_SECOND),
+        )
+    }
+
+    /// Take one token for `uid`, or return 429 with the wait until one is available.
+    pub fn check(&self, uid: i32) -> Result<(), AppError> {
+        self.check_at(uid, Instant::now()).map_err(|retry_after| {
+            AppError::TooManyRequests(retry_after.as_secs_f64().ceil() as u64)
+        })
+    }
+
+    fn check_at(&self, uid: i32, now: Instant) -> Result<(), Duration> {
+        let result = {
+            let mut bucket = self.buckets.entry(uid).or_insert(Bucket {
+                tokens: self.burst,
+                updated_at: now,
+            });
+            bucket.tokens = self.refilled(&bucket, now);
+            bucket.updated_at = now;
+
+            if bucket.tokens >= 1.0 {
+                bucket.tokens -= 1.0;
+                Ok(())
+            } else {
+                Err(Duration::from_secs_f64(
+                    (1.0 - bucket.tokens) / self.per_second,
+                ))
+            }
+        };
+        if self.buckets.len() > SWEEP_THRESHOLD {
+            self.sweep_idle(now);
+        }
+        result
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now
+            .saturating_duration_since(bucket.updated_at)
+            .as_secs_f64();
+        (bucket.tokens + elapsed * self.per_second).min(self.burst)
+    }
+
+    /// Drop buckets that have refilled to the burst size. They are no
+    /// different from the fresh bucket a user's next send would create.
+    fn sweep_idle(&self, now: Instant) {
+        self.buckets
+            .retain(|_, bucket| self.refilled(bucket, now) < self.burst);
+    }
+}
+
+fn read_positive_f64(var_name: &str, default: f64) -> f64 {
+    std::env::var(var_name)
+        .ok()
+        .map(|value| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|parsed| parsed.is_finite() && *parsed > 0.0)
+                .unwrap_or_else(|| panic!("{var_name} must be a positive number"))
+        })
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_the_burst_then_rejects_until_refilled() {
+        let limiter = RateLimiter::new(10.0, 2.0);
+        let start = Instant::now();
+
+        for _ in 0..10 {
+            assert!(limiter.check_at(1, start).is_ok());
+        }
+        let retry_after = limiter
+            .check_at(1, start)
+            .expect_err("11th message in the same instant should be limited");
+        assert_eq!(retry_after, Duration::from_millis(500));
+
+        assert!(limiter
+            .check_at(1, start + Duration::from_millis(499))
+            .is_err());
+        assert!(limiter
+            .check_at(1, start + Duration::from_millis(1000))
+            .is_ok());
+    }
+
+    #[test]
+    fn buckets_are_per_user() {
+        let limiter = RateLimiter::new(1.0, 1.0);
+        let now = Instant::now();
+
+        assert!(limiter.check_at(1, now).is_ok());
+        assert!(limiter.check_at(1, now).is_err());
+        assert!(limiter.check_at(2, now).is_ok());
+    }
+
+    #[test]
+    fn refilled_buckets_are_swept_once_the_map_grows() {
+        let limiter = RateLimiter::new(2.0, 1.0);
+        let start = Instant::now();
+        for uid in 0..SWEEP_THRESHOLD as i32 {
+            assert!(limiter.check_at(uid, start).is_ok());
+        }
+        assert!(limiter.check_at(0, start).is_ok());
+        assert_eq!(limiter.buckets.len(), SWEEP_THRESHOLD);
+
+        // One second later only uid 0, which spent its whole burst, is still
+        // refilling; everyone else is back to full and can be forgotten.
+        let later = start + Duration::from_secs(1);
+        assert!(limiter.check_at(-1, later).is_ok());
+        let mut remaining: Vec<i32> = limiter.buckets.iter().map(|e| *e.key()).collect();
+        remaining.sort_unstable();
+        assert_eq!(remaining, vec![-1, 0]);
+    }
+
+    #[test]
+    fn rejection_maps_to_429_with_whole_second_retry_after() {
+        let limiter = RateLimiter::new(1.0, 4.0);
+
+        assert!(limiter.check(1).is_ok());
+        assert!(matches!(
+            limiter.check(1),
+            Err(AppError::TooManyRequests(1))
+        ));
+    }
+}
@@ -57,8 +57,8 @@ sealed class ApiWsEvent {
         return MessageUpdatedWsEvent.fromJson(json);
       case 'messageDeleted':
         return MessageDeletedWsEvent.fromJson(json);
-      case 'reactionUpdated':
-        return ReactionUpdatedWsEvent.fromJson(json);
+      case 'reactionDelta':
+        return ReactionDeltaWsEvent.fromJson(json);
       case 'threadUpdate':
         return ThreadUpdatedWsEvent.fromJson(json);
       case 'stickerPackOrderUpdated':
@@ -121,41 +121,54 @@ class MessageDeletedWsEvent extends ApiWsEvent {
   Map<String, dynamic> toJson() => _$MessageDeletedWsEventToJson(this);
 }
 
+/// One reaction added or removed. [newCount] is the emoji's count after the
+/// change as stored on the server, so it is applied as-is.
 @JsonSerializable(explicitToJson: true)
-class ReactionUpdatePayloadDto {
-  const ReactionUpdatePayloadDto({
+class ReactionDeltaPayloadDto {
+  const ReactionDeltaPayloadDto({
     required this.messageId,
     required this.chatId,
-    this.reactions = const <ReactionSummaryDto>[],
+    required this.emoji,
+    required this.uid,
+    required this.op,
+    required this.newCount,
   });
 
   @FlexibleIntConverter()
   final int messageId;
   @FlexibleIntConverter()
   final int chatId;
-  @JsonKey(defaultValue: <ReactionSummaryDto>[])
-  final List<ReactionSummaryDto> reactions;
+  final String emoji;
+  @FlexibleIntConverter()
+  final int uid;
+
+  /// `add` or `remove`.
+  final String op;
+  @FlexibleIntConverter()
+  final int newCount;
+
+  bool get isAdd => op == 'add';
 
-  factory ReactionUpdatePayloadDto.fromJson(Map<String, dynamic> json) =>
-      _$ReactionUpdatePayloadDtoFromJson(json);
+  factory ReactionDeltaPayloadDto.fromJson(Map<String, dynamic> json) =>
+      _$ReactionDeltaPayloadDtoFromJson(json);
 
-  Map<String, dynamic> toJson() => _$ReactionUpdatePayloadDtoToJson(this);
+  Map<String, dynamic> toJson() => _$ReactionDeltaPayloadDtoToJson(this);
 }
 
 @JsonSerializable(explicitToJson: true)
-class ReactionUpdatedWsEvent extends ApiWsEvent {
-  const ReactionUpdatedWsEvent({
-    this.type = 'reactionUpdated',
+class ReactionDeltaWsEvent extends ApiWsEvent {
+  const ReactionDeltaWsEvent({
+    this.type = 'reactionDelta',
     required this.payload,
   });
 
   final String type;
-  final ReactionUpdatePayloadDto payload;
+  final ReactionDeltaPayloadDto payload;
 
-  factory ReactionUpdatedWsEvent.fromJson(Map<String, dynamic> json) =>
-      _$ReactionUpdatedWsEventFromJson(json);
+  factory ReactionDeltaWsEvent.fromJson(Map<String, dynamic> json) =>
+      _$ReactionDeltaWsEventFromJson(json);
 
-  Map<String, dynamic> toJson() => _$ReactionUpdatedWsEventToJson(this);
+  Map<String, dynamic> toJson() => _$ReactionDeltaWsEventToJson(this);
 }
 
 @JsonSerializable(explicitToJson: true)
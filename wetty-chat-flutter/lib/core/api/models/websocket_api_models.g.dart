@@ -70,37 +70,39 @@ Map<String, dynamic> _$MessageDeletedWsEventToJson(
   'payload': instance.payload.toJson(),
 };
 
-ReactionUpdatePayloadDto _$ReactionUpdatePayloadDtoFromJson(
+ReactionDeltaPayloadDto _$ReactionDeltaPayloadDtoFromJson(
   Map<String, dynamic> json,
-) => ReactionUpdatePayloadDto(
+) => ReactionDeltaPayloadDto(
   messageId: const FlexibleIntConverter().fromJson(json['messageId']),
   chatId: const FlexibleIntConverter().fromJson(json['chatId']),
-  reactions:
-      (json['reactions'] as List<dynamic>?)
-          ?.map((e) => ReactionSummaryDto.fromJson(e as Map<String, dynamic>))
-          .toList() ??
-      [],
+  emoji: json['emoji'] as String,
+  uid: const FlexibleIntConverter().fromJson(json['uid']),
+  op: json['op'] as String,
+  newCount: const FlexibleIntConverter().fromJson(json['newCount']),
 );
 
-Map<String, dynamic> _$ReactionUpdatePayloadDtoToJson(
-  ReactionUpdatePayloadDto instance,
+Map<String, dynamic> _$ReactionDeltaPayloadDtoToJson(
+  ReactionDeltaPayloadDto instance,
 ) => <String, dynamic>{
   'messageId': const FlexibleIntConverter().toJson(instance.messageId),
   'chatId': const FlexibleIntConverter().toJson(instance.chatId),
-  'reactions': instance.reactions.map((e) => e.toJson()).toList(),
+  'emoji': instance.emoji,
+  'uid': const FlexibleIntConverter().toJson(instance.uid),
+  'op': instance.op,
+  'newCount': const FlexibleIntConverter().toJson(instance.newCount),
 };
 
-ReactionUpdatedWsEvent _$ReactionUpdatedWsEventFromJson(
+ReactionDeltaWsEvent _$ReactionDeltaWsEventFromJson(
   Map<String, dynamic> json,
-) => ReactionUpdatedWsEvent(
-  type: json['type'] as String? ?? 'reactionUpdated',
-  payload: ReactionUpdatePayloadDto.fromJson(
+) => ReactionDeltaWsEvent(
+  type: json['type'] as String? ?? 'reactionDelta',
+  payload: ReactionDeltaPayloadDto.fromJson(
     json['payload'] as Map<String, dynamic>,
   ),
 );
 
-Map<String, dynamic> _$ReactionUpdatedWsEventToJson(
-  ReactionUpdatedWsEvent instance,
+Map<String, dynamic> _$ReactionDeltaWsEventToJson(
+  ReactionDeltaWsEvent instance,
 ) => <String, dynamic>{
   'type': instance.type,
   'payload': instance.payload.toJson(),
@@ -41,13 +41,14 @@ class _WsEventDeduplicator {
         payload.createdAt?.millisecondsSinceEpoch,
         payload.isDeleted,
       ].join(':'),
-      ReactionUpdatedWsEvent(:final payload) => [
-        'reactionUpdated',
+      ReactionDeltaWsEvent(:final payload) => [
+        'reactionDelta',
         payload.chatId,
         payload.messageId,
-        payload.reactions
-            .map((reaction) => '${reaction.emoji}:${reaction.count}')
-            .join(','),
+        payload.emoji,
+        payload.uid,
+        payload.op,
+        payload.newCount,
       ].join(':'),
       ThreadUpdatedWsEvent(:final payload) => [
         'threadUpdated',
@@ -96,7 +97,7 @@ final wsEventRouterProvider = Provider<void>((ref) {
           ref.read(threadListStateProvider.notifier).applyRealtimeEvent(event);
           ref.read(unreadBadgeProvider.notifier).scheduleReconcile();
           return;
-        case ReactionUpdatedWsEvent():
+        case ReactionDeltaWsEvent():
           ref.read(conversationRealtimeRegistryProvider).dispatch(event);
           return;
         case ThreadUpdatedWsEvent():
@@ -1139,7 +1139,7 @@ class ConversationTimelineViewModel
       case MessageDeletedWsEvent(:final payload):
         _pendingLiveMessageIds.remove(payload.id);
       case MessageUpdatedWsEvent():
-      case ReactionUpdatedWsEvent():
+      case ReactionDeltaWsEvent():
       case ThreadUpdatedWsEvent():
       case StickerPackOrderUpdatedWsEvent():
       case PongWsEvent():
@@ -4,6 +4,7 @@ import 'package:flutter_riverpod/flutter_riverpod.dart';
 
 import '../../../../core/api/models/messages_api_models.dart';
 import '../../../../core/api/models/websocket_api_models.dart';
+import '../../../../core/session/dev_session_store.dart';
 import '../../message_domain/domain/message_domain.dart';
 import '../../models/message_api_mapper.dart';
 import '../../models/message_models.dart';
@@ -16,6 +17,7 @@ class ConversationRepository {
     required this.scope,
     required MessageApiService service,
     required MessageDomainStore store,
+    this.currentUserId,
   }) : _service = service,
        _store = store;
 
@@ -35,7 +37,13 @@ class ConversationRepository {
   /// context ahead. Cycle: grow from trimTarget → softWindowCap, trim, repeat.
   static const int trimTarget = 300;
 
+  /// Reactors the server names per emoji in a message's reaction summary.
+  static const int summaryReactorsPerEmoji = 5;
+
   final ConversationScope scope;
+
+  /// The signed-in user, so `reactedByMe` follows their own reaction deltas.
+  final int? currentUserId;
   final MessageApiService _service;
   final MessageDomainStore _store;
   final Map<String, ConversationMessage> _optimisticSnapshots = {};
@@ -524,7 +532,7 @@ class ConversationRepository {
         payload,
         deleted: true,
       ),
-      ReactionUpdatedWsEvent(:final payload) => _applyReactionEvent(payload),
+      ReactionDeltaWsEvent(:final payload) => _applyReactionDelta(payload),
       _ => false,
     };
   }
@@ -586,7 +594,7 @@ class ConversationRepository {
         : _ConversationRealtimeEventType.updated;
   }
 
-  bool _applyReactionEvent(ReactionUpdatePayloadDto payload) {
+  bool _applyReactionDelta(ReactionDeltaPayloadDto payload) {
     if (payload.chatId.toString() != scope.chatId) {
       return false;
     }
@@ -602,16 +610,52 @@ class ConversationRepository {
 
     _store.upsertCanonicalMessage(
       message.copyWith(
-        reactions: _mergeReactions(
-          message.reactions,
-          payload.reactions.map((reaction) => reaction.toDomain()).toList(),
-        ),
+        reactions: _reactionsWithDelta(message.reactions, payload),
       ),
     );
     _optimisticSnapshots.remove(stableKey);
     return true;
   }
 
+  /// Applies one server reaction delta: the emoji takes the server's count
+  /// and drops out at zero, and the reacting user joins or leaves its reactors.
+  List<ReactionSummary> _reactionsWithDelta(
+    List<ReactionSummary> reactions,
+    ReactionDeltaPayloadDto delta,
+  ) {
+    if (delta.newCount <= 0) {
+      return reactions
+          .where((reaction) => reaction.emoji != delta.emoji)
+          .toList(growable: false);
+    }
+    final index = reactions.indexWhere(
+      (reaction) => reaction.emoji == delta.emoji,
+    );
+    final previous = index >= 0
+        ? reactions[index]
+        : ReactionSummary(emoji: delta.emoji, count: 0);
+    final others = (previous.reactors ?? const <ReactionReactor>[])
+        .where((reactor) => reactor.uid != delta.uid)
+        .toList();
+    if (delta.isAdd && others.length < summaryReactorsPerEmoji) {
+      others.add(ReactionReactor(uid: delta.uid));
+    }
+    final next = previous.copyWith(
+      count: delta.newCount,
+      reactedByMe: delta.uid == currentUserId
+          ? delta.isAdd
+          : previous.reactedByMe,
+      reactors: others,
+    );
+    if (index < 0) {
+      return [...reactions, next];
+    }
+    return [
+      for (var i = 0; i < reactions.length; i++)
+        i == index ? next : reactions[i],
+    ];
+  }
+
   bool _messageBelongsToScope(ConversationMessage message) {
     final threadRootId = scope.threadRootId;
     if (threadRootId == null) {
@@ -934,5 +978,6 @@ final conversationRepositoryProvider =
         scope: scope,
         service: ref.read(messageApiServiceProvider),
         store: ref.read(messageDomainStoreProvider),
+        currentUserId: ref.read(authSessionProvider).currentUserId,
       );
     });
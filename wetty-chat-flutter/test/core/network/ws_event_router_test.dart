@@ -182,11 +182,14 @@ void main() {
       );
 
       controller.add(
-        ReactionUpdatedWsEvent(
-          payload: const ReactionUpdatePayloadDto(
+        const ReactionDeltaWsEvent(
+          payload: ReactionDeltaPayloadDto(
             messageId: 100,
             chatId: 1,
-            reactions: [ReactionSummaryDto(emoji: ':+1:', count: 1)],
+            emoji: ':+1:',
+            uid: 8,
+            op: 'add',
+            newCount: 1,
           ),
         ),
       );
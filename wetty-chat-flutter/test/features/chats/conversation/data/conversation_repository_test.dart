@@ -510,7 +510,7 @@ void main() {
     });
 
     test(
-      'reactionUpdated websocket events override the cached reaction summary',
+      'reactionDelta websocket events apply the server count and reactors',
       () async {
         final service = _FakeMessageApiService(
           messages: [
@@ -520,6 +520,7 @@ void main() {
                   emoji: '👍',
                   count: 1,
                   reactedByMe: true,
+                  reactors: [ReactionReactorDto(uid: 7, name: 'Tester')],
                 ),
               ],
             ),
@@ -529,29 +530,23 @@ void main() {
           scope: const ConversationScope.chat(chatId: '1'),
           service: service,
           store: MessageDomainStore(),
-        );
+          currentUserId: 7,
+        );
+        ReactionDeltaWsEvent delta(int uid, String op, int newCount) =>
+            ReactionDeltaWsEvent(
+              payload: ReactionDeltaPayloadDto(
+                messageId: 1,
+                chatId: 1,
+                emoji: '👍',
+                uid: uid,
+                op: op,
+                newCount: newCount,
+              ),
+            );
 
         await repository.loadLatestWindow();
-        final handled = repository.applyRealtimeEvent(
-          ReactionUpdatedWsEvent(
-            payload: ReactionUpdatePayloadDto(
-              messageId: 1,
-              chatId: 1,
-              reactions: [
-                ReactionSummaryDto(
-                  emoji: '👍',
-                  count: 2,
-                  reactors: [
-                    const ReactionReactorDto(uid: 7, name: 'Tester'),
-                    const ReactionReactorDto(uid: 8, name: 'Peer'),
-                  ],
-                ),
-              ],
-            ),
-          ),
-        );
 
-        expect(handled, isTrue);
+        expect(repository.applyRealtimeEvent(delta(8, 'add', 2)), isTrue);
         expect(repository.messageForServerId(1)?.reactions, [
           const ReactionSummary(
             emoji: '👍',
@@ -559,10 +554,23 @@ void main() {
             reactedByMe: true,
             reactors: [
               ReactionReactor(uid: 7, name: 'Tester'),
-              ReactionReactor(uid: 8, name: 'Peer'),
+              ReactionReactor(uid: 8),
             ],
           ),
         ]);
+
+        expect(repository.applyRealtimeEvent(delta(7, 'remove', 1)), isTrue);
+        expect(repository.messageForServerId(1)?.reactions, [
+          const ReactionSummary(
+            emoji: '👍',
+            count: 1,
+            reactedByMe: false,
+            reactors: [ReactionReactor(uid: 8)],
+          ),
+        ]);
+
+        expect(repository.applyRealtimeEvent(delta(8, 'remove', 0)), isTrue);
+        expect(repository.messageForServerId(1)?.reactions, isEmpty);
       },
     );
 
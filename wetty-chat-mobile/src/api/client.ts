@@ -40,4 +40,17 @@ if (import.meta.env.PROD && __AUTH_REDIRECT_URL__) {
   );
 }
 
+/** Body the backend sends with every error status. */
+export interface ApiErrorBody {
+  error: { code: string; message: string };
+}
+
+/** The backend's error message for a failed request, else the transport error's. */
+export function apiErrorMessage(err: unknown): string | undefined {
+  if (axios.isAxiosError<ApiErrorBody>(err)) {
+    return err.response?.data?.error?.message || err.message;
+  }
+  return err instanceof Error ? err.message : undefined;
+}
+
 export default apiClient;
@@ -1,6 +1,6 @@
 import apiClient from '@/api/client';
 import { syncApp } from '@/api/sync';
-import type { MessageResponse, ReactionSummary } from '@/api/messages';
+import type { MessageResponse } from '@/api/messages';
 import { setActiveConnections, setWsConnected } from '@/store/connectionSlice';
 import { selectEffectiveLocale } from '@/store/settingsSlice';
 import { setChatArchived, setChatMutedUntil } from '@/store/chatsSlice';
@@ -21,7 +21,7 @@ import {
   messageConfirmed,
   messagePatched,
   messagesBulkDeleted,
-  reactionsUpdated,
+  reactionDeltaReceived,
 } from '@/store/messageEvents';
 import { getStoredJwtToken } from '@/utils/jwtToken';
 import { formatNotificationBody, getNotificationPreviewLabels } from '@/utils/messagePreview';
@@ -408,18 +408,25 @@ async function connectWebSocket(): Promise<void> {
           return;
         }
 
-        if (message.type === 'reactionUpdated' && message.payload != null) {
+        if (message.type === 'reactionDelta' && message.payload != null) {
           const payload = message.payload as {
             messageId: string;
             chatId: string;
-            reactions: ReactionSummary[];
+            emoji: string;
+            uid: number;
+            op: 'add' | 'remove';
+            newCount: number;
           };
-          if (payload.messageId && payload.chatId) {
+          if (payload.messageId && payload.chatId && payload.emoji) {
             store.dispatch(
-              reactionsUpdated({
+              reactionDeltaReceived({
                 chatId: payload.chatId,
                 messageId: payload.messageId,
-                reactions: payload.reactions ?? [],
+                emoji: payload.emoji,
+                uid: payload.uid,
+                op: payload.op,
+                newCount: payload.newCount,
+                currentUid: store.getState().user.uid,
               }),
             );
           }
@@ -33,6 +33,18 @@ export interface ReactionsUpdatedPayload {
   reactions: ReactionSummary[];
 }
 
+export interface ReactionDeltaPayload {
+  chatId: string;
+  messageId: string;
+  emoji: string;
+  uid: number;
+  op: 'add' | 'remove';
+  /** The emoji's count after the change, as stored on the server. */
+  newCount: number;
+  /** The signed-in user, so `reactedByMe` follows their own changes. */
+  currentUid: number | null;
+}
+
 export interface MessagesBulkDeletedPayload {
   chatId: string;
   messageIds: string[];
@@ -43,3 +55,27 @@ export const messageConfirmed = createAction<MessageConfirmedPayload>('messages/
 export const messagePatched = createAction<MessagePatchedPayload>('messages/messagePatched');
 export const messagesBulkDeleted = createAction<MessagesBulkDeletedPayload>('messages/messagesBulkDeleted');
 export const reactionsUpdated = createAction<ReactionsUpdatedPayload>('messages/reactionsUpdated');
+export const reactionDeltaReceived = createAction<ReactionDeltaPayload>('messages/reactionDeltaReceived');
+
+/** Reactors the server names per emoji in a message's reaction summary. */
+const SUMMARY_REACTORS_PER_EMOJI = 5;
+
+/** Apply one server reaction delta to a message's reaction summaries. */
+export function applyReactionDelta(reactions: ReactionSummary[], delta: ReactionDeltaPayload): ReactionSummary[] {
+  const { emoji, uid, op, newCount, currentUid } = delta;
+  if (newCount <= 0) {
+    return reactions.filter((r) => r.emoji !== emoji);
+  }
+  const index = reactions.findIndex((r) => r.emoji === emoji);
+  const prev: ReactionSummary = index >= 0 ? reactions[index] : { emoji, count: 0 };
+  const others = (prev.reactors ?? []).filter((r) => r.uid !== uid);
+  const reactors =
+    op === 'add' && others.length < SUMMARY_REACTORS_PER_EMOJI ? [...others, { uid, name: null }] : others;
+  const next: ReactionSummary = {
+    ...prev,
+    count: newCount,
+    reactedByMe: currentUid != null && uid === currentUid ? op === 'add' : prev.reactedByMe,
+    reactors,
+  };
+  return index >= 0 ? reactions.map((r, i) => (i === index ? next : r)) : [...reactions, next];
+}
@@ -1,6 +1,14 @@
 import { createSelector, createSlice } from '@reduxjs/toolkit';
 import type { MessageResponse } from '@/api/messages';
-import { messageAdded, messageConfirmed, messagePatched, messagesBulkDeleted, reactionsUpdated } from './messageEvents';
+import {
+  applyReactionDelta,
+  messageAdded,
+  messageConfirmed,
+  messagePatched,
+  messagesBulkDeleted,
+  reactionDeltaReceived,
+  reactionsUpdated,
+} from './messageEvents';
 import { compareMessageOrder } from './messageProjection';
 
 const MAX_WINDOWS = 5;
@@ -467,6 +475,20 @@ const messagesSlice = createSlice({
             }
           }
         }
+      })
+      .addCase(reactionDeltaReceived, (state, action) => {
+        const { chatId, messageId } = action.payload;
+        for (const [storeKey, chat] of Object.entries(state.chats)) {
+          if (storeKey !== chatId && !storeKey.startsWith(`${chatId}_thread_`)) continue;
+          for (const win of chat.windows) {
+            for (let i = 0; i < win.messages.length; i++) {
+              if (win.messages[i].id === messageId) {
+                const reactions = applyReactionDelta(win.messages[i].reactions ?? [], action.payload);
+                win.messages[i] = { ...win.messages[i], reactions };
+              }
+            }
+          }
+        }
       });
   },
 });
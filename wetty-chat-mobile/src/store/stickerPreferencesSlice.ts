@@ -2,6 +2,7 @@ import type { PayloadAction } from '@reduxjs/toolkit';
 import { createAsyncThunk, createSlice } from '@reduxjs/toolkit';
 import type { RootState } from './index';
 import { fetchCurrentUser } from './userSlice';
+import { apiErrorMessage } from '@/api/client';
 import { usersApi, type StickerPackOrderItem, type UpdateStickerPackOrderItem } from '@/api/users';
 
 export interface StickerPreferencesState {
@@ -170,7 +171,7 @@ export const syncStickerPackOrder = createAsyncThunk<void, UpdateStickerPackOrde
       await usersApi.updateStickerPackOrder(order);
     } catch (err: any) {
       dispatch(fetchCurrentUser());
-      return rejectWithValue(err.response?.data || err.message || 'Failed to sync sticker pack order');
+      return rejectWithValue(apiErrorMessage(err) || 'Failed to sync sticker pack order');
     }
   },
 );
@@ -1,6 +1,7 @@
 import type { PayloadAction } from '@reduxjs/toolkit';
 import { createAsyncThunk, createSlice } from '@reduxjs/toolkit';
 import type { RootState } from './index';
+import { apiErrorMessage } from '@/api/client';
 import { usersApi } from '@/api/users';
 
 export interface UserState {
@@ -25,7 +26,7 @@ export const fetchCurrentUser = createAsyncThunk('user/fetchCurrentUser', async
   try {
     return await usersApi.getCurrentUser();
   } catch (err: any) {
-    return rejectWithValue(err.response?.data || err.message);
+    return rejectWithValue(apiErrorMessage(err));
   }
 });
 